@@ -21,7 +21,7 @@ OUTPUT_DIR = PROJECT_ROOT / "output"  # 输出目录
 
 # 打包内容配置
 PACK_ITEMS = [
-    "bin", "config", "docs", "META-INF", "script", "webroot",
+    "bin", "config", "configs", "docs", "META-INF", "script", "webroot",
     "action.sh", "customize.sh", "module.prop", "service.sh", 
     "uninstall.sh", "volt_list.txt"
 ]
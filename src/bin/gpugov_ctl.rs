@@ -0,0 +1,140 @@
+//! GPU Governor 命令行控制工具
+//!
+//! 通过控制命令文件与守护进程通信，并读取 status.json 显示运行状态，便于脚本自动化。
+//!
+//! 用法：
+//! - `gpugov-ctl status`
+//! - `gpugov-ctl mode <powersave|balance|performance|fast>`
+//! - `gpugov-ctl boost [performance|fast] <duration>` / `gpugov-ctl unboost`
+//! - `gpugov-ctl lock <freq> [--for <duration>]` / `gpugov-ctl unlock`
+//! - `gpugov-ctl bypass` / `gpugov-ctl resume`
+//! - `gpugov-ctl record <duration|stop>`
+
+use std::{
+    env, fs, process, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow};
+use gpugovernor::datasource::{
+    control::{REQUEST_ID_PREFIX, parse_command},
+    file_path::{CONTROL_COMMAND_FILE, CONTROL_RESPONSE_FILE, STATUS_JSON_PATH},
+};
+
+/// 等待守护进程响应的最长时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+const RESPONSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: gpugov-ctl <command>\n\
+         \n\
+         Commands:\n\
+         \x20 status                              Show governor status\n\
+         \x20 mode <mode>                         Switch mode (powersave|balance|performance|fast)\n\
+         \x20 boost [performance|fast] <dur>      Temporarily switch mode\n\
+         \x20 unboost                             End temporary mode\n\
+         \x20 lock <freq> [--for <dur>]           Lock GPU frequency (KHz)\n\
+         \x20 unlock                              Release frequency lock\n\
+         \x20 bypass | resume                     Hand control to kernel / take it back\n\
+         \x20 calibrate                           Test every OPP and drop broken ones\n\
+         \x20 dump-table [toml|json]              Export the parsed frequency table\n\
+         \x20 log-tail [lines]                    Export recent log lines to control/log_tail\n\
+         \x20 record <dur|stop>                   Record a load trace"
+    );
+    process::exit(2);
+}
+
+/// 将命令行参数转换为控制命令文件中的一行
+fn build_command(args: &[String]) -> Result<String> {
+    let mut parts = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--for" => parts.push(
+                iter.next()
+                    .ok_or_else(|| anyhow!("Missing value for --for"))?
+                    .clone(),
+            ),
+            _ => parts.push(arg.clone()),
+        }
+    }
+
+    let line = parts.join(" ");
+    // 在本地先校验，避免把无效命令写给守护进程
+    parse_command(&line)?;
+    Ok(line)
+}
+
+/// 本次调用的请求ID
+fn request_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    format!("{}-{nanos}", process::id())
+}
+
+/// 写入控制命令并等待守护进程写回执行结果
+///
+/// 命令先写入临时文件再改名，守护进程不会读到写了一半的命令；结果文件第一行是请求ID，
+/// 同时有多个调用时只接受带有自己请求ID的结果
+fn send_command(line: &str) -> Result<String> {
+    let id_line = format!("{REQUEST_ID_PREFIX}{}", request_id());
+    let tmp_path = format!("{CONTROL_COMMAND_FILE}.{}.tmp", process::id());
+    if let Err(e) = fs::write(&tmp_path, format!("{id_line}\n{line}\n"))
+        .and_then(|()| fs::rename(&tmp_path, CONTROL_COMMAND_FILE))
+    {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(anyhow!(e).context(format!(
+            "Failed to write {CONTROL_COMMAND_FILE}, is the governor running?"
+        )));
+    }
+
+    let start = Instant::now();
+    while start.elapsed() < RESPONSE_TIMEOUT {
+        if let Ok(response) = fs::read_to_string(CONTROL_RESPONSE_FILE) {
+            let (first, rest) = response.split_once('\n').unwrap_or((&response, ""));
+            if first.trim() == id_line {
+                return Ok(rest.to_string());
+            }
+        }
+        thread::sleep(RESPONSE_POLL_INTERVAL);
+    }
+
+    Err(anyhow!(
+        "No response from governor within {}s",
+        RESPONSE_TIMEOUT.as_secs()
+    ))
+}
+
+fn run() -> Result<bool> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None | Some("-h" | "--help" | "help") => usage(),
+        Some("status") => {
+            let status = fs::read_to_string(STATUS_JSON_PATH)
+                .with_context(|| format!("Failed to read {STATUS_JSON_PATH}"))?;
+            println!("{}", status.trim_end());
+            Ok(true)
+        }
+        Some(_) => {
+            let line = build_command(&args)?;
+            let response = send_command(&line)?;
+            let response = response.trim();
+            println!("{response}");
+            Ok(!response.lines().any(|l| l.starts_with("error:")))
+        }
+    }
+}
+
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("gpugov-ctl: {e:#}");
+            process::exit(1);
+        }
+    }
+}
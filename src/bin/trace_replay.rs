@@ -0,0 +1,196 @@
+//! 负载轨迹回放工具
+//!
+//! 将记录的负载轨迹（CSV）逐条送入 `FrequencyAdjustmentEngine`，所有节点写入由 sysfs
+//! 模拟层接管，输出每个采样点的调频决策，用于离线调参和对比不同版本的调频行为。
+//!
+//! 用法：
+//! `trace-replay <trace.csv> --freq-table <gpu_freq_table.toml> [--config <config.toml>]
+//!  [--mode <mode>] [--v2] [--output <decisions.csv>]`
+//!
+//! 轨迹文件每行为 `timestamp_ms,load[,...]`，非数字行（如表头）会被忽略，多余的列不影响回放。
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    process,
+};
+
+use anyhow::{Context, Result, anyhow};
+use gpugovernor::{
+    datasource::{
+        config_parser::read_config_delta_from, file_path::*, freq_table_parser::freq_table_read,
+    },
+    model::{frequency_engine::FrequencyAdjustmentEngine, gpu::GPU},
+    utils::sysfs_mock,
+};
+
+struct Options {
+    trace: String,
+    freq_table: String,
+    config: Option<String>,
+    mode: Option<String>,
+    gpuv2: bool,
+    output: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: trace-replay <trace.csv> --freq-table <file> [--config <file>] [--mode <mode>] [--v2] [--output <file>]"
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Result<Options> {
+    let mut args = env::args().skip(1);
+    let mut trace = None;
+    let mut freq_table = None;
+    let mut config = None;
+    let mut mode = None;
+    let mut gpuv2 = false;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("Missing value for {name}"))
+        };
+        match arg.as_str() {
+            "--freq-table" => freq_table = Some(value("--freq-table")?),
+            "--config" => config = Some(value("--config")?),
+            "--mode" => mode = Some(value("--mode")?),
+            "--output" | "-o" => output = Some(value("--output")?),
+            "--v2" => gpuv2 = true,
+            "--help" | "-h" => usage(),
+            _ if arg.starts_with('-') => return Err(anyhow!("Unknown option: {arg}")),
+            _ => trace = Some(arg),
+        }
+    }
+
+    Ok(Options {
+        trace: trace.ok_or_else(|| anyhow!("Missing trace file"))?,
+        freq_table: freq_table.ok_or_else(|| anyhow!("Missing --freq-table"))?,
+        config,
+        mode,
+        gpuv2,
+        output,
+    })
+}
+
+/// 解析轨迹文件，返回 (时间戳, 负载) 序列
+fn read_trace(path: &str) -> Result<Vec<(u64, i32)>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read trace file: {path}"))?;
+
+    let samples = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let timestamp = fields.next()?.parse::<u64>().ok()?;
+            let load = fields.next()?.parse::<i32>().ok()?;
+            Some((timestamp, load.clamp(0, 100)))
+        })
+        .collect::<Vec<_>>();
+
+    if samples.is_empty() {
+        return Err(anyhow!("No samples found in trace file: {path}"));
+    }
+    Ok(samples)
+}
+
+/// 构建回放用的GPU对象
+fn build_gpu(options: &Options) -> Result<GPU> {
+    let mut gpu = GPU::new();
+    gpu.set_gpuv2(options.gpuv2);
+    gpu.set_dcs_enable(options.gpuv2);
+
+    freq_table_read(&options.freq_table, &mut gpu)?;
+
+    if let Some(config) = &options.config {
+        let mut delta = read_config_delta_from(config, options.mode.as_deref())?;
+        if options.mode.is_some() {
+            delta.mode = options.mode.clone();
+        }
+        gpu.apply_config_delta(&delta);
+    }
+
+    // 与守护进程一致，从频率表第一项开始
+    gpu.set_cur_freq(gpu.get_freq_by_index(0));
+    gpu.frequency_mut().gen_cur_volt();
+    Ok(gpu)
+}
+
+fn run() -> Result<()> {
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        usage();
+    });
+
+    sysfs_mock::install(&[
+        GPUFREQ_VOLT,
+        GPUFREQ_OPP,
+        GPUFREQV2_VOLT,
+        GPUFREQV2_OPP,
+        MALI_DVFS_ENABLE,
+        DVFSRC_V1_PATH,
+        DVFSRC_V2_PATH_1,
+        DVFSRC_V2_PATH_2,
+    ]);
+
+    let samples = read_trace(&options.trace)?;
+    let mut gpu = build_gpu(&options)?;
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create output: {path}"))?,
+        )),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+    writeln!(
+        out,
+        "timestamp_ms,load,freq,volt,ddr_opp,sampling_interval,writes"
+    )?;
+
+    let mut changes = 0usize;
+    let mut freq_sum = 0i64;
+    let mut last_freq = gpu.get_cur_freq();
+
+    for &(timestamp, load) in &samples {
+        FrequencyAdjustmentEngine::process_load(&mut gpu, load, timestamp)?;
+
+        let freq = gpu.get_cur_freq();
+        if freq != last_freq {
+            changes += 1;
+            last_freq = freq;
+        }
+        freq_sum += freq.khz();
+
+        let ddr_opp = if gpu.is_ddr_freq_fixed() {
+            gpu.ddr_manager().get_ddr_freq()
+        } else {
+            DDR_AUTO_MODE_V1
+        };
+        writeln!(
+            out,
+            "{timestamp},{load},{freq},{},{ddr_opp},{},{}",
+            gpu.frequency().cur_volt,
+            gpu.frequency_strategy.get_sampling_interval(),
+            sysfs_mock::take_writes().len()
+        )?;
+    }
+    out.flush()?;
+
+    eprintln!(
+        "Replayed {} samples: {changes} frequency changes, average {}KHz",
+        samples.len(),
+        freq_sum / samples.len() as i64
+    );
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("trace-replay: {e:#}");
+        process::exit(1);
+    }
+}
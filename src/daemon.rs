@@ -0,0 +1,510 @@
+//! 守护进程启动流程
+//!
+//! 初始化GPU和配置、启动监控线程并运行调频主循环。可执行文件只负责解析命令行参数，
+//! 其余逻辑都在这里，便于其他工具复用核心库。
+
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use log::{debug, error, info, warn};
+
+use crate::{
+    datasource::{
+        battery_saver::monitor_battery_saver,
+        config_parser::{
+            ConfigDelta, ForegroundConfig, load_config, read_config_delta, read_section,
+        },
+        control::{ControlCommand, monitor_control_commands},
+        device_profile::{active_config_path, log_active_config},
+        display_monitor::monitor_display_state,
+        file_path::*,
+        foreground_app::monitor_foreground_app,
+        foreground_provider::ForegroundDetector,
+        freq_table::gpufreq_table_init,
+        freq_table_parser::freq_table_read,
+        gpu_limiter::monitor_gpu_limiters,
+        load_monitor::utilization_init,
+        node_monitor::{monitor_custom_config, monitor_freq_table_config},
+        power_supply::monitor_charging_state,
+    },
+    model::{
+        calibration::apply_saved_calibration, frequency_manager::FrequencyManager, gpu::GPU,
+        stress_test::run_stress_test,
+    },
+    utils::{
+        constants::{self, strategy},
+        crash_report::write_crash_report,
+        file_status::get_status,
+        log_level_manager::start_unified_log_level_monitor,
+        logger::init_logger,
+        mode_list::write_mode_list,
+        node_probe::probe_controlled_nodes,
+        shutdown::{acknowledge_shutdown, clear_shutdown_handshake, install_signal_handlers},
+        stats::record_start,
+    },
+};
+
+/// 等待监控线程启动的最长时间
+const THREAD_START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 初始化GPU配置
+fn initialize_gpu_config(gpu: &mut GPU) -> Result<()> {
+    // 负载监控节点的检测与频率表、驱动检测互不依赖，并行进行
+    let (load_probe, table_result) = thread::scope(|s| {
+        let load_probe = s.spawn(|| {
+            utilization_init()?;
+            Ok::<_, anyhow::Error>(get_status(DEBUG_DVFS_LOAD) || get_status(DEBUG_DVFS_LOAD_OLD))
+        });
+        let table_result = load_freq_table_and_config(gpu);
+        let load_probe = load_probe
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Load monitor probe thread panicked")));
+        (load_probe, table_result)
+    });
+    let precise = load_probe?;
+    table_result?;
+
+    // 剔除校准时发现的损坏档位
+    apply_saved_calibration(gpu);
+
+    // 检查受控节点是否可写，不可写的节点从写入路径中排除
+    probe_controlled_nodes(gpu);
+
+    // 设置精确模式
+    gpu.set_precise(precise);
+
+    Ok(())
+}
+
+/// 读取频率表和策略配置，并检测GPU驱动
+fn load_freq_table_and_config(gpu: &mut GPU) -> Result<()> {
+    // 读取频率表配置文件
+    if fs::exists(FREQ_TABLE_CONFIG_FILE)? {
+        info!("Reading frequency table config file: {FREQ_TABLE_CONFIG_FILE}");
+        freq_table_read(FREQ_TABLE_CONFIG_FILE, gpu)
+            .map_err(|e| anyhow::anyhow!("Failed to read frequency table config file: {}", e))?;
+    } else {
+        return Err(anyhow::anyhow!(
+            "Frequency table config file not found: {}",
+            FREQ_TABLE_CONFIG_FILE
+        ));
+    }
+
+    // 尝试加载TOML策略配置
+    log_active_config("startup");
+    let config_file = active_config_path();
+    if fs::exists(config_file)? {
+        info!("Reading TOML config file: {config_file}");
+        if let Err(e) = load_config(gpu, None) {
+            warn!("Failed to load TOML config: {e}, using default settings");
+        }
+    } else {
+        warn!("TOML config file not found: {config_file}, using default settings");
+    }
+
+    // 初始化GPU频率表
+    gpufreq_table_init(gpu)
+}
+
+/// 启动监控线程
+///
+/// 每个线程启动后通过 `ready` 报告线程名并释放自己的发送端，所有线程都启动后通道断开
+fn start_monitoring_threads(
+    gpu: GPU,
+    tx: std::sync::mpsc::Sender<ConfigDelta>,
+    control_tx: std::sync::mpsc::Sender<ControlCommand>,
+    ready: Sender<&'static str>,
+) {
+    // 频率表配置监控线程
+    let gpu_clone2 = gpu.clone();
+    let tx_clone = tx.clone();
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(FREQ_TABLE_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, FREQ_TABLE_MONITOR_THREAD);
+            if let Err(e) = monitor_freq_table_config(gpu_clone2, tx_clone) {
+                error!("Frequency table config monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn frequency table config monitor thread");
+
+    // 前台应用监控线程（延迟启动，出错或panic后自动重启）
+    let gpu_clone = gpu.clone();
+    let tx_clone = tx.clone(); // 克隆 sender 用于前台应用监控
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(FOREGROUND_APP_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, FOREGROUND_APP_THREAD);
+            info!(
+                "Foreground app monitor will start in {} seconds",
+                strategy::FOREGROUND_APP_STARTUP_DELAY
+            );
+            thread::sleep(Duration::from_secs(strategy::FOREGROUND_APP_STARTUP_DELAY));
+            info!("Starting foreground app monitor now");
+            supervise_foreground_app(gpu_clone, tx_clone);
+        })
+        .expect("Failed to spawn foreground app monitor thread");
+
+    // 统一的日志等级监控线程
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(LOG_LEVEL_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, LOG_LEVEL_MONITOR_THREAD);
+            if let Err(e) = start_unified_log_level_monitor() {
+                error!("Unified log level monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn log level monitor thread");
+
+    // 自定义配置监控线程
+    let tx_clone = tx.clone();
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(CONFIG_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, CONFIG_MONITOR_THREAD);
+            if let Err(e) = monitor_custom_config(tx_clone) {
+                error!("Custom config monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn custom config monitor thread");
+
+    // 显示状态监控线程（折叠屏/外接显示器）
+    let tx_clone = tx.clone();
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(DISPLAY_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, DISPLAY_MONITOR_THREAD);
+            if let Err(e) = monitor_display_state(tx_clone) {
+                error!("Display state monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn display state monitor thread");
+
+    // 旁路充电状态监控线程
+    let tx_clone = tx.clone();
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(CHARGING_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, CHARGING_MONITOR_THREAD);
+            if let Err(e) = monitor_charging_state(tx_clone) {
+                error!("Charging state monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn charging state monitor thread");
+
+    // 系统省电模式监控线程
+    let tx_clone = tx.clone();
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(BATTERY_SAVER_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, BATTERY_SAVER_MONITOR_THREAD);
+            if let Err(e) = monitor_battery_saver(tx_clone) {
+                error!("Battery saver monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn battery saver monitor thread");
+
+    // 内核限频来源监控线程
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(LIMITER_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, LIMITER_MONITOR_THREAD);
+            if let Err(e) = monitor_gpu_limiters() {
+                error!("Limiter monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn limiter monitor thread");
+
+    // 控制命令监控线程
+    let started = ready.clone();
+    thread::Builder::new()
+        .name(CONTROL_MONITOR_THREAD.to_string())
+        .spawn(move || {
+            report_started(started, CONTROL_MONITOR_THREAD);
+            if let Err(e) = monitor_control_commands(control_tx) {
+                error!("Control command monitor error: {e}");
+            }
+        })
+        .expect("Failed to spawn control command monitor thread");
+}
+
+/// 运行前台应用监控，退出后重新启动
+///
+/// 检测状态保存在共享状态中，重启后从上一次检测到的应用继续，不会重新应用全局模式
+fn supervise_foreground_app(gpu: GPU, tx: std::sync::mpsc::Sender<ConfigDelta>) {
+    loop {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let provider =
+                ForegroundDetector::new(read_section::<ForegroundConfig>("foreground").provider);
+            monitor_foreground_app(gpu.clone(), Some(tx.clone()), provider)
+        }));
+        match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => error!("Foreground app monitor error: {e}"),
+            Err(_) => error!("Foreground app monitor panicked"),
+        }
+        warn!(
+            "Restarting foreground app monitor in {} seconds",
+            strategy::FOREGROUND_APP_RESTART_DELAY
+        );
+        thread::sleep(Duration::from_secs(strategy::FOREGROUND_APP_RESTART_DELAY));
+    }
+}
+
+/// 通知主线程监控线程已启动
+fn report_started(started: Sender<&'static str>, name: &'static str) {
+    let _ = started.send(name);
+}
+
+/// 等待所有监控线程报告启动，超时后不再等待
+fn wait_for_threads(ready: Receiver<&'static str>) {
+    let deadline = Instant::now() + THREAD_START_TIMEOUT;
+    loop {
+        match ready.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(name) => debug!("Thread started: {name}"),
+            Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => {
+                warn!(
+                    "Not all monitor threads started within {}s, continuing",
+                    THREAD_START_TIMEOUT.as_secs()
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// 显示系统信息
+fn display_system_info(gpu: &GPU) {
+    info!("Monitor Inited");
+    info!("{MAIN_THREAD} Start");
+
+    // 频率信息
+    info!("BootFreq: {}KHz", gpu.get_cur_freq());
+    info!(
+        "Driver: gpufreq{}",
+        if gpu.is_gpuv2() { "v2" } else { "v1" }
+    );
+    info!(
+        "Is Precise: {}",
+        if gpu.is_precise() { "Yes" } else { "No" }
+    );
+    info!("Max Freq: {}KHz", gpu.get_max_freq());
+    info!("Middle Freq: {}KHz", gpu.get_middle_freq());
+    info!("Min Freq: {}KHz", gpu.get_min_freq());
+    info!("Current Margin: {}%", gpu.get_margin());
+
+    // DCS信息
+    if gpu.is_gpuv2() {
+        info!(
+            "DCS: {}",
+            if gpu.is_dcs_enabled() {
+                "Enabled"
+            } else {
+                "Disabled"
+            }
+        );
+    }
+
+    // DDR频率信息
+    display_ddr_info(gpu);
+}
+
+/// 显示DDR相关信息
+fn display_ddr_info(gpu: &GPU) {
+    if gpu.is_ddr_freq_fixed() {
+        info!(
+            "DDR Frequency: Fixed at {}",
+            gpu.ddr_manager().get_ddr_freq()
+        );
+    } else {
+        info!("DDR Frequency: Auto mode");
+    }
+
+    match gpu.ddr_manager().get_ddr_freq_table() {
+        Ok(freq_table) => {
+            info!("Available DDR frequency options:");
+            for (i, entry) in freq_table.iter().enumerate().take(3) {
+                info!(
+                    "  Option {}: OPP={}, Description: {}",
+                    i + 1,
+                    entry.opp,
+                    entry.description
+                );
+            }
+            if freq_table.len() > 3 {
+                info!("  ... and {} more options", freq_table.len() - 3);
+            }
+        }
+        Err(e) => {
+            warn!("Failed to get DDR frequency table: {e}");
+        }
+    }
+
+    if gpu.is_gpuv2() {
+        let ddr_freqs = gpu.ddr_manager().get_ddr_v2_supported_freqs();
+        if !ddr_freqs.is_empty() {
+            info!("V2 driver supported DDR frequencies: {ddr_freqs:?}");
+        }
+
+        let gpu_freqs = gpu.get_v2_supported_freqs();
+        if !gpu_freqs.is_empty() {
+            info!("V2 driver supported GPU frequencies: {gpu_freqs:?}");
+        }
+    }
+}
+
+/// `--stress-test`：依次切换所有档位并监视驱动错误，输出通过/失败矩阵
+///
+/// 直接写入频率节点，需先停止守护进程；结束后将频率控制交还内核
+pub fn stress_test() -> Result<()> {
+    let mut gpu = GPU::new();
+    initialize_gpu_config(&mut gpu)?;
+
+    println!(
+        "Stress testing {} OPPs, this may take a while...",
+        gpu.get_config_list().len()
+    );
+    let result = run_stress_test(&mut gpu);
+
+    if let Err(e) = gpu.frequency().release_to_kernel() {
+        eprintln!("Failed to release GPU frequency control: {e}");
+    }
+    FrequencyManager::restore_dvfs();
+
+    let report = result?;
+    println!("{}", report.matrix());
+    println!("Report saved to {STRESS_TEST_PATH}");
+    if report.failed() > 0 {
+        return Err(anyhow::anyhow!("{} OPP(s) failed", report.failed()));
+    }
+    Ok(())
+}
+
+/// `--status`：输出守护进程写入的运行状态后退出
+pub fn print_status() -> ! {
+    match fs::read_to_string(STATUS_JSON_PATH) {
+        Ok(status) => {
+            println!("{}", status.trim_end());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Failed to read {STATUS_JSON_PATH}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 运行守护进程，`launched` 为进程启动时间，用于统计启动耗时
+///
+/// 依次初始化日志、GPU驱动和配置，启动各监控线程后进入调频主循环，
+/// 主循环退出后将频率控制交还内核
+pub fn run(launched: Instant) -> Result<()> {
+    // 设置主线程名称（使用pthread_setname_np）
+    unsafe {
+        let name = std::ffi::CString::new(MAIN_THREAD).unwrap();
+        let result = libc::pthread_setname_np(libc::pthread_self(), name.as_ptr());
+        if result != 0 {
+            eprintln!("Warning: Failed to set main thread name: {result}");
+        }
+    }
+
+    // 初始化日志
+    init_logger()?;
+
+    // panic时恢复DVFS，避免GPU停留在手动调频状态
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        error!("Governor panicked: {info}");
+        match write_crash_report(info) {
+            Ok(path) => error!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {e}"),
+        }
+        FrequencyManager::restore_dvfs();
+        log::logger().flush();
+        default_hook(info);
+    }));
+    install_signal_handlers();
+    clear_shutdown_handshake();
+
+    let restarts = record_start();
+    if restarts > 0 {
+        warn!("Governor restarted {restarts} time(s) since boot");
+    }
+
+    // 版本信息写入到日志文件
+    info!("{}", constants::NOTES);
+    info!("{}", constants::AUTHOR);
+    info!("{}", constants::SPECIAL);
+    info!("{}", constants::VERSION);
+
+    // 初始化GPU
+    let mut gpu = GPU::new();
+    info!("Loading");
+
+    // 初始化GPU配置
+    initialize_gpu_config(&mut gpu)?;
+
+    if let Err(e) = write_mode_list() {
+        warn!("Failed to write mode list: {e}");
+    }
+
+    // 启动监控线程
+    let (tx, rx) = std::sync::mpsc::channel::<ConfigDelta>();
+    let (control_tx, control_rx) = std::sync::mpsc::channel::<ControlCommand>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel::<&'static str>();
+    start_monitoring_threads(gpu.clone(), tx, control_tx, ready_tx);
+
+    // 发送一次初始配置增量（非必须，保证与初始化加载一致）
+    if let Ok(delta) = read_config_delta(None) {
+        gpu.apply_config_delta(&delta);
+    }
+
+    // 等待线程启动
+    wait_for_threads(ready_rx);
+
+    // 初始化频率和电压
+    gpu.set_cur_freq(gpu.get_freq_by_index(0));
+    gpu.frequency_mut().gen_cur_volt();
+
+    // 显示系统信息
+    display_system_info(&gpu);
+
+    info!(
+        "Advanced GPU Governor Started ({}ms after launch)",
+        launched.elapsed().as_millis()
+    );
+
+    // 开始频率调整
+    let result = gpu.adjust_gpufreq_with_updates(rx, control_rx);
+    if let Err(e) = &result {
+        error!("Adjustment loop exited with error: {e}");
+    }
+
+    // 无论以何种方式退出，都将频率控制交还内核
+    let restore = gpu.frequency().release_to_kernel();
+    if let Err(e) = &restore {
+        warn!("Failed to release GPU frequency control: {e}");
+    }
+    gpu.devfreq_clamp.release();
+    FrequencyManager::restore_dvfs();
+    acknowledge_shutdown(restore.map_err(|e| e.to_string()));
+    info!("Advanced GPU Governor Stopped");
+    log::logger().flush();
+
+    result
+}
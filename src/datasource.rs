@@ -1,7 +1,26 @@
+pub mod bandwidth_monitor;
+pub mod battery_saver;
 pub mod config_parser;
+pub mod control;
+pub mod device_profile;
+pub mod display_monitor;
+pub mod driver_quirks;
+pub mod dumpsys;
 pub mod file_path;
 pub mod foreground_app;
+pub mod foreground_provider;
 pub mod freq_table;
 pub mod freq_table_parser;
+pub mod game_discovery;
+pub mod gpu_limiter;
+pub mod kmsg;
 pub mod load_monitor;
 pub mod node_monitor;
+pub mod package_id;
+pub mod power_supply;
+pub mod suspend;
+pub mod system_server;
+pub mod thermal;
+pub mod update_activity;
+pub mod voltage;
+pub mod workload_classifier;
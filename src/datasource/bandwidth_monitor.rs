@@ -0,0 +1,206 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{datasource::file_path::*, utils::file_operate::read_file};
+
+/// 两次实际读取之间的最小间隔，避免每个采样周期都读取EMI节点
+const MIN_READ_INTERVAL: Duration = Duration::from_millis(100);
+/// 未配置峰值时，建立观测峰值需要的采样数，之前不计算利用率
+const PEAK_WARMUP_SAMPLES: u32 = 50;
+
+/// 带宽汇总行的标签和数值（如 `EMI_TOTAL_BW: 1234`），数值紧跟标签后的分隔符
+static BANDWIDTH_FIELD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)([a-z_ ]*bw[a-z_ ]*)[:=]\s*(\d+)").unwrap());
+
+/// 内存带宽监控器 - 读取MTK EMI带宽计数，作为DDR策略的第二个维度
+#[derive(Clone, Debug)]
+pub struct BandwidthMonitor {
+    /// 可用的带宽节点
+    node: Option<String>,
+    /// 观测到的最大带宽（MB/s），未配置峰值时作为利用率的分母
+    observed_peak: u64,
+    /// 最近一次读取之前的观测峰值，当前采样不参与自身利用率的分母
+    previous_peak: u64,
+    /// 实际读取的次数
+    samples: u32,
+    last_read: Option<Instant>,
+    last_value: u64,
+}
+
+impl BandwidthMonitor {
+    /// 创建未绑定节点的监控器
+    pub fn new() -> Self {
+        Self {
+            node: None,
+            observed_peak: 0,
+            previous_peak: 0,
+            samples: 0,
+            last_read: None,
+            last_value: 0,
+        }
+    }
+
+    /// 探测可用的EMI带宽节点
+    pub fn probe() -> Self {
+        let mut monitor = Self::new();
+        monitor.node = find_bandwidth_node();
+        match &monitor.node {
+            Some(node) => info!("EMI bandwidth node: {node}"),
+            None => info!("No EMI bandwidth node found, bandwidth-aware DDR policy unavailable"),
+        }
+        monitor
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.node.is_some()
+    }
+
+    /// 读取当前带宽（MB/s），读取间隔内返回缓存值
+    pub fn read_bandwidth(&mut self) -> Option<u64> {
+        let node = self.node.as_ref()?;
+
+        if let Some(last) = self.last_read
+            && last.elapsed() < MIN_READ_INTERVAL
+        {
+            return Some(self.last_value);
+        }
+
+        let content = match read_file(node, 4096) {
+            Ok(content) => content,
+            Err(e) => {
+                debug!("Failed to read EMI bandwidth from {node}: {e}");
+                return None;
+            }
+        };
+
+        let value = parse_bandwidth(&content)?;
+        self.record_sample(value);
+        debug!("EMI bandwidth: {value}MB/s (peak {})", self.observed_peak);
+        Some(value)
+    }
+
+    fn record_sample(&mut self, value: u64) {
+        self.last_read = Some(Instant::now());
+        self.last_value = value;
+        self.samples = self.samples.saturating_add(1);
+        self.previous_peak = self.observed_peak;
+        self.observed_peak = self.observed_peak.max(value);
+    }
+
+    /// 计算带宽利用率百分比，`configured_peak` 为0时使用之前采样观测到的峰值
+    ///
+    /// 观测峰值需要先积累一段采样：否则首个采样和每个新的最大值都会算成100%，
+    /// 总是触发DDR提升
+    pub fn utilization(&mut self, configured_peak: u64) -> Option<u32> {
+        let value = self.read_bandwidth()?;
+        let peak = if configured_peak > 0 {
+            configured_peak
+        } else if self.samples < PEAK_WARMUP_SAMPLES {
+            return None;
+        } else {
+            self.previous_peak
+        };
+        if peak == 0 {
+            return None;
+        }
+        Some((value.saturating_mul(100) / peak).min(100) as u32)
+    }
+}
+
+impl Default for BandwidthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 查找EMI带宽节点：优先使用已知的dvfsrc调试节点，其次扫描平台设备目录
+fn find_bandwidth_node() -> Option<String> {
+    for path in [DVFSRC_V1_DUMP, DVFSRC_V2_DUMP_1, DVFSRC_V2_DUMP_2] {
+        if let Ok(content) = read_file(path, 8192)
+            && parse_bandwidth(&content).is_some()
+        {
+            return Some(path.to_string());
+        }
+    }
+
+    let entries = fs::read_dir(PLATFORM_DEVICES_DIR).ok()?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if !name.contains("emi") {
+            continue;
+        }
+        let Ok(files) = fs::read_dir(entry.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let file_name = file.file_name().to_string_lossy().to_lowercase();
+            let path = file.path();
+            if file_name.contains("bw")
+                && Path::new(&path).is_file()
+                && let Ok(content) = read_file(&path, 4096)
+                && parse_bandwidth(&content).is_some()
+            {
+                return Some(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    None
+}
+
+/// 解析带宽数值：优先匹配标签含 "bw" 且含 "total" 或 "emi" 的字段（如 `EMI_TOTAL_BW: 1234`），
+/// 取标签分隔符后的数值而不是行内第一个数字（dvfsrc 输出的行首常是索引或OPP编号）；
+/// 否则接受只包含单个数字的节点内容
+fn parse_bandwidth(content: &str) -> Option<u64> {
+    for line in content.lines() {
+        for field in BANDWIDTH_FIELD.captures_iter(line) {
+            let label = field[1].to_lowercase();
+            if (label.contains("total") || label.contains("emi"))
+                && let Ok(value) = field[2].parse::<u64>()
+            {
+                return Some(value);
+            }
+        }
+    }
+
+    content.trim().parse::<u64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_value_after_label() {
+        assert_eq!(parse_bandwidth("EMI_TOTAL_BW: 1234 MB/s"), Some(1234));
+        assert_eq!(parse_bandwidth("[3] opp 2 total_bw=5120"), Some(5120));
+        assert_eq!(parse_bandwidth("4096\n"), Some(4096));
+    }
+
+    #[test]
+    fn ignores_numbers_outside_the_bandwidth_field() {
+        assert_eq!(parse_bandwidth("OPP 4 vcore 650000\nDDR: 3200"), None);
+        assert_eq!(parse_bandwidth("opp_bw_level: 3\nEMI BW: 2048"), Some(2048));
+    }
+
+    #[test]
+    fn observed_peak_needs_warmup_and_excludes_current_sample() {
+        let mut monitor = BandwidthMonitor::new();
+        monitor.node = Some(String::new());
+        // 读取间隔内 utilization 使用最近一次记录的采样
+        monitor.record_sample(1000);
+        assert_eq!(monitor.utilization(0), None);
+        for _ in 1..PEAK_WARMUP_SAMPLES {
+            monitor.record_sample(500);
+        }
+        assert_eq!(monitor.utilization(0), Some(50));
+        assert_eq!(monitor.utilization(2000), Some(25));
+    }
+}
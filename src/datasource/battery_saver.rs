@@ -0,0 +1,89 @@
+//! 系统省电模式
+//!
+//! 轮询 `settings get global low_power` 跟踪系统省电模式，开启时按 `[battery_saver]`
+//! 配置将模式参数降为省电模式，避免调速器与系统省电策略相互抵消。
+//! 原生进程无法注册 ContentObserver，因此以轮询代替设置监听；未启用时只读取配置，不启动子进程。
+
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::{debug, info, warn};
+
+use crate::{
+    datasource::{
+        config_parser::{BatterySaverConfig, ConfigDelta, read_config_delta, read_section},
+        display_monitor::read_active_mode,
+        file_path::BATTERY_SAVER_MONITOR_THREAD,
+    },
+    utils::status_report::update_status,
+};
+
+/// 省电模式设置的轮询间隔
+const BATTERY_SAVER_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+static BATTERY_SAVER: AtomicBool = AtomicBool::new(false);
+
+/// 系统省电模式是否开启（未启用 `[battery_saver]` 时始终为 `false`）
+pub fn is_battery_saver_active() -> bool {
+    BATTERY_SAVER.load(Ordering::Relaxed)
+}
+
+/// 读取系统省电模式设置
+fn read_low_power_setting() -> Result<bool> {
+    let output = Command::new("settings")
+        .args(["get", "global", "low_power"])
+        .output()
+        .context("Failed to run settings")?;
+    if !output.status.success() {
+        return Err(anyhow!("settings exited with {}", output.status));
+    }
+    // 从未开启过省电模式时输出 null
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// 监控系统省电模式，变化时发送配置增量以切换 `[battery_saver]` 配置
+pub fn monitor_battery_saver(tx: Sender<ConfigDelta>) -> Result<()> {
+    info!("{BATTERY_SAVER_MONITOR_THREAD} Start");
+
+    loop {
+        let active = if read_section::<BatterySaverConfig>("battery_saver").enabled {
+            match read_low_power_setting() {
+                Ok(active) => active,
+                Err(e) => {
+                    debug!("Failed to read battery saver setting: {e}");
+                    is_battery_saver_active()
+                }
+            }
+        } else {
+            false
+        };
+
+        if active != BATTERY_SAVER.swap(active, Ordering::Relaxed) {
+            info!("Battery saver state changed: {active}");
+            update_status(|s| s.battery_saver = active);
+
+            let mode = read_active_mode();
+            match read_config_delta(mode.as_deref()) {
+                Ok(mut delta) => {
+                    if mode.is_some() {
+                        delta.mode = mode;
+                    }
+                    if tx.send(delta).is_err() {
+                        warn!("Failed to send battery saver config delta");
+                    }
+                }
+                Err(e) => debug!("Failed to read config delta for battery saver change: {e}"),
+            }
+        }
+
+        thread::sleep(BATTERY_SAVER_POLL_INTERVAL);
+    }
+}
@@ -1,40 +1,655 @@
-use std::fs;
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use log::{debug, info, warn};
-use serde::Deserialize;
+use serde::{Deserialize, de::DeserializeOwned};
 
 use crate::{
-    datasource::file_path::{CONFIG_TOML_FILE, CURRENT_MODE_PATH},
-    model::gpu::GPU,
-    utils::file_operate::write_file,
+    datasource::{
+        battery_saver::is_battery_saver_active, device_profile::active_config_path,
+        display_monitor::current_display_state, freq_table_parser::freq_table_generation,
+        power_supply::is_charge_bypass_active,
+    },
+    model::{config_apply::apply_delta, gpu::GPU, mode_state::ModeSource},
 };
 
-#[derive(Deserialize, Clone)]
+/// 游戏转入后台但仍有前台服务时使用的模式名
+pub const GAME_BACKGROUND_MODE: &str = "game_background";
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     global: Global,
-    powersave: ModeParams,
-    balance: ModeParams,
-    performance: ModeParams,
-    fast: ModeParams,
+    /// 各模式共用的基础参数（`[default_mode]`）
+    #[serde(default)]
+    default_mode: ModeParams,
+    /// 各模式只需填写与 `[default_mode]` 不同的参数，整段省略时完全继承
+    #[serde(default)]
+    powersave: ModeOverrides,
+    #[serde(default)]
+    balance: ModeOverrides,
+    #[serde(default)]
+    performance: ModeOverrides,
+    #[serde(default)]
+    fast: ModeOverrides,
+    /// 游戏转入后台但仍有前台服务（如下载资源）时的模式（`[game_background]`），
+    /// 在省电模式的基础上覆盖，整段省略时不启用
+    #[serde(default)]
+    game_background: Option<ModeOverrides>,
+    #[serde(default)]
+    ddr: DdrConfig,
+    #[serde(default)]
+    load_source: LoadSourceConfig,
+    #[serde(default)]
+    charging: ChargingConfig,
+    #[serde(default)]
+    battery_saver: BatterySaverConfig,
+    #[serde(default)]
+    foreground: ForegroundConfig,
+    #[serde(default)]
+    permissions: PermissionsConfig,
+    #[serde(default)]
+    gpufreqv2: V2TuningConfig,
+    #[serde(default)]
+    write_delay: WriteDelayConfig,
+    #[serde(default)]
+    mode_notify: ModeNotifyConfig,
+    #[serde(default)]
+    audit: AuditConfig,
+    #[serde(default)]
+    idle: IdleConfig,
+    /// 按显示状态覆盖模式参数（`[display.unfolded]`、`[display.external]`、`[display.casting]`、
+    /// `[display.120hz]`），多个同时匹配时按刷新率、展开、外接显示器、无线投屏的顺序叠加，后者优先
+    #[serde(default)]
+    display: HashMap<String, ModeOverrides>,
 }
 
 impl Config {
     pub fn global_mode(&self) -> &str {
         &self.global.mode
     }
+
+    /// 游戏离开前台后保持游戏模式的宽限期
+    pub fn game_exit_grace(&self) -> Duration {
+        Duration::from_millis(self.global.game_exit_grace_ms)
+    }
+
+    /// 是否配置了游戏后台模式
+    pub fn has_game_background_mode(&self) -> bool {
+        self.game_background.is_some()
+    }
+
+    /// `[foreground]` 配置，附带前台监控线程需要的游戏退出宽限期和游戏后台模式开关
+    pub fn foreground_config(&self) -> ForegroundConfig {
+        ForegroundConfig {
+            game_exit_grace: self.game_exit_grace(),
+            game_background: self.has_game_background_mode(),
+            ..self.foreground.clone()
+        }
+    }
+
+    /// 指定模式段的覆盖项，非法模式返回None
+    pub fn mode_overrides(&self, mode: &str) -> Option<&ModeOverrides> {
+        match mode {
+            "powersave" => Some(&self.powersave),
+            "balance" => Some(&self.balance),
+            "performance" => Some(&self.performance),
+            "fast" => Some(&self.fast),
+            _ => None,
+        }
+    }
+
+    /// 解析指定模式的参数：在 `[default_mode]` 基础上叠加该模式的覆盖项，非法模式返回None
+    fn mode_params(&self, mode: &str) -> Option<ModeParams> {
+        if mode == GAME_BACKGROUND_MODE {
+            let powersave = self.mode_params("powersave")?;
+            return self
+                .game_background
+                .as_ref()
+                .map(|overrides| overrides.resolve(&powersave));
+        }
+        self.mode_overrides(mode)
+            .map(|overrides| overrides.resolve(&self.default_mode))
+    }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct Global {
     mode: String,
     idle_threshold: i32,
+    /// 根据负载源空闲时上报的负载下限自动提高空闲阈值
+    #[serde(default)]
+    adaptive_idle: bool,
+    /// 自适应空闲阈值的上限
+    #[serde(default = "default_adaptive_idle_max")]
+    adaptive_idle_max: i32,
+    /// 频率节点连续写入失败时进入错误状态并切换到旁路模式
+    #[serde(default)]
+    strict_writes: bool,
+    /// 严格写入模式下允许的连续写入失败次数
+    #[serde(default = "default_write_failure_limit")]
+    write_failure_limit: u32,
+    /// 刷新率缩放的基准刷新率，模式参数按该刷新率填写
+    #[serde(default = "default_reference_refresh_rate")]
+    reference_refresh_rate: u32,
+    /// 游戏离开前台后恢复全局模式前的宽限期（毫秒），0表示立即恢复
+    #[serde(default = "default_game_exit_grace_ms")]
+    game_exit_grace_ms: u64,
+    /// 游戏模式下允许选择会触发DCS核心缩减的频率
+    #[serde(default)]
+    allow_dcs_in_game: bool,
+    /// 目标频率落入DCS核心缩减区间时的处理方式
+    #[serde(default)]
+    dcs_snap_policy: DcsSnapPolicy,
+    /// 受控节点被其他工具修改时的处理方式
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
+    /// 系统更新或应用安装期间进入旁路模式，默认关闭
+    #[serde(default)]
+    update_bypass: bool,
+    /// 内核日志出现GPU故障时回退到内核调频，并将当前频率表标记为可疑
+    #[serde(default)]
+    fault_rollback: bool,
+    /// 游戏模式下同时设置Mali devfreq的频率下限，固定档位写入被拒绝时内核DVFS也不会降得过低
+    #[serde(default)]
+    devfreq_clamp: bool,
+    /// 启动后的预热时长（秒），期间使用更高的余量和更短的防抖时间，0表示不预热
+    #[serde(default)]
+    warmup_seconds: u64,
+    /// 在频率表最高档位持续满载时允许请求驱动支持的最高频率（可能高于频率表上限）
+    #[serde(default)]
+    allow_emergency_stock_max: bool,
+    /// 触发紧急升频所需的连续满载采样数
+    #[serde(default = "default_emergency_ramp_samples")]
+    emergency_ramp_samples: u32,
+}
+
+/// 目标频率落入DCS核心缩减区间时的处理方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DcsSnapPolicy {
+    /// 仅在游戏模式下提升到不触发DCS的最低频率（`allow_dcs_in_game` 可关闭）
+    #[default]
+    Auto,
+    /// 始终提升到不触发DCS的最低频率，适用于DCS生效时界面闪烁的设备
+    SnapUp,
+    /// 始终允许DCS，游戏模式下也不提升
+    Allow,
+}
+
+/// 受控节点被外部修改时的处理方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 仅记录日志
+    #[default]
+    Log,
+    /// 重新写入调速器的值
+    Reassert,
+    /// 暂时让出控制权，一段时间后再恢复调频
+    BackOff,
+}
+
+/// 默认的游戏退出宽限期（毫秒）
+pub const DEFAULT_GAME_EXIT_GRACE_MS: u64 = 3000;
+
+fn default_game_exit_grace_ms() -> u64 {
+    DEFAULT_GAME_EXIT_GRACE_MS
+}
+
+/// 默认的紧急升频触发采样数
+pub const DEFAULT_EMERGENCY_RAMP_SAMPLES: u32 = 50;
+
+fn default_emergency_ramp_samples() -> u32 {
+    DEFAULT_EMERGENCY_RAMP_SAMPLES
+}
+
+fn default_adaptive_idle_max() -> i32 {
+    12
+}
+
+fn default_write_failure_limit() -> u32 {
+    10
+}
+
+fn default_reference_refresh_rate() -> u32 {
+    60
+}
+
+/// 游戏模式下DDR档位的选择依据
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DdrPolicy {
+    /// 按频率表中GPU频率对应的DDR档位
+    #[default]
+    Freq,
+    /// 按负载区间选择DDR档位
+    LoadZone,
+}
+
+/// DDR策略配置（`[ddr]`），所有字段均可省略
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DdrConfig {
+    /// 内存带宽饱和时提升一档DDR频率
+    pub bandwidth_boost: bool,
+    /// 判定带宽饱和的利用率阈值（百分比）
+    pub bandwidth_threshold: u32,
+    /// 峰值带宽（MB/s），0表示使用运行期间观测到的最大值
+    pub bandwidth_peak: u64,
+    pub policy: DdrPolicy,
+    /// 负载区间分界阈值（百分比），N个阈值划分出N+1个区间
+    pub zone_thresholds: Vec<i32>,
+    /// 各负载区间对应的DDR档位，从低负载到高负载排列，999表示自动
+    pub zone_opps: Vec<i64>,
+    /// 降低负载区间前需要低于阈值的回差（百分比）
+    pub zone_hysteresis: i32,
+    /// GPU温度过高时将固定的DDR档位降低一档
+    pub thermal_derate: bool,
+    /// 触发降档的GPU温度（摄氏度）
+    pub thermal_threshold: i32,
+    /// 恢复原档位前需要低于阈值的回差（摄氏度）
+    pub thermal_hysteresis: i32,
+    /// 自动模式写入DDR节点的值，省略时在初始化时探测（999或-1）
+    pub auto_value: Option<i64>,
+}
+
+impl Default for DdrConfig {
+    fn default() -> Self {
+        Self {
+            bandwidth_boost: false,
+            bandwidth_threshold: 85,
+            bandwidth_peak: 0,
+            policy: DdrPolicy::Freq,
+            zone_thresholds: vec![30, 60, 85],
+            zone_opps: vec![999, 2, 1, 0],
+            zone_hysteresis: 5,
+            thermal_derate: false,
+            thermal_threshold: 75,
+            thermal_hysteresis: 5,
+            auto_value: None,
+        }
+    }
+}
+
+/// 旁路充电配置（`[charging]`），充电器在线且电池闲置时提升调频激进程度
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ChargingConfig {
+    pub enabled: bool,
+    /// 仅在游戏模式下生效
+    pub gaming_only: bool,
+    /// 在模式margin基础上额外增加的余量
+    pub margin_boost: i64,
+    /// DDR档位下限（OPP索引，数值越小频率越高），仅游戏模式下生效
+    pub ddr_floor: Option<i64>,
+}
+
+impl Default for ChargingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gaming_only: true,
+            margin_boost: 10,
+            ddr_floor: None,
+        }
+    }
+}
+
+/// 系统省电模式配置（`[battery_saver]`），系统开启省电模式时改用指定模式的参数
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct BatterySaverConfig {
+    pub enabled: bool,
+    /// 省电模式开启时使用其参数的模式
+    pub mode: String,
+    /// 游戏模式下保持原模式的参数
+    pub skip_gaming: bool,
+}
+
+impl Default for BatterySaverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: "powersave".to_string(),
+            skip_gaming: true,
+        }
+    }
+}
+
+/// gpufreqv2驱动的可选调节项（`[gpufreqv2]`），未填写的项保持内核默认设置
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct V2TuningConfig {
+    /// 老化余量（aging margin）
+    pub aging: Option<bool>,
+    /// 节能模式
+    pub eco: Option<bool>,
+}
+
+/// 模式切换通知配置（`[mode_notify]`）
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ModeNotifyConfig {
+    /// 模式变化时发送 `am broadcast`
+    pub broadcast: bool,
+    /// 广播的action
+    pub action: String,
+}
+
+impl Default for ModeNotifyConfig {
+    fn default() -> Self {
+        Self {
+            broadcast: false,
+            action: "com.gpugovernor.MODE_CHANGED".to_string(),
+        }
+    }
+}
+
+/// 频率节点写入间隔配置（`[write_delay]`），省略时使用校准结果或驱动默认值
+///
+/// gpufreqv2 默认在释放固定档位后等待10毫秒再写入电压，gpufreq（v1）默认不等待
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct WriteDelayConfig {
+    /// gpufreqv2 驱动的写入间隔（毫秒）
+    pub gpufreqv2_ms: Option<u64>,
+    /// gpufreq（v1）驱动的写入间隔（毫秒）
+    pub gpufreq_ms: Option<u64>,
+}
+
+/// 长时间空闲时的GPU断电辅助配置（`[idle]`），仅对提供电源控制节点的 gpufreqv2 内核生效
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// 空闲持续超过 `power_off_after_ms` 后允许GPU断电
+    pub power_off: bool,
+    /// 允许断电前需要持续空闲的时长（毫秒）
+    pub power_off_after_ms: u64,
+    /// 断电后首次出现负载时保持高频的时长（毫秒），用于掩盖上电延迟
+    pub exit_boost_ms: u64,
+    /// 退出断电时使用的频率（KHz），省略时使用最高频率
+    pub exit_boost_freq: Option<i64>,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            power_off: false,
+            power_off_after_ms: 10_000,
+            exit_boost_ms: 200,
+            exit_boost_freq: None,
+        }
+    }
+}
+
+/// 调频线程的调度策略
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RealtimePolicy {
+    /// 普通分时调度
+    #[default]
+    None,
+    /// `SCHED_FIFO`
+    Fifo,
+    /// `SCHED_RR`
+    Rr,
+}
+
+/// 调频线程实时调度配置（`[realtime]`），只在调频循环启动时读取
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RealtimeConfig {
+    pub policy: RealtimePolicy,
+    /// 实时优先级，超出内核允许范围时截断
+    pub priority: i32,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            policy: RealtimePolicy::None,
+            priority: 10,
+        }
+    }
+}
+
+/// 负载-频率曲线的一个断点（games.toml 的 `curve`）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurvePoint {
+    /// GPU负载（百分比）
+    pub load: i32,
+    /// 该负载下的目标频率（KHz）
+    pub freq: i64,
+}
+
+/// 游戏会话中的一个余量阶段（games.toml 的 `phases`）
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarginPhase {
+    /// 游戏进入前台多少秒后开始该阶段
+    pub after_secs: u64,
+    /// 该阶段使用的余量（百分比）
+    pub margin: i64,
+}
+
+/// 前台应用检测方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForegroundProviderKind {
+    /// 启动时探测可用的 dumpsys 方式，失败后回退到 cgroup
+    #[default]
+    Auto,
+    /// dumpsys activity lru
+    DumpsysLru,
+    /// dumpsys window 焦点窗口
+    DumpsysWindow,
+    /// top-app cgroup
+    Cgroup,
+}
+
+/// 游戏列表文件不存在时的处理方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingGamesAction {
+    /// 输出一次说明如何创建游戏列表
+    #[default]
+    Notice,
+    /// 根据已安装应用中的游戏引擎库生成初始游戏列表
+    Generate,
+    /// 不做处理
+    Ignore,
+}
+
+/// 前台应用检测配置（`[foreground]`）
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ForegroundConfig {
+    pub provider: ForegroundProviderKind,
+    /// games.toml 不存在时的处理方式
+    pub missing_games: MissingGamesAction,
+    /// 生成的初始游戏列表中各游戏使用的模式
+    pub starter_mode: String,
+    /// 前台检测完全忽略的包名（桌面、系统界面、输入法等），
+    /// 这些应用短暂获得焦点时视为前台未变化，不会结束游戏模式
+    pub ignore_packages: Vec<String>,
+    /// 根据负载识别未列入游戏列表的游戏（`[foreground.game_detection]`）
+    pub game_detection: GameDetectionConfig,
+    /// 游戏离开前台后保持游戏模式的宽限期，取自 `[global]`
+    #[serde(skip)]
+    pub game_exit_grace: Duration,
+    /// 是否配置了游戏后台模式（`[game_background]`）
+    #[serde(skip)]
+    pub game_background: bool,
+}
+
+/// 疑似游戏识别配置（`[foreground.game_detection]`）
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct GameDetectionConfig {
+    /// 未列入游戏列表的前台应用持续高负载时判定为疑似游戏，并提示添加到游戏列表
+    pub enabled: bool,
+    /// 判定窗口内的平均GPU负载阈值（百分比）
+    pub load_threshold: i32,
+    /// 判定窗口时长（秒）
+    pub duration_secs: u64,
+    /// 判定为疑似游戏后自动应用的模式，不填写时只输出建议
+    pub auto_mode: Option<String>,
+}
+
+impl Default for GameDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            load_threshold: 60,
+            duration_secs: 60,
+            auto_mode: None,
+        }
+    }
+}
+
+impl ForegroundConfig {
+    /// 包名是否在忽略列表中（不区分用户）
+    pub fn is_ignored(&self, package: &str) -> bool {
+        self.ignore_packages
+            .iter()
+            .any(|ignored| ignored == package)
+    }
+}
+
+impl Default for ForegroundConfig {
+    fn default() -> Self {
+        Self {
+            provider: ForegroundProviderKind::default(),
+            missing_games: MissingGamesAction::default(),
+            starter_mode: "balance".to_string(),
+            ignore_packages: vec!["com.android.systemui".to_string()],
+            game_detection: GameDetectionConfig::default(),
+            game_exit_grace: Duration::from_millis(DEFAULT_GAME_EXIT_GRACE_MS),
+            game_background: false,
+        }
+    }
+}
+
+/// sysfs写入审计配置（`[audit]`）
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// 记录每次sysfs写入的路径、内容、结果和耗时
+    pub enabled: bool,
+    /// 每秒最多记录的写入条数
+    pub max_per_second: u32,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_per_second: 50,
+        }
+    }
+}
+
+/// 单类文件的权限设置
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct FilePermission {
+    /// 文件权限位，如 `0o644`
+    pub mode: u32,
+    /// 文件属主（uid），不填写时保持不变
+    pub owner: Option<u32>,
+    /// 文件属组（gid），不填写时保持不变
+    pub group: Option<u32>,
+}
+
+impl Default for FilePermission {
+    fn default() -> Self {
+        Self {
+            mode: 0o644,
+            owner: None,
+            group: None,
+        }
+    }
+}
+
+/// 调速器生成文件的权限配置（`[permissions]`），按文件类别设置
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct PermissionsConfig {
+    /// 状态文件（current_mode、status.json）
+    pub state: FilePermission,
+    /// 控制命令的响应文件
+    pub control: FilePermission,
+    /// 写入文件时自动创建的目录
+    pub directory: FilePermission,
 }
 
-#[derive(Deserialize, Clone)]
+impl Default for PermissionsConfig {
+    fn default() -> Self {
+        Self {
+            state: FilePermission::default(),
+            control: FilePermission::default(),
+            directory: FilePermission {
+                mode: 0o755,
+                owner: None,
+                group: None,
+            },
+        }
+    }
+}
+
+/// 多个负载来源的合并方式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadCombine {
+    /// 按优先级使用第一个非零来源
+    #[default]
+    Fallback,
+    /// 取各来源的最大值
+    Max,
+    /// 按权重加权平均
+    Weighted,
+}
+
+/// 负载来源配置（`[load_source]`），所有字段均可省略
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoadSourceConfig {
+    pub combine: LoadCombine,
+    /// 每个周期最多采样的来源数量（按优先级选取可用来源）
+    pub max_sources: usize,
+    /// 加权平均时各来源的权重，按优先级顺序对应，缺省为1.0
+    pub weights: Vec<f64>,
+    /// `/proc/mtk_mali/utilization` 分别报告片元和计算负载时（Valhall），
+    /// 取两者中的较大值代替总体负载，计算密集的游戏中响应更快
+    pub job_slot_max: bool,
+}
+
+impl Default for LoadSourceConfig {
+    fn default() -> Self {
+        Self {
+            combine: LoadCombine::Fallback,
+            max_sources: 3,
+            weights: Vec::new(),
+            job_slot_max: false,
+        }
+    }
+}
+
+/// 模式参数（`[default_mode]`），所有字段均可省略
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct ModeParams {
     margin: i64,
     aggressive_down: bool,
+    /// 激进降频的负载下限（百分比），负载低于该值时跳过降频防抖并直接降到目标频率
+    aggressive_down_floor: i32,
     sampling_interval: u64,
     gaming_mode: bool,
     adaptive_sampling: bool,
@@ -42,65 +657,327 @@ pub struct ModeParams {
     max_adaptive_interval: u64,
     up_rate_delay: u64,
     down_rate_delay: u64,
+    /// 按当前刷新率缩放采样间隔和防抖时间
+    refresh_rate_scaling: bool,
+    /// 连续多少个高负载采样后忽略单个降频采样，0表示关闭
+    hold_samples: u32,
+    /// 写入电压上限（10微伏），不论频率表中的电压如何都不会超过该值
+    max_volt: Option<i64>,
+    /// 频率上限（KHz），调频目标不超过频率表中不高于该值的最高档位
+    max_freq: Option<i64>,
+}
+
+impl Default for ModeParams {
+    fn default() -> Self {
+        Self {
+            margin: 27,
+            aggressive_down: true,
+            aggressive_down_floor: 30,
+            sampling_interval: 8,
+            gaming_mode: false,
+            adaptive_sampling: false,
+            min_adaptive_interval: 2,
+            max_adaptive_interval: 20,
+            up_rate_delay: 500,
+            down_rate_delay: 500,
+            refresh_rate_scaling: false,
+            hold_samples: 0,
+            max_volt: None,
+            max_freq: None,
+        }
+    }
+}
+
+/// 采样间隔的允许范围（毫秒），为0时调频循环会空转占满CPU
+pub const SAMPLING_INTERVAL_RANGE: (u64, u64) = (1, 1000);
+/// 升降频防抖时间的允许范围（毫秒）
+const RATE_DELAY_RANGE: (u64, u64) = (0, 10_000);
+
+/// 将时间参数限制在允许范围内，超出时给出警告
+fn clamp_interval(name: &str, value: u64, (min, max): (u64, u64)) -> u64 {
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        warn!("{name}={value}ms is out of range [{min}, {max}], using {clamped}ms");
+    }
+    clamped
+}
+
+/// 校验采样间隔和防抖时间
+struct Intervals<'a> {
+    sampling_interval: &'a mut u64,
+    min_adaptive_interval: &'a mut u64,
+    max_adaptive_interval: &'a mut u64,
+    up_rate_delay: &'a mut u64,
+    down_rate_delay: &'a mut u64,
+}
+
+impl Intervals<'_> {
+    fn validate(self) {
+        *self.sampling_interval = clamp_interval(
+            "sampling_interval",
+            *self.sampling_interval,
+            SAMPLING_INTERVAL_RANGE,
+        );
+        *self.min_adaptive_interval = clamp_interval(
+            "min_adaptive_interval",
+            *self.min_adaptive_interval,
+            SAMPLING_INTERVAL_RANGE,
+        );
+        *self.max_adaptive_interval = clamp_interval(
+            "max_adaptive_interval",
+            *self.max_adaptive_interval,
+            SAMPLING_INTERVAL_RANGE,
+        );
+        if *self.max_adaptive_interval < *self.min_adaptive_interval {
+            warn!(
+                "max_adaptive_interval={}ms is below min_adaptive_interval={}ms, using {}ms",
+                self.max_adaptive_interval, self.min_adaptive_interval, self.min_adaptive_interval
+            );
+            *self.max_adaptive_interval = *self.min_adaptive_interval;
+        }
+        *self.up_rate_delay =
+            clamp_interval("up_rate_delay", *self.up_rate_delay, RATE_DELAY_RANGE);
+        *self.down_rate_delay =
+            clamp_interval("down_rate_delay", *self.down_rate_delay, RATE_DELAY_RANGE);
+    }
+}
+
+impl ConfigDelta {
+    fn validate_intervals(&mut self) {
+        Intervals {
+            sampling_interval: &mut self.sampling_interval,
+            min_adaptive_interval: &mut self.min_adaptive_interval,
+            max_adaptive_interval: &mut self.max_adaptive_interval,
+            up_rate_delay: &mut self.up_rate_delay,
+            down_rate_delay: &mut self.down_rate_delay,
+        }
+        .validate();
+    }
+}
+
+/// 按当前刷新率与基准刷新率的比例缩放采样间隔和防抖时间
+///
+/// 高刷新率下帧间隔更短，采样和防抖随之收紧；低刷新率下相应放宽
+fn scale_for_refresh_rate(delta: &mut ConfigDelta, reference: u32, refresh_rate: f64) {
+    if reference == 0 || refresh_rate <= 0.0 {
+        return;
+    }
+
+    let factor = reference as f64 / refresh_rate;
+    let scale = |value: u64| ((value as f64 * factor).round() as u64).max(1);
+    delta.sampling_interval = scale(delta.sampling_interval);
+    delta.min_adaptive_interval = scale(delta.min_adaptive_interval);
+    delta.max_adaptive_interval = scale(delta.max_adaptive_interval);
+    delta.up_rate_delay = scale(delta.up_rate_delay);
+    delta.down_rate_delay = scale(delta.down_rate_delay);
+    debug!("Scaled sampling/debounce by {factor:.2} for {refresh_rate:.0}Hz");
+}
+
+/// 模式参数覆盖项，未填写的字段保持原模式的值
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ModeOverrides {
+    pub margin: Option<i64>,
+    pub aggressive_down: Option<bool>,
+    pub aggressive_down_floor: Option<i32>,
+    pub sampling_interval: Option<u64>,
+    pub gaming_mode: Option<bool>,
+    pub adaptive_sampling: Option<bool>,
+    pub min_adaptive_interval: Option<u64>,
+    pub max_adaptive_interval: Option<u64>,
+    pub up_rate_delay: Option<u64>,
+    pub down_rate_delay: Option<u64>,
+    pub hold_samples: Option<u32>,
+    pub max_volt: Option<i64>,
+    pub max_freq: Option<i64>,
+    /// 仅在模式段中生效，显示状态覆盖中忽略
+    pub refresh_rate_scaling: Option<bool>,
+    /// 模式说明，键为语言（如 `zh`、`en`），仅用于生成前端模式列表
+    pub description: HashMap<String, String>,
+}
+
+impl ModeOverrides {
+    /// 将覆盖项应用到配置增量
+    pub fn apply_to(&self, delta: &mut ConfigDelta) {
+        if let Some(v) = self.margin {
+            delta.margin = v;
+        }
+        if let Some(v) = self.aggressive_down {
+            delta.aggressive_down = v;
+        }
+        if let Some(v) = self.aggressive_down_floor {
+            delta.aggressive_down_floor = v;
+        }
+        if let Some(v) = self.sampling_interval {
+            delta.sampling_interval = v;
+        }
+        if let Some(v) = self.gaming_mode {
+            delta.gaming_mode = v;
+        }
+        if let Some(v) = self.adaptive_sampling {
+            delta.adaptive_sampling = v;
+        }
+        if let Some(v) = self.min_adaptive_interval {
+            delta.min_adaptive_interval = v;
+        }
+        if let Some(v) = self.max_adaptive_interval {
+            delta.max_adaptive_interval = v;
+        }
+        if let Some(v) = self.up_rate_delay {
+            delta.up_rate_delay = v;
+        }
+        if let Some(v) = self.down_rate_delay {
+            delta.down_rate_delay = v;
+        }
+        if let Some(v) = self.hold_samples {
+            delta.hold_samples = v;
+        }
+        if let Some(v) = self.max_volt {
+            delta.max_volt = Some(v);
+        }
+        if let Some(v) = self.max_freq {
+            delta.max_freq = Some(v);
+        }
+    }
+
+    /// 以 `base` 为基础叠加覆盖项，得到完整的模式参数
+    fn resolve(&self, base: &ModeParams) -> ModeParams {
+        ModeParams {
+            margin: self.margin.unwrap_or(base.margin),
+            aggressive_down: self.aggressive_down.unwrap_or(base.aggressive_down),
+            aggressive_down_floor: self
+                .aggressive_down_floor
+                .unwrap_or(base.aggressive_down_floor),
+            sampling_interval: self.sampling_interval.unwrap_or(base.sampling_interval),
+            gaming_mode: self.gaming_mode.unwrap_or(base.gaming_mode),
+            adaptive_sampling: self.adaptive_sampling.unwrap_or(base.adaptive_sampling),
+            min_adaptive_interval: self
+                .min_adaptive_interval
+                .unwrap_or(base.min_adaptive_interval),
+            max_adaptive_interval: self
+                .max_adaptive_interval
+                .unwrap_or(base.max_adaptive_interval),
+            up_rate_delay: self.up_rate_delay.unwrap_or(base.up_rate_delay),
+            down_rate_delay: self.down_rate_delay.unwrap_or(base.down_rate_delay),
+            refresh_rate_scaling: self
+                .refresh_rate_scaling
+                .unwrap_or(base.refresh_rate_scaling),
+            hold_samples: self.hold_samples.unwrap_or(base.hold_samples),
+            max_volt: self.max_volt.or(base.max_volt),
+            max_freq: self.max_freq.or(base.max_freq),
+        }
+    }
+}
+
+/// 日志配置（`[logging]`），所有字段均可省略
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// 日志文件大小上限（MB）
+    pub max_size_mb: u64,
+    /// 日志达到上限的该比例时轮转（0.0-1.0）
+    pub rotation_threshold: f64,
+    /// 日志大小检查间隔（秒）
+    pub check_interval: u64,
+    /// 保留的备份文件数量
+    pub backups: u32,
+    /// 日志等级文件不存在或内容无效时使用的默认等级
+    pub level: String,
+    /// 日志刷盘策略：`line`、`interval:<毫秒>` 或 `size:<KB>`，
+    /// 省略时info及以上等级按间隔刷盘，debug等级逐行刷盘
+    pub flush_policy: Option<String>,
+    /// 启动时清空日志文件，关闭后追加写入并以分隔标记区分每次启动
+    pub truncate_on_start: bool,
+    /// 调频决策日志，不受日志等级影响
+    pub decision_log: DecisionLogLevel,
+}
+
+/// 调频决策日志的详细程度
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionLogLevel {
+    /// 不记录
+    #[default]
+    Off,
+    /// 每次实际调频记录一行，包含负载、余量和防抖状态
+    Summary,
+    /// 每个采样周期都记录
+    Full,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: 10,
+            rotation_threshold: 0.8,
+            check_interval: 60,
+            backups: 1,
+            level: "info".to_string(),
+            flush_policy: None,
+            truncate_on_start: true,
+            decision_log: DecisionLogLevel::Off,
+        }
+    }
+}
+
+/// 读取配置文件中的一个顶层配置段，配置文件不存在、该段缺失或解析失败时返回默认值
+///
+/// 只解析该段，其余配置不完整时也能读取。每次调用都会读取整个配置文件，
+/// 频繁执行的路径应保存读取结果，在 [`config_generation`] 变化时再重新读取
+pub fn read_section<T: DeserializeOwned + Default>(key: &str) -> T {
+    fs::read_to_string(active_config_path())
+        .ok()
+        .and_then(|content| toml::from_str::<toml::Table>(&content).ok())
+        .and_then(|mut table| table.remove(key))
+        .and_then(|section| section.try_into().ok())
+        .unwrap_or_default()
+}
+
+/// 配置文件的代数，配置监控线程每检测到一次修改加一
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 当前的配置文件代数
+pub fn config_generation() -> u64 {
+    CONFIG_GENERATION.load(Ordering::SeqCst)
+}
+
+/// 配置文件发生变化，返回新的代数
+pub fn bump_config_generation() -> u64 {
+    CONFIG_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
 }
 
 pub fn load_config(gpu: &mut GPU, target_mode: Option<&str>) -> Result<()> {
-    let content = fs::read_to_string(CONFIG_TOML_FILE)?;
-    let config: Config = toml::from_str(&content)?;
+    let content = fs::read_to_string(active_config_path())?;
+    let config: Arc<Config> = Arc::new(toml::from_str(&content)?);
 
-    gpu.idle_manager_mut()
-        .set_idle_threshold(config.global.idle_threshold);
+    // 预热只在启动时生效，不属于配置增量
+    gpu.set_warmup(Duration::from_secs(config.global.warmup_seconds));
 
     let mode = target_mode.unwrap_or(&config.global.mode);
+    let source = if target_mode.is_some() {
+        ModeSource::Game
+    } else {
+        ModeSource::Config
+    };
 
     if gpu.current_mode() == mode {
         debug!("Mode `{}` 已经生效，跳过重新加载", mode);
-        // 即使跳过重新加载，也要确保文件内容正确
-        if let Err(e) = write_file(CURRENT_MODE_PATH, mode.as_bytes(), 1024) {
-            warn!("Failed to write current_mode file: {e}");
-        }
+        gpu.set_mode(mode, source);
         return Ok(());
     }
 
-    // 存储当前模式以便访问
-    gpu.set_current_mode(mode.to_string());
-    let params = match mode {
-        "powersave" => &config.powersave,
-        "balance" => &config.balance,
-        "performance" => &config.performance,
-        "fast" => &config.fast,
-        _ => {
-            // 非法模式：采用回退策略并给出警告
-            warn!("Invalid mode '{mode}', using balance mode");
-            &config.balance
-        }
-    };
+    if config.mode_params(mode).is_none() {
+        // 非法模式：采用回退策略并给出警告
+        warn!("Invalid mode '{mode}', using balance mode");
+    }
 
-    let strategy = gpu.frequency_strategy_mut();
-    strategy.set_margin(params.margin.try_into().unwrap());
-    strategy.set_aggressive_down(params.aggressive_down);
-    strategy.set_sampling_interval(params.sampling_interval);
-
-    // 使用GPU配置方法
-    gpu.set_gaming_mode(params.gaming_mode);
-    gpu.set_adaptive_sampling(
-        params.adaptive_sampling,
-        params.min_adaptive_interval,
-        params.max_adaptive_interval,
-        params.sampling_interval,
-    );
-    gpu.set_up_rate_delay(params.up_rate_delay);
-    gpu.set_debounce_times(params.up_rate_delay, params.down_rate_delay);
+    let mut delta = config_delta(&config, target_mode);
+    delta.mode = Some(mode.to_string());
+    delta.mode_source = Some(source);
+    apply_delta(gpu, &delta);
 
     info!("Loaded config for mode: {}", mode);
 
-    // 写入当前模式到文件
-    if let Err(e) = write_file(CURRENT_MODE_PATH, mode.as_bytes(), 1024) {
-        warn!("Failed to write current_mode file: {e}");
-    } else {
-        debug!("Current mode written to file: {mode}");
-    }
-
     Ok(())
 }
 
@@ -108,6 +985,7 @@ pub fn load_config(gpu: &mut GPU, target_mode: Option<&str>) -> Result<()> {
 pub struct ConfigDelta {
     pub margin: i64,
     pub aggressive_down: bool,
+    pub aggressive_down_floor: i32,
     pub sampling_interval: u64,
     pub gaming_mode: bool,
     pub adaptive_sampling: bool,
@@ -115,24 +993,115 @@ pub struct ConfigDelta {
     pub max_adaptive_interval: u64,
     pub up_rate_delay: u64,
     pub down_rate_delay: u64,
+    pub hold_samples: u32,
+    /// 模式的电压上限（10微伏），`None` 表示不限制
+    pub max_volt: Option<i64>,
+    /// 模式的频率上限（KHz），`None` 表示不限制
+    pub max_freq: Option<i64>,
     pub idle_threshold: Option<i32>,
+    pub adaptive_idle: bool,
+    pub adaptive_idle_max: i32,
+    pub strict_writes: bool,
+    pub write_failure_limit: u32,
+    pub allow_dcs_in_game: bool,
+    pub dcs_snap_policy: DcsSnapPolicy,
+    pub conflict_policy: ConflictPolicy,
+    pub update_bypass: bool,
+    pub fault_rollback: bool,
+    pub devfreq_clamp: bool,
+    pub allow_emergency_stock_max: bool,
+    pub emergency_ramp_samples: u32,
+    /// 固定频率策略（KHz），0表示频率表中间档位，`None` 表示按负载调频
+    pub pinned_freq: Option<i64>,
+    /// 游戏规则的电压偏移（10微伏），`None` 表示不偏移
+    pub volt_offset: Option<i64>,
+    /// 游戏规则的分阶段余量，为空表示始终使用模式余量
+    pub margin_phases: Vec<MarginPhase>,
+    /// 游戏规则的负载-频率曲线，为空表示使用调频公式
+    pub freq_curve: Vec<CurvePoint>,
+    /// 生成增量时的频率表代数
+    pub table_generation: u64,
+    /// 生成增量的时间，调频线程据此丢弃过期的增量
+    pub created_at: Instant,
     pub mode: Option<String>, // 新增：用于同步 global.mode / 当前模式名
+    /// 模式切换来源，`None` 表示沿用当前来源
+    pub mode_source: Option<ModeSource>,
+    /// 来源放弃对模式的控制（如游戏离开前台），由优先级更低的来源接管
+    pub release: bool,
+    pub ddr: DdrConfig,
+    pub load_source: LoadSourceConfig,
+    /// 旁路充电时的DDR档位下限
+    pub ddr_floor: Option<i64>,
+    pub permissions: PermissionsConfig,
+    pub gpufreqv2: V2TuningConfig,
+    pub write_delay: WriteDelayConfig,
+    pub mode_notify: ModeNotifyConfig,
+    pub audit: AuditConfig,
+    pub idle: IdleConfig,
+    /// 生成增量时使用的配置，模式仲裁据此重新生成生效的请求，无需再次读取配置文件
+    pub config: Arc<Config>,
 }
 
 pub fn read_config_delta(target_mode: Option<&str>) -> Result<ConfigDelta> {
-    let content = std::fs::read_to_string(CONFIG_TOML_FILE)?;
+    read_config_delta_from(active_config_path(), target_mode)
+}
+
+/// 从指定的配置文件生成配置增量
+///
+/// `target_mode` 为 `None` 时使用 `global.mode`，增量的来源记为配置文件
+///
+/// ```
+/// use gpugovernor::{
+///     datasource::config_parser::read_config_delta_from, model::mode_state::ModeSource,
+/// };
+///
+/// let path = std::env::temp_dir().join(format!("doctest-config-{}.toml", std::process::id()));
+/// std::fs::write(&path, "[global]\nmode = \"balance\"\nidle_threshold = 5\n").unwrap();
+///
+/// let delta = read_config_delta_from(path.to_str().unwrap(), None).unwrap();
+/// assert_eq!(delta.mode.as_deref(), Some("balance"));
+/// assert_eq!(delta.mode_source, Some(ModeSource::Config));
+/// assert_eq!(delta.idle_threshold, Some(5));
+/// assert!(!delta.update_bypass);
+///
+/// // 配置文件不存在或格式错误时返回错误
+/// std::fs::write(&path, "[global\n").unwrap();
+/// assert!(read_config_delta_from(path.to_str().unwrap(), None).is_err());
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_config_delta_from(path: &str, target_mode: Option<&str>) -> Result<ConfigDelta> {
+    let content = std::fs::read_to_string(path)?;
     let config: Config = toml::from_str(&content)?;
+    Ok(config_delta(&Arc::new(config), target_mode))
+}
+
+/// 按当前显示、充电和省电状态由配置生成指定模式（`None` 表示全局模式）的配置增量
+pub fn config_delta(config: &Arc<Config>, target_mode: Option<&str>) -> ConfigDelta {
     let mode = target_mode.unwrap_or(&config.global.mode);
-    let params = match mode {
-        "powersave" => &config.powersave,
-        "balance" => &config.balance,
-        "performance" => &config.performance,
-        "fast" => &config.fast,
-        _ => &config.balance,
-    };
-    Ok(ConfigDelta {
+    let mut params = config
+        .mode_params(mode)
+        .unwrap_or_else(|| config.balance.resolve(&config.default_mode));
+
+    // 系统省电模式开启时改用省电模式的参数，模式名保持不变，省电模式关闭后即可恢复
+    let saver = &config.battery_saver;
+    if saver.enabled
+        && is_battery_saver_active()
+        && !(saver.skip_gaming && params.gaming_mode)
+        && saver.mode != mode
+    {
+        match config.mode_params(&saver.mode) {
+            Some(saver_params) => {
+                debug!("Battery saver active, using `{}` parameters", saver.mode);
+                params = saver_params;
+            }
+            None => warn!("Invalid battery_saver mode '{}', ignored", saver.mode),
+        }
+    }
+
+    let mut delta = ConfigDelta {
         margin: params.margin,
         aggressive_down: params.aggressive_down,
+        aggressive_down_floor: params.aggressive_down_floor,
         sampling_interval: params.sampling_interval,
         gaming_mode: params.gaming_mode,
         adaptive_sampling: params.adaptive_sampling,
@@ -140,7 +1109,71 @@ pub fn read_config_delta(target_mode: Option<&str>) -> Result<ConfigDelta> {
         max_adaptive_interval: params.max_adaptive_interval,
         up_rate_delay: params.up_rate_delay,
         down_rate_delay: params.down_rate_delay,
+        hold_samples: params.hold_samples,
+        max_volt: params.max_volt,
+        max_freq: params.max_freq,
         idle_threshold: Some(config.global.idle_threshold),
+        adaptive_idle: config.global.adaptive_idle,
+        adaptive_idle_max: config.global.adaptive_idle_max,
+        strict_writes: config.global.strict_writes,
+        write_failure_limit: config.global.write_failure_limit,
+        allow_dcs_in_game: config.global.allow_dcs_in_game,
+        dcs_snap_policy: config.global.dcs_snap_policy,
+        conflict_policy: config.global.conflict_policy,
+        update_bypass: config.global.update_bypass,
+        fault_rollback: config.global.fault_rollback,
+        devfreq_clamp: config.global.devfreq_clamp,
+        allow_emergency_stock_max: config.global.allow_emergency_stock_max,
+        emergency_ramp_samples: config.global.emergency_ramp_samples,
+        pinned_freq: None,
+        volt_offset: None,
+        margin_phases: Vec::new(),
+        freq_curve: Vec::new(),
+        table_generation: freq_table_generation(),
+        created_at: Instant::now(),
         mode: Some(config.global.mode.clone()),
-    })
+        mode_source: target_mode.is_none().then_some(ModeSource::Config),
+        release: false,
+        ddr: config.ddr.clone(),
+        load_source: config.load_source.clone(),
+        ddr_floor: None,
+        permissions: config.permissions.clone(),
+        gpufreqv2: config.gpufreqv2,
+        write_delay: config.write_delay,
+        mode_notify: config.mode_notify.clone(),
+        audit: config.audit.clone(),
+        idle: config.idle.clone(),
+        config: Arc::clone(config),
+    };
+
+    let display = current_display_state();
+    if params.refresh_rate_scaling {
+        scale_for_refresh_rate(
+            &mut delta,
+            config.global.reference_refresh_rate,
+            display.refresh_rate,
+        );
+    }
+
+    // 按优先级依次叠加当前显示状态匹配的覆盖配置
+    for key in display.profile_keys() {
+        if let Some(overrides) = config.display.get(&key) {
+            debug!("Applying display profile `{key}`");
+            overrides.apply_to(&mut delta);
+        }
+    }
+
+    // 旁路充电时使用更激进的配置
+    let charging = &config.charging;
+    if charging.enabled && is_charge_bypass_active() && (delta.gaming_mode || !charging.gaming_only)
+    {
+        debug!("Applying charge bypass profile");
+        delta.margin += charging.margin_boost;
+        delta.ddr_floor = charging.ddr_floor;
+    }
+
+    // 显示状态覆盖和刷新率缩放之后再次校验
+    delta.validate_intervals();
+
+    delta
 }
@@ -0,0 +1,279 @@
+use std::{fs, sync::mpsc::Sender, time::Duration};
+
+use anyhow::{Result, anyhow};
+use inotify::WatchMask;
+use log::{info, warn};
+
+use crate::{
+    datasource::file_path::*,
+    utils::{
+        file_operate::{FileClass, write_file},
+        inotify::InotifyWatcher,
+        log_tail::LOG_TAIL_CAPACITY,
+        shutdown::request_shutdown_handshake,
+        trace_recorder::MAX_TRACE_DURATION,
+    },
+};
+
+/// 控制命令 - 由控制文件写入，转发给调频主循环执行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// 临时切换到指定模式，到期后自动恢复
+    Boost { mode: String, duration: Duration },
+    /// 提前结束临时模式
+    CancelBoost,
+    /// 手动进入旁路模式，将频率控制交还内核
+    Bypass,
+    /// 退出旁路模式，恢复调频
+    Resume,
+    /// 录制负载轨迹，到期后自动停止
+    StartRecording { duration: Duration },
+    /// 提前停止录制
+    StopRecording,
+    /// 切换当前模式
+    SetMode { mode: String },
+    /// 锁定GPU频率，`duration` 为空时持续到 `unlock`
+    Lock {
+        freq: i64,
+        duration: Option<Duration>,
+    },
+    /// 解除频率锁定
+    Unlock,
+    /// 依次测试每个频率档位并生成校准报告
+    Calibrate,
+    /// 导出解析后的频率表
+    DumpTable { format: DumpFormat },
+    /// 导出内存中的最近日志
+    LogTail { lines: usize },
+}
+
+/// 频率表导出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat {
+    Toml,
+    Json,
+}
+
+/// 请求ID行的前缀：命令文件中以此开头的行不是命令，守护进程将其原样写回结果文件的第一行，
+/// 调用方据此确认读到的是自己的结果
+pub const REQUEST_ID_PREFIX: &str = "request-id ";
+
+/// 允许作为临时提升目标的模式
+pub const BOOST_MODES: [&str; 2] = ["performance", "fast"];
+/// 所有可切换的模式
+pub const MODES: [&str; 4] = ["powersave", "balance", "performance", "fast"];
+
+/// 解析时长字符串，支持 `30`、`30s`、`5m`、`1h`、`500ms`
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim().to_lowercase();
+    let (number, unit_ms) = if let Some(n) = text.strip_suffix("ms") {
+        (n, 1)
+    } else if let Some(n) = text.strip_suffix('s') {
+        (n, 1000)
+    } else if let Some(n) = text.strip_suffix('m') {
+        (n, 60 * 1000)
+    } else if let Some(n) = text.strip_suffix('h') {
+        (n, 60 * 60 * 1000)
+    } else {
+        (text.as_str(), 1000)
+    };
+
+    let value = number
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| anyhow!("Invalid duration: {text}"))?;
+    if value == 0 {
+        return Err(anyhow!("Duration must be greater than zero: {text}"));
+    }
+
+    Ok(Duration::from_millis(value.saturating_mul(unit_ms)))
+}
+
+/// 解析单行控制命令
+///
+/// 支持的格式：
+/// - `boost <duration>`：临时切换到 performance 模式
+/// - `boost <performance|fast> <duration>`：临时切换到指定模式
+/// - `boost cancel` / `unboost`：提前结束临时模式
+/// - `bypass` / `resume`：进入/退出旁路模式
+/// - `record <duration>` / `record stop`：开始/停止录制负载轨迹
+/// - `mode <powersave|balance|performance|fast>`：切换当前模式
+/// - `lock <freq> [duration]` / `unlock`：锁定/解除锁定GPU频率（KHz）
+/// - `calibrate`：校准频率档位，剔除无法达到的档位
+/// - `dump-table [toml|json]`：导出解析后的频率表及实际使用的电压
+/// - `log-tail [lines]`：导出最近的日志到 `control/log_tail`，默认导出缓冲区中的全部日志
+pub fn parse_command(line: &str) -> Result<ControlCommand> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts.as_slice() {
+        ["bypass"] => Ok(ControlCommand::Bypass),
+        ["resume"] => Ok(ControlCommand::Resume),
+        ["boost", "cancel"] | ["unboost"] => Ok(ControlCommand::CancelBoost),
+        ["record", "stop"] => Ok(ControlCommand::StopRecording),
+        ["mode", mode] => {
+            if !MODES.contains(mode) {
+                return Err(anyhow!("Invalid mode '{mode}', expected one of {MODES:?}"));
+            }
+            Ok(ControlCommand::SetMode {
+                mode: mode.to_string(),
+            })
+        }
+        ["unlock"] => Ok(ControlCommand::Unlock),
+        ["calibrate"] => Ok(ControlCommand::Calibrate),
+        ["dump-table"] | ["dump-table", "toml"] => Ok(ControlCommand::DumpTable {
+            format: DumpFormat::Toml,
+        }),
+        ["log-tail"] => Ok(ControlCommand::LogTail {
+            lines: LOG_TAIL_CAPACITY,
+        }),
+        ["log-tail", lines] => {
+            let lines = lines
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| anyhow!("Invalid line count: {lines}"))?;
+            Ok(ControlCommand::LogTail {
+                lines: lines.min(LOG_TAIL_CAPACITY),
+            })
+        }
+        ["dump-table", "json"] => Ok(ControlCommand::DumpTable {
+            format: DumpFormat::Json,
+        }),
+        ["lock", freq, rest @ ..] if rest.len() <= 1 => {
+            let freq = freq
+                .parse::<i64>()
+                .ok()
+                .filter(|f| *f > 0)
+                .ok_or_else(|| anyhow!("Invalid frequency: {freq}"))?;
+            let duration = rest.first().map(|d| parse_duration(d)).transpose()?;
+            Ok(ControlCommand::Lock { freq, duration })
+        }
+        ["record", duration] => {
+            let duration = parse_duration(duration)?;
+            if duration > MAX_TRACE_DURATION {
+                return Err(anyhow!(
+                    "Recording duration exceeds limit of {}s",
+                    MAX_TRACE_DURATION.as_secs()
+                ));
+            }
+            Ok(ControlCommand::StartRecording { duration })
+        }
+        ["boost", duration] => Ok(ControlCommand::Boost {
+            mode: "performance".to_string(),
+            duration: parse_duration(duration)?,
+        }),
+        ["boost", mode, duration] => {
+            if !BOOST_MODES.contains(mode) {
+                return Err(anyhow!(
+                    "Invalid boost mode '{mode}', expected one of {BOOST_MODES:?}"
+                ));
+            }
+            Ok(ControlCommand::Boost {
+                mode: mode.to_string(),
+                duration: parse_duration(duration)?,
+            })
+        }
+        [] => Err(anyhow!("Empty command")),
+        _ => Err(anyhow!("Unknown command: {line}")),
+    }
+}
+
+/// 写入控制命令的执行结果，供脚本读取
+fn write_response(response: &str) {
+    if let Err(e) = write_file(
+        CONTROL_RESPONSE_FILE,
+        response.as_bytes(),
+        4096,
+        FileClass::Control,
+    ) {
+        warn!("Failed to write control response: {e}");
+    }
+}
+
+/// 取走控制命令文件：先改名再读取，处理期间新写入的命令会创建新的命令文件，不会被清除
+///
+/// 命令文件已被取走（同一次写入产生多个事件）时返回 `None`
+fn claim_command_file() -> Result<Option<String>> {
+    match fs::rename(CONTROL_COMMAND_FILE, CONTROL_COMMAND_CLAIMED_FILE) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let content = fs::read_to_string(CONTROL_COMMAND_CLAIMED_FILE)?;
+    if let Err(e) = fs::remove_file(CONTROL_COMMAND_CLAIMED_FILE) {
+        warn!("Failed to remove claimed control command file: {e}");
+    }
+    Ok(Some(content))
+}
+
+/// 监控控制命令文件，解析后通过 channel 发送到调频主循环
+pub fn monitor_control_commands(tx: Sender<ControlCommand>) -> Result<()> {
+    info!("{CONTROL_MONITOR_THREAD} Start");
+
+    fs::create_dir_all(CONTROL_DIR)?;
+
+    let command_filename = std::path::Path::new(CONTROL_COMMAND_FILE)
+        .file_name()
+        .unwrap_or(std::ffi::OsStr::new("command"))
+        .to_string_lossy()
+        .to_string();
+    let shutdown_filename = std::path::Path::new(SHUTDOWN_REQUEST_FILE)
+        .file_name()
+        .unwrap_or(std::ffi::OsStr::new("shutdown_request"))
+        .to_string_lossy()
+        .to_string();
+
+    let mut inotify = InotifyWatcher::new()?;
+    inotify.add(CONTROL_DIR, WatchMask::MOVED_TO | WatchMask::CLOSE_WRITE)?;
+    info!("Watching control command file: {CONTROL_COMMAND_FILE}");
+
+    loop {
+        let events = inotify.wait_and_handle()?;
+
+        if events
+            .iter()
+            .any(|event| event.name.as_deref() == Some(shutdown_filename.as_str()))
+        {
+            request_shutdown_handshake();
+        }
+
+        let command_written = events
+            .iter()
+            .any(|event| event.name.as_deref() == Some(command_filename.as_str()));
+        if !command_written {
+            continue;
+        }
+
+        let content = match claim_command_file() {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Failed to read control command file: {e}");
+                continue;
+            }
+        };
+
+        let mut responses = Vec::new();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if line.starts_with(REQUEST_ID_PREFIX) {
+                responses.insert(0, line.to_string());
+                continue;
+            }
+            match parse_command(line) {
+                Ok(command) => {
+                    info!("Control command received: {command:?}");
+                    if tx.send(command).is_ok() {
+                        responses.push(format!("ok: {line}"));
+                    } else {
+                        responses.push(format!("error: {line}: governor loop not running"));
+                    }
+                }
+                Err(e) => {
+                    warn!("Invalid control command '{line}': {e}");
+                    responses.push(format!("error: {line}: {e}"));
+                }
+            }
+        }
+
+        write_response(&responses.join("\n"));
+    }
+}
@@ -0,0 +1,101 @@
+//! 按设备平台选择配置文件
+//!
+//! 模块可以在 `configs/` 目录中附带针对不同SoC调校的配置（如 `mt6893.toml`），启动时根据
+//! `ro.board.platform` 或 `/proc/cpuinfo` 识别平台并选择对应配置，找不到时使用 config.toml。
+//! 设备配置存在时 config.toml 不再生效，启动和重新加载时都会提示，避免修改了不生效的文件。
+
+use std::{fs, path::Path};
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::datasource::file_path::{CONFIG_TOML_FILE, CONFIGS_DIR, CPUINFO_PATH};
+
+/// 可能包含 `ro.board.platform` 的属性文件
+const BUILD_PROP_FILES: [&str; 3] = [
+    "/vendor/build.prop",
+    "/odm/etc/build.prop",
+    "/system/build.prop",
+];
+
+static SOC_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(mt\d{4}[a-z]?)\b").unwrap());
+
+/// 从属性文件读取 `ro.board.platform`
+fn read_board_platform() -> Option<String> {
+    BUILD_PROP_FILES.iter().find_map(|file| {
+        fs::read_to_string(file).ok()?.lines().find_map(|line| {
+            line.strip_prefix("ro.board.platform=")
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+        })
+    })
+}
+
+/// 从 `/proc/cpuinfo` 的 Hardware 字段提取SoC型号
+fn read_cpuinfo_soc() -> Option<String> {
+    let cpuinfo = fs::read_to_string(CPUINFO_PATH).ok()?;
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("Hardware"))
+        .find_map(|line| SOC_RE.captures(line))
+        .map(|c| c[1].to_lowercase())
+}
+
+/// 设备平台候选名称，按优先级排列
+fn platform_candidates() -> Vec<String> {
+    let mut candidates = Vec::new();
+    for platform in [read_board_platform(), read_cpuinfo_soc()]
+        .into_iter()
+        .flatten()
+    {
+        if !candidates.contains(&platform) {
+            candidates.push(platform);
+        }
+    }
+    candidates
+}
+
+/// 选择当前设备使用的配置文件
+fn select_config() -> String {
+    let candidates = platform_candidates();
+    if candidates.is_empty() {
+        debug!("Device platform not detected");
+    }
+
+    for platform in &candidates {
+        let path = Path::new(CONFIGS_DIR).join(format!("{platform}.toml"));
+        if path.is_file() {
+            info!("Using device profile for {platform}: {}", path.display());
+            return path.to_string_lossy().into_owned();
+        }
+    }
+
+    info!(
+        "No device profile for {:?}, using {CONFIG_TOML_FILE}",
+        candidates
+    );
+    CONFIG_TOML_FILE.to_string()
+}
+
+static ACTIVE_CONFIG: Lazy<String> = Lazy::new(select_config);
+
+/// 当前生效的配置文件路径，首次调用时探测设备平台
+pub fn active_config_path() -> &'static str {
+    ACTIVE_CONFIG.as_str()
+}
+
+/// 设备配置生效但 config.toml 同时存在时，返回被忽略的 config.toml 路径
+pub fn shadowed_config_path() -> Option<&'static str> {
+    (active_config_path() != CONFIG_TOML_FILE && Path::new(CONFIG_TOML_FILE).is_file())
+        .then_some(CONFIG_TOML_FILE)
+}
+
+/// 记录当前生效的配置文件，`event` 为记录的时机（如启动、重新加载）
+pub fn log_active_config(event: &str) {
+    let path = active_config_path();
+    info!("Active config file ({event}): {path}");
+    if let Some(shadowed) = shadowed_config_path() {
+        warn!("{shadowed} is ignored because the device profile {path} takes precedence");
+    }
+}
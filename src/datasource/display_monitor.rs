@@ -0,0 +1,256 @@
+use std::{
+    sync::{Mutex, MutexGuard, mpsc::Sender},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Result;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::datasource::{
+    config_parser::{ConfigDelta, read_config_delta},
+    dumpsys::Dumpsys,
+    file_path::*,
+};
+
+/// `dumpsys display` 轮询间隔
+const DISPLAY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 连接 display 服务失败后的首次重试间隔，之后每次翻倍
+const DUMPSYS_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// 连接 display 服务的最长重试间隔
+const DUMPSYS_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// 宽高比（短边/长边）不低于该值时视为展开的大屏
+const UNFOLDED_ASPECT_RATIO: f64 = 0.75;
+
+/// 显示状态 - 由显示监控线程维护，用于选择 `[display.<key>]` 配置
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisplayState {
+    /// 内置屏幕分辨率
+    pub width: u32,
+    pub height: u32,
+    /// 内置屏幕当前刷新率
+    pub refresh_rate: f64,
+    /// 是否处于展开（接近方形的大屏）状态
+    pub unfolded: bool,
+    /// 是否连接了外接显示器
+    pub external: bool,
+    /// 是否正在无线投屏（Miracast 或投屏应用创建的虚拟显示）
+    pub casting: bool,
+}
+
+impl DisplayState {
+    /// 当前显示状态匹配的配置键，按优先级从低到高排列
+    ///
+    /// 投屏会增加编码负载和发热，`casting` 优先级最高，同时匹配时覆盖外接显示器等配置
+    pub fn profile_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        if self.refresh_rate > 0.0 {
+            keys.push(format!("{}hz", self.refresh_rate.round() as u32));
+        }
+        if self.unfolded {
+            keys.push("unfolded".to_string());
+        }
+        if self.external {
+            keys.push("external".to_string());
+        }
+        if self.casting {
+            keys.push("casting".to_string());
+        }
+        keys
+    }
+}
+
+static DISPLAY_STATE: Lazy<Mutex<DisplayState>> = Lazy::new(|| Mutex::new(DisplayState::default()));
+
+/// 锁定显示状态，锁中毒时继续使用其中的数据
+fn display_state() -> MutexGuard<'static, DisplayState> {
+    DISPLAY_STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 获取当前显示状态
+pub fn current_display_state() -> DisplayState {
+    display_state().clone()
+}
+
+static DEVICE_INFO_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"DisplayDeviceInfo\{").unwrap());
+static SIZE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+) x (\d+)").unwrap());
+static FPS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:renderFrameRate|refreshRate|fps)[ =]([0-9]+(?:\.[0-9]+)?)").unwrap()
+});
+static TYPE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"type ([A-Z_]+)").unwrap());
+
+/// 投屏应用创建的虚拟显示名称中的关键字
+const CAST_DISPLAY_KEYWORDS: [&str; 3] = ["cast", "wfd", "mirror"];
+
+/// 虚拟显示是否由投屏创建
+fn is_cast_display(line: &str) -> bool {
+    let name = line
+        .split_once('"')
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map_or("", |(name, _)| name)
+        .to_lowercase();
+    CAST_DISPLAY_KEYWORDS
+        .iter()
+        .any(|keyword| name.contains(keyword))
+}
+
+/// 解析 `dumpsys display` 输出
+pub fn parse_display_dump(output: &str) -> DisplayState {
+    let mut state = DisplayState::default();
+    let mut internal_found = false;
+    let mut active_internal_found = false;
+
+    for line in output.lines().filter(|l| DEVICE_INFO_RE.is_match(l)) {
+        let display_type = TYPE_RE
+            .captures(line)
+            .map(|c| c[1].to_string())
+            .unwrap_or_default();
+
+        match display_type.as_str() {
+            "EXTERNAL" => state.external = true,
+            // Miracast 无线显示同时视为外接显示器
+            "WIFI" => {
+                state.external = true;
+                state.casting = true;
+            }
+            "VIRTUAL" if is_cast_display(line) => state.casting = true,
+            "INTERNAL" => {
+                // 折叠屏有多个内置屏幕，优先使用处于点亮状态的那一个；
+                // 找到后继续扫描，外接和虚拟显示排在内置屏幕之后
+                let active = line.contains("state ON");
+                if active_internal_found || (internal_found && !active) {
+                    continue;
+                }
+                if let Some(size) = SIZE_RE.captures(line) {
+                    state.width = size[1].parse().unwrap_or(0);
+                    state.height = size[2].parse().unwrap_or(0);
+                }
+                if let Some(fps) = FPS_RE.captures(line) {
+                    state.refresh_rate = fps[1].parse().unwrap_or(0.0);
+                }
+                internal_found = true;
+                active_internal_found = active;
+            }
+            _ => {}
+        }
+    }
+
+    let (short, long) = (
+        state.width.min(state.height) as f64,
+        state.width.max(state.height) as f64,
+    );
+    state.unfolded = long > 0.0 && short / long >= UNFOLDED_ASPECT_RATIO;
+    state
+}
+
+/// 读取当前生效的模式（包括游戏模式），用于显示状态变化时重新生成配置增量
+pub(crate) fn read_active_mode() -> Option<String> {
+    std::fs::read_to_string(CURRENT_MODE_PATH)
+        .ok()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+}
+
+/// 监控显示状态（折叠/展开、外接显示器、刷新率），变化时发送配置增量
+pub fn monitor_display_state(tx: Sender<ConfigDelta>) -> Result<()> {
+    info!("{DISPLAY_MONITOR_THREAD} Start");
+
+    let mut backoff = DUMPSYS_INITIAL_BACKOFF;
+    let dumper = loop {
+        match Dumpsys::new("display") {
+            Some(d) => break d,
+            None => {
+                if backoff == DUMPSYS_INITIAL_BACKOFF {
+                    warn!("Display service is not available, retrying with backoff");
+                }
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(DUMPSYS_MAX_BACKOFF);
+            }
+        }
+    };
+    if backoff != DUMPSYS_INITIAL_BACKOFF {
+        info!("Connected to display service");
+    }
+
+    loop {
+        match dumper.dump(&[]) {
+            Ok(output) => {
+                let state = parse_display_dump(&output);
+                let changed = {
+                    let mut current = display_state();
+                    if *current != state {
+                        *current = state.clone();
+                        true
+                    } else {
+                        false
+                    }
+                };
+
+                if changed {
+                    info!(
+                        "Display state changed: {}x{} @ {:.0}Hz, unfolded={}, external={}, casting={}",
+                        state.width,
+                        state.height,
+                        state.refresh_rate,
+                        state.unfolded,
+                        state.external,
+                        state.casting
+                    );
+
+                    let mode = read_active_mode();
+                    match read_config_delta(mode.as_deref()) {
+                        Ok(mut delta) => {
+                            if mode.is_some() {
+                                delta.mode = mode;
+                            }
+                            if tx.send(delta).is_err() {
+                                warn!("Failed to send display profile config delta");
+                            }
+                        }
+                        Err(e) => debug!("Failed to read config delta for display change: {e}"),
+                    }
+                }
+            }
+            Err(e) => debug!("Failed to dump display service: {e}"),
+        }
+
+        thread::sleep(DISPLAY_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_after_the_active_panel_are_parsed() {
+        let dump = r#"
+Display Devices: size=4
+  DisplayDeviceInfo{"Built-in Screen": uniqueId="local:0", 1080 x 2400, modeId 1, renderFrameRate 120.0, type INTERNAL, state ON, FLAG_DEFAULT_DISPLAY}
+  DisplayDeviceInfo{"Cover Screen": uniqueId="local:1", 720 x 1600, modeId 2, renderFrameRate 60.0, type INTERNAL, state OFF}
+  DisplayDeviceInfo{"HDMI Screen": uniqueId="local:2", 1920 x 1080, modeId 3, renderFrameRate 60.0, type EXTERNAL, state ON}
+  DisplayDeviceInfo{"CastScreen": uniqueId="virtual:cast", 1280 x 720, modeId 4, renderFrameRate 60.0, type VIRTUAL, state ON}
+"#;
+        let state = parse_display_dump(dump);
+        assert_eq!((state.width, state.height), (1080, 2400));
+        assert_eq!(state.refresh_rate, 120.0);
+        assert!(!state.unfolded);
+        assert!(state.external);
+        assert!(state.casting);
+    }
+
+    #[test]
+    fn active_panel_wins_over_an_earlier_inactive_one() {
+        let dump = r#"
+  DisplayDeviceInfo{"Cover Screen": 720 x 1600, renderFrameRate 60.0, type INTERNAL, state OFF}
+  DisplayDeviceInfo{"Inner Screen": 2176 x 1812, renderFrameRate 120.0, type INTERNAL, state ON}
+"#;
+        let state = parse_display_dump(dump);
+        assert_eq!((state.width, state.height), (2176, 1812));
+        assert!(state.unfolded);
+        assert!(!state.external);
+        assert!(!state.casting);
+    }
+}
@@ -0,0 +1,193 @@
+//! GPU驱动版本识别与兼容性修正
+//!
+//! 初始化时读取 gpufreq 驱动状态和 Mali 驱动的版本信息，与内置的兼容性表匹配，
+//! 自动应用对应的修正（跳过电压写入、禁用DCS、延长写入间隔等），
+//! 识别结果写入日志和 [`CAPABILITIES_PATH`]，便于排查设备问题。
+
+use std::{fs, time::Duration};
+
+use anyhow::Result;
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    datasource::file_path::{
+        CAPABILITIES_PATH, GPUFREQ_STATUS, GPUFREQV2_STATUS, MALI_GPUINFO, MALI_KBASE_VERSION,
+    },
+    model::gpu::GPU,
+    utils::file_operate::{FileClass, write_file},
+};
+
+static VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?im)^\W*(?:gpufreq\w*[ _-]?)?(?:driver[ _-]?)?version\W*[:=]\s*(\S.*?)\s*$")
+        .unwrap()
+});
+
+/// 检测到的GPU驱动信息
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DriverInfo {
+    /// 调频驱动（`gpufreqv2` 或 `gpufreq`）
+    pub driver: &'static str,
+    /// 调频驱动版本
+    pub driver_version: Option<String>,
+    /// GPU型号（如 `Mali-G77 MC9`）
+    pub gpu: Option<String>,
+    /// Mali 内核驱动版本（如 `r38p1`）
+    pub kbase_version: Option<String>,
+}
+
+impl DriverInfo {
+    /// 用于匹配兼容性表的描述，各字段以空格连接
+    fn fingerprint(&self) -> String {
+        [
+            Some(self.driver),
+            self.driver_version.as_deref(),
+            self.gpu.as_deref(),
+            self.kbase_version.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
+
+/// 兼容性表中的一项，`pattern` 的所有关键字都出现在驱动描述中时生效
+struct Quirk {
+    name: &'static str,
+    pattern: &'static [&'static str],
+    /// 电压节点写入无效或会导致档位错乱，只写入频率
+    skip_volt_writes: bool,
+    /// DCS核心缩减节点状态不可靠，禁用DCS
+    disable_dcs: bool,
+    /// 释放固定档位后需要更长的等待时间才能写入电压
+    write_delay: Option<Duration>,
+}
+
+/// 内置兼容性表
+const QUIRKS: [Quirk; 3] = [
+    Quirk {
+        name: "mali-g57-no-custom-volt",
+        pattern: &["gpufreqv2", "Mali-G57"],
+        skip_volt_writes: true,
+        disable_dcs: false,
+        write_delay: None,
+    },
+    Quirk {
+        name: "mali-g610-dcs-unreliable",
+        pattern: &["gpufreqv2", "Mali-G610"],
+        skip_volt_writes: false,
+        disable_dcs: true,
+        write_delay: None,
+    },
+    Quirk {
+        name: "kbase-r32-slow-opp-release",
+        pattern: &["gpufreq", "r32p"],
+        skip_volt_writes: false,
+        disable_dcs: false,
+        write_delay: Some(Duration::from_millis(20)),
+    },
+];
+
+/// 驱动信息和已应用的修正，写入能力文件
+#[derive(Serialize)]
+struct Capabilities<'a> {
+    #[serde(flatten)]
+    driver: &'a DriverInfo,
+    quirks: Vec<&'static str>,
+    dcs: bool,
+    skip_volt_writes: bool,
+    write_delay_ms: u64,
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// 读取驱动版本信息
+pub fn detect_driver_info(gpuv2: bool) -> DriverInfo {
+    let status_path = if gpuv2 {
+        GPUFREQV2_STATUS
+    } else {
+        GPUFREQ_STATUS
+    };
+    let driver_version = fs::read_to_string(status_path)
+        .ok()
+        .and_then(|content| VERSION_RE.captures(&content).map(|c| c[1].to_string()));
+    // gpuinfo 形如 `Mali-G77 9 cores r0p1 0x0000`，只保留型号和核心数
+    let gpu = read_trimmed(MALI_GPUINFO).map(|info| {
+        info.split_whitespace()
+            .take_while(|word| !word.starts_with("0x"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+
+    DriverInfo {
+        driver: if gpuv2 { "gpufreqv2" } else { "gpufreq" },
+        driver_version,
+        gpu,
+        kbase_version: read_trimmed(MALI_KBASE_VERSION),
+    }
+}
+
+/// 识别驱动版本并应用匹配的兼容性修正，在驱动检测和DCS检测之后调用
+pub fn apply_driver_quirks(gpu: &mut GPU) {
+    let info = detect_driver_info(gpu.is_gpuv2());
+    info!(
+        "GPU driver: {} version {}, GPU {}, kbase {}",
+        info.driver,
+        info.driver_version.as_deref().unwrap_or("unknown"),
+        info.gpu.as_deref().unwrap_or("unknown"),
+        info.kbase_version.as_deref().unwrap_or("unknown")
+    );
+
+    let fingerprint = info.fingerprint();
+    let mut applied = Vec::new();
+    for quirk in QUIRKS
+        .iter()
+        .filter(|quirk| quirk.pattern.iter().all(|key| fingerprint.contains(key)))
+    {
+        info!("Applying driver quirk: {}", quirk.name);
+        if quirk.skip_volt_writes {
+            gpu.frequency_mut().skip_volt_writes = true;
+        }
+        if quirk.disable_dcs {
+            gpu.set_dcs_enable(false);
+        }
+        if let Some(delay) = quirk.write_delay {
+            let manager = gpu.frequency_mut();
+            manager.write_delay = manager.write_delay.max(delay);
+        }
+        applied.push(quirk.name);
+    }
+    if applied.is_empty() {
+        debug!("No driver quirks matched: {fingerprint}");
+    }
+
+    if let Err(e) = write_capabilities(gpu, &info, applied) {
+        debug!("Failed to write capabilities file: {e}");
+    }
+}
+
+fn write_capabilities(gpu: &GPU, info: &DriverInfo, quirks: Vec<&'static str>) -> Result<()> {
+    let capabilities = Capabilities {
+        driver: info,
+        quirks,
+        dcs: gpu.is_dcs_enabled(),
+        skip_volt_writes: gpu.frequency().skip_volt_writes,
+        write_delay_ms: gpu.frequency().effective_write_delay().as_millis() as u64,
+    };
+    let content = serde_json::to_string_pretty(&capabilities)?;
+    write_file(
+        CAPABILITIES_PATH,
+        content.as_bytes(),
+        content.len(),
+        FileClass::State,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! dumpsys 服务访问
+//!
+//! 默认的 `android` feature 使用 dumpsys-rs 通过 binder 读取系统服务。关闭该 feature
+//! （`--no-default-features`）后改用同名的空实现：所有服务都不可用，前台应用和显示状态
+//! 检测按服务不可用处理，配置解析、调频逻辑和轨迹回放不受影响，可在桌面 Linux 上构建、运行
+//! 和测试（`cargo test --no-default-features`）。
+
+#[cfg(feature = "android")]
+pub use dumpsys_rs::Dumpsys;
+
+/// 非 Android 构建中的 dumpsys，连接任何服务都返回 `None`
+#[cfg(not(feature = "android"))]
+pub struct Dumpsys;
+
+#[cfg(not(feature = "android"))]
+impl Dumpsys {
+    pub fn new(_service: &str) -> Option<Self> {
+        None
+    }
+
+    pub fn dump(&self, _args: &[&str]) -> anyhow::Result<String> {
+        anyhow::bail!("dumpsys requires the android feature")
+    }
+}
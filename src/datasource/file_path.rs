@@ -17,6 +17,16 @@ pub const FOREGROUND_APP_THREAD: &str = "FgAppWatcher";
 pub const LOG_LEVEL_MONITOR_THREAD: &str = "LogLevelMonitor";
 /// 配置文件监控线程名称
 pub const CONFIG_MONITOR_THREAD: &str = "ConfigMonitor";
+/// 控制命令监控线程名称
+pub const CONTROL_MONITOR_THREAD: &str = "CtrlMonitor";
+/// 显示状态监控线程名称
+pub const DISPLAY_MONITOR_THREAD: &str = "DisplayMonitor";
+/// 充电状态监控线程名称
+pub const CHARGING_MONITOR_THREAD: &str = "ChargingMonitor";
+/// 系统省电模式监控线程名称
+pub const BATTERY_SAVER_MONITOR_THREAD: &str = "SaverMonitor";
+/// 内核限频来源监控线程名称
+pub const LIMITER_MONITOR_THREAD: &str = "LimiterMonitor";
 
 // =============================================================================
 // 配置文件路径常量
@@ -24,19 +34,72 @@ pub const CONFIG_MONITOR_THREAD: &str = "ConfigMonitor";
 
 /// 主配置文件路径 - TOML格式的主要配置文件
 pub const CONFIG_TOML_FILE: &str = "/data/adb/gpu_governor/config/config.toml";
+/// 按设备平台区分的配置目录（`<platform>.toml`）
+pub const CONFIGS_DIR: &str = "/data/adb/gpu_governor/configs";
 /// GPU频率表配置文件路径 - 定义GPU频率和电压表
 pub const FREQ_TABLE_CONFIG_FILE: &str = "/data/adb/gpu_governor/config/gpu_freq_table.toml";
+/// 可疑频率表快照路径 - 检测到GPU故障时复制当时使用的频率表
+pub const SUSPECT_FREQ_TABLE_PATH: &str = "/data/adb/gpu_governor/suspect_freq_table.toml";
 /// 当前工作模式文件路径 - 存储当前使用的调频模式
 pub const CURRENT_MODE_PATH: &str = "/data/adb/gpu_governor/config/current_mode";
 /// 游戏配置文件路径 - 游戏应用检测和优化配置
 pub const GAMES_CONF_PATH: &str = "/data/adb/gpu_governor/game/games.toml";
 
+// =============================================================================
+// 控制接口路径常量
+// =============================================================================
+
+/// 控制接口目录
+pub const CONTROL_DIR: &str = "/data/adb/gpu_governor/control";
+/// 控制命令文件路径 - 脚本写入命令（如 `boost fast 60s`）
+pub const CONTROL_COMMAND_FILE: &str = "/data/adb/gpu_governor/control/command";
+/// 守护进程处理中的控制命令 - 处理前将命令文件改名至此，处理期间写入的新命令不会被覆盖
+pub const CONTROL_COMMAND_CLAIMED_FILE: &str = "/data/adb/gpu_governor/control/.command.processing";
+/// 控制命令结果文件路径 - 守护进程写入最近一次命令的执行结果
+pub const CONTROL_RESPONSE_FILE: &str = "/data/adb/gpu_governor/control/response";
+/// 最近日志导出路径 - `log-tail` 控制命令写入内存中的最近日志
+pub const LOG_TAIL_PATH: &str = "/data/adb/gpu_governor/control/log_tail";
+/// 退出请求文件路径 - 模块脚本更新前写入，请求守护进程恢复内核调频后退出
+pub const SHUTDOWN_REQUEST_FILE: &str = "/data/adb/gpu_governor/control/shutdown_request";
+/// 退出确认文件路径 - 守护进程恢复内核调频后写入，脚本据此判断可以安全替换文件
+pub const SHUTDOWN_ACK_FILE: &str = "/data/adb/gpu_governor/control/shutdown_ack";
+/// 运行状态文件路径 - JSON格式，供前端读取
+pub const STATUS_JSON_PATH: &str = "/data/adb/gpu_governor/status.json";
+/// 共享内存路径文件 - 启用 shm-export 特性时写入决策共享内存的 `/proc/<pid>/fd/<fd>` 路径
+pub const SHM_EXPORT_PATH: &str = "/data/adb/gpu_governor/shm_path";
+/// 可用模式列表路径 - TOML格式，供前端渲染模式选择
+pub const MODES_LIST_PATH: &str = "/data/adb/gpu_governor/modes.toml";
+/// 驱动能力文件路径 - JSON格式，记录检测到的驱动版本和已应用的兼容性修正
+pub const CAPABILITIES_PATH: &str = "/data/adb/gpu_governor/capabilities.json";
+/// 启动记录文件路径 - 持久化同一次开机内的重启次数
+pub const STATS_PATH: &str = "/data/adb/gpu_governor/stats.json";
+/// 游戏时长统计文件路径 - JSON格式，记录各游戏的累计前台时长
+pub const PLAY_TIME_PATH: &str = "/data/adb/gpu_governor/play_time.json";
+/// 开机ID - 用于区分不同的开机周期
+pub const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+/// 频率档位校准报告路径
+pub const CALIBRATION_PATH: &str = "/data/adb/gpu_governor/calibration.json";
+/// 档位压力测试报告路径
+pub const STRESS_TEST_PATH: &str = "/data/adb/gpu_governor/stress_test.json";
+/// 解析后的频率表导出路径（TOML格式）
+pub const FREQ_TABLE_DUMP_TOML: &str = "/data/adb/gpu_governor/freq_table_dump.toml";
+/// 解析后的频率表导出路径（JSON格式）
+pub const FREQ_TABLE_DUMP_JSON: &str = "/data/adb/gpu_governor/freq_table_dump.json";
+/// 负载轨迹录制目录
+pub const TRACE_DIR: &str = "/data/adb/gpu_governor/traces";
+
 // =============================================================================
 // 日志系统路径常量
 // =============================================================================
 
 /// 主日志文件路径
 pub const LOG_PATH: &str = "/data/adb/gpu_governor/log/gpu_gov.log";
+/// 崩溃报告目录 - 保存 `crash-<时间>.txt`
+pub const CRASH_REPORT_DIR: &str = "/data/adb/gpu_governor/log";
+/// GPU故障报告目录 - 保存 `fault-<时间>.txt`
+pub const FAULT_REPORT_DIR: &str = "/data/adb/gpu_governor/log";
+/// sysfs写入审计文件路径
+pub const SYSFS_AUDIT_PATH: &str = "/data/adb/gpu_governor/log/sysfs_audit.log";
 /// 动态日志级别控制文件路径
 pub const LOG_LEVEL_PATH: &str = "/data/adb/gpu_governor/log/log_level";
 
@@ -80,11 +143,31 @@ pub const GPUFREQV2_OPP: &str = "/proc/gpufreqv2/fix_target_opp_index";
 pub const GPUFREQ_VOLT: &str = "/proc/gpufreq/gpufreq_fixed_freq_volt";
 /// GPU电压控制路径 - GPUFreq v2版本
 pub const GPUFREQV2_VOLT: &str = "/proc/gpufreqv2/fix_custom_freq_volt";
+/// DCS（动态核心缩减）状态路径 - GPUFreq v2版本
+pub const GPUFREQV2_DCS_MODE: &str = "/proc/gpufreqv2/dcs_mode";
+/// 老化余量控制路径 - 较新的GPUFreq v2内核提供
+pub const GPUFREQV2_AGING_MODE: &str = "/proc/gpufreqv2/aging_mode";
+/// 节能模式控制路径 - 较新的GPUFreq v2内核提供
+pub const GPUFREQV2_ECO_MODE: &str = "/proc/gpufreqv2/eco_mode";
+/// gpufreqv2 GPU电源控制节点 - 长时间空闲时允许GPU断电
+pub const GPUFREQV2_POWER_CONTROL: &str = "/proc/gpufreqv2/mfgsys_power_control";
+/// gpufreqv2 驱动状态节点 - 包含驱动版本信息
+pub const GPUFREQV2_STATUS: &str = "/proc/gpufreqv2/gpufreq_status";
+/// gpufreq（v1）驱动状态节点
+pub const GPUFREQ_STATUS: &str = "/proc/gpufreq/gpufreq_status";
+/// gpufreqv2 限频表节点 - 各限频来源（温控、电池过流、PBM等）的上下限
+pub const GPUFREQV2_LIMIT_TABLE: &str = "/proc/gpufreqv2/limit_table";
+/// gpufreq（v1）限频表节点
+pub const GPUFREQ_LIMIT_TABLE: &str = "/proc/gpufreq/gpufreq_limit_table";
 
 // =============================================================================
 // Mali GPU DVFS路径常量
 // =============================================================================
 
+/// Mali GPU型号信息（如 `Mali-G77 9 cores r0p1 0x0000`）
+pub const MALI_GPUINFO: &str = "/sys/class/misc/mali0/device/gpuinfo";
+/// Mali 内核驱动版本
+pub const MALI_KBASE_VERSION: &str = "/sys/module/mali_kbase/version";
 /// Mali GPU DVFS使能控制路径
 pub const MALI_DVFS_ENABLE: &str = "/proc/mali/dvfs_enable";
 /// Mali GPU利用率路径 - 标准接口
@@ -118,6 +201,37 @@ pub const DVFSRC_V2_OPP_TABLE_1: &str =
 pub const DVFSRC_V2_OPP_TABLE_2: &str =
     "/sys/devices/platform/1c00f000.dvfsrc/1c00f000.dvfsrc:dvfsrc-helper/dvfsrc_opp_table";
 
+// =============================================================================
+// EMI内存带宽监控路径常量
+// =============================================================================
+
+/// DVFSRC v1驱动调试信息路径 - 包含EMI带宽统计
+pub const DVFSRC_V1_DUMP: &str = "/sys/devices/platform/10012000.dvfsrc/helio-dvfsrc/dvfsrc_dump";
+/// DVFSRC v2驱动调试信息路径（SOC平台）
+pub const DVFSRC_V2_DUMP_1: &str =
+    "/sys/devices/platform/soc/1c00f000.dvfsrc/1c00f000.dvfsrc:dvfsrc-helper/dvfsrc_dump";
+/// DVFSRC v2驱动调试信息路径（直接平台）
+pub const DVFSRC_V2_DUMP_2: &str =
+    "/sys/devices/platform/1c00f000.dvfsrc/1c00f000.dvfsrc:dvfsrc-helper/dvfsrc_dump";
+/// 平台设备目录 - 用于扫描名称包含 emi 的设备节点
+pub const PLATFORM_DEVICES_DIR: &str = "/sys/devices/platform";
+/// 温度传感器目录 - 用于查找GPU所在的 thermal_zone
+pub const THERMAL_ZONE_DIR: &str = "/sys/class/thermal";
+/// devfreq设备目录 - 用于在游戏模式下设置Mali内核DVFS的频率下限
+pub const DEVFREQ_DIR: &str = "/sys/class/devfreq";
+/// top-app cgroup 进程列表 - dumpsys 不可用时用于检测前台应用
+pub const TOP_APP_CGROUP_PROCS: &str = "/dev/cpuset/top-app/cgroup.procs";
+/// 电源目录 - 用于检测充电器和电池状态
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+/// 稳压器目录 - 用于读取GPU实际电压
+pub const REGULATOR_DIR: &str = "/sys/class/regulator";
+/// 成功休眠次数 - 用于检测系统休眠唤醒
+pub const SUSPEND_STATS_SUCCESS: &str = "/sys/power/suspend_stats/success";
+/// 内核日志 - 用于检测GPU驱动报告的错误
+pub const KMSG_PATH: &str = "/dev/kmsg";
+/// CPU信息 - 用于识别SoC型号
+pub const CPUINFO_PATH: &str = "/proc/cpuinfo";
+
 // =============================================================================
 // DDR频率档位常量定义
 // =============================================================================
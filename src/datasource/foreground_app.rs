@@ -1,12 +1,11 @@
 use std::{
-    collections::HashMap,
-    sync::{Mutex, mpsc::Sender},
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard, mpsc::Sender},
     thread,
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, anyhow};
-use dumpsys_rs::Dumpsys;
+use anyhow::{Context, Result};
 use inotify::WatchMask;
 use log::{debug, info, warn};
 use once_cell::sync::Lazy;
@@ -15,17 +14,86 @@ use serde::Deserialize;
 
 use crate::{
     datasource::{
-        config_parser::{Config, ConfigDelta, load_config},
+        config_parser::{
+            Config, ConfigDelta, CurvePoint, ForegroundConfig, GAME_BACKGROUND_MODE, MarginPhase,
+            config_generation, load_config, read_config_delta, read_section,
+        },
+        device_profile::active_config_path,
+        dumpsys::Dumpsys,
         file_path::*,
+        foreground_provider::ForegroundProvider,
+        game_discovery::handle_missing_games_list,
+        package_id::PackageId,
+        system_server::SystemServerWatcher,
+        workload_classifier::WorkloadClassifier,
+    },
+    model::{
+        freq_curve::FreqCurve, frequency_manager::VOLT_STEP, gpu::GPU, mode_state::ModeSource,
+    },
+    utils::{
+        file_operate::check_read_simple,
+        inotify::InotifyWatcher,
+        stats::{record_foreground_game, tick_play_time},
+        status_report::update_status,
     },
-    model::gpu::GPU,
-    utils::{file_operate::check_read_simple, inotify::InotifyWatcher},
 };
 
+/// 游戏的调频策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GamePolicy {
+    /// 按负载调频
+    #[default]
+    Reactive,
+    /// 固定在一个适中的频率，适用于云游戏/串流等负载平稳但对抖动敏感的解码负载
+    Pinned,
+}
+
 #[derive(Debug, Deserialize)]
 struct GameEntry {
+    /// 包名，或 `包名/Activity` 为指定界面单独设置模式；
+    /// 包名写作 `包名@用户ID` 时只匹配该用户（如工作资料），否则匹配所有用户
     package: String,
     mode: String,
+    #[serde(default)]
+    policy: GamePolicy,
+    /// `pinned` 策略的固定频率（KHz），省略时使用频率表中间档位
+    pinned_freq: Option<i64>,
+    /// 游戏运行期间叠加在频率表电压上的偏移（10微伏），须为625的整数倍
+    volt_offset: Option<i64>,
+    /// 按游戏时长切换余量，如加载和着色器编译期间使用更高的余量
+    #[serde(default)]
+    phases: Vec<MarginPhase>,
+    /// 负载-频率曲线断点，设置后代替调频公式
+    #[serde(default)]
+    curve: Vec<CurvePoint>,
+}
+
+/// 游戏列表中一个条目解析后的规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GameRule {
+    mode: String,
+    /// 固定频率策略（KHz），0表示频率表中间档位
+    pinned_freq: Option<i64>,
+    /// 电压偏移（10微伏）
+    volt_offset: Option<i64>,
+    /// 分阶段余量
+    phases: Vec<MarginPhase>,
+    /// 负载-频率曲线断点
+    curve: Vec<CurvePoint>,
+}
+
+impl GameRule {
+    /// 只指定模式、不带其他游戏参数的规则
+    fn for_mode(mode: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            pinned_freq: None,
+            volt_offset: None,
+            phases: Vec::new(),
+            curve: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,16 +101,115 @@ struct GamesConfig {
     games: Vec<GameEntry>,
 }
 
+/// 游戏列表，键为规范化的包名或 `包名/Activity`（Activity 为完整类名）
+#[derive(Debug, Default)]
+struct GamesList {
+    modes: HashMap<String, GameRule>,
+}
+
+impl GamesList {
+    fn len(&self) -> usize {
+        self.modes.len()
+    }
+
+    /// 列表中出现的包名（不含用户ID和 Activity）
+    fn packages(&self) -> HashSet<String> {
+        self.modes
+            .keys()
+            .map(|key| {
+                let package = key.split_once('/').map_or(key.as_str(), |(pkg, _)| pkg);
+                PackageId::parse(package).package
+            })
+            .collect()
+    }
+
+    /// 是否为该应用配置了按 Activity 区分的模式
+    fn has_activity_rules(&self, id: &PackageId) -> bool {
+        self.modes.keys().any(|key| {
+            key.split_once('/')
+                .is_some_and(|(pkg, _)| PackageId::parse(pkg).package == id.package)
+        })
+    }
+
+    /// 查找应用对应的游戏模式，Activity 条目优先于包名条目，同类条目中指定用户的优先
+    fn mode_for(&self, id: &PackageId, activity: Option<&str>) -> Option<&GameRule> {
+        let candidates = [id.clone(), id.any_user()];
+        activity
+            .and_then(|activity| {
+                candidates
+                    .iter()
+                    .find_map(|id| self.modes.get(&format!("{id}/{activity}")))
+            })
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find_map(|id| self.modes.get(&id.to_string()))
+            })
+    }
+}
+
+/// 将 `.MainActivity` 形式的简写补全为完整类名
+fn normalize_activity(package: &str, activity: &str) -> String {
+    if activity.starts_with('.') {
+        format!("{package}{activity}")
+    } else {
+        activity.to_string()
+    }
+}
+
+/// 前台监控线程的检测状态
+///
+/// 保存在线程之外，监控线程重启后据此恢复，避免把仍在运行的游戏当作新出现的应用，
+/// 或丢失游戏离开前台后的宽限期
+#[derive(Default)]
+struct ForegroundState {
+    package: String,
+    activity: Option<String>,
+    pending_revert: Option<PendingRevert>,
+    background_game: Option<BackgroundGame>,
+}
+
+static FOREGROUND_STATE: Lazy<Mutex<ForegroundState>> =
+    Lazy::new(|| Mutex::new(ForegroundState::default()));
+
+/// 锁定前台检测状态
+///
+/// 监控线程 panic 后会被重启并从这里恢复状态，锁中毒时继续使用其中的数据
+fn foreground_state() -> MutexGuard<'static, ForegroundState> {
+    FOREGROUND_STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 当前前台应用包名（不含用户），尚未检测到时为空
+pub fn current_foreground_package() -> String {
+    PackageId::parse(&foreground_state().package).package
+}
+
+/// 保存游戏退出宽限期状态
+fn save_pending_revert(pending: &Option<PendingRevert>) {
+    foreground_state().pending_revert.clone_from(pending);
+}
+
+/// 保存游戏后台模式状态
+fn save_background_game(background: &Option<BackgroundGame>) {
+    foreground_state().background_game.clone_from(background);
+}
+
 // 缓存前台应用信息，避免频繁调用系统命令
 struct ForegroundAppCache {
+    /// 规范化的前台应用标识（见 [`PackageId`]）
     package_name: String,
+    /// 前台 Activity，仅在游戏列表为该应用配置了 Activity 条目时获取
+    activity: Option<String>,
     last_update: Instant,
 }
 
 impl ForegroundAppCache {
-    fn new() -> Self {
+    /// 从共享状态恢复上一次检测到的前台应用
+    fn restore() -> Self {
+        let state = foreground_state();
         Self {
-            package_name: String::new(),
+            package_name: state.package.clone(),
+            activity: state.activity.clone(),
             last_update: Instant::now(),
         }
     }
@@ -51,8 +218,14 @@ impl ForegroundAppCache {
         self.last_update.elapsed() > ttl
     }
 
-    fn update(&mut self, package_name: String) {
+    fn update(&mut self, package_name: String, activity: Option<String>) {
+        if package_name != self.package_name || activity != self.activity {
+            let mut state = foreground_state();
+            state.package.clone_from(&package_name);
+            state.activity.clone_from(&activity);
+        }
         self.package_name = package_name;
+        self.activity = activity;
         self.last_update = Instant::now();
     }
 }
@@ -85,116 +258,320 @@ impl WarningThrottler {
     }
 }
 
-// 使用dumpsys activity lru命令获取前台应用包名
-fn get_foreground_app_activity() -> Result<String> {
-    debug!("Trying to get foreground app using dumpsys activity lru method");
+/// 使用 dumpsys activity activities 获取指定应用当前显示的 Activity
+fn get_foreground_activity(package: &str) -> Option<String> {
+    static RESUMED_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"ResumedActivity[:=]\s*ActivityRecord\{\S+ \S+ ([\w.]+)/([\w.$]+)").unwrap()
+    });
+
+    let output = Dumpsys::new("activity")?.dump(&["activities"]).ok()?;
+    let caps = RESUMED_RE.captures(&output)?;
+    if &caps[1] != package {
+        debug!("Resumed activity belongs to {}, not {package}", &caps[1]);
+        return None;
+    }
 
-    // 新增：为error日志添加12小时限流器
-    static ERROR_THROTTLER: Lazy<Mutex<WarningThrottler>> =
-        Lazy::new(|| Mutex::new(WarningThrottler::new(43200)));
-    let dumper = loop {
-        match Dumpsys::new("activity") {
-            Some(s) => break s,
-            None => std::thread::sleep(std::time::Duration::from_secs(1)),
-        };
-    };
-    let output = loop {
-        match dumper.dump(&["lru"]) {
-            Ok(d) => break d,
-            Err(e) => {
-                // 线程安全的全局限流器
-                {
-                    let mut throttler = ERROR_THROTTLER.lock().unwrap();
-                    if throttler.should_warn() {
-                        log::error!("Unable to get foreground application: {e}");
-                    } else {
-                        log::debug!("Unable to get foreground application (throttled): {e}");
+    let activity = normalize_activity(package, &caps[2]);
+    debug!("Foreground activity: {package}/{activity}");
+    Some(activity)
+}
+
+// 读取游戏列表
+fn read_games_list(path: &str) -> Result<GamesList> {
+    if !check_read_simple(path) {
+        return Ok(GamesList::default());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read games list file: {path}"))?;
+
+    let config: GamesConfig = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse TOML from games list file: {path}"))?;
+
+    let modes = config
+        .games
+        .into_iter()
+        .map(|entry| {
+            let key = match entry.package.split_once('/') {
+                Some((package, activity)) => {
+                    let id = PackageId::parse(package);
+                    format!("{id}/{}", normalize_activity(&id.package, activity))
+                }
+                None => PackageId::parse(&entry.package).to_string(),
+            };
+            let pinned_freq = match entry.policy {
+                GamePolicy::Reactive => None,
+                GamePolicy::Pinned => Some(entry.pinned_freq.unwrap_or(0).max(0)),
+            };
+            let volt_offset = entry.volt_offset.filter(|&offset| {
+                let valid = offset % VOLT_STEP == 0;
+                if !valid {
+                    warn!("Ignoring volt_offset {offset} for {key}: not a multiple of {VOLT_STEP}");
+                }
+                valid && offset != 0
+            });
+            let phases = entry
+                .phases
+                .into_iter()
+                .filter(|phase| {
+                    let valid = (0..=100).contains(&phase.margin);
+                    if !valid {
+                        warn!(
+                            "Ignoring margin phase at {}s for {key}: margin {} out of range [0, 100]",
+                            phase.after_secs, phase.margin
+                        );
                     }
+                    valid
+                })
+                .collect();
+            let curve = match FreqCurve::new(&entry.curve) {
+                Ok(_) => entry.curve,
+                Err(e) => {
+                    if !entry.curve.is_empty() {
+                        warn!("Ignoring curve for {key}: {e}");
+                    }
+                    Vec::new()
                 }
-                std::thread::sleep(Duration::from_secs(1));
-            }
-        };
-    };
+            };
+            let rule = GameRule {
+                mode: entry.mode,
+                pinned_freq,
+                volt_offset,
+                phases,
+                curve,
+            };
+            (key, rule)
+        })
+        .collect();
+    Ok(GamesList { modes })
+}
 
-    // 使用正则表达式提取前台应用包名
-    let re = Regex::new(r"(\d+):([a-zA-Z][a-zA-Z0-9_]*(\.[a-zA-Z][a-zA-Z0-9_]*)+)/").unwrap();
-    for line in output.lines() {
-        if line.contains("fg") && line.contains("TOP") && !line.contains("BTOP") {
-            debug!("Trying regex on line: {line}");
-
-            // 使用正则表达式提取包名部分
-            if let Some(caps) = re.captures(line) {
-                let package_name = caps[2].to_string();
-                debug!("Extracted package name with regex: {package_name}");
-                return Ok(package_name);
-            }
+/// 游戏离开前台后等待恢复全局模式的状态
+#[derive(Clone)]
+struct PendingRevert {
+    /// 离开前台的游戏包名
+    package: String,
+    /// 仍在生效的游戏规则
+    rule: GameRule,
+    /// 恢复全局模式的时间点
+    deadline: Instant,
+}
+
+/// 游戏后台模式期间检查前台服务的间隔
+const BACKGROUND_SERVICE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 转入后台但仍有前台服务、正在使用游戏后台模式的游戏
+#[derive(Clone)]
+struct BackgroundGame {
+    package: String,
+    /// 下一次检查前台服务的时间点
+    next_check: Instant,
+}
+
+impl BackgroundGame {
+    fn new(package: String) -> Self {
+        Self {
+            package,
+            next_check: Instant::now() + BACKGROUND_SERVICE_CHECK_INTERVAL,
         }
     }
+}
+
+/// 使用 dumpsys activity services 检查应用是否有前台服务（如下载资源）
+fn has_foreground_service(package: &str) -> bool {
+    Dumpsys::new("activity")
+        .and_then(|dumper| dumper.dump(&["services", package]).ok())
+        .is_some_and(|output| output.contains("isForeground=true"))
+}
 
-    // 如果上面的匹配失败，记录一些调试信息
-    debug!("Failed to find foreground app using dumpsys activity lru method");
-    debug!("Dumpsys activity lru output first few lines:");
-    for (i, line) in output.lines().take(5).enumerate() {
-        debug!("Line {}: {}", i + 1, line);
+/// 游戏离开前台后退出游戏模式：游戏仍有前台服务且配置了游戏后台模式时改用该模式，否则恢复全局模式
+fn leave_game_mode(
+    gpu: &mut GPU,
+    tx: &Option<Sender<ConfigDelta>>,
+    config: &ForegroundConfig,
+    package: &str,
+) -> Option<BackgroundGame> {
+    if config.game_background && has_foreground_service(&PackageId::parse(package).package) {
+        info!(
+            "{package} keeps a foreground service in background, applying {GAME_BACKGROUND_MODE} mode"
+        );
+        apply_mode(gpu, tx, Some(&GameRule::for_mode(GAME_BACKGROUND_MODE)));
+        return Some(BackgroundGame::new(package.to_string()));
     }
-    debug!("Lines containing 'TOP':");
-    for line in output.lines().filter(|l| l.contains("TOP")) {
-        debug!("Line with TOP: {line}");
+    apply_mode(gpu, tx, None);
+    None
+}
+
+/// 读取前台监控线程使用的配置
+///
+/// 需要 `[global]` 和 `[game_background]` 中的设置，因此解析整个配置文件，解析失败时返回错误
+fn read_foreground_config() -> Result<ForegroundConfig> {
+    let content = std::fs::read_to_string(active_config_path())?;
+    Ok(toml::from_str::<Config>(&content)?.foreground_config())
+}
+
+/// 加载指定游戏规则的模式（`None` 表示全局模式）并将配置增量发送到主调频循环
+fn apply_mode(gpu: &mut GPU, tx: &Option<Sender<ConfigDelta>>, rule: Option<&GameRule>) {
+    let target_mode = rule.map(|rule| rule.mode.as_str());
+    let mode_name = target_mode.unwrap_or("global");
+    if let Err(e) = load_config(gpu, target_mode) {
+        warn!("Failed to apply {mode_name} mode: {e}");
+        return;
     }
-    Err(anyhow!(
-        "Failed to find foreground app in dumpsys activity lru output"
-    ))
-}
-
-// 获取前台应用包名
-fn get_foreground_app() -> Result<String> {
-    // 直接使用activity lru方法
-    match get_foreground_app_activity() {
-        Ok(package_name) => {
-            debug!("Successfully got foreground app using activity lru method: {package_name}");
-            Ok(package_name)
-        }
-        Err(e) => {
-            // 如果失败，直接返回错误
-            debug!("Activity lru method failed: {e}");
-            Err(anyhow!("Failed to get foreground app: {e}"))
+
+    let Some(sender) = tx else {
+        return;
+    };
+    match read_config_delta(target_mode) {
+        Ok(mut delta) => {
+            // 恢复全局模式即放弃游戏模式的控制，由调频线程的模式仲裁决定接管的模式
+            delta.mode_source = Some(ModeSource::Game);
+            delta.release = target_mode.is_none();
+            if let Some(mode) = target_mode {
+                delta.mode = Some(mode.to_string());
+            }
+            delta.pinned_freq = rule.and_then(|rule| rule.pinned_freq);
+            delta.volt_offset = rule.and_then(|rule| rule.volt_offset);
+            delta.margin_phases = rule.map(|rule| rule.phases.clone()).unwrap_or_default();
+            delta.freq_curve = rule.map(|rule| rule.curve.clone()).unwrap_or_default();
+            if sender.send(delta).is_ok() {
+                info!("{mode_name} mode config delta sent to main loop");
+            } else {
+                warn!("Failed to send {mode_name} mode config delta");
+            }
         }
+        Err(e) => warn!("Failed to read config delta for {mode_name} mode: {e}"),
     }
 }
 
-// 读取游戏列表
-fn read_games_list(path: &str) -> Result<HashMap<String, String>> {
-    if !check_read_simple(path) {
-        return Ok(HashMap::new());
+/// 前台应用变化引起的游戏模式切换
+#[derive(Debug, PartialEq, Eq)]
+enum ModeSwitch {
+    /// 应用新前台游戏（或游戏界面）的规则
+    Enter(GameRule),
+    /// 游戏离开前台，恢复全局模式
+    Leave(GameRule),
+}
+
+/// 一次前台应用（或 Activity）变化
+struct ForegroundChange {
+    /// 规范化的新前台应用标识
+    package_name: String,
+    activity: Option<String>,
+    /// 上一个前台应用，尚未检测到时为空
+    prev_package: String,
+    /// 新前台应用匹配的游戏规则
+    rule: Option<GameRule>,
+    /// 上一个前台应用匹配的游戏规则
+    prev_rule: Option<GameRule>,
+}
+
+impl ForegroundChange {
+    /// 需要执行的游戏模式切换，同一游戏内切换到规则相同的界面时无需切换
+    fn mode_switch(&self) -> Option<ModeSwitch> {
+        match (&self.rule, &self.prev_rule) {
+            (Some(rule), prev) if prev.as_ref() != Some(rule) => {
+                Some(ModeSwitch::Enter(rule.clone()))
+            }
+            (None, Some(prev)) => Some(ModeSwitch::Leave(prev.clone())),
+            _ => None,
+        }
     }
+}
 
-    let content = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read games list file: {path}"))?;
+/// 通过 `provider` 检测前台应用，前台应用或 Activity 变化时更新缓存并返回这次变化
+///
+/// 前台应用未变化或在忽略列表中（如下拉通知栏）时返回 `None`
+fn poll_foreground<P: ForegroundProvider>(
+    provider: &mut P,
+    games: &GamesList,
+    config: &ForegroundConfig,
+    app_cache: &mut ForegroundAppCache,
+) -> Result<Option<ForegroundChange>> {
+    let raw_package = provider.foreground_package()?;
+    // 去掉用户前缀和进程后缀，统一为规范形式后再查找游戏列表
+    let id = PackageId::parse(&raw_package);
+    let package_name = id.to_string();
+    let activity = if games.has_activity_rules(&id) {
+        get_foreground_activity(&id.package)
+    } else {
+        None
+    };
 
-    let config: GamesConfig = toml::from_str(&content)
-        .with_context(|| format!("Failed to parse TOML from games list file: {path}"))?;
+    // 只有当包名或 Activity 变化时才处理
+    if package_name == app_cache.package_name && activity == app_cache.activity {
+        app_cache.update(package_name, activity);
+        return Ok(None);
+    }
+    // 下拉通知栏等短暂焦点不算前台切换，保持上一个应用的状态
+    if config.is_ignored(&id.package) {
+        debug!("Ignoring foreground package {package_name}");
+        return Ok(None);
+    }
 
-    Ok(config
-        .games
-        .into_iter()
-        .map(|entry| (entry.package, entry.mode))
-        .collect())
+    let rule = games.mode_for(&id, activity.as_deref()).cloned();
+    let prev_rule = if app_cache.package_name.is_empty() {
+        None
+    } else {
+        games
+            .mode_for(
+                &PackageId::parse(&app_cache.package_name),
+                app_cache.activity.as_deref(),
+            )
+            .cloned()
+    };
+    let prev_package = app_cache.package_name.clone();
+    app_cache.update(package_name.clone(), activity.clone());
+
+    Ok(Some(ForegroundChange {
+        package_name,
+        activity,
+        prev_package,
+        rule,
+        prev_rule,
+    }))
 }
 
-// 监控前台应用
-pub fn monitor_foreground_app(mut gpu: GPU, tx: Option<Sender<ConfigDelta>>) -> Result<()> {
+/// 监控前台应用，通过 `provider` 检测前台应用包名
+pub fn monitor_foreground_app<P: ForegroundProvider>(
+    mut gpu: GPU,
+    tx: Option<Sender<ConfigDelta>>,
+    mut provider: P,
+) -> Result<()> {
     // 设置线程名称
     info!("{FOREGROUND_APP_THREAD} Start");
 
-    // 初始化缓存
-    let mut app_cache = ForegroundAppCache::new();
+    // system_server 重启后 binder 连接和前台缓存都已失效
+    let mut system_server = SystemServerWatcher::new();
+
+    // 初始化缓存，线程重启时从共享状态恢复
+    let mut app_cache = ForegroundAppCache::restore();
+    if !app_cache.package_name.is_empty() {
+        info!(
+            "Resuming foreground tracking from {}",
+            app_cache.package_name
+        );
+    }
     let cache_ttl = Duration::from_millis(1000); // 缓存有效期1秒
     // 初始化警告限流器，设置60秒的限流时间
     let mut warning_throttler = WarningThrottler::new(43200); // 12小时限流
 
+    // 前台检测配置，配置文件变化后重新读取
+    let mut config_seen = config_generation();
+    let mut config = read_foreground_config().unwrap_or_else(|e| {
+        warn!("Failed to read config for foreground monitor: {e}, using [foreground] only");
+        read_section("foreground")
+    });
+
+    // 游戏列表不存在时按配置输出说明或生成初始列表
+    if !check_read_simple(GAMES_CONF_PATH) {
+        handle_missing_games_list(GAMES_CONF_PATH, &config);
+    }
+
     // 读取游戏列表
     let mut games = read_games_list(GAMES_CONF_PATH)?;
     info!("Loaded {} games from {}", games.len(), GAMES_CONF_PATH);
+    provider.set_known_packages(&games.packages());
 
     // 设置文件监控
     let mut inotify = InotifyWatcher::new()?;
@@ -207,52 +584,136 @@ pub fn monitor_foreground_app(mut gpu: GPU, tx: Option<Sender<ConfigDelta>>) ->
         info!("Games list file does not exist: {GAMES_CONF_PATH}");
     }
 
+    // 游戏离开前台后等待恢复全局模式
+    let mut pending_revert = foreground_state().pending_revert.clone();
+    // 游戏转入后台但仍有前台服务时使用游戏后台模式
+    let mut background_game = foreground_state().background_game.clone();
+    // 根据负载识别未列入游戏列表的游戏
+    let mut classifier = WorkloadClassifier::new(config.game_detection.clone());
+
     // 主循环
     loop {
+        tick_play_time();
+
+        // 配置文件变化后重新读取前台检测配置
+        let generation = config_generation();
+        if generation != config_seen {
+            config_seen = generation;
+            match read_foreground_config() {
+                Ok(reloaded) => {
+                    classifier.set_config(reloaded.game_detection.clone());
+                    config = reloaded;
+                }
+                Err(e) => warn!("Failed to reload foreground config: {e}, keeping previous"),
+            }
+        }
+
+        // 软重启完成后清空前台缓存、重建 dumpsys 连接并恢复全局模式
+        if system_server.check() {
+            info!("Resyncing foreground state after system_server restart");
+            provider.reset();
+            app_cache.update(String::new(), None);
+            pending_revert = None;
+            save_pending_revert(&pending_revert);
+            background_game = None;
+            save_background_game(&background_game);
+            classifier.leave_auto_mode("");
+            record_foreground_game(None);
+            apply_mode(&mut gpu, &tx, None);
+            update_status(|s| s.system_server_restarts += 1);
+        }
+
+        // 宽限期结束且游戏未返回，退出游戏模式
+        if let Some(pending) = pending_revert.take_if(|pending| Instant::now() >= pending.deadline)
+        {
+            save_pending_revert(&pending_revert);
+            info!("Game exit grace period expired, leaving game mode");
+            background_game = leave_game_mode(&mut gpu, &tx, &config, &pending.package);
+            save_background_game(&background_game);
+        }
+
+        // 后台游戏的前台服务结束后恢复全局模式
+        if let Some(background) = &mut background_game
+            && Instant::now() >= background.next_check
+        {
+            if has_foreground_service(&PackageId::parse(&background.package).package) {
+                background.next_check = Instant::now() + BACKGROUND_SERVICE_CHECK_INTERVAL;
+            } else {
+                info!(
+                    "{} has no foreground service left, reverting to global mode",
+                    background.package
+                );
+                background_game = None;
+                apply_mode(&mut gpu, &tx, None);
+            }
+            save_background_game(&background_game);
+        }
+
         // 检查inotify事件，只在游戏列表文件变化时才重新读取
         if let Ok(events) = inotify.check_events()
             && !events.is_empty()
         {
             debug!("Detected changes in games list file");
             games = read_games_list(GAMES_CONF_PATH)?;
+            provider.set_known_packages(&games.packages());
             info!(
                 "The game configuration file has changed. Loaded {} games.",
                 games.len()
             );
         }
 
+        // 未列入游戏列表的前台应用持续高负载时判定为疑似游戏
+        let listed = games
+            .mode_for(
+                &PackageId::parse(&app_cache.package_name),
+                app_cache.activity.as_deref(),
+            )
+            .is_some();
+        if let Some(mode) = classifier.observe(&app_cache.package_name, listed) {
+            apply_mode(&mut gpu, &tx, Some(&GameRule::for_mode(&mode)));
+        }
+
         // 获取前台应用
         if app_cache.is_expired(cache_ttl) {
-            match get_foreground_app() {
-                Ok(package_name) => {
-                    // 只有当包名变化时才处理
-                    if package_name == app_cache.package_name {
-                        // 包名未变化,更新缓存时间戳后继续下一次循环
-                        app_cache.update(package_name);
-                        thread::sleep(Duration::from_millis(1000));
-                        continue;
-                    }
+            match poll_foreground(&mut provider, &games, &config, &mut app_cache) {
+                Ok(None) => {}
+                Ok(Some(change)) => {
+                    let package_name = &change.package_name;
+                    let activity = &change.activity;
+                    let prev_package = &change.prev_package;
+
                     // 将前台应用变化的日志改为debug级别
-                    debug!("Foreground app changed: {package_name}");
+                    debug!("Foreground app changed: {package_name} ({activity:?})");
+
+                    // 检查是否是游戏（或配置了模式的游戏界面）
+                    let is_game = change.rule.is_some();
 
-                    // 检查是否是游戏
-                    let is_game = games.contains_key(&package_name); // 将 contains 改为 contains_key
+                    // 离开自动应用了游戏模式的疑似游戏
+                    if classifier.leave_auto_mode(package_name) && !is_game {
+                        info!("Likely game left foreground, reverting to global mode");
+                        apply_mode(&mut gpu, &tx, None);
+                    }
 
                     // 检查前一个应用是否是游戏
-                    let prev_is_game = !app_cache.package_name.is_empty()
-                        && games.contains_key(&app_cache.package_name); // 将 contains 改为 contains_key
+                    let prev_is_game = change.prev_rule.is_some();
+                    let same_package = package_name == prev_package;
 
                     // 只有在游戏模式状态变化时才记录info级别日志
                     if is_game {
                         if !prev_is_game {
                             info!("Game mode enabled: {package_name}");
+                        } else if same_package {
+                            info!(
+                                "Game activity changed: {package_name}/{}",
+                                activity.as_deref().unwrap_or("-")
+                            );
                         } else {
                             // 游戏切换到另一个游戏时也记录
                             info!("Game changed: {package_name}");
                         }
                     } else if prev_is_game {
                         // 读取全局模式名称用于日志显示
-                        let global_mode = match std::fs::read_to_string(CONFIG_TOML_FILE) {
+                        let global_mode = match std::fs::read_to_string(active_config_path()) {
                             Ok(content) => match toml::from_str::<Config>(&content) {
                                 Ok(config) => config.global_mode().to_string(),
                                 Err(_) => "balance".to_string(), // 默认模式
@@ -260,67 +721,57 @@ pub fn monitor_foreground_app(mut gpu: GPU, tx: Option<Sender<ConfigDelta>>) ->
                             Err(_) => "balance".to_string(), // 默认模式
                         };
                         info!(
-                            "Game mode disabled: switching to global mode ({global_mode}): {package_name}"
+                            "Game left foreground, global mode ({global_mode}) pending: {prev_package}"
                         );
                     }
 
                     // 根据应用类型写入对应的模式文件
-                    if is_game {
-                        if let Some(target_mode) = games.get(&package_name) {
-                            info!("Game detected, applying {target_mode} mode");
-                            if let Err(e) = load_config(&mut gpu, Some(target_mode)) {
-                                warn!("Failed to apply game-specific mode: {e}");
+                    match change.mode_switch() {
+                        Some(ModeSwitch::Enter(target_rule)) => {
+                            // 游戏回到前台或切换到其他游戏，游戏后台模式结束
+                            if background_game.take().is_some() {
+                                save_background_game(&background_game);
+                            }
+                            let returned = pending_revert.take().is_some_and(|pending| {
+                                &pending.package == package_name && pending.rule == target_rule
+                            });
+                            if returned {
+                                // 宽限期内回到同一个游戏，游戏模式仍然生效
+                                info!("Game returned within grace period: {package_name}");
                             } else {
-                                // 通过 channel 发送配置增量到主调频循环
-                                if let Some(ref sender) = tx {
-                                    match crate::datasource::config_parser::read_config_delta(Some(
-                                        target_mode,
-                                    )) {
-                                        Ok(delta) => {
-                                            if sender.send(delta).is_ok() {
-                                                info!(
-                                                    "Game mode config delta sent to main loop: {}",
-                                                    target_mode
-                                                );
-                                            } else {
-                                                warn!("Failed to send game mode config delta");
-                                            }
-                                        }
-                                        Err(e) => {
-                                            warn!("Failed to read config delta for game mode: {e}")
-                                        }
-                                    }
-                                }
+                                info!("Game detected, applying {} mode", target_rule.mode);
+                                apply_mode(&mut gpu, &tx, Some(&target_rule));
                             }
                         }
-                    } else if prev_is_game {
-                        // 只有从游戏模式切换到非游戏时才需要恢复全局模式
-                        if let Err(e) = load_config(&mut gpu, None) {
-                            warn!("Failed to revert to global mode: {e}");
-                        } else {
-                            // 通过 channel 发送配置增量到主调频循环
-                            if let Some(ref sender) = tx {
-                                match crate::datasource::config_parser::read_config_delta(None) {
-                                    Ok(delta) => {
-                                        if sender.send(delta).is_ok() {
-                                            info!("Global mode config delta sent to main loop");
-                                        } else {
-                                            warn!("Failed to send global mode config delta");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to read config delta for global mode: {e}")
-                                    }
-                                }
+                        Some(ModeSwitch::Leave(prev_rule)) => {
+                            // 只有从游戏模式切换到非游戏时才需要恢复全局模式
+                            let grace = config.game_exit_grace;
+                            if grace.is_zero() {
+                                background_game =
+                                    leave_game_mode(&mut gpu, &tx, &config, prev_package);
+                                save_background_game(&background_game);
+                            } else {
+                                info!(
+                                    "Game left foreground, keeping game mode for {}ms",
+                                    grace.as_millis()
+                                );
+                                pending_revert = Some(PendingRevert {
+                                    package: prev_package.clone(),
+                                    rule: prev_rule,
+                                    deadline: Instant::now() + grace,
+                                });
                             }
                         }
+                        // 同一游戏内规则未变，或前后都不是游戏，不需要做任何操作
+                        None => {}
                     }
-                    // 如果之前不是游戏且当前也不是游戏，则不需要做任何操作
 
-                    // 更新缓存
-                    app_cache.update(package_name);
+                    record_foreground_game(is_game.then_some(package_name.as_str()));
+                    save_pending_revert(&pending_revert);
                 }
                 Err(e) => {
+                    // binder 调用失败可能是 system_server 正在重启，尽快确认
+                    system_server.check_soon();
                     // 使用警告限流器检查是否应该显示警告
                     if warning_throttler.should_warn() {
                         warn!("Failed to get foreground app: {e}");
@@ -336,3 +787,74 @@ pub fn monitor_foreground_app(mut gpu: GPU, tx: Option<Sender<ConfigDelta>>) ->
         thread::sleep(Duration::from_millis(1000));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    /// 按顺序返回预设包名的检测方式
+    struct StubProvider(VecDeque<&'static str>);
+
+    impl ForegroundProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        fn foreground_package(&mut self) -> Result<String> {
+            self.0
+                .pop_front()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("no foreground package"))
+        }
+    }
+
+    #[test]
+    fn package_sequence_switches_game_mode() {
+        let path = std::env::temp_dir().join(format!("games-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[[games]]\npackage = \"com.example.game\"\nmode = \"performance\"\n",
+        )
+        .unwrap();
+        let games = read_games_list(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut provider = StubProvider(VecDeque::from([
+            "com.android.launcher3",
+            "com.example.game:render",
+            "com.example.game",
+            "com.android.systemui",
+            "com.android.launcher3",
+        ]));
+        let mut app_cache = ForegroundAppCache {
+            package_name: String::new(),
+            activity: None,
+            last_update: Instant::now(),
+        };
+
+        let config = ForegroundConfig::default();
+        let mut switches = Vec::new();
+        while let Ok(change) = poll_foreground(&mut provider, &games, &config, &mut app_cache) {
+            switches.push(change.and_then(|change| change.mode_switch()));
+        }
+
+        let performance = GameRule::for_mode("performance");
+        assert_eq!(
+            switches,
+            [
+                None,
+                Some(ModeSwitch::Enter(performance.clone())),
+                // 子进程后缀规范化后仍是同一个应用
+                None,
+                // 下拉通知栏不算离开游戏
+                None,
+                Some(ModeSwitch::Leave(performance)),
+            ]
+        );
+        assert_eq!(app_cache.package_name, "com.android.launcher3");
+    }
+}
@@ -0,0 +1,400 @@
+//! 前台应用检测方式
+//!
+//! 各检测方式实现 [`ForegroundProvider`]，由 `[foreground] provider` 选择；
+//! `auto` 时启动探测第一个可用的 dumpsys 方式，连续失败后回退到 top-app cgroup。
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt,
+    os::unix::fs::MetadataExt,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    datasource::{
+        config_parser::ForegroundProviderKind, dumpsys::Dumpsys, file_path::TOP_APP_CGROUP_PROCS,
+        package_id::PackageId,
+    },
+    utils::status_report::update_status,
+};
+
+/// dumpsys 首次失败后的重试间隔
+const DUMPSYS_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// dumpsys 重试间隔上限
+const DUMPSYS_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// dumpsys 连续失败多少次后改用 cgroup 检测
+const DUMPSYS_FALLBACK_FAILURES: u32 = 5;
+
+/// Android 每个用户占用的UID范围
+const PER_USER_RANGE: u32 = 100_000;
+
+/// 前台应用检测方式
+pub trait ForegroundProvider {
+    /// 检测方式名称，写入 status.json
+    fn name(&self) -> &'static str;
+
+    /// 获取前台应用包名，非主用户的应用以 `包名@用户ID` 返回
+    fn foreground_package(&mut self) -> Result<String>;
+
+    /// system_server 重启后重置内部状态
+    fn reset(&mut self) {}
+
+    /// 设置游戏列表中的包名，检测方式可据此跳过完整解析
+    fn set_known_packages(&mut self, _packages: &HashSet<String>) {}
+}
+
+/// 检测方式本身不可用（如无法连接系统服务），区别于输出中找不到前台应用
+#[derive(Debug)]
+pub struct ProviderUnavailable(String);
+
+impl fmt::Display for ProviderUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for ProviderUnavailable {}
+
+/// 执行 dumpsys，失败说明无法连接对应服务
+fn dump_service(service: &str, args: &[&str]) -> Result<String> {
+    let dumper = Dumpsys::new(service)
+        .ok_or_else(|| ProviderUnavailable(format!("{service} service is not available")))?;
+    dumper.dump(args).map_err(|e| {
+        ProviderUnavailable(format!("dumpsys {service} {} failed: {e}", args.join(" "))).into()
+    })
+}
+
+/// 通过 dumpsys activity lru 检测
+///
+/// 游戏运行时前台应用基本不变，TOP 行中的进程是游戏列表中的包名时直接按字符串匹配返回，
+/// 不再对该行执行正则
+#[derive(Default)]
+pub struct DumpsysLruProvider {
+    /// 游戏列表中的包名
+    known_packages: HashSet<String>,
+}
+
+impl DumpsysLruProvider {
+    /// TOP 行中的进程为已知包名时返回 `包名@用户ID` 形式的包名
+    fn known_package_in(&self, line: &str) -> Option<String> {
+        self.known_packages.iter().find_map(|package| {
+            let (_, rest) = line.split_once(&format!(":{package}/"))?;
+            // 进程UID形如 u10a123，u 后为用户ID
+            let user = rest.strip_prefix('u').and_then(|uid| {
+                let digits = uid.find(|c: char| !c.is_ascii_digit()).unwrap_or(uid.len());
+                uid[..digits].parse().ok()
+            });
+            Some(PackageId::new(package, user).to_string())
+        })
+    }
+
+    // 从dumpsys activity lru输出中提取前台应用包名
+    fn parse(&self, output: &str) -> Result<String> {
+        static RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(\d+):([a-zA-Z][a-zA-Z0-9_]*(\.[a-zA-Z][a-zA-Z0-9_]*)+)/(?:u(\d+))?")
+                .unwrap()
+        });
+        for line in output.lines() {
+            if line.contains("fg") && line.contains("TOP") && !line.contains("BTOP") {
+                if let Some(package_name) = self.known_package_in(line) {
+                    debug!("Matched known package on TOP line: {package_name}");
+                    return Ok(package_name);
+                }
+                debug!("Trying regex on line: {line}");
+
+                // 使用正则表达式提取包名部分
+                if let Some(caps) = RE.captures(line) {
+                    // 进程UID形如 u10a123，u 后为用户ID
+                    let user = caps.get(4).and_then(|user| user.as_str().parse().ok());
+                    let package_name = PackageId::new(&caps[2], user).to_string();
+                    debug!("Extracted package name with regex: {package_name}");
+                    return Ok(package_name);
+                }
+            }
+        }
+
+        // 如果上面的匹配失败，记录一些调试信息
+        debug!("Failed to find foreground app using dumpsys activity lru method");
+        debug!("Dumpsys activity lru output first few lines:");
+        for (i, line) in output.lines().take(5).enumerate() {
+            debug!("Line {}: {}", i + 1, line);
+        }
+        debug!("Lines containing 'TOP':");
+        for line in output.lines().filter(|l| l.contains("TOP")) {
+            debug!("Line with TOP: {line}");
+        }
+        Err(anyhow!(
+            "Failed to find foreground app in dumpsys activity lru output"
+        ))
+    }
+}
+
+impl ForegroundProvider for DumpsysLruProvider {
+    fn name(&self) -> &'static str {
+        "dumpsys-lru"
+    }
+
+    fn foreground_package(&mut self) -> Result<String> {
+        debug!("Trying to get foreground app using dumpsys activity lru method");
+        self.parse(&dump_service("activity", &["lru"])?)
+    }
+
+    fn set_known_packages(&mut self, packages: &HashSet<String>) {
+        self.known_packages = packages.clone();
+    }
+}
+
+/// 通过 dumpsys window 的焦点窗口检测，适用于 lru 输出格式不兼容的系统
+pub struct DumpsysWindowProvider;
+
+impl DumpsysWindowProvider {
+    fn parse(output: &str) -> Result<String> {
+        static FOCUS_RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"(?:mCurrentFocus|mFocusedApp)=\S*\{[^}]*?(?: u(\d+))? ([a-zA-Z][a-zA-Z0-9_]*(?:\.[a-zA-Z][a-zA-Z0-9_]*)+)/",
+            )
+            .unwrap()
+        });
+        FOCUS_RE
+            .captures(output)
+            .map(|caps| {
+                let user = caps.get(1).and_then(|user| user.as_str().parse().ok());
+                PackageId::new(&caps[2], user).to_string()
+            })
+            .ok_or_else(|| anyhow!("Failed to find focused window in dumpsys window output"))
+    }
+}
+
+impl ForegroundProvider for DumpsysWindowProvider {
+    fn name(&self) -> &'static str {
+        "dumpsys-window"
+    }
+
+    fn foreground_package(&mut self) -> Result<String> {
+        debug!("Trying to get foreground app using dumpsys window method");
+        Self::parse(&dump_service("window", &["windows"])?)
+    }
+}
+
+/// 通过 top-app cgroup 检测，不依赖 binder
+///
+/// top-app 中 oom_score_adj 为0的应用进程即前台应用，子进程名（`包名:进程`）取冒号前的部分
+pub struct CgroupProvider;
+
+impl ForegroundProvider for CgroupProvider {
+    fn name(&self) -> &'static str {
+        "cgroup"
+    }
+
+    fn foreground_package(&mut self) -> Result<String> {
+        let procs = std::fs::read_to_string(TOP_APP_CGROUP_PROCS)
+            .with_context(|| format!("Failed to read {TOP_APP_CGROUP_PROCS}"))?;
+
+        for pid in procs.lines().map(str::trim).filter(|p| !p.is_empty()) {
+            let is_foreground = std::fs::read_to_string(format!("/proc/{pid}/oom_score_adj"))
+                .is_ok_and(|adj| adj.trim() == "0");
+            if !is_foreground {
+                continue;
+            }
+
+            let Ok(cmdline) = std::fs::read(format!("/proc/{pid}/cmdline")) else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(&cmdline);
+            let process = name.split('\0').next().unwrap_or_default();
+            let package = process.split(':').next().unwrap_or_default();
+            if !package.starts_with('/') && package.contains('.') {
+                let user = std::fs::metadata(format!("/proc/{pid}"))
+                    .ok()
+                    .map(|meta| meta.uid() / PER_USER_RANGE);
+                let package = PackageId::new(package, user).to_string();
+                debug!("Foreground app from top-app cgroup: {package} (pid {pid})");
+                return Ok(package);
+            }
+        }
+
+        Err(anyhow!("No foreground app process found in top-app cgroup"))
+    }
+}
+
+/// 按配置创建单个检测方式，`auto` 时依次探测 dumpsys lru 和 dumpsys window
+fn create_provider(kind: ForegroundProviderKind) -> Box<dyn ForegroundProvider> {
+    match kind {
+        ForegroundProviderKind::DumpsysLru => Box::new(DumpsysLruProvider::default()),
+        ForegroundProviderKind::DumpsysWindow => Box::new(DumpsysWindowProvider),
+        ForegroundProviderKind::Cgroup => Box::new(CgroupProvider),
+        ForegroundProviderKind::Auto => {
+            let candidates: [Box<dyn ForegroundProvider>; 2] = [
+                Box::new(DumpsysLruProvider::default()),
+                Box::new(DumpsysWindowProvider),
+            ];
+            for mut candidate in candidates {
+                match candidate.foreground_package() {
+                    Ok(_) => {
+                        info!("Foreground provider probed: {}", candidate.name());
+                        return candidate;
+                    }
+                    Err(e) => debug!("Foreground provider {} unusable: {e}", candidate.name()),
+                }
+            }
+            Box::new(DumpsysLruProvider::default())
+        }
+    }
+}
+
+/// 前台应用检测器
+///
+/// 主检测方式连接系统服务失败时按指数退避重试；`auto` 模式下连续失败达到阈值后
+/// 改用 top-app cgroup 检测，并继续按最大退避间隔尝试恢复主检测方式
+pub struct ForegroundDetector {
+    primary: Box<dyn ForegroundProvider>,
+    /// 主检测方式不可用时的备用方式
+    fallback: Option<Box<dyn ForegroundProvider>>,
+    /// 是否正在使用备用方式
+    using_fallback: bool,
+    /// 主检测方式连续失败次数
+    failures: u32,
+    /// 当前退避间隔
+    backoff: Duration,
+    /// 下一次允许尝试主检测方式的时间
+    next_retry: Instant,
+}
+
+impl ForegroundDetector {
+    pub fn new(kind: ForegroundProviderKind) -> Self {
+        let fallback: Option<Box<dyn ForegroundProvider>> = match kind {
+            ForegroundProviderKind::Auto => Some(Box::new(CgroupProvider)),
+            _ => None,
+        };
+        let detector = Self {
+            primary: create_provider(kind),
+            fallback,
+            using_fallback: false,
+            failures: 0,
+            backoff: DUMPSYS_INITIAL_BACKOFF,
+            next_retry: Instant::now(),
+        };
+        detector.publish_status();
+        detector
+    }
+
+    fn publish_status(&self) {
+        let backend = self.name();
+        let healthy = self.failures == 0;
+        update_status(|s| {
+            s.foreground_backend = Some(backend.to_string());
+            s.dumpsys_healthy = healthy;
+            s.dumpsys_failures = self.failures;
+        });
+    }
+
+    fn on_primary_success(&mut self) {
+        if self.failures > 0 {
+            info!(
+                "{} recovered after {} failed attempt(s)",
+                self.primary.name(),
+                self.failures
+            );
+        }
+        if self.using_fallback {
+            info!(
+                "Switching foreground detection back to {}",
+                self.primary.name()
+            );
+        }
+        let changed = self.failures > 0 || self.using_fallback;
+        self.using_fallback = false;
+        self.failures = 0;
+        self.backoff = DUMPSYS_INITIAL_BACKOFF;
+        if changed {
+            self.publish_status();
+        }
+    }
+
+    fn on_primary_failure(&mut self, e: &anyhow::Error) {
+        self.failures += 1;
+        self.next_retry = Instant::now() + self.backoff;
+        self.backoff = (self.backoff * 2).min(DUMPSYS_MAX_BACKOFF);
+
+        let name = self.primary.name();
+        if self.failures == 1 {
+            warn!("{name} failed, retrying with backoff: {e}");
+        } else {
+            debug!("{name} failed ({} in a row): {e}", self.failures);
+        }
+        if let Some(fallback) = &self.fallback
+            && !self.using_fallback
+            && self.failures >= DUMPSYS_FALLBACK_FAILURES
+        {
+            warn!(
+                "{name} failed {} times in a row, falling back to {} detection",
+                self.failures,
+                fallback.name()
+            );
+            self.using_fallback = true;
+        }
+        self.publish_status();
+    }
+}
+
+impl ForegroundProvider for ForegroundDetector {
+    fn name(&self) -> &'static str {
+        match &self.fallback {
+            Some(fallback) if self.using_fallback => fallback.name(),
+            _ => self.primary.name(),
+        }
+    }
+
+    fn foreground_package(&mut self) -> Result<String> {
+        if Instant::now() >= self.next_retry {
+            match self.primary.foreground_package() {
+                Ok(package) => {
+                    self.on_primary_success();
+                    return Ok(package);
+                }
+                Err(e) if e.is::<ProviderUnavailable>() => self.on_primary_failure(&e),
+                // 服务可用但输出中没有前台应用，不计入失败
+                Err(e) => {
+                    self.on_primary_success();
+                    return Err(e);
+                }
+            }
+        }
+
+        match &mut self.fallback {
+            Some(fallback) if self.using_fallback => fallback.foreground_package(),
+            _ => Err(anyhow!(
+                "{} unavailable, retrying in {}s",
+                self.primary.name(),
+                self.next_retry
+                    .saturating_duration_since(Instant::now())
+                    .as_secs()
+            )),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.primary.reset();
+        if let Some(fallback) = &mut self.fallback {
+            fallback.reset();
+        }
+        self.using_fallback = false;
+        self.failures = 0;
+        self.backoff = DUMPSYS_INITIAL_BACKOFF;
+        self.next_retry = Instant::now();
+        self.publish_status();
+    }
+
+    fn set_known_packages(&mut self, packages: &HashSet<String>) {
+        self.primary.set_known_packages(packages);
+        if let Some(fallback) = &mut self.fallback {
+            fallback.set_known_packages(packages);
+        }
+    }
+}
@@ -3,12 +3,24 @@ use std::{
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
+    thread,
 };
 
 use anyhow::{Context, Result};
 use log::{debug, info, warn};
-
-use crate::{datasource::file_path::*, model::gpu::GPU, utils::file_operate::check_read_simple};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    datasource::{
+        bandwidth_monitor::BandwidthMonitor, driver_quirks::apply_driver_quirks, file_path::*,
+    },
+    model::{
+        freq::{Freq, FreqUnit},
+        gpu::GPU,
+    },
+    utils::file_operate::check_read_simple,
+};
 
 // 检测GPU驱动类型，但不读取系统支持的频率表
 fn detect_gpu_driver_type(gpu: &mut GPU) -> Result<()> {
@@ -74,14 +86,14 @@ fn detect_gpu_driver_type(gpu: &mut GPU) -> Result<()> {
     Ok(())
 }
 
-// 读取v2 driver设备的频率表
-fn read_v2_driver_freq_table() -> Result<Vec<i64>> {
+// 读取v2 driver设备的频率表，返回转换为KHz的频率列表和频率表使用的单位
+fn read_v2_driver_freq_table() -> Result<(Vec<Freq>, FreqUnit)> {
     let mut freq_list = Vec::new();
 
     // 检查频率表文件是否存在
     if !fs::exists(GPUFREQV2_TABLE).unwrap_or(false) || !check_read_simple(GPUFREQV2_TABLE) {
         warn!("V2 driver frequency table file not found: {GPUFREQV2_TABLE}");
-        return Ok(freq_list);
+        return Ok((Vec::new(), FreqUnit::KHz));
     }
 
     // 打开并读取频率表文件
@@ -105,12 +117,22 @@ fn read_v2_driver_freq_table() -> Result<Vec<i64>> {
         }
     }
 
+    // 部分内核的频率表以Hz为单位，统一转换为KHz
+    let unit = FreqUnit::detect(freq_list.iter().copied());
+    if unit != FreqUnit::KHz {
+        info!("V2 driver frequency table is in {unit}, converting to KHz");
+    }
+    let mut freq_list: Vec<Freq> = freq_list
+        .into_iter()
+        .map(|freq| Freq::from_unit(freq, unit))
+        .collect();
+
     // 按降序排序（从高到低）
     freq_list.sort_by(|a, b| b.cmp(a));
 
     info!("Read {} frequencies from V2 driver table", freq_list.len());
 
-    Ok(freq_list)
+    Ok((freq_list, unit))
 }
 
 // 检测内存频率控制文件
@@ -152,24 +174,101 @@ fn detect_ddr_freq_paths() -> Result<()> {
     Ok(())
 }
 
-pub fn gpufreq_table_init(gpu: &mut GPU) -> Result<()> {
-    // 检测GPU驱动类型
-    detect_gpu_driver_type(gpu)?;
-
-    // 检测内存频率控制文件
-    detect_ddr_freq_paths()?; // 读取系统支持的频率表
-    let v2_supported_freqs = if gpu.is_gpuv2() {
-        info!("Reading V2 driver frequency table");
-        read_v2_driver_freq_table()?
-    } else {
-        // V1 driver使用配置文件中的频率，不需要读取系统频率表
-        Vec::new()
+static DCS_FREQ_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)freq\w*\s*[:=]\s*(\d+)").unwrap());
+static DCS_OPP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)opp\w*\s*[:=]\s*(\d+)").unwrap());
+
+/// 解析DCS状态节点
+///
+/// 返回 (DCS是否开启, 不触发核心缩减的最低频率)。节点中的阈值可能以频率或OPP索引给出，
+/// OPP索引按v2频率表（从高到低）换算为频率，频率值按频率表的单位解析
+fn parse_dcs_state(content: &str, v2_freqs: &[Freq], unit: FreqUnit) -> (bool, Option<Freq>) {
+    let lower = content.trim().to_lowercase();
+    let enabled =
+        !(lower.is_empty() || lower == "0" || lower == "off" || lower.contains("disable"));
+
+    let floor = DCS_FREQ_RE
+        .captures(content)
+        .and_then(|c| c[1].parse::<i64>().ok())
+        .map(|freq| Freq::from_unit(freq, unit))
+        .or_else(|| {
+            DCS_OPP_RE
+                .captures(content)
+                .and_then(|c| c[1].parse::<usize>().ok())
+                .and_then(|idx| v2_freqs.get(idx).copied())
+        });
+
+    (enabled, floor)
+}
+
+/// 读取DCS状态，确定游戏模式下允许的最低频率
+fn detect_dcs_state(gpu: &mut GPU) {
+    if !fs::exists(GPUFREQV2_DCS_MODE).unwrap_or(false) || !check_read_simple(GPUFREQV2_DCS_MODE) {
+        debug!("DCS state node not found: {GPUFREQV2_DCS_MODE}");
+        return;
+    }
+
+    let content = match fs::read_to_string(GPUFREQV2_DCS_MODE) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("Failed to read DCS state: {e}");
+            return;
+        }
     };
 
+    let (enabled, floor) = parse_dcs_state(
+        &content,
+        &gpu.get_v2_supported_freqs(),
+        gpu.frequency().v2_table_unit,
+    );
+    if !enabled {
+        info!("DCS is disabled by driver");
+        gpu.set_dcs_enable(false);
+        return;
+    }
+
+    match floor {
+        Some(freq) => info!("DCS core scaling below {freq}KHz"),
+        None => warn!("DCS is enabled but core scaling threshold is unknown"),
+    }
+    gpu.set_dcs_floor_freq(floor);
+}
+
+pub fn gpufreq_table_init(gpu: &mut GPU) -> Result<()> {
+    // 内存频率控制文件和EMI带宽节点的检测与GPU驱动无关，与驱动检测并行进行
+    let (ddr_probe, v2_supported_freqs) = thread::scope(|s| {
+        let ddr_probe = s.spawn(|| {
+            detect_ddr_freq_paths()?;
+            Ok::<_, anyhow::Error>(BandwidthMonitor::probe())
+        });
+
+        // 检测GPU驱动类型并读取系统支持的频率表
+        let v2_supported_freqs = detect_gpu_driver_type(gpu).and_then(|()| {
+            if gpu.is_gpuv2() {
+                info!("Reading V2 driver frequency table");
+                read_v2_driver_freq_table()
+            } else {
+                // V1 driver使用配置文件中的频率，不需要读取系统频率表
+                Ok((Vec::new(), FreqUnit::KHz))
+            }
+        });
+
+        let ddr_probe = ddr_probe
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("DDR probe thread panicked")));
+        (ddr_probe, v2_supported_freqs)
+    });
+    let (v2_supported_freqs, v2_table_unit) = v2_supported_freqs?;
+
+    // 检测EMI带宽节点
+    gpu.ddr_manager_mut().set_bandwidth_monitor(ddr_probe?);
+    // 驱动类型确定后探测DDR节点接受的自动模式值
+    gpu.ddr_manager_mut().probe_auto_value();
+    gpu.ddr_manager().prefetch_ddr_freq_table();
+
     // 保存v2 driver支持的频率列表到GPU对象
     if gpu.is_gpuv2() && !v2_supported_freqs.is_empty() {
         // 将支持的频率列表保存到GPU对象，以便后续使用
-        gpu.set_v2_supported_freqs(v2_supported_freqs.clone());
+        gpu.set_v2_supported_freqs(v2_supported_freqs.clone(), v2_table_unit);
 
         if let Some(&max_freq) = v2_supported_freqs.first() {
             info!("V2 Driver Max Supported Freq: {max_freq}");
@@ -211,12 +310,20 @@ pub fn gpufreq_table_init(gpu: &mut GPU) -> Result<()> {
         info!("Using frequencies from config file only");
     }
 
+    // 检测DCS核心缩减状态
+    if gpu.is_gpuv2() {
+        detect_dcs_state(gpu);
+    }
+
+    // 识别驱动版本并应用兼容性修正
+    apply_driver_quirks(gpu);
+
     // 输出当前频率表信息
     let config_list = gpu.get_config_list();
     if !config_list.is_empty() {
         // 找出最大和最小频率，不假设列表的排序方式
-        let max_freq = *config_list.iter().max().unwrap_or(&0);
-        let min_freq = *config_list.iter().min().unwrap_or(&0);
+        let max_freq = config_list.iter().max().copied().unwrap_or_default();
+        let min_freq = config_list.iter().min().copied().unwrap_or_default();
 
         info!("Config Max Freq: {max_freq}");
         info!("Config Min Freq: {min_freq}");
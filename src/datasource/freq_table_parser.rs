@@ -1,14 +1,38 @@
 use std::{
     collections::HashMap,
     fs::{self},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use anyhow::Result;
 use log::{error, info, warn};
-use serde::Deserialize;
 use serde::de::{self, Visitor};
+use serde::{Deserialize, Serialize};
 
-use crate::model::gpu::{GPU, TabType};
+use crate::{
+    datasource::{
+        control::DumpFormat,
+        file_path::{FREQ_TABLE_DUMP_JSON, FREQ_TABLE_DUMP_TOML},
+    },
+    model::{
+        freq::Freq,
+        gpu::{GPU, TabType},
+    },
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 频率表代数，频率表文件每次变化时加一
+static FREQ_TABLE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 当前的频率表代数
+pub fn freq_table_generation() -> u64 {
+    FREQ_TABLE_GENERATION.load(Ordering::SeqCst)
+}
+
+/// 频率表文件发生变化，返回新的代数
+pub fn bump_freq_table_generation() -> u64 {
+    FREQ_TABLE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
 
 fn de_i64_lenient<'de, D>(deserializer: D) -> std::result::Result<i64, D::Error>
 where
@@ -96,18 +120,81 @@ fn volt_is_valid(v: i64) -> bool {
     v != 0 && v % 625 == 0
 }
 
-pub fn freq_table_read(config_file: &str, gpu: &mut GPU) -> Result<()> {
-    let file = fs::read_to_string(config_file)?;
-    let toml: FreqTableConfig = toml::from_str(&file).map_err(|e| {
+/// 解析并校验完成、尚未生效的频率表
+pub struct ParsedFreqTable {
+    config_list: Vec<Freq>,
+    fvtab: HashMap<Freq, i64>,
+    fdtab: HashMap<Freq, i64>,
+}
+
+impl ParsedFreqTable {
+    pub fn len(&self) -> usize {
+        self.config_list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.config_list.is_empty()
+    }
+
+    /// 用新频率表整体替换GPU当前的频率表
+    pub fn apply(self, gpu: &mut GPU, generation: u64) {
+        gpu.set_config_list(self.config_list);
+        gpu.replace_tab(TabType::FreqVolt, self.fvtab);
+        gpu.replace_tab(TabType::FreqDram, self.fdtab);
+        gpu.set_table_generation(generation);
+
+        info!("Load frequency table config succeed");
+
+        for &freq in &gpu.get_config_list() {
+            let volt = gpu.read_tab(TabType::FreqVolt, freq);
+            let dram = gpu.read_tab(TabType::FreqDram, freq);
+            info!("Freq={freq}, Volt={volt}, Dram={dram}");
+        }
+    }
+}
+
+/// 解析并校验频率表内容，不修改GPU当前的频率表
+///
+/// `config_file` 只用于日志和错误信息，电压不是 625 的整数倍的条目会被跳过
+///
+/// ```
+/// use gpugovernor::{
+///     datasource::freq_table_parser::parse_freq_table,
+///     model::{freq::Freq, gpu::GPU},
+/// };
+///
+/// let content = r#"
+/// [[freq_table]]
+/// freq = 350000
+/// volt = 60000
+/// ddr_opp = 3
+///
+/// [[freq_table]]
+/// freq = 850000
+/// volt = 70001
+/// ddr_opp = 0
+/// "#;
+/// let mut gpu = GPU::new();
+/// let table = parse_freq_table(content, "gpu_freq_table.toml", &gpu).unwrap();
+/// assert_eq!(table.len(), 1);
+///
+/// table.apply(&mut gpu, 0);
+/// assert_eq!(gpu.get_config_list(), vec![Freq::from_khz(350000)]);
+///
+/// // 没有有效条目时返回错误
+/// assert!(parse_freq_table("freq_table = []", "empty.toml", &gpu).is_err());
+/// ```
+pub fn parse_freq_table(content: &str, config_file: &str, gpu: &GPU) -> Result<ParsedFreqTable> {
+    let toml: FreqTableConfig = toml::from_str(content).map_err(|e| {
         error!("TOML解析失败（{config_file}）: {e}");
         anyhow::anyhow!("Failed to parse frequency table: {}", e)
     })?;
-    let mut new_config_list = Vec::new();
-    let mut new_fvtab = HashMap::new();
-    let mut new_fdtab = HashMap::new();
+    let mut config_list = Vec::new();
+    let mut fvtab = HashMap::new();
+    let mut fdtab = HashMap::new();
 
     for entry in toml.freq_table {
-        let freq = entry.freq;
+        let freq = Freq::from_khz(entry.freq);
         let volt = entry.volt;
         let dram = entry.ddr_opp;
 
@@ -124,12 +211,12 @@ pub fn freq_table_read(config_file: &str, gpu: &mut GPU) -> Result<()> {
             );
         }
 
-        new_config_list.push(freq);
-        new_fvtab.insert(freq, volt);
-        new_fdtab.insert(freq, dram);
+        config_list.push(freq);
+        fvtab.insert(freq, volt);
+        fdtab.insert(freq, dram);
     }
 
-    if new_config_list.is_empty() {
+    if config_list.is_empty() {
         error!("No valid frequency entries found in frequency table config file");
         return Err(anyhow::anyhow!(
             "No valid frequency entries found in frequency table config file: {config_file}"
@@ -142,19 +229,63 @@ pub fn freq_table_read(config_file: &str, gpu: &mut GPU) -> Result<()> {
 
     info!(
         "Loaded {} frequency entries from frequency table config file (no limit)",
-        new_config_list.len()
+        config_list.len()
     );
 
-    gpu.set_config_list(new_config_list);
-    gpu.replace_tab(TabType::FreqVolt, new_fvtab);
-    gpu.replace_tab(TabType::FreqDram, new_fdtab);
-
-    info!("Load frequency table config succeed");
+    Ok(ParsedFreqTable {
+        config_list,
+        fvtab,
+        fdtab,
+    })
+}
 
-    for &freq in &gpu.get_config_list() {
-        let volt = gpu.read_tab(TabType::FreqVolt, freq);
-        let dram = gpu.read_tab(TabType::FreqDram, freq);
-        info!("Freq={freq}, Volt={volt}, Dram={dram}");
-    }
+/// 读取频率表文件，校验通过后才替换当前频率表，失败时保留原频率表
+pub fn freq_table_read(config_file: &str, gpu: &mut GPU) -> Result<()> {
+    // 先取代数再读文件，读取期间文件再次变化时代数落后，之后会再刷新一次
+    let generation = freq_table_generation();
+    let file = fs::read_to_string(config_file)?;
+    parse_freq_table(&file, config_file, gpu)?.apply(gpu, generation);
     Ok(())
 }
+
+/// 解析后实际生效的频率表条目
+#[derive(Serialize)]
+pub struct ResolvedFreqEntry {
+    pub freq: Freq,
+    /// 写入该频率时实际使用的电压
+    pub volt: i64,
+    pub ddr_opp: i64,
+    /// 驱动是否支持该频率（v1驱动没有支持列表，始终为true）
+    pub supported: bool,
+}
+
+#[derive(Serialize)]
+struct ResolvedFreqTable {
+    freq_table: Vec<ResolvedFreqEntry>,
+}
+
+/// 当前生效的频率表（已剔除无效条目和校准标记的损坏档位）
+pub fn resolved_freq_table(gpu: &GPU) -> Vec<ResolvedFreqEntry> {
+    gpu.get_config_list()
+        .into_iter()
+        .map(|freq| ResolvedFreqEntry {
+            freq,
+            volt: gpu.frequency().effective_volt(freq),
+            ddr_opp: gpu.read_tab(TabType::FreqDram, freq),
+            supported: gpu.is_freq_supported_by_v2_driver(freq),
+        })
+        .collect()
+}
+
+/// 导出当前生效的频率表，返回导出文件路径
+pub fn dump_freq_table(gpu: &GPU, format: DumpFormat) -> Result<&'static str> {
+    let table = ResolvedFreqTable {
+        freq_table: resolved_freq_table(gpu),
+    };
+    let (path, content) = match format {
+        DumpFormat::Toml => (FREQ_TABLE_DUMP_TOML, toml::to_string_pretty(&table)?),
+        DumpFormat::Json => (FREQ_TABLE_DUMP_JSON, serde_json::to_string_pretty(&table)?),
+    };
+    write_file(path, content.as_bytes(), 1 << 20, FileClass::State)?;
+    Ok(path)
+}
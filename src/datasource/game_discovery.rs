@@ -0,0 +1,187 @@
+//! 游戏列表缺失时的处理
+//!
+//! games.toml 不存在时游戏检测不会生效，新用户往往以为游戏模式坏了。按
+//! `[foreground] missing_games` 的设置输出一次说明，或根据已安装应用生成初始游戏列表。
+//! 生成时通过 `pm list packages -f -3` 取得第三方应用的安装路径，
+//! 以解压后的原生库中是否包含常见游戏引擎的库判断是否为游戏；
+//! 原生库未解压（`extractNativeLibs=false`）的应用无法识别，需要手动添加。
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::{debug, info, warn};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, value};
+
+use crate::{
+    datasource::config_parser::{ForegroundConfig, MissingGamesAction},
+    utils::file_operate::{edit_toml_file, ensure_parent_dir},
+};
+
+/// 生成的游戏列表开头的说明
+const STARTER_HEADER: &str =
+    "# 由 gpugovernor 根据已安装应用中的游戏引擎库自动生成，可自由增删和修改\n\n";
+
+/// 常见游戏引擎的原生库
+const GAME_ENGINE_LIBS: [&str; 7] = [
+    "libunity.so",
+    "libil2cpp.so",
+    "libUE4.so",
+    "libUnreal.so",
+    "libcocos2dcpp.so",
+    "libcocos2djs.so",
+    "libgodot_android.so",
+];
+
+/// 说明只在进程生命周期内输出一次，前台监控线程重启后不再重复
+static NOTICE_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// 检查应用安装目录中是否有游戏引擎的原生库
+fn has_game_engine_lib(apk_path: &str) -> bool {
+    let Some(lib_dir) = Path::new(apk_path).parent().map(|dir| dir.join("lib")) else {
+        return false;
+    };
+    let Ok(abis) = fs::read_dir(&lib_dir) else {
+        return false;
+    };
+    abis.flatten().any(|abi| {
+        GAME_ENGINE_LIBS
+            .iter()
+            .any(|lib| abi.path().join(lib).exists())
+    })
+}
+
+/// 从已安装的第三方应用中找出游戏，按包名排序
+pub fn discover_games() -> Result<Vec<String>> {
+    let output = Command::new("pm")
+        .args(["list", "packages", "-f", "-3"])
+        .output()
+        .context("Failed to run pm list packages")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "pm list packages exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // 每行格式为 package:<apk路径>=<包名>，apk路径本身可能包含 '='
+    let mut games: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("package:")?.rsplit_once('='))
+        .filter(|(apk_path, package)| {
+            let is_game = has_game_engine_lib(apk_path);
+            if is_game {
+                debug!("Detected game engine libraries in {package}");
+            }
+            is_game
+        })
+        .map(|(_, package)| package.to_string())
+        .collect();
+    games.sort();
+    games.dedup();
+    Ok(games)
+}
+
+/// 将尚未列出的游戏追加到游戏列表文档，返回追加的数量
+fn add_games(document: &mut DocumentMut, games: &[String], mode: &str) -> Result<usize> {
+    if document.is_empty() {
+        document.decor_mut().set_prefix(STARTER_HEADER);
+    }
+    let entries = document
+        .entry("games")
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+        .as_array_of_tables_mut()
+        .ok_or_else(|| anyhow!("`games` is not an array of tables"))?;
+
+    let listed: HashSet<String> = entries
+        .iter()
+        .filter_map(|entry| entry.get("package")?.as_str().map(str::to_string))
+        .collect();
+    let mut added = 0;
+    for package in games.iter().filter(|package| !listed.contains(*package)) {
+        let mut entry = Table::new();
+        entry["package"] = value(package.as_str());
+        entry["mode"] = value(mode);
+        entries.push(entry);
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// 生成初始游戏列表，返回写入的游戏数量
+///
+/// 经由 [`edit_toml_file`] 写入：生成期间用户已创建的文件保留原有内容和注释，只追加未列出的游戏
+fn write_starter_games_list(path: &str, mode: &str) -> Result<usize> {
+    let games = discover_games()?;
+    ensure_parent_dir(path)?;
+    edit_toml_file(path, |document| add_games(document, &games, mode))
+}
+
+/// 游戏列表文件不存在时按配置处理
+pub fn handle_missing_games_list(path: &str, config: &ForegroundConfig) {
+    match config.missing_games {
+        MissingGamesAction::Ignore => {}
+        MissingGamesAction::Notice => {
+            if !NOTICE_SHOWN.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "Game detection is disabled: {path} does not exist. Create it with [[games]] entries (package = \"...\", mode = \"...\"), or set [foreground] missing_games = \"generate\" to build a starter list from installed games"
+                );
+            }
+        }
+        MissingGamesAction::Generate => {
+            match write_starter_games_list(path, &config.starter_mode) {
+                Ok(count) => info!(
+                    "Generated starter games list with {count} game(s) at {path}, mode {}",
+                    config.starter_mode
+                ),
+                Err(e) => warn!("Failed to generate starter games list: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn games(packages: &[&str]) -> Vec<String> {
+        packages.iter().map(|package| package.to_string()).collect()
+    }
+
+    #[test]
+    fn new_list_gets_header_and_entries() {
+        let mut document = DocumentMut::new();
+        let added = add_games(&mut document, &games(&["com.a.game"]), "performance").unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(
+            document.to_string(),
+            format!(
+                "{STARTER_HEADER}[[games]]\npackage = \"com.a.game\"\nmode = \"performance\"\n"
+            )
+        );
+    }
+
+    #[test]
+    fn existing_comments_and_entries_are_kept() {
+        let original =
+            "# my games\n[[games]]\npackage = \"com.a.game\" # favourite\nmode = \"fast\"\n";
+        let mut document: DocumentMut = original.parse().unwrap();
+        let added = add_games(
+            &mut document,
+            &games(&["com.a.game", "com.b.game"]),
+            "performance",
+        )
+        .unwrap();
+        assert_eq!(added, 1);
+        let updated = document.to_string();
+        assert!(updated.starts_with(original));
+        assert!(updated.contains("package = \"com.b.game\""));
+        assert!(!updated.contains("自动生成"));
+    }
+}
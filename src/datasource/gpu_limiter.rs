@@ -0,0 +1,141 @@
+//! 内核限频来源
+//!
+//! MTK 内核的温控、电池过流（BATT_OC）、PBM 等模块通过限频表压低GPU频率上限，
+//! 用户看到的“频率一直上不去”常被误认为是调速器的问题。定期读取 gpufreq 的限频表，
+//! 记录正在压低频率上限的来源并写入状态文件；[`crate::model::thermal_cap`] 只能从实际频率推断上限，
+//! 这里补充上限的来源。
+//!
+//! 限频表的列因内核版本而异，按表头识别上限档位列和上限开关列：v1 为
+//! `up_enable`/`up_limit_idx`，v2 为 `ceiling`/`c_enable`。档位索引0为最高频率，
+//! 开启且索引大于0的来源视为正在限频。
+
+use std::{cmp::Reverse, path::Path, thread, time::Duration};
+
+use anyhow::Result;
+use log::{debug, info};
+
+use crate::{
+    datasource::file_path::{GPUFREQ_LIMIT_TABLE, GPUFREQV2_LIMIT_TABLE, LIMITER_MONITOR_THREAD},
+    utils::{file_operate::read_file, status_report::update_status},
+};
+
+/// 限频表的轮询间隔
+const LIMITER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 限频表的最大读取长度
+const LIMIT_TABLE_MAX_LEN: usize = 16 * 1024;
+
+/// 正在压低频率上限的限频来源
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActiveLimiter {
+    pub name: String,
+    /// 上限档位索引，越大频率越低
+    pub ceiling_idx: i64,
+}
+
+/// 限频表中上限档位列和上限开关列的位置
+struct Columns {
+    ceiling: usize,
+    enable: usize,
+}
+
+/// 从表头识别列位置，同时包含上限档位列和上限开关列的行才视为表头
+///
+/// v1 的用法说明中也会出现 `upper_limit_idx` 等列名，但不会同时出现开关列
+fn parse_header(line: &str) -> Option<Columns> {
+    let tokens: Vec<String> = line.split_whitespace().map(str::to_lowercase).collect();
+    let is_ceiling = |token: &str| token.starts_with("ceil") || token.starts_with("up");
+    let ceiling = tokens
+        .iter()
+        .position(|token| is_ceiling(token) && !token.contains("enable"))?;
+    let enable = tokens.iter().position(|token| {
+        token.contains("enable") && (is_ceiling(token) || token.starts_with("c_"))
+    })?;
+    Some(Columns { ceiling, enable })
+}
+
+/// 解析限频表，返回按上限档位从低到高频率排序的限频来源
+pub fn parse_limit_table(content: &str) -> Vec<ActiveLimiter> {
+    let mut columns = None;
+    let mut limiters = Vec::new();
+
+    for line in content.lines() {
+        let tokens: Vec<&str> = line
+            .split_whitespace()
+            .map(|token| token.trim_matches(|c| c == '[' || c == ']' || c == ','))
+            .collect();
+        let Some(name) = tokens.first() else {
+            continue;
+        };
+        if name.parse::<i64>().is_ok() {
+            continue;
+        }
+        // 跳过表头之前的用法说明
+        let Some(cols) = &columns else {
+            columns = parse_header(line);
+            continue;
+        };
+
+        let value = |idx: usize| tokens.get(idx).and_then(|v| v.parse::<i64>().ok());
+        let Some(ceiling_idx) = value(cols.ceiling) else {
+            continue;
+        };
+        if value(cols.enable) == Some(1) && ceiling_idx > 0 {
+            limiters.push(ActiveLimiter {
+                name: name.to_uppercase(),
+                ceiling_idx,
+            });
+        }
+    }
+
+    limiters.sort_by_key(|limiter| Reverse(limiter.ceiling_idx));
+    limiters
+}
+
+/// 当前设备的限频表节点，v2优先
+fn limit_table_path() -> Option<&'static str> {
+    [GPUFREQV2_LIMIT_TABLE, GPUFREQ_LIMIT_TABLE]
+        .into_iter()
+        .find(|path| Path::new(path).exists())
+}
+
+fn describe(limiters: &[ActiveLimiter]) -> Vec<String> {
+    limiters
+        .iter()
+        .map(|limiter| format!("{} (ceiling OPP {})", limiter.name, limiter.ceiling_idx))
+        .collect()
+}
+
+/// 监控内核限频来源，变化时记录日志并更新状态文件
+pub fn monitor_gpu_limiters() -> Result<()> {
+    info!("{LIMITER_MONITOR_THREAD} Start");
+
+    let Some(path) = limit_table_path() else {
+        info!("No gpufreq limit table found, limiter monitoring disabled");
+        return Ok(());
+    };
+    info!("Watching kernel frequency limiters at {path}");
+
+    let mut last: Vec<String> = Vec::new();
+    loop {
+        match read_file(path, LIMIT_TABLE_MAX_LEN) {
+            Ok(content) => {
+                let active = describe(&parse_limit_table(&content));
+                if active != last {
+                    if active.is_empty() {
+                        info!("No kernel limiter is capping the GPU");
+                    } else {
+                        info!(
+                            "GPU frequency capped by kernel limiter(s): {}",
+                            active.join(", ")
+                        );
+                    }
+                    update_status(|s| s.freq_limiters = active.clone());
+                    last = active;
+                }
+            }
+            Err(e) => debug!("Failed to read limit table: {e}"),
+        }
+
+        thread::sleep(LIMITER_POLL_INTERVAL);
+    }
+}
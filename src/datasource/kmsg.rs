@@ -0,0 +1,94 @@
+//! 内核日志读取
+//!
+//! 以非阻塞方式读取 `/dev/kmsg`，打开后跳到末尾，之后每次只返回新产生的内核日志。
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
+    os::unix::fs::OpenOptionsExt,
+};
+
+use anyhow::{Context, Result};
+
+use crate::datasource::file_path::KMSG_PATH;
+
+/// 驱动相关的内核日志来源
+const DRIVER_TAGS: [&str; 5] = ["mali", "gpufreq", "ged", "dvfs", "gpu"];
+/// 表示驱动出错的关键字
+const ERROR_KEYWORDS: [&str; 7] = [
+    "error", "fail", "fault", "timeout", "oops", "bug", "warning",
+];
+
+/// Mali驱动相关的内核日志来源
+const MALI_TAGS: [&str; 3] = ["mali", "kbase", "gpu"];
+/// GPU故障特征：页错误、总线错误和驱动复位
+const GPU_FAULT_SIGNATURES: [&str; 6] = [
+    "page fault",
+    "bus fault",
+    "gpu fault",
+    "job fault",
+    "gpu reset",
+    "resetting gpu",
+];
+
+/// `/dev/kmsg` 增量读取器
+pub struct KmsgReader {
+    file: File,
+}
+
+impl KmsgReader {
+    /// 打开内核日志并跳过已有内容
+    pub fn open() -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(KMSG_PATH)
+            .with_context(|| format!("Failed to open {KMSG_PATH}"))?;
+        file.seek(SeekFrom::End(0))
+            .with_context(|| format!("Failed to seek {KMSG_PATH}"))?;
+        Ok(Self { file })
+    }
+
+    /// 读取上次调用以来新产生的内核日志（只保留消息正文）
+    pub fn read_new(&mut self) -> Vec<String> {
+        let mut messages = Vec::new();
+        // 每次 read 返回一条完整记录
+        let mut buf = [0u8; 8192];
+        loop {
+            match self.file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let record = String::from_utf8_lossy(&buf[..n]);
+                    // 记录格式：`优先级,序号,时间戳,标志;消息`
+                    let message = record.split_once(';').map_or(&*record, |(_, msg)| msg);
+                    messages.push(message.lines().next().unwrap_or_default().to_string());
+                }
+                // 缓冲区覆盖了未读记录时返回 EPIPE，继续读取之后的记录
+                Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+        messages
+    }
+}
+
+/// 内核日志是否为GPU驱动报告的错误
+pub fn is_driver_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    DRIVER_TAGS.iter().any(|tag| message.contains(tag))
+        && ERROR_KEYWORDS
+            .iter()
+            .any(|keyword| message.contains(keyword))
+}
+
+/// 内核日志中的GPU故障特征，不是GPU故障时返回 `None`
+pub fn gpu_fault_signature(message: &str) -> Option<&'static str> {
+    let message = message.to_lowercase();
+    if !MALI_TAGS.iter().any(|tag| message.contains(tag)) {
+        return None;
+    }
+    GPU_FAULT_SIGNATURES
+        .into_iter()
+        .find(|signature| message.contains(signature))
+}
@@ -1,168 +1,214 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{BufRead, BufReader},
+    sync::Mutex,
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, anyhow};
-use log::{debug, error, info};
+use anyhow::{Result, anyhow};
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
 
 use crate::{
-    datasource::file_path::*,
+    datasource::{
+        config_parser::{LoadCombine, LoadSourceConfig},
+        file_path::*,
+    },
+    model::freq::Freq,
     utils::{
         file_operate::{check_read, read_file},
         file_status::{get_status, write_status},
     },
 };
 
-fn module_ged_load() -> Result<i32> {
-    if !get_status(MODULE_LOAD) {
-        return Ok(-1);
-    }
-
-    let buf = read_file(MODULE_LOAD, 32)?;
-    let load = buf
-        .trim()
-        .parse::<i32>()
-        .with_context(|| format!("Failed to parse GPU load from {MODULE_LOAD}"))?;
-
-    Ok(load)
+/// 单个负载来源的读取耗时预算，部分内核的 debugfs 节点读取需要数毫秒，超出后会挤占采样周期
+const SOURCE_READ_BUDGET: Duration = Duration::from_millis(8);
+
+/// 负载来源，按默认回退链的优先级从高到低排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadSource {
+    DebugDvfs,
+    GpuFreq,
+    MtkMali,
+    Mali,
+    KernelDebugGed,
+    KernelDGed,
+    KernelGed,
+    ModuleGed,
 }
 
-fn module_ged_idle() -> Result<i32> {
-    if !get_status(MODULE_IDLE) {
-        return module_ged_load();
+impl LoadSource {
+    pub const ALL: [LoadSource; 8] = [
+        LoadSource::DebugDvfs,
+        LoadSource::GpuFreq,
+        LoadSource::MtkMali,
+        LoadSource::Mali,
+        LoadSource::KernelDebugGed,
+        LoadSource::KernelDGed,
+        LoadSource::KernelGed,
+        LoadSource::ModuleGed,
+    ];
+
+    /// 初始化检测时该来源的节点是否可读
+    pub fn is_available(self) -> bool {
+        match self {
+            LoadSource::DebugDvfs => get_status(DEBUG_DVFS_LOAD) || get_status(DEBUG_DVFS_LOAD_OLD),
+            LoadSource::GpuFreq => get_status(GPU_FREQ_LOAD_PATH),
+            LoadSource::MtkMali => get_status(PROC_MTK_LOAD),
+            LoadSource::Mali => get_status(PROC_MALI_LOAD),
+            LoadSource::KernelDebugGed => get_status(KERNEL_DEBUG_LOAD),
+            LoadSource::KernelDGed => get_status(KERNEL_D_LOAD),
+            LoadSource::KernelGed => get_status(KERNEL_LOAD),
+            LoadSource::ModuleGed => get_status(MODULE_IDLE) || get_status(MODULE_LOAD),
+        }
     }
 
-    let buf = read_file(MODULE_IDLE, 32)?;
-    let idle = buf
-        .trim()
-        .parse::<i32>()
-        .with_context(|| format!("Failed to parse GPU idle from {MODULE_IDLE}"))?;
-
-    let load = 100 - idle;
-    debug!("module {load}");
-    Ok(100 - idle)
-}
-
-fn kernel_ged_load() -> Result<i32> {
-    if !get_status(KERNEL_LOAD) {
-        return module_ged_idle();
+    /// 读取该来源的负载，节点不可用或内容无法解析时返回 `None`
+    pub fn read(self) -> Result<Option<i32>> {
+        let load = match self {
+            LoadSource::DebugDvfs => debug_dvfs_load()?,
+            LoadSource::GpuFreq => gpufreq_load()?,
+            LoadSource::MtkMali => mtk_load()?,
+            LoadSource::Mali => mali_load()?,
+            LoadSource::KernelDebugGed => ged_idle_load(KERNEL_DEBUG_LOAD)?,
+            LoadSource::KernelDGed => ged_idle_load(KERNEL_D_LOAD)?,
+            LoadSource::KernelGed => ged_idle_load(KERNEL_LOAD)?,
+            LoadSource::ModuleGed => module_ged_load()?,
+        };
+        if let Some(load) = load {
+            debug!("{self:?} {load}");
+        }
+        Ok(load)
     }
+}
 
-    let buf = read_file(KERNEL_LOAD, 32)?;
-    let parts: Vec<&str> = buf.split_whitespace().collect();
-
-    if parts.len() >= 3
-        && let Ok(idle) = parts[2].parse::<i32>()
-    {
-        let load = 100 - idle;
-        debug!("gedload {load}");
-        return Ok(if 100 - idle == 0 {
-            module_ged_load()?
-        } else {
-            100 - idle
-        });
-    }
+/// 当前的多负载来源合并策略
+static LOAD_SOURCE_POLICY: Lazy<Mutex<LoadSourceConfig>> =
+    Lazy::new(|| Mutex::new(LoadSourceConfig::default()));
 
-    module_ged_idle()
+/// 设置多负载来源合并策略
+pub fn set_load_source_policy(policy: LoadSourceConfig) {
+    *LOAD_SOURCE_POLICY.lock().unwrap() = policy;
 }
 
-fn kernel_debug_ged_load() -> Result<i32> {
-    if !get_status(KERNEL_D_LOAD) {
-        return kernel_ged_load();
+fn module_ged_load() -> Result<Option<i32>> {
+    if get_status(MODULE_IDLE) {
+        let buf = read_file(MODULE_IDLE, 32)?;
+        return Ok(buf.trim().parse::<i32>().ok().map(|idle| 100 - idle));
     }
 
-    let buf = read_file(KERNEL_D_LOAD, 32)?;
-    let parts: Vec<&str> = buf.split_whitespace().collect();
-
-    if parts.len() >= 3
-        && let Ok(idle) = parts[2].parse::<i32>()
-    {
-        let load = 100 - idle;
-        debug!("dbggedload {load}");
-        return Ok(if 100 - idle == 0 {
-            kernel_ged_load()?
-        } else {
-            100 - idle
-        });
+    if get_status(MODULE_LOAD) {
+        let buf = read_file(MODULE_LOAD, 32)?;
+        return Ok(buf.trim().parse::<i32>().ok());
     }
 
-    kernel_ged_load()
+    Ok(None)
 }
 
-fn kernel_d_ged_load() -> Result<i32> {
-    if !get_status(KERNEL_DEBUG_LOAD) {
-        return kernel_debug_ged_load();
+/// 读取 ged 的 `... ... idle` 格式节点
+fn ged_idle_load(path: &str) -> Result<Option<i32>> {
+    if !get_status(path) {
+        return Ok(None);
     }
 
-    let buf = read_file(KERNEL_DEBUG_LOAD, 32)?;
+    let buf = read_file(path, 32)?;
     let parts: Vec<&str> = buf.split_whitespace().collect();
-
     if parts.len() >= 3
         && let Ok(idle) = parts[2].parse::<i32>()
     {
-        let load = 100 - idle;
-        debug!("dgedload {load}");
-        return Ok(if 100 - idle == 0 {
-            kernel_debug_ged_load()?
-        } else {
-            100 - idle
-        });
+        return Ok(Some(100 - idle));
     }
 
-    kernel_debug_ged_load()
+    Ok(None)
 }
 
-fn mali_load() -> Result<i32> {
+fn mali_load() -> Result<Option<i32>> {
     if !get_status(PROC_MALI_LOAD) {
-        return kernel_d_ged_load();
+        return Ok(None);
     }
 
     let buf = read_file(PROC_MALI_LOAD, 256)?;
 
     // Parse "gpu/cljs0/cljs1=XX" format
-    if let Some(pos) = buf.find('=')
-        && let Ok(load) = buf[pos + 1..].trim().parse::<i32>()
-    {
-        debug!("mali {load}");
-        return Ok(if load == 0 {
-            kernel_d_ged_load()?
-        } else {
-            load
-        });
+    Ok(buf
+        .find('=')
+        .and_then(|pos| buf[pos + 1..].trim().parse::<i32>().ok()))
+}
+
+/// `/proc/mtk_mali/utilization` 的各项负载
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MaliUtilization {
+    /// 总体负载（`ACTIVE`）
+    pub active: Option<i32>,
+    /// 片元作业槽负载，仅Valhall等较新的驱动提供
+    pub fragment: Option<i32>,
+    /// 计算作业槽负载，仅Valhall等较新的驱动提供
+    pub compute: Option<i32>,
+}
+
+impl MaliUtilization {
+    /// 解析 `ACTIVE=XX` 以及 `FRAG=XX COMP=XX` 形式的 `键=值` 列表
+    pub fn parse(content: &str) -> Self {
+        let mut utilization = Self::default();
+        for token in content.split(|c: char| c.is_whitespace() || c == ',') {
+            let Some((key, value)) = token.split_once('=') else {
+                continue;
+            };
+            let Ok(value) = value.trim().trim_end_matches('%').parse::<i32>() else {
+                continue;
+            };
+            match key.trim().to_ascii_uppercase().as_str() {
+                "ACTIVE" => utilization.active = Some(value),
+                "FRAG" | "FRAGMENT" | "JS0" => utilization.fragment = Some(value),
+                "COMP" | "COMPUTE" | "JS1" => utilization.compute = Some(value),
+                _ => {}
+            }
+        }
+        utilization
     }
 
-    kernel_d_ged_load()
+    /// 片元和计算负载中的较大值，驱动未分别报告时返回 `None`
+    pub fn job_slot_max(&self) -> Option<i32> {
+        match (self.fragment, self.compute) {
+            (Some(fragment), Some(compute)) => Some(fragment.max(compute)),
+            (Some(load), None) | (None, Some(load)) => Some(load),
+            (None, None) => None,
+        }
+    }
 }
 
-fn mtk_load() -> Result<i32> {
+fn mtk_load() -> Result<Option<i32>> {
     if !get_status(PROC_MTK_LOAD) {
-        return mali_load();
+        return Ok(None);
     }
 
     let buf = read_file(PROC_MTK_LOAD, 256)?;
+    let utilization = MaliUtilization::parse(&buf);
 
-    // Parse "ACTIVE=XX" format
-    if let Some(pos) = buf.find("ACTIVE=")
-        && let Ok(load) = buf[pos + 7..].trim().parse::<i32>()
+    if LOAD_SOURCE_POLICY.lock().unwrap().job_slot_max
+        && let Some(load) = utilization.job_slot_max()
     {
-        debug!("mtk_mali {load}");
-        return Ok(if load == 0 { mali_load()? } else { load });
+        debug!(
+            "Mali job slots: fragment {:?}, compute {:?}, active {:?}",
+            utilization.fragment, utilization.compute, utilization.active
+        );
+        return Ok(Some(load));
     }
-
-    mali_load()
+    Ok(utilization.active)
 }
 
-fn gpufreq_load() -> Result<i32> {
+fn gpufreq_load() -> Result<Option<i32>> {
     if !get_status(GPU_FREQ_LOAD_PATH) {
-        return mtk_load();
+        return Ok(None);
     }
 
     let file = match File::open(GPU_FREQ_LOAD_PATH) {
         Ok(file) => file,
         Err(_) => {
             write_status(GPU_FREQ_LOAD_PATH, false);
-            return Ok(0);
+            return Ok(None);
         }
     };
 
@@ -175,36 +221,33 @@ fn gpufreq_load() -> Result<i32> {
         if let Some(pos) = line.find("gpu_loading = ")
             && let Ok(load) = line[pos + 14..].trim().parse::<i32>()
         {
-            debug!("gpufreq {load}");
-            return Ok(if load == 0 { mtk_load()? } else { load });
+            return Ok(Some(load));
         }
     }
 
-    mtk_load()
+    Ok(None)
 }
 
-fn debug_dvfs_load_func() -> Result<i32> {
+/// debug_dvfs_load 上一次读取的累计值 (busy, idle, protm)
+static PREV_DVFS_COUNTERS: Mutex<(i64, i64, i64)> = Mutex::new((0, 0, 0));
+
+fn debug_dvfs_load() -> Result<Option<i32>> {
     // Check if debug_dvfs_load or debug_dvfs_load_old exists
     let path = if get_status(DEBUG_DVFS_LOAD) {
         DEBUG_DVFS_LOAD
     } else if get_status(DEBUG_DVFS_LOAD_OLD) {
         DEBUG_DVFS_LOAD_OLD
     } else {
-        return gpufreq_load();
+        return Ok(None);
     };
 
     let buf = read_file(path, 256)?;
     let lines: Vec<&str> = buf.lines().collect();
 
     if lines.len() < 2 {
-        return gpufreq_load();
+        return Ok(None);
     }
 
-    // Static variables to keep track of previous values
-    static mut PREV_BUSY: i64 = 0;
-    static mut PREV_IDLE: i64 = 0;
-    static mut PREV_PROTM: i64 = 0;
-
     // Parse the second line which contains the values
     let parts: Vec<&str> = lines[1].split_whitespace().collect();
 
@@ -215,40 +258,240 @@ fn debug_dvfs_load_func() -> Result<i32> {
             parts[2].parse::<i64>(),
         )
     {
-        // Get previous values safely
-        let (prev_busy, prev_idle, prev_protm) = unsafe { (PREV_BUSY, PREV_IDLE, PREV_PROTM) };
+        let (prev_busy, prev_idle, prev_protm) = {
+            let mut prev = PREV_DVFS_COUNTERS.lock().unwrap();
+            std::mem::replace(&mut *prev, (busy, idle, protm))
+        };
 
         // Calculate differences
         let diff_busy = busy - prev_busy;
         let diff_idle = idle - prev_idle;
         let diff_protm = protm - prev_protm;
 
-        // Update previous values
-        unsafe {
-            PREV_BUSY = busy;
-            PREV_IDLE = idle;
-            PREV_PROTM = protm;
-        }
-
         // Calculate load percentage
         let total = diff_busy + diff_idle + diff_protm;
         if total > 0 {
             let load = ((diff_busy + diff_protm) * 100 / total) as i32;
-            let load = if load < 0 { 0 } else { load };
-
             debug!("debugutil: {load} {diff_busy} {diff_idle} {diff_protm}");
-            return Ok(if load == 0 { mtk_load()? } else { load });
+            return Ok(Some(load.max(0)));
         }
     }
 
-    gpufreq_load()
+    Ok(None)
+}
+
+/// 负载来源的读取耗时统计（微秒）
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SourceTiming {
+    /// 读取次数
+    pub reads: u64,
+    /// 读取失败次数
+    pub errors: u64,
+    /// 最近一次读取耗时
+    pub last_us: u64,
+    /// 平均读取耗时
+    pub mean_us: u64,
+    /// 最长读取耗时
+    pub max_us: u64,
+    /// 超出读取预算的次数
+    pub over_budget: u64,
+    /// 累计耗时，用于计算平均值
+    #[serde(skip)]
+    total_us: u64,
+}
+
+impl SourceTiming {
+    const ZERO: Self = Self {
+        reads: 0,
+        errors: 0,
+        last_us: 0,
+        mean_us: 0,
+        max_us: 0,
+        over_budget: 0,
+        total_us: 0,
+    };
+}
+
+/// 各来源的读取耗时统计，按 [`LoadSource::ALL`] 的顺序排列
+///
+/// 每个采样周期最多读取多个来源，统计只在这里累加，写入 status.json 时再复制到运行状态，
+/// 避免在采样路径上争用运行状态的锁
+static SOURCE_TIMINGS: Mutex<[SourceTiming; LoadSource::ALL.len()]> =
+    Mutex::new([SourceTiming::ZERO; LoadSource::ALL.len()]);
+
+/// 读取过的来源的耗时统计，键为来源名称
+pub fn source_timings() -> BTreeMap<String, SourceTiming> {
+    let timings = SOURCE_TIMINGS.lock().unwrap();
+    LoadSource::ALL
+        .into_iter()
+        .zip(timings.iter())
+        .filter(|(_, timing)| timing.reads > 0)
+        .map(|(source, timing)| (format!("{source:?}"), timing.clone()))
+        .collect()
+}
+
+/// 记录一次读取的耗时，首次超出预算时输出警告
+fn record_source_timing(source: LoadSource, elapsed: Duration, failed: bool) {
+    let elapsed_us = elapsed.as_micros() as u64;
+    let over_budget = elapsed > SOURCE_READ_BUDGET;
+    let first_over_budget = {
+        let mut timings = SOURCE_TIMINGS.lock().unwrap();
+        let timing = &mut timings[source as usize];
+        timing.reads += 1;
+        if failed {
+            timing.errors += 1;
+        }
+        timing.last_us = elapsed_us;
+        timing.total_us += elapsed_us;
+        timing.mean_us = timing.total_us / timing.reads;
+        timing.max_us = timing.max_us.max(elapsed_us);
+        if over_budget {
+            timing.over_budget += 1;
+        }
+        over_budget && timing.over_budget == 1
+    };
+
+    if first_over_budget {
+        warn!(
+            "Reading load from {source:?} took {}ms, exceeding the {}ms budget",
+            elapsed.as_millis(),
+            SOURCE_READ_BUDGET.as_millis()
+        );
+    }
+}
+
+/// 读取单个来源并记录耗时，读取失败时记录日志并视为不可用
+fn read_source(source: LoadSource) -> Option<i32> {
+    let start = Instant::now();
+    let result = source.read();
+    record_source_timing(source, start.elapsed(), result.is_err());
+    match result {
+        Ok(load) => load,
+        Err(e) => {
+            debug!("Failed to read load from {source:?}: {e}");
+            None
+        }
+    }
 }
 
+/// 来源被确认有效后信任其0值读数的时长
+const SOURCE_TRUST_WINDOW: Duration = Duration::from_secs(30);
+
+/// 各来源最近一次被确认有效的时间，按 [`LoadSource::ALL`] 的顺序排列
+static SOURCE_VALIDATED: Mutex<[Option<Instant>; LoadSource::ALL.len()]> =
+    Mutex::new([None; LoadSource::ALL.len()]);
+
+/// 来源最近是否被确认有效（报告过非零负载，或与其他来源一致地报告0）
+fn is_source_trusted(source: LoadSource) -> bool {
+    SOURCE_VALIDATED.lock().unwrap()[source as usize]
+        .is_some_and(|validated| validated.elapsed() < SOURCE_TRUST_WINDOW)
+}
+
+fn mark_source_validated(source: LoadSource) {
+    SOURCE_VALIDATED.lock().unwrap()[source as usize] = Some(Instant::now());
+}
+
+/// 严格回退链：按优先级返回第一个非零负载，没有任何来源可读时返回 `None`
+///
+/// 读到0时通常继续尝试下一个来源，以跳过只会报告0的损坏来源；但最近确认有效的来源
+/// 报告的0视为GPU确实空闲，直接返回，避免空闲时每次采样都读取整条回退链
+fn fallback_load() -> Option<i32> {
+    let mut first_zero = None;
+    for source in LoadSource::ALL {
+        match read_source(source) {
+            Some(load) if load != 0 => {
+                mark_source_validated(source);
+                return Some(load);
+            }
+            Some(load) => {
+                if is_source_trusted(source) {
+                    return Some(load);
+                }
+                first_zero.get_or_insert(source);
+            }
+            None => {}
+        }
+    }
+
+    // 所有来源都报告0，说明GPU确实空闲，此后信任第一个来源的0值
+    let source = first_zero?;
+    mark_source_validated(source);
+    Some(0)
+}
+
+/// 同时采样多个来源，按 `combine` 合并成功读取的读数，所有来源都不可用时回退到严格回退链
+///
+/// `combine` 的第一个参数为参与采样的来源（按优先级排列），第二个参数为各来源的读数
+fn combined_load(
+    policy: &LoadSourceConfig,
+    combine: impl FnOnce(&[LoadSource], &[(LoadSource, i32)]) -> i32,
+) -> Option<i32> {
+    let sources: Vec<LoadSource> = LoadSource::ALL
+        .into_iter()
+        .filter(|source| source.is_available())
+        .take(policy.max_sources.max(1))
+        .collect();
+    let readings: Vec<(LoadSource, i32)> = sources
+        .iter()
+        .filter_map(|&source| read_source(source).map(|load| (source, load)))
+        .collect();
+
+    if readings.is_empty() {
+        return fallback_load();
+    }
+    Some(combine(&sources, &readings))
+}
+
+/// 各来源读数的最大值
+fn max_load(readings: &[(LoadSource, i32)]) -> i32 {
+    readings.iter().map(|&(_, load)| load).max().unwrap_or(0)
+}
+
+/// 各来源读数的加权平均，权重按来源在 `sources` 中的位置对应，读取失败的来源不占用其他来源的权重
+fn weighted_load(weights: &[f64], sources: &[LoadSource], readings: &[(LoadSource, i32)]) -> i32 {
+    // 间歇性报告0的来源不参与加权，避免拉低整体负载
+    let any_nonzero = readings.iter().any(|&(_, load)| load != 0);
+    let (sum, weight_sum) = readings
+        .iter()
+        .filter(|&&(_, load)| !any_nonzero || load != 0)
+        .fold((0.0, 0.0), |(sum, weight_sum), &(source, load)| {
+            let weight = sources
+                .iter()
+                .position(|&s| s == source)
+                .and_then(|i| weights.get(i))
+                .copied()
+                .unwrap_or(1.0)
+                .max(0.0);
+            (sum + load as f64 * weight, weight_sum + weight)
+        });
+    if weight_sum > 0.0 {
+        (sum / weight_sum).round() as i32
+    } else {
+        0
+    }
+}
+
+/// 按负载来源策略读取GPU负载，所有来源都无法读取时返回错误
 pub fn get_gpu_load() -> Result<i32> {
-    debug_dvfs_load_func()
+    let policy = LOAD_SOURCE_POLICY.lock().unwrap().clone();
+    let load = match policy.combine {
+        LoadCombine::Fallback => fallback_load(),
+        LoadCombine::Max => combined_load(&policy, |_, readings| max_load(readings)),
+        LoadCombine::Weighted => combined_load(&policy, |sources, readings| {
+            weighted_load(&policy.weights, sources, readings)
+        }),
+    };
+    load.ok_or_else(|| anyhow!("No GPU load source is readable"))
 }
 
-pub fn get_gpu_current_freq(is_v1_driver: bool) -> Result<i64> {
+/// 读取GPU当前频率
+///
+/// 各节点在不同内核上可能以Hz或KHz上报，按读数的量级识别单位后统一为KHz
+pub fn get_gpu_current_freq(is_v1_driver: bool) -> Result<Freq> {
+    read_gpu_current_freq_raw(is_v1_driver).map(Freq::from_reading)
+}
+
+fn read_gpu_current_freq_raw(is_v1_driver: bool) -> Result<i64> {
     // 对于v1驱动设备
     if is_v1_driver {
         return read_v1_gpu_freq();
@@ -549,3 +792,37 @@ pub fn utilization_init() -> Result<()> {
     info!("Test Finished.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_follow_sources_when_a_middle_source_fails() {
+        let sources = [
+            LoadSource::DebugDvfs,
+            LoadSource::GpuFreq,
+            LoadSource::MtkMali,
+        ];
+        // GpuFreq 读取失败，MtkMali 仍使用第三个权重
+        let readings = [(LoadSource::DebugDvfs, 40), (LoadSource::MtkMali, 80)];
+        assert_eq!(weighted_load(&[1.0, 5.0, 3.0], &sources, &readings), 70);
+    }
+
+    #[test]
+    fn missing_weights_default_to_one() {
+        let sources = [LoadSource::DebugDvfs, LoadSource::GpuFreq];
+        let readings = [(LoadSource::DebugDvfs, 30), (LoadSource::GpuFreq, 50)];
+        assert_eq!(weighted_load(&[], &sources, &readings), 40);
+        assert_eq!(max_load(&readings), 50);
+    }
+
+    #[test]
+    fn zero_readings_are_skipped_unless_all_are_zero() {
+        let sources = [LoadSource::DebugDvfs, LoadSource::GpuFreq];
+        let readings = [(LoadSource::DebugDvfs, 0), (LoadSource::GpuFreq, 60)];
+        assert_eq!(weighted_load(&[1.0, 1.0], &sources, &readings), 60);
+        let idle = [(LoadSource::DebugDvfs, 0), (LoadSource::GpuFreq, 0)];
+        assert_eq!(weighted_load(&[1.0, 1.0], &sources, &idle), 0);
+    }
+}
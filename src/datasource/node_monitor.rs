@@ -1,42 +1,52 @@
-use std::sync::mpsc::Sender;
+use std::{fs, sync::mpsc::Sender, thread, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use inotify::WatchMask;
 use log::{error, info, warn};
-use serde::Deserialize;
 
 use crate::{
     datasource::{
-        config_parser::{ConfigDelta, read_config_delta},
+        config_parser::{ConfigDelta, bump_config_generation, read_config_delta},
+        device_profile::{active_config_path, log_active_config, shadowed_config_path},
+        display_monitor::read_active_mode,
         file_path::*,
-        freq_table_parser::freq_table_read,
+        freq_table_parser::{
+            ParsedFreqTable, bump_freq_table_generation, freq_table_read, parse_freq_table,
+        },
     },
-    model::gpu::GPU,
+    model::{freq::Freq, gpu::GPU},
     utils::{
-        file_operate::{check_read_simple, write_file},
-        inotify::InotifyWatcher,
+        file_operate::check_read_simple, inotify::InotifyWatcher, logger::reload_logging_config,
+        mode_list::write_mode_list,
     },
 };
 
-/// 仅包含 global 部分的简化配置结构，用于提取全局模式
-/// 不需要解析完整配置，只需要 global.mode 字段
-#[derive(Deserialize)]
-struct GlobalConfigOnly {
-    global: GlobalOnly,
-}
-
-#[derive(Deserialize)]
-struct GlobalOnly {
-    mode: String,
-}
-
-impl GlobalConfigOnly {
-    fn global_mode(&self) -> &str {
-        &self.global.mode
+/// 频率表文件内容保持不变多久后视为写入完成
+const FREQ_TABLE_SETTLE_TIME: Duration = Duration::from_millis(200);
+/// 等待频率表写入完成的最多次数
+const FREQ_TABLE_SETTLE_ATTEMPTS: u32 = 10;
+
+/// 等待频率表文件内容稳定后解析校验
+///
+/// 写入方可能分多次写入，收到 close_write 时文件内容仍可能不完整，
+/// 两次读取内容一致后才解析
+fn read_settled_freq_table(gpu: &GPU) -> Result<ParsedFreqTable> {
+    let mut content = fs::read_to_string(FREQ_TABLE_CONFIG_FILE)?;
+    for _ in 0..FREQ_TABLE_SETTLE_ATTEMPTS {
+        thread::sleep(FREQ_TABLE_SETTLE_TIME);
+        let latest = fs::read_to_string(FREQ_TABLE_CONFIG_FILE)?;
+        if latest == content {
+            return parse_freq_table(&content, FREQ_TABLE_CONFIG_FILE, gpu);
+        }
+        content = latest;
     }
+    Err(anyhow!(
+        "{FREQ_TABLE_CONFIG_FILE} is still being written after {}ms",
+        FREQ_TABLE_SETTLE_TIME.as_millis() * FREQ_TABLE_SETTLE_ATTEMPTS as u128
+    ))
 }
 
-pub fn monitor_freq_table_config(mut gpu: GPU) -> Result<()> {
+pub fn monitor_freq_table_config(mut gpu: GPU, tx: Sender<ConfigDelta>) -> Result<()> {
     // 设置线程名称（在Rust中无法轻易设置当前线程名称）
     info!("{FREQ_TABLE_MONITOR_THREAD} Start");
 
@@ -61,10 +71,10 @@ pub fn monitor_freq_table_config(mut gpu: GPU) -> Result<()> {
     let min_freq = gpu.get_min_freq();
     let max_freq = gpu.get_max_freq();
     // 使用read_freq_ge方法获取大于等于特定频率的最小频率
-    let target_freq = 600000; // 600MHz
+    let target_freq = Freq::from_mhz(600);
     let _ge_freq = gpu.read_freq_ge(target_freq);
     // 使用read_freq_le方法获取小于等于特定频率的最大频率
-    let target_freq2 = 800000; // 800MHz
+    let target_freq2 = Freq::from_mhz(800);
     let _le_freq = gpu.read_freq_le(target_freq2);
 
     // 从GPU对象获取margin值
@@ -80,8 +90,10 @@ pub fn monitor_freq_table_config(mut gpu: GPU) -> Result<()> {
     )?;
 
     // 初始读取频率表配置
-    if check_read_simple(FREQ_TABLE_CONFIG_FILE) {
-        freq_table_read(FREQ_TABLE_CONFIG_FILE, &mut gpu)?;
+    if check_read_simple(FREQ_TABLE_CONFIG_FILE)
+        && let Err(e) = freq_table_read(FREQ_TABLE_CONFIG_FILE, &mut gpu)
+    {
+        warn!("Failed to read freq table config, keeping the current table: {e}");
     }
 
     loop {
@@ -99,8 +111,34 @@ pub fn monitor_freq_table_config(mut gpu: GPU) -> Result<()> {
         }
 
         if config_changed {
-            info!("Detected change in freq table config: {FREQ_TABLE_CONFIG_FILE}");
-            freq_table_read(FREQ_TABLE_CONFIG_FILE, &mut gpu)?;
+            // 新频率表校验通过后才切换，写入未完成或内容非法时保留当前频率表
+            let table = match read_settled_freq_table(&gpu) {
+                Ok(table) => table,
+                Err(e) => {
+                    warn!("Ignoring freq table update, keeping the current table: {e}");
+                    continue;
+                }
+            };
+            let generation = bump_freq_table_generation();
+            info!(
+                "Detected change in freq table config: {FREQ_TABLE_CONFIG_FILE} (generation {generation}, {} entries)",
+                table.len()
+            );
+            table.apply(&mut gpu, generation);
+
+            // 携带新代数的增量让调频循环同步刷新频率表和参数
+            let mode = read_active_mode();
+            match read_config_delta(mode.as_deref()) {
+                Ok(mut delta) => {
+                    if mode.is_some() {
+                        delta.mode = mode;
+                    }
+                    if tx.send(delta).is_err() {
+                        warn!("Failed to send freq table refresh delta");
+                    }
+                }
+                Err(e) => warn!("Failed to read config delta for freq table refresh: {e}"),
+            }
         }
     }
 }
@@ -110,7 +148,8 @@ pub fn monitor_custom_config(tx: Sender<ConfigDelta>) -> Result<()> {
     info!("{CONFIG_MONITOR_THREAD} Start");
 
     // 使用自定义配置文件
-    let config_path = std::path::Path::new(CONFIG_TOML_FILE);
+    let config_file = active_config_path();
+    let config_path = std::path::Path::new(config_file);
     let config_dir = config_path.parent().unwrap_or(std::path::Path::new("/"));
     let config_filename = config_path
         .file_name()
@@ -119,8 +158,8 @@ pub fn monitor_custom_config(tx: Sender<ConfigDelta>) -> Result<()> {
         .to_string();
 
     // 检查自定义配置文件是否存在
-    if !check_read_simple(CONFIG_TOML_FILE) {
-        warn!("Custom config file not found: {CONFIG_TOML_FILE}");
+    if !check_read_simple(config_file) {
+        warn!("Custom config file not found: {config_file}");
         // 即使文件不存在，我们也应该监控目录，以便文件被创建时能检测到
     }
 
@@ -134,12 +173,16 @@ pub fn monitor_custom_config(tx: Sender<ConfigDelta>) -> Result<()> {
     // 注意：InotifyWatcher::add 会自动添加 DELETE_SELF 和 MOVE_SELF，这对目录监控也是有用的
     inotify.add(config_dir, WatchMask::MOVED_TO | WatchMask::CLOSE_WRITE)?;
 
-    // 记录上一次的全局模式（启动时读取一次，失败则留空）
-    // 使用简化的 GlobalConfigOnly 结构来提取模式，更宽容地处理配置格式
-    let mut last_mode: Option<String> = std::fs::read_to_string(CONFIG_TOML_FILE)
-        .ok()
-        .and_then(|c| toml::from_str::<GlobalConfigOnly>(&c).ok())
-        .map(|cfg| cfg.global_mode().to_string());
+    // 设备配置生效时同时监控被忽略的 config.toml，修改它时提示不会生效
+    let shadowed = shadowed_config_path();
+    let shadowed_filename = shadowed
+        .and_then(|path| std::path::Path::new(path).file_name())
+        .map(|name| name.to_string_lossy().to_string());
+    if let Some(shadowed_dir) = shadowed.and_then(|path| std::path::Path::new(path).parent())
+        && let Err(e) = inotify.add(shadowed_dir, WatchMask::MOVED_TO | WatchMask::CLOSE_WRITE)
+    {
+        warn!("Failed to watch {}: {e}", shadowed_dir.display());
+    }
 
     loop {
         // 等待事件
@@ -147,22 +190,38 @@ pub fn monitor_custom_config(tx: Sender<ConfigDelta>) -> Result<()> {
 
         // 检查是否有针对 config.toml 的事件
         let mut config_changed = false;
+        let mut shadowed_changed = false;
         for event in events {
-            if let Some(name) = &event.name
-                && name == &config_filename
-            {
+            let Some(name) = &event.name else {
+                continue;
+            };
+            if name == &config_filename {
                 config_changed = true;
-                break;
+            } else if shadowed_filename.as_ref() == Some(name) {
+                shadowed_changed = true;
             }
         }
 
+        if shadowed_changed && let Some(shadowed) = shadowed {
+            warn!(
+                "{shadowed} changed but is ignored, edit the device profile {config_file} instead"
+            );
+        }
         if !config_changed {
             continue;
         }
 
-        info!("Detected change in config file: {CONFIG_TOML_FILE}");
+        info!("Detected change in config file: {config_file}");
+        log_active_config("reload");
+        bump_config_generation();
 
-        // 先发送参数增量
+        // 日志配置不属于模式参数，直接在监控线程中生效
+        reload_logging_config();
+        if let Err(e) = write_mode_list() {
+            warn!("Failed to update mode list: {e}");
+        }
+
+        // 发送参数增量，模式变化由主调频循环同步到 current_mode 文件
         match read_config_delta(None) {
             Ok(delta) => {
                 if tx.send(delta).is_ok() {
@@ -171,28 +230,5 @@ pub fn monitor_custom_config(tx: Sender<ConfigDelta>) -> Result<()> {
             }
             Err(e) => warn!("Failed to parse custom config: {e}"),
         }
-
-        // 检测全局模式是否变化，若变化则更新 CURRENT_MODE_PATH
-        // 使用简化的 GlobalConfigOnly 结构，只需要 global.mode 字段
-        // 这样即使其他配置字段不完整，也能正确更新当前模式
-        match std::fs::read_to_string(CONFIG_TOML_FILE) {
-            Ok(content) => match toml::from_str::<GlobalConfigOnly>(&content) {
-                Ok(cfg) => {
-                    let mode_now = cfg.global_mode().to_string();
-                    if last_mode.as_deref() != Some(mode_now.as_str()) {
-                        // 更新文件
-                        match write_file(CURRENT_MODE_PATH, mode_now.as_bytes(), 1024) {
-                            Ok(_) => info!(
-                                "Global mode changed -> {mode_now}, current_mode file updated"
-                            ),
-                            Err(e) => warn!("Failed to write current_mode file: {e}"),
-                        }
-                        last_mode = Some(mode_now);
-                    }
-                }
-                Err(e) => warn!("Failed to parse config.toml when checking mode change: {e}"),
-            },
-            Err(e) => warn!("Failed to read config.toml when checking mode change: {e}"),
-        }
     }
 }
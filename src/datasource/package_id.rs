@@ -0,0 +1,78 @@
+//! 前台应用包名规范化
+//!
+//! 工作资料和多用户下，各检测方式上报的包名可能带有用户ID前缀（`10:包名`、`u10_包名`）、
+//! 用户后缀（`包名@10`）或子进程后缀（`包名:进程`），直接用来查找游戏列表会匹配失败。
+//! 统一解析为 [`PackageId`]，规范形式为 `包名`（主用户）或 `包名@用户ID`。
+
+use std::fmt;
+
+/// 主用户ID，规范形式中省略
+const PRIMARY_USER: u32 = 0;
+
+/// 包名及其所属用户
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PackageId {
+    pub package: String,
+    /// 所属用户，主用户或未知时为 `None`
+    pub user: Option<u32>,
+}
+
+impl PackageId {
+    /// 由包名和用户ID构造，主用户视为未指定
+    pub fn new(package: &str, user: Option<u32>) -> Self {
+        Self {
+            package: package.to_string(),
+            user: user.filter(|&user| user != PRIMARY_USER),
+        }
+    }
+
+    /// 解析检测方式上报的包名或游戏列表中的条目
+    pub fn parse(raw: &str) -> Self {
+        let mut rest = raw.trim();
+        let mut user = None;
+
+        // `10:包名`
+        if let Some((prefix, package)) = rest.split_once(':')
+            && let Ok(id) = prefix.parse::<u32>()
+        {
+            user = Some(id);
+            rest = package;
+        }
+        // `u10_包名`
+        if let Some((prefix, package)) = rest.split_once('_')
+            && let Some(id) = prefix
+                .strip_prefix('u')
+                .and_then(|id| id.parse::<u32>().ok())
+        {
+            user = Some(id);
+            rest = package;
+        }
+        // `包名@10`
+        if let Some((package, suffix)) = rest.rsplit_once('@')
+            && let Ok(id) = suffix.parse::<u32>()
+        {
+            user = Some(id);
+            rest = package;
+        }
+        // `包名:进程`
+        if let Some((package, _)) = rest.split_once(':') {
+            rest = package;
+        }
+
+        Self::new(rest, user)
+    }
+
+    /// 去掉用户后的标识，用于匹配不区分用户的条目
+    pub fn any_user(&self) -> Self {
+        Self::new(&self.package, None)
+    }
+}
+
+impl fmt::Display for PackageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.user {
+            Some(user) => write!(f, "{}@{user}", self.package),
+            None => f.write_str(&self.package),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::Result;
+use log::{debug, info, warn};
+
+use crate::datasource::{
+    config_parser::{ConfigDelta, read_config_delta},
+    display_monitor::read_active_mode,
+    file_path::*,
+};
+
+/// 充电状态轮询间隔
+const CHARGING_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 电池电流绝对值低于该值（微安）时视为电池闲置，由充电器直接供电
+const BATTERY_IDLE_CURRENT_UA: i64 = 50_000;
+/// 充电器类型的 power_supply
+const CHARGER_TYPES: [&str; 5] = ["Mains", "USB", "USB_PD", "USB_C", "Wireless"];
+
+static CHARGE_BYPASS: AtomicBool = AtomicBool::new(false);
+
+/// 是否处于旁路充电状态（充电器在线且电池闲置）
+pub fn is_charge_bypass_active() -> bool {
+    CHARGE_BYPASS.load(Ordering::Relaxed)
+}
+
+fn read_attr(supply: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(supply.join(attr))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// 从 power_supply 节点检测旁路充电状态
+fn detect_charge_bypass() -> bool {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY_DIR) else {
+        return false;
+    };
+
+    let mut charger_online = false;
+    let mut battery_idle = false;
+    for supply in entries.flatten().map(|entry| entry.path()) {
+        let Some(supply_type) = read_attr(&supply, "type") else {
+            continue;
+        };
+
+        if supply_type == "Battery" {
+            let not_charging = read_attr(&supply, "status").is_some_and(|s| s == "Not charging");
+            let idle_current = read_attr(&supply, "current_now")
+                .and_then(|c| c.parse::<i64>().ok())
+                .is_some_and(|c| c.abs() < BATTERY_IDLE_CURRENT_UA);
+            battery_idle |= not_charging || idle_current;
+        } else if CHARGER_TYPES.contains(&supply_type.as_str()) {
+            charger_online |= read_attr(&supply, "online").is_some_and(|o| o == "1");
+        }
+    }
+
+    charger_online && battery_idle
+}
+
+/// 监控旁路充电状态，变化时发送配置增量以切换 `[charging]` 配置
+pub fn monitor_charging_state(tx: Sender<ConfigDelta>) -> Result<()> {
+    info!("{CHARGING_MONITOR_THREAD} Start");
+
+    loop {
+        let active = detect_charge_bypass();
+        if active != CHARGE_BYPASS.swap(active, Ordering::Relaxed) {
+            info!("Charge bypass state changed: {active}");
+
+            let mode = read_active_mode();
+            match read_config_delta(mode.as_deref()) {
+                Ok(mut delta) => {
+                    if mode.is_some() {
+                        delta.mode = mode;
+                    }
+                    if tx.send(delta).is_err() {
+                        warn!("Failed to send charging profile config delta");
+                    }
+                }
+                Err(e) => debug!("Failed to read config delta for charging change: {e}"),
+            }
+        }
+
+        thread::sleep(CHARGING_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,79 @@
+//! 系统休眠唤醒检测
+//!
+//! 深度休眠期间 `CLOCK_MONOTONIC` 停止计时而 `CLOCK_BOOTTIME` 继续计时，
+//! 两者的增量出现明显差异即说明设备刚从休眠中唤醒；
+//! 同时定期读取 `/sys/power/suspend_stats/success`，计数变化同样视为发生过休眠。
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use crate::datasource::file_path::SUSPEND_STATS_SUCCESS;
+
+/// 两种时钟增量之差超过该值时视为发生过休眠
+const SUSPEND_GAP_THRESHOLD: Duration = Duration::from_secs(1);
+/// 读取休眠统计的间隔
+const SUSPEND_STATS_INTERVAL: Duration = Duration::from_secs(2);
+
+fn clock_now(clock: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+fn read_suspend_count() -> Option<u64> {
+    fs::read_to_string(SUSPEND_STATS_SUCCESS)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// 休眠唤醒检测器
+pub struct ResumeDetector {
+    monotonic: Duration,
+    boottime: Duration,
+    suspend_count: Option<u64>,
+    stats_read_at: Instant,
+}
+
+impl ResumeDetector {
+    pub fn new() -> Self {
+        Self {
+            monotonic: clock_now(libc::CLOCK_MONOTONIC),
+            boottime: clock_now(libc::CLOCK_BOOTTIME),
+            suspend_count: read_suspend_count(),
+            stats_read_at: Instant::now(),
+        }
+    }
+
+    /// 检查上次调用以来是否发生过休眠，返回估计的休眠时长
+    pub fn check(&mut self) -> Option<Duration> {
+        let monotonic = clock_now(libc::CLOCK_MONOTONIC);
+        let boottime = clock_now(libc::CLOCK_BOOTTIME);
+        let asleep = (boottime.saturating_sub(self.boottime))
+            .saturating_sub(monotonic.saturating_sub(self.monotonic));
+        self.monotonic = monotonic;
+        self.boottime = boottime;
+
+        let mut resumed = asleep >= SUSPEND_GAP_THRESHOLD;
+        if resumed || self.stats_read_at.elapsed() >= SUSPEND_STATS_INTERVAL {
+            let count = read_suspend_count();
+            resumed |=
+                count.is_some() && self.suspend_count.is_some() && count != self.suspend_count;
+            self.suspend_count = count;
+            self.stats_read_at = Instant::now();
+        }
+        resumed.then_some(asleep)
+    }
+}
+
+impl Default for ResumeDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
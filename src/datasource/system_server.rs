@@ -0,0 +1,102 @@
+//! system_server 重启检测
+//!
+//! 软重启（zygote/system_server 崩溃后重新拉起）不会重启守护进程，但 binder 连接全部失效，
+//! 前台应用缓存也已过期。通过 system_server 的PID变化识别重启，等待 `sys.boot_completed`
+//! 重新置位后再通知前台监控线程重新同步。
+
+use std::{
+    fs,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+
+/// 进程名
+const SYSTEM_SERVER: &str = "system_server";
+/// 常规检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 读取进程名，进程不存在时返回 `None`
+fn process_name(pid: u32) -> Option<String> {
+    let cmdline = fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    let name = String::from_utf8_lossy(&cmdline);
+    Some(name.split('\0').next().unwrap_or_default().to_string())
+}
+
+/// 遍历 /proc 查找 system_server 的PID
+fn find_system_server() -> Option<u32> {
+    fs::read_dir("/proc")
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .find(|&pid| process_name(pid).as_deref() == Some(SYSTEM_SERVER))
+}
+
+/// 系统是否已完成启动
+fn boot_completed() -> bool {
+    Command::new("getprop")
+        .arg("sys.boot_completed")
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// system_server 重启检测器
+pub struct SystemServerWatcher {
+    /// 当前 system_server 的PID
+    pid: Option<u32>,
+    /// 检测到重启后等待系统重新完成启动
+    restarting: bool,
+    next_check: Instant,
+}
+
+impl SystemServerWatcher {
+    pub fn new() -> Self {
+        let pid = find_system_server();
+        debug!("system_server pid: {pid:?}");
+        Self {
+            pid,
+            restarting: false,
+            next_check: Instant::now() + CHECK_INTERVAL,
+        }
+    }
+
+    /// 下一次调用 [`check`](Self::check) 时立即检查，用于 binder 调用失败后尽快确认原因
+    pub fn check_soon(&mut self) {
+        self.next_check = Instant::now();
+    }
+
+    /// 检查 system_server 是否重启过，重启完成且系统重新启动完毕时返回 `true`
+    pub fn check(&mut self) -> bool {
+        if Instant::now() < self.next_check {
+            return false;
+        }
+        self.next_check = Instant::now() + CHECK_INTERVAL;
+
+        // 进程仍在时只需确认PID对应的进程名，避免每次遍历 /proc
+        let alive = self
+            .pid
+            .is_some_and(|pid| process_name(pid).as_deref() == Some(SYSTEM_SERVER));
+        if !alive {
+            let pid = find_system_server();
+            if pid != self.pid {
+                if self.pid.is_some() && !self.restarting {
+                    info!("system_server restarted ({:?} -> {pid:?})", self.pid);
+                    self.restarting = true;
+                }
+                self.pid = pid;
+            }
+        }
+
+        if self.restarting && self.pid.is_some() && boot_completed() {
+            self.restarting = false;
+            return true;
+        }
+        false
+    }
+}
+
+impl Default for SystemServerWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fs, path::PathBuf};
+
+use log::{debug, info};
+use once_cell::sync::Lazy;
+
+use crate::{datasource::file_path::THERMAL_ZONE_DIR, utils::file_operate::read_file};
+
+/// GPU温度传感器类型名称中可能包含的关键字
+const GPU_ZONE_KEYWORDS: [&str; 3] = ["gpu", "mali", "mfg"];
+
+/// GPU温度节点，首次使用时探测
+static GPU_TEMP_NODE: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let node = find_gpu_thermal_zone();
+    match &node {
+        Some(path) => info!("GPU thermal zone: {}", path.display()),
+        None => info!("No GPU thermal zone found"),
+    }
+    node
+});
+
+/// 在 thermal_zone 中查找类型名称与GPU相关的传感器
+fn find_gpu_thermal_zone() -> Option<PathBuf> {
+    let mut zones = fs::read_dir(THERMAL_ZONE_DIR)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("thermal_zone"))
+        })
+        .collect::<Vec<_>>();
+    zones.sort();
+
+    zones.into_iter().find_map(|zone| {
+        let zone_type = fs::read_to_string(zone.join("type")).ok()?.to_lowercase();
+        GPU_ZONE_KEYWORDS
+            .iter()
+            .any(|keyword| zone_type.contains(keyword))
+            .then(|| zone.join("temp"))
+    })
+}
+
+/// 读取GPU温度（摄氏度），没有可用传感器时返回 `None`
+pub fn read_gpu_temperature() -> Option<i32> {
+    let node = GPU_TEMP_NODE.as_ref()?;
+    let content = match read_file(node, 32) {
+        Ok(content) => content,
+        Err(e) => {
+            debug!("Failed to read GPU temperature: {e}");
+            return None;
+        }
+    };
+
+    let value = content.trim().parse::<i32>().ok()?;
+    // 大多数内核以毫摄氏度为单位上报
+    Some(if value.abs() >= 1000 {
+        value / 1000
+    } else {
+        value
+    })
+}
@@ -0,0 +1,55 @@
+//! 系统更新检测
+//!
+//! OTA 更新和应用安装期间 dex2oat 会大量编译，固定的低频率会拖慢编译，
+//! 检测到这类活动时由调频线程临时进入旁路模式，将频率控制交还内核。
+//! 该功能默认关闭，需在 config.toml 中设置 `update_bypass = true` 启用；
+//! 扫描 /proc 的开销较大，调用方应按较长的间隔传入 `scan_processes`。
+
+use std::fs;
+
+use crate::datasource::foreground_app::current_foreground_package;
+
+/// dexopt/OTA 预优化进程名前缀（`comm` 最长15个字符，按前缀匹配）
+const DEXOPT_PROCESS_PREFIXES: [&str; 2] = ["dex2oat", "otapreopt"];
+
+/// 安装器应用包名
+const INSTALLER_PACKAGES: [&str; 2] = [
+    "com.android.packageinstaller",
+    "com.google.android.packageinstaller",
+];
+
+/// 查找正在运行的 dexopt 进程
+fn find_dexopt_process() -> Option<String> {
+    fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .find_map(|entry| {
+            let comm = fs::read_to_string(entry.path().join("comm")).ok()?;
+            let comm = comm.trim();
+            DEXOPT_PROCESS_PREFIXES
+                .iter()
+                .any(|prefix| comm.starts_with(prefix))
+                .then(|| comm.to_string())
+        })
+}
+
+/// 检测正在进行的系统更新或应用安装，返回检测到的活动描述
+///
+/// 前台安装器每次都会检查，`scan_processes` 为 false 时跳过 dexopt 进程扫描
+pub fn detect_update_activity(scan_processes: bool) -> Option<String> {
+    let foreground = current_foreground_package();
+    if INSTALLER_PACKAGES.contains(&foreground.as_str()) {
+        return Some(format!("{foreground} in foreground"));
+    }
+
+    if !scan_processes {
+        return None;
+    }
+    find_dexopt_process().map(|process| format!("{process} running"))
+}
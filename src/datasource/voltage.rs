@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    datasource::file_path::{GPU_FREQ_LOAD_PATH, GPUFREQV2_TABLE, REGULATOR_DIR},
+    utils::file_operate::read_file,
+};
+
+static V2_VOLT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"volt:\s*(\d+)").unwrap());
+static V1_VOLT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"g_cur_gpu_volt\s*=\s*(\d+)").unwrap());
+
+/// GPU稳压器的 microvolts 节点，首次使用时探测
+static GPU_REGULATOR_NODE: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let node = find_gpu_regulator();
+    match &node {
+        Some(path) => info!("GPU regulator: {}", path.display()),
+        None => debug!("No GPU regulator found"),
+    }
+    node
+});
+
+/// 在稳压器中查找名称与GPU相关的节点
+fn find_gpu_regulator() -> Option<PathBuf> {
+    let mut regulators = fs::read_dir(REGULATOR_DIR)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    regulators.sort();
+
+    regulators.into_iter().find_map(|regulator| {
+        let name = fs::read_to_string(regulator.join("name"))
+            .ok()?
+            .to_lowercase();
+        let node = regulator.join("microvolts");
+        (name.contains("gpu") && node.exists()).then_some(node)
+    })
+}
+
+/// 从v2驱动的工作OPP表中读取当前档位（带 `*` 或 `<-` 标记的行）的电压
+fn read_v2_table_voltage() -> Option<i64> {
+    let content = read_file(GPUFREQV2_TABLE, 8192).ok()?;
+    content
+        .lines()
+        .find(|line| line.trim_start().starts_with('*') || line.contains("<-"))
+        .and_then(|line| V2_VOLT_RE.captures(line))
+        .and_then(|c| c[1].parse().ok())
+}
+
+/// 从v1驱动的变量转储中读取当前电压
+fn read_v1_dump_voltage() -> Option<i64> {
+    let content = read_file(GPU_FREQ_LOAD_PATH, 8192).ok()?;
+    V1_VOLT_RE
+        .captures(&content)
+        .and_then(|c| c[1].parse().ok())
+}
+
+/// 从稳压器读取电压，单位由微伏换算为驱动使用的10微伏
+fn read_regulator_voltage() -> Option<i64> {
+    let node = GPU_REGULATOR_NODE.as_ref()?;
+    let microvolts = fs::read_to_string(node).ok()?.trim().parse::<i64>().ok()?;
+    Some(microvolts / 10)
+}
+
+/// 内核是否提供了可回读GPU电压的节点
+pub fn gpu_voltage_node_exists(gpuv2: bool) -> bool {
+    let driver_node = if gpuv2 {
+        GPUFREQV2_TABLE
+    } else {
+        GPU_FREQ_LOAD_PATH
+    };
+    Path::new(driver_node).exists() || GPU_REGULATOR_NODE.is_some()
+}
+
+/// 读取GPU当前实际电压（10微伏），内核未提供时返回 `None`
+pub fn read_gpu_voltage(gpuv2: bool) -> Option<i64> {
+    let driver_volt = if gpuv2 {
+        read_v2_table_voltage()
+    } else {
+        read_v1_dump_voltage()
+    };
+    driver_volt
+        .filter(|&volt| volt > 0)
+        .or_else(read_regulator_voltage)
+}
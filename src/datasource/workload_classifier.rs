@@ -0,0 +1,126 @@
+//! 疑似游戏识别
+//!
+//! 游戏列表之外的游戏不会进入游戏模式，用户往往不知道需要手动添加。启用
+//! `[foreground.game_detection]` 后，前台应用在一个判定窗口内的平均GPU负载超过阈值时
+//! 判定为疑似游戏：输出添加到 games.toml 的建议并写入 status.json，配置了 `auto_mode`
+//! 时直接应用该模式，离开前台后恢复全局模式。
+
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use crate::{
+    datasource::{config_parser::GameDetectionConfig, file_path::GAMES_CONF_PATH},
+    utils::status_report::{read_status, update_status},
+};
+
+/// 疑似游戏识别器，由前台监控线程每秒调用一次
+pub struct WorkloadClassifier {
+    config: GameDetectionConfig,
+    /// 当前观察的前台应用
+    package: String,
+    /// 判定窗口的开始时间
+    window_start: Instant,
+    /// 窗口内的负载采样数和负载之和
+    samples: u32,
+    total_load: i64,
+    /// 已判定为疑似游戏的应用，建议只输出一次
+    flagged: HashSet<String>,
+    /// 已自动应用游戏模式的应用
+    auto_applied: Option<String>,
+}
+
+impl WorkloadClassifier {
+    pub fn new(config: GameDetectionConfig) -> Self {
+        Self {
+            config,
+            package: String::new(),
+            window_start: Instant::now(),
+            samples: 0,
+            total_load: 0,
+            flagged: HashSet::new(),
+            auto_applied: None,
+        }
+    }
+
+    /// 配置文件变化后更新识别配置
+    pub fn set_config(&mut self, config: GameDetectionConfig) {
+        self.config = config;
+    }
+
+    fn reset_window(&mut self) {
+        self.window_start = Instant::now();
+        self.samples = 0;
+        self.total_load = 0;
+    }
+
+    /// 记录一次前台应用和当前负载，需要自动应用游戏模式时返回模式名
+    ///
+    /// `listed` 表示该应用已在游戏列表中，此时不参与识别
+    pub fn observe(&mut self, package: &str, listed: bool) -> Option<String> {
+        if !self.config.enabled || package.is_empty() || listed {
+            self.package.clear();
+            return None;
+        }
+        if package != self.package {
+            self.package = package.to_string();
+            self.reset_window();
+        }
+
+        // 已判定过的应用回到前台时直接应用模式，无需重新判定
+        if self.flagged.contains(package) {
+            return self.auto_mode_for(package);
+        }
+
+        self.samples += 1;
+        self.total_load += i64::from(read_status(|s| s.gpu_load).max(0));
+        if self.window_start.elapsed() < Duration::from_secs(self.config.duration_secs) {
+            return None;
+        }
+
+        let average = self.total_load / i64::from(self.samples.max(1));
+        self.reset_window();
+        if average < i64::from(self.config.load_threshold) {
+            return None;
+        }
+
+        info!(
+            "{package} looks like a game (average GPU load {average}% over {}s), consider adding it to {GAMES_CONF_PATH}",
+            self.config.duration_secs
+        );
+        self.flagged.insert(package.to_string());
+        let likely = package.to_string();
+        update_status(|s| {
+            if !s.likely_games.contains(&likely) {
+                s.likely_games.push(likely);
+            }
+        });
+        self.auto_mode_for(package)
+    }
+
+    fn auto_mode_for(&mut self, package: &str) -> Option<String> {
+        if self.auto_applied.as_deref() == Some(package) {
+            return None;
+        }
+        let mode = self.config.auto_mode.clone()?;
+        info!("Applying {mode} mode to likely game {package}");
+        self.auto_applied = Some(package.to_string());
+        Some(mode)
+    }
+
+    /// 前台切换到 `package` 时调用，之前自动应用的游戏模式需要恢复时返回 `true`
+    pub fn leave_auto_mode(&mut self, package: &str) -> bool {
+        self.auto_applied
+            .take_if(|applied| applied != package)
+            .is_some()
+    }
+}
+
+impl Default for WorkloadClassifier {
+    fn default() -> Self {
+        Self::new(GameDetectionConfig::default())
+    }
+}
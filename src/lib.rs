@@ -0,0 +1,10 @@
+//! GPU Governor 核心库
+//!
+//! 守护进程与开发工具（如负载轨迹回放）共用的数据源、调频模型和工具模块。
+//! 频率表和配置的解析校验、调频引擎均可在其他 Rust 项目（前端、测试工具）中直接使用，
+//! 守护进程本身的启动流程位于 [`daemon`]，可执行文件只是它的一层薄封装。
+
+pub mod daemon;
+pub mod datasource;
+pub mod model;
+pub mod utils;
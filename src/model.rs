@@ -1,6 +1,21 @@
+pub mod calibration;
+pub mod config_apply;
+pub mod conflict_detector;
 pub mod ddr_manager;
+pub mod delivery_stats;
+pub mod devfreq_clamp;
+pub mod fault_rollback;
+pub mod freq;
+pub mod freq_curve;
 pub mod frequency_engine;
 pub mod frequency_manager;
 pub mod frequency_strategy;
 pub mod gpu;
 pub mod idle_manager;
+pub mod load_analyzer;
+pub mod margin_schedule;
+pub mod mode_arbiter;
+pub mod mode_state;
+pub mod stress_test;
+pub mod thermal_cap;
+pub mod timer_wheel;
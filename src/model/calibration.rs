@@ -0,0 +1,345 @@
+//! 频率档位自校准
+//!
+//! 依次将GPU锁定在频率表的每个档位上停留一小段时间，测量实际运行频率、负载上报情况和
+//! 节点写入耗时，生成校准报告。实际频率达不到请求档位的视为损坏档位，
+//! 之后从可选频率中剔除，可选频率范围随之收窄到可用档位。
+
+use std::{
+    fs,
+    ops::{Deref, DerefMut},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{
+        file_path::*,
+        load_monitor::{get_gpu_current_freq, get_gpu_load},
+    },
+    model::{freq::Freq, frequency_manager::FrequencyManager, gpu::GPU},
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 每个档位的停留时间
+const CALIBRATION_DWELL: Duration = Duration::from_millis(300);
+/// 停留期间的采样间隔
+const CALIBRATION_SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+/// 实际频率允许低于请求频率的比例（百分比）
+const CALIBRATION_TOLERANCE_PERCENT: i64 = 5;
+/// 依次尝试的写入间隔（毫秒），取第一个能稳定切换档位的值
+const WRITE_DELAY_CANDIDATES_MS: [u64; 6] = [0, 2, 5, 10, 20, 40];
+/// 每个候选写入间隔的档位切换次数
+const WRITE_DELAY_TRIALS: usize = 4;
+/// 写入后等待实际频率生效的时间
+const WRITE_DELAY_SETTLE: Duration = Duration::from_millis(100);
+
+/// 单个档位的校准结果
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OppCalibration {
+    /// 请求的频率（KHz）
+    pub freq: Freq,
+    /// 停留期间读到的最高实际频率（KHz），读取失败时为0
+    pub achieved: Freq,
+    /// 实际频率是否达到请求频率
+    pub usable: bool,
+    /// 停留期间负载读数的最小值和最大值
+    pub load_min: i32,
+    pub load_max: i32,
+    /// 写入频率和电压节点的耗时（微秒）
+    pub write_latency_us: u64,
+}
+
+/// 校准报告
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CalibrationReport {
+    /// 生成时间（Unix时间戳，秒）
+    pub created_at: u64,
+    pub gpuv2: bool,
+    /// 被写入的频率控制节点
+    pub opp_node: String,
+    pub volt_node: String,
+    pub opps: Vec<OppCalibration>,
+    /// 可用档位中的最低和最高频率
+    pub usable_min: Option<Freq>,
+    pub usable_max: Option<Freq>,
+    /// 损坏的档位
+    pub broken: Vec<Freq>,
+    /// 测得的最短可靠写入间隔（毫秒），仅 gpufreqv2 测量
+    #[serde(default)]
+    pub write_delay_ms: Option<u64>,
+}
+
+/// 读取已保存的校准报告
+pub fn load_calibration() -> Option<CalibrationReport> {
+    let content = fs::read_to_string(CALIBRATION_PATH).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            warn!("Ignoring invalid calibration report {CALIBRATION_PATH}: {e}");
+            None
+        }
+    }
+}
+
+/// 根据校准报告剔除损坏档位
+///
+/// 可选频率由完整频率表重新生成，之前被剔除、重新校准后恢复正常的档位会重新启用
+pub fn apply_calibration(gpu: &mut GPU, report: &CalibrationReport) {
+    if report.gpuv2 != gpu.is_gpuv2() {
+        warn!("Calibration report was generated for a different driver, ignoring it");
+        return;
+    }
+
+    if let Some(delay) = report.write_delay_ms {
+        debug!("Calibration: using measured write delay {delay}ms");
+        gpu.frequency_mut().calibrated_write_delay = Some(Duration::from_millis(delay));
+    }
+
+    let table = gpu.table_freqs();
+    let usable: Vec<Freq> = table
+        .iter()
+        .copied()
+        .filter(|freq| !report.broken.contains(freq))
+        .collect();
+    if usable.is_empty() {
+        warn!("Calibration marks every frequency as broken, ignoring it");
+        return;
+    }
+    if usable == gpu.get_config_list() {
+        return;
+    }
+
+    info!(
+        "Calibration: excluding {} broken OPP(s), usable range {}-{}KHz",
+        table.len() - usable.len(),
+        usable[0],
+        usable[usable.len() - 1]
+    );
+    gpu.set_config_list(usable);
+}
+
+/// 读取并应用已保存的校准报告
+pub fn apply_saved_calibration(gpu: &mut GPU) {
+    if let Some(report) = load_calibration() {
+        apply_calibration(gpu, &report);
+    }
+}
+
+/// 在单个档位上停留并测量
+fn calibrate_opp(gpu: &mut GPU, freq: Freq) -> Result<OppCalibration> {
+    gpu.set_cur_freq(freq);
+    gpu.frequency_mut().cur_freq_idx = gpu.frequency().read_freq_index(freq);
+    gpu.frequency_mut().gen_cur_volt();
+
+    let start = Instant::now();
+    gpu.frequency().write_freq(false, false)?;
+    let write_latency_us = start.elapsed().as_micros() as u64;
+
+    let expected = if gpu.is_gpuv2() {
+        gpu.frequency().get_closest_v2_supported_freq(freq)
+    } else {
+        freq
+    };
+
+    let mut achieved = Freq::ZERO;
+    let mut load_min = i32::MAX;
+    let mut load_max = 0;
+    let deadline = Instant::now() + CALIBRATION_DWELL;
+    while Instant::now() < deadline {
+        thread::sleep(CALIBRATION_SAMPLE_INTERVAL);
+        if let Ok(current) = get_gpu_current_freq(!gpu.is_gpuv2()) {
+            achieved = achieved.max(current);
+        }
+        if let Ok(load) = get_gpu_load() {
+            load_min = load_min.min(load);
+            load_max = load_max.max(load);
+        }
+    }
+
+    let usable = achieved >= expected.percent(100 - CALIBRATION_TOLERANCE_PERCENT);
+    debug!("Calibrated {freq}KHz: achieved {achieved}KHz, write {write_latency_us}us");
+    Ok(OppCalibration {
+        freq,
+        achieved,
+        usable,
+        load_min: load_min.min(load_max),
+        load_max,
+        write_latency_us,
+    })
+}
+
+/// 写入指定档位并确认实际频率是否在等待时间内达到
+fn switch_and_verify(gpu: &mut GPU, freq: Freq) -> Result<bool> {
+    gpu.set_cur_freq(freq);
+    gpu.frequency_mut().cur_freq_idx = gpu.frequency().read_freq_index(freq);
+    gpu.frequency_mut().gen_cur_volt();
+    gpu.frequency().write_freq(false, false)?;
+    thread::sleep(WRITE_DELAY_SETTLE);
+
+    let expected = gpu.frequency().get_closest_v2_supported_freq(freq);
+    Ok(get_gpu_current_freq(false)
+        .is_ok_and(|current| current >= expected.percent(100 - CALIBRATION_TOLERANCE_PERCENT)))
+}
+
+/// 测量 gpufreqv2 释放固定档位后写入电压所需的最短间隔
+///
+/// 在两个可用档位之间来回切换，从最短的候选间隔开始，取第一个每次切换都能达到目标频率的值；
+/// 所有候选值都不可靠时返回 `None`，继续使用默认间隔
+fn measure_write_delay(gpu: &mut GPU, usable: &[Freq]) -> Result<Option<u64>> {
+    let (Some(&low), Some(&high)) = (usable.first(), usable.last()) else {
+        return Ok(None);
+    };
+    if low == high {
+        return Ok(None);
+    }
+
+    let previous = gpu.frequency().calibrated_write_delay;
+    let mut measured = None;
+    for delay_ms in WRITE_DELAY_CANDIDATES_MS {
+        gpu.frequency_mut().calibrated_write_delay = Some(Duration::from_millis(delay_ms));
+        let mut reliable = true;
+        for trial in 0..WRITE_DELAY_TRIALS {
+            let target = if trial % 2 == 0 { high } else { low };
+            if !switch_and_verify(gpu, target)? {
+                reliable = false;
+                break;
+            }
+        }
+        debug!("Write delay {delay_ms}ms reliable: {reliable}");
+        if reliable {
+            measured = Some(delay_ms);
+            break;
+        }
+    }
+    gpu.frequency_mut().calibrated_write_delay = measured.map(Duration::from_millis).or(previous);
+    Ok(measured)
+}
+
+/// 校准前的频率状态，离开作用域时（包括出错提前返回）恢复
+///
+/// 校准前GPU处于手动调频时重新写入原来的频率和电压，否则将频率控制交还内核并恢复DVFS
+struct FrequencyRestore<'a> {
+    gpu: &'a mut GPU,
+    cur_freq: Freq,
+    cur_freq_idx: i64,
+    cur_volt: i64,
+    dvfs_disabled: bool,
+}
+
+impl<'a> FrequencyRestore<'a> {
+    fn new(gpu: &'a mut GPU) -> Self {
+        let frequency = gpu.frequency();
+        Self {
+            cur_freq: frequency.cur_freq,
+            cur_freq_idx: frequency.cur_freq_idx,
+            cur_volt: frequency.cur_volt,
+            dvfs_disabled: FrequencyManager::is_dvfs_disabled(),
+            gpu,
+        }
+    }
+}
+
+impl Deref for FrequencyRestore<'_> {
+    type Target = GPU;
+
+    fn deref(&self) -> &GPU {
+        self.gpu
+    }
+}
+
+impl DerefMut for FrequencyRestore<'_> {
+    fn deref_mut(&mut self) -> &mut GPU {
+        self.gpu
+    }
+}
+
+impl Drop for FrequencyRestore<'_> {
+    fn drop(&mut self) {
+        let frequency = self.gpu.frequency_mut();
+        frequency.cur_freq = self.cur_freq;
+        frequency.cur_freq_idx = self.cur_freq_idx;
+        frequency.cur_volt = self.cur_volt;
+
+        let result = if self.dvfs_disabled && self.cur_freq.is_positive() {
+            self.gpu
+                .frequency()
+                .write_freq(self.gpu.need_dcs, self.gpu.is_idle())
+        } else {
+            self.gpu.frequency().release_to_kernel()
+        };
+        match result {
+            Ok(()) => debug!(
+                "Calibration: restored frequency state ({}KHz)",
+                self.cur_freq
+            ),
+            Err(e) => warn!("Failed to restore frequency state after calibration: {e}"),
+        }
+    }
+}
+
+/// 依次校准频率表中的每个档位，保存并返回校准报告
+///
+/// 校准期间调频循环暂停；检测到内核温控限频时拒绝执行，避免把被限制的高频档位误判为损坏。
+/// 校准覆盖完整频率表（包括之前被剔除的档位），结束或出错后恢复校准前的频率和DVFS状态
+pub fn run_calibration(gpu: &mut GPU) -> Result<CalibrationReport> {
+    if let Some(cap) = gpu.thermal_cap.cap() {
+        return Err(anyhow!(
+            "Kernel thermal cap active ({cap}KHz), calibration skipped"
+        ));
+    }
+
+    let table = gpu.table_freqs();
+    if table.is_empty() {
+        return Err(anyhow!("Frequency table is empty"));
+    }
+
+    let mut gpu = FrequencyRestore::new(gpu);
+    info!("Calibrating {} OPPs", table.len());
+    let mut opps = Vec::with_capacity(table.len());
+    for &freq in &table {
+        opps.push(calibrate_opp(&mut gpu, freq)?);
+    }
+
+    let usable: Vec<Freq> = opps.iter().filter(|o| o.usable).map(|o| o.freq).collect();
+    let broken: Vec<Freq> = opps.iter().filter(|o| !o.usable).map(|o| o.freq).collect();
+    let write_delay_ms = if gpu.is_gpuv2() {
+        measure_write_delay(&mut gpu, &usable)?
+    } else {
+        None
+    };
+    let (opp_node, volt_node) = if gpu.is_gpuv2() {
+        (GPUFREQV2_OPP, GPUFREQV2_VOLT)
+    } else {
+        (GPUFREQ_OPP, GPUFREQ_VOLT)
+    };
+    let report = CalibrationReport {
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        gpuv2: gpu.is_gpuv2(),
+        opp_node: opp_node.to_string(),
+        volt_node: volt_node.to_string(),
+        usable_min: usable.iter().min().copied(),
+        usable_max: usable.iter().max().copied(),
+        opps,
+        broken,
+        write_delay_ms,
+    };
+
+    let json = serde_json::to_string_pretty(&report)?;
+    write_file(CALIBRATION_PATH, json.as_bytes(), 1 << 20, FileClass::State)?;
+
+    info!(
+        "Calibration finished: {} usable, {} broken ({:?}), write delay {:?}ms, report saved to {CALIBRATION_PATH}",
+        usable.len(),
+        report.broken.len(),
+        report.broken,
+        report.write_delay_ms
+    );
+    Ok(report)
+}
@@ -0,0 +1,362 @@
+//! 配置增量到调速器状态的映射
+//!
+//! 启动时加载配置和运行时收到的配置增量都经由 [`apply_delta`] 写入调频策略、空闲管理、
+//! DDR 和频率管理器的状态，两条路径不会再出现字段遗漏或处理不一致。
+//!
+//! 增量是完整的参数集合，每个字段都直接覆盖对应状态而不是在原值上累加，
+//! 重复应用同一增量得到的状态不变；各 setter 只在值变化时输出日志。
+
+use log::info;
+
+use crate::{
+    datasource::{config_parser::ConfigDelta, load_monitor::set_load_source_policy},
+    model::{freq::Freq, gpu::GPU},
+    utils::{
+        file_operate::set_permissions_policy, mode_notify::set_mode_notify_policy,
+        sysfs_audit::set_audit_policy,
+    },
+};
+
+/// 将配置增量应用到GPU状态
+pub fn apply_delta(gpu: &mut GPU, delta: &ConfigDelta) {
+    apply_strategy(gpu, delta);
+    apply_idle(gpu, delta);
+    apply_ddr(gpu, delta);
+    apply_frequency(gpu, delta);
+    apply_policies(delta);
+
+    // 同步模式状态，未指定来源时沿用当前来源
+    if let Some(mode_name) = &delta.mode {
+        let source = delta.mode_source.unwrap_or(gpu.mode_state().source);
+        gpu.set_mode(mode_name, source);
+    }
+
+    info!(
+        "Applied config delta: margin={} sampling={} adaptive={} gaming={} idle_threshold={:?}",
+        delta.margin,
+        delta.sampling_interval,
+        delta.adaptive_sampling,
+        delta.gaming_mode,
+        delta.idle_threshold
+    );
+}
+
+/// 调频策略：余量、降频、采样和防抖
+fn apply_strategy(gpu: &mut GPU, delta: &ConfigDelta) {
+    let strategy = gpu.frequency_strategy_mut();
+    strategy.set_margin(delta.margin as u32);
+    strategy.set_aggressive_down(delta.aggressive_down);
+    strategy.set_aggressive_down_floor(delta.aggressive_down_floor);
+    strategy.set_hold_samples(delta.hold_samples);
+
+    if delta.adaptive_sampling {
+        gpu.set_adaptive_sampling(
+            true,
+            delta.min_adaptive_interval,
+            delta.max_adaptive_interval,
+            delta.sampling_interval,
+        );
+    } else {
+        gpu.set_adaptive_sampling(false, 0, 0, delta.sampling_interval);
+    }
+    gpu.set_up_rate_delay(delta.up_rate_delay);
+    gpu.set_debounce_times(delta.up_rate_delay, delta.down_rate_delay);
+    gpu.set_gaming_mode(delta.gaming_mode);
+    gpu.set_emergency_ramp(
+        delta.allow_emergency_stock_max,
+        delta.emergency_ramp_samples,
+    );
+    gpu.set_pinned_freq(delta.pinned_freq.map(Freq::from_khz));
+    gpu.set_freq_curve(&delta.freq_curve);
+}
+
+/// 空闲判定和断电
+fn apply_idle(gpu: &mut GPU, delta: &ConfigDelta) {
+    let idle = gpu.idle_manager_mut();
+    if let Some(threshold) = delta.idle_threshold {
+        idle.set_idle_threshold(threshold);
+    }
+    idle.set_adaptive(delta.adaptive_idle, delta.adaptive_idle_max);
+    idle.set_power_collapse(delta.idle.clone());
+}
+
+/// DDR策略和旁路充电下限
+fn apply_ddr(gpu: &mut GPU, delta: &ConfigDelta) {
+    gpu.set_ddr_policy(delta.ddr.clone());
+    gpu.ddr_manager_mut().set_ddr_floor(delta.ddr_floor);
+}
+
+/// 频率写入：电压、DCS、写入策略和驱动调优
+fn apply_frequency(gpu: &mut GPU, delta: &ConfigDelta) {
+    gpu.set_strict_writes(delta.strict_writes, delta.write_failure_limit);
+    gpu.set_allow_dcs_in_game(delta.allow_dcs_in_game);
+    gpu.set_dcs_snap_policy(delta.dcs_snap_policy);
+    gpu.set_conflict_policy(delta.conflict_policy);
+    gpu.set_update_bypass(delta.update_bypass);
+    gpu.set_fault_rollback(delta.fault_rollback);
+    gpu.devfreq_clamp.set_enabled(delta.devfreq_clamp);
+    gpu.set_volt_offset(delta.volt_offset);
+    gpu.set_max_volt(delta.max_volt);
+    gpu.set_max_freq(delta.max_freq);
+    gpu.set_v2_tuning(delta.gpufreqv2);
+    gpu.frequency_mut().write_delay_config = delta.write_delay;
+}
+
+/// 进程级策略，不属于某个GPU实例
+fn apply_policies(delta: &ConfigDelta) {
+    set_load_source_policy(delta.load_source.clone());
+    set_permissions_policy(delta.permissions.clone());
+    set_mode_notify_policy(delta.mode_notify.clone());
+    set_audit_policy(delta.audit.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::datasource::config_parser::{
+        ConflictPolicy, CurvePoint, DcsSnapPolicy, DdrPolicy, IdleConfig, V2TuningConfig,
+        WriteDelayConfig, read_config_delta_from,
+    };
+
+    const MINIMAL_CONFIG: &str = "[global]\nmode = \"balance\"\nidle_threshold = 5\n";
+
+    /// 由最小配置生成的增量，不带模式名，避免测试写入模式文件
+    fn base_delta(name: &str) -> ConfigDelta {
+        let path: PathBuf = std::env::temp_dir().join(format!(
+            "gpugovernor-config-apply-{name}-{}.toml",
+            std::process::id()
+        ));
+        fs::write(&path, MINIMAL_CONFIG).unwrap();
+        let mut delta = read_config_delta_from(path.to_str().unwrap(), None).unwrap();
+        fs::remove_file(&path).ok();
+        delta.mode = None;
+        delta
+    }
+
+    /// 每个字段都与默认值不同的增量
+    fn custom_delta() -> ConfigDelta {
+        let mut delta = base_delta("custom");
+        delta.margin = 42;
+        delta.aggressive_down = false;
+        delta.aggressive_down_floor = 12;
+        delta.sampling_interval = 24;
+        delta.adaptive_sampling = true;
+        delta.min_adaptive_interval = 6;
+        delta.max_adaptive_interval = 48;
+        delta.up_rate_delay = 30;
+        delta.down_rate_delay = 700;
+        delta.hold_samples = 3;
+        delta.gaming_mode = true;
+        delta.allow_emergency_stock_max = true;
+        delta.emergency_ramp_samples = 7;
+        delta.pinned_freq = Some(650_000);
+        delta.freq_curve = vec![
+            CurvePoint {
+                load: 0,
+                freq: 300_000,
+            },
+            CurvePoint {
+                load: 100,
+                freq: 900_000,
+            },
+        ];
+        delta.idle_threshold = Some(9);
+        delta.adaptive_idle = true;
+        delta.adaptive_idle_max = 12;
+        delta.idle = IdleConfig {
+            power_off: true,
+            power_off_after_ms: 3000,
+            exit_boost_ms: 50,
+            exit_boost_freq: Some(800_000),
+        };
+        delta.ddr.bandwidth_boost = true;
+        delta.ddr.thermal_derate = true;
+        delta.ddr.policy = DdrPolicy::LoadZone;
+        delta.ddr_floor = Some(2);
+        delta.strict_writes = true;
+        delta.write_failure_limit = 4;
+        delta.allow_dcs_in_game = true;
+        delta.dcs_snap_policy = DcsSnapPolicy::SnapUp;
+        delta.conflict_policy = ConflictPolicy::BackOff;
+        delta.update_bypass = false;
+        delta.fault_rollback = true;
+        delta.devfreq_clamp = true;
+        delta.volt_offset = Some(-1250);
+        delta.max_volt = Some(80_000);
+        delta.max_freq = Some(850_000);
+        delta.gpufreqv2 = V2TuningConfig {
+            aging: Some(false),
+            eco: Some(true),
+        };
+        delta.write_delay = WriteDelayConfig {
+            gpufreqv2_ms: Some(15),
+            gpufreq_ms: Some(2),
+        };
+        delta
+    }
+
+    /// 增量会写入的全部调速器状态
+    fn snapshot(gpu: &GPU) -> String {
+        let strategy = gpu.frequency_strategy();
+        let idle = gpu.idle_manager();
+        let ddr = gpu.ddr_manager();
+        let frequency = gpu.frequency();
+        format!(
+            "{:?}",
+            (
+                (
+                    strategy.margin,
+                    strategy.aggressive_down,
+                    strategy.aggressive_down_floor,
+                    strategy.get_sampling_interval(),
+                    strategy.hold_samples,
+                    strategy.up_debounce_time,
+                    strategy.down_debounce_time,
+                    strategy.emergency_samples,
+                ),
+                (
+                    gpu.adaptive_sampling(),
+                    gpu.is_gaming_mode(),
+                    gpu.pinned_target(),
+                    gpu.freq_curve(),
+                ),
+                (idle.idle_threshold, idle.adaptive(), idle.power_collapse(),),
+                (
+                    ddr.bandwidth_policy(),
+                    ddr.is_thermal_derate_enabled(),
+                    ddr.is_load_zone_policy(),
+                    ddr.ddr_floor(),
+                ),
+                (
+                    gpu.strict_writes(),
+                    gpu.allows_dcs_in_game(),
+                    gpu.dcs_snap_policy(),
+                    gpu.conflict_policy(),
+                    gpu.is_update_bypass_enabled(),
+                    gpu.is_fault_rollback_enabled(),
+                    gpu.devfreq_clamp.is_enabled(),
+                ),
+                (
+                    frequency.volt_offset,
+                    frequency.max_volt,
+                    gpu.max_freq(),
+                    gpu.v2_tuning(),
+                    frequency.write_delay_config,
+                ),
+            )
+        )
+    }
+
+    #[test]
+    fn strategy_fields_are_applied() {
+        let mut gpu = GPU::new();
+        apply_delta(&mut gpu, &custom_delta());
+
+        let strategy = gpu.frequency_strategy();
+        assert_eq!(strategy.margin, 42);
+        assert!(!strategy.aggressive_down);
+        assert_eq!(strategy.aggressive_down_floor, 12);
+        assert_eq!(strategy.hold_samples, 3);
+        assert_eq!(strategy.up_debounce_time, 30);
+        assert_eq!(strategy.down_debounce_time, 700);
+        assert_eq!(strategy.emergency_samples, 7);
+        // 自适应采样从下限开始
+        assert_eq!(strategy.get_sampling_interval(), 6);
+        assert_eq!(gpu.adaptive_sampling(), Some((6, 48)));
+        assert!(gpu.is_gaming_mode());
+        assert_eq!(gpu.pinned_target(), Some(Freq::from_khz(650_000)));
+        assert!(gpu.freq_curve().is_some());
+    }
+
+    #[test]
+    fn fixed_sampling_and_disabled_emergency_ramp() {
+        let mut gpu = GPU::new();
+        let mut delta = custom_delta();
+        delta.adaptive_sampling = false;
+        delta.allow_emergency_stock_max = false;
+        apply_delta(&mut gpu, &delta);
+
+        assert_eq!(gpu.adaptive_sampling(), None);
+        assert_eq!(gpu.frequency_strategy().get_sampling_interval(), 24);
+        assert_eq!(gpu.frequency_strategy().emergency_samples, 0);
+    }
+
+    #[test]
+    fn idle_fields_are_applied() {
+        let mut gpu = GPU::new();
+        let delta = custom_delta();
+        apply_delta(&mut gpu, &delta);
+
+        let idle = gpu.idle_manager();
+        assert_eq!(idle.idle_threshold, 9);
+        assert_eq!(idle.adaptive(), (true, 12));
+        assert_eq!(idle.power_collapse(), &delta.idle);
+    }
+
+    #[test]
+    fn ddr_fields_are_applied() {
+        let mut gpu = GPU::new();
+        apply_delta(&mut gpu, &custom_delta());
+
+        let ddr = gpu.ddr_manager();
+        assert!(ddr.bandwidth_policy().bandwidth_boost);
+        assert!(ddr.is_thermal_derate_enabled());
+        assert!(ddr.is_load_zone_policy());
+        assert_eq!(ddr.ddr_floor(), Some(2));
+    }
+
+    #[test]
+    fn frequency_fields_are_applied() {
+        let mut gpu = GPU::new();
+        let delta = custom_delta();
+        apply_delta(&mut gpu, &delta);
+
+        assert_eq!(gpu.strict_writes(), (true, 4));
+        assert!(gpu.allows_dcs_in_game());
+        assert_eq!(gpu.dcs_snap_policy(), DcsSnapPolicy::SnapUp);
+        assert_eq!(gpu.conflict_policy(), ConflictPolicy::BackOff);
+        assert!(!gpu.is_update_bypass_enabled());
+        assert!(gpu.is_fault_rollback_enabled());
+        assert!(gpu.devfreq_clamp.is_enabled());
+        assert_eq!(gpu.frequency().volt_offset, -1250);
+        assert_eq!(gpu.frequency().max_volt, Some(80_000));
+        assert_eq!(gpu.max_freq(), Some(Freq::from_khz(850_000)));
+        assert_eq!(gpu.v2_tuning(), delta.gpufreqv2);
+        assert_eq!(gpu.frequency().write_delay_config, delta.write_delay);
+    }
+
+    #[test]
+    fn missing_idle_threshold_keeps_current_value() {
+        let mut gpu = GPU::new();
+        apply_delta(&mut gpu, &custom_delta());
+
+        let mut delta = custom_delta();
+        delta.idle_threshold = None;
+        apply_delta(&mut gpu, &delta);
+        assert_eq!(gpu.idle_manager().idle_threshold, 9);
+    }
+
+    #[test]
+    fn applying_twice_is_idempotent() {
+        for delta in [base_delta("idempotent"), custom_delta()] {
+            let mut gpu = GPU::new();
+            apply_delta(&mut gpu, &delta);
+            let once = snapshot(&gpu);
+            apply_delta(&mut gpu, &delta);
+            assert_eq!(snapshot(&gpu), once);
+        }
+    }
+
+    #[test]
+    fn later_delta_fully_overrides_earlier_one() {
+        let mut reverted = GPU::new();
+        apply_delta(&mut reverted, &custom_delta());
+        apply_delta(&mut reverted, &base_delta("override"));
+
+        let mut fresh = GPU::new();
+        apply_delta(&mut fresh, &base_delta("fresh"));
+        assert_eq!(snapshot(&reverted), snapshot(&fresh));
+    }
+}
@@ -0,0 +1,182 @@
+//! 受控节点冲突检测
+//!
+//! 记录调速器最后一次写入各受控节点的值，定期回读比较。两次写入之间节点值发生变化，
+//! 说明有其他工具（如内核调节器）也在写入同一节点，由调频线程按 `conflict_policy` 处理。
+
+use std::{collections::HashMap, fs, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+#[cfg(any(test, feature = "trace-replay"))]
+use crate::utils::sysfs_mock;
+
+/// `reassert` 策略下同一冲突最多重新写入的次数，间隔按检查次数翻倍
+const MAX_REASSERTS: u32 = 5;
+
+/// 各受控节点最后一次写入的值，只记录回读格式可比较的节点
+static OWNED_NODES: Lazy<Mutex<HashMap<&'static str, i64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 记录一次对受控节点的成功写入
+pub fn record_owned_write(path: &'static str, value: i64) {
+    OWNED_NODES.lock().unwrap().insert(path, value);
+}
+
+/// 节点已交还内核，不再检测外部写入
+pub fn release_owned_node(path: &str) {
+    OWNED_NODES.lock().unwrap().remove(path);
+}
+
+/// 释放所有受控节点
+pub fn release_all_owned_nodes() {
+    OWNED_NODES.lock().unwrap().clear();
+}
+
+/// 回读节点当前值，取内容中的最后一个整数（兼容 `dvfs_enable: 0` 之类的输出）
+fn read_node_value(path: &str) -> Option<i64> {
+    #[cfg(any(test, feature = "trace-replay"))]
+    if sysfs_mock::is_installed() {
+        return parse_node_value(&sysfs_mock::read(path)?);
+    }
+    parse_node_value(&fs::read_to_string(path).ok()?)
+}
+
+fn parse_node_value(content: &str) -> Option<i64> {
+    content
+        .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .filter_map(|token| token.parse::<i64>().ok())
+        .next_back()
+}
+
+/// 一次外部写入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeConflict {
+    pub path: &'static str,
+    /// 调速器最后写入的值
+    pub expected: i64,
+    /// 回读到的值
+    pub actual: i64,
+}
+
+/// 外部写入检测器
+#[derive(Default)]
+pub struct ConflictDetector {
+    /// 已报告过的冲突，同一节点的同一外部值只报告一次
+    reported: HashMap<&'static str, i64>,
+    /// 各节点连续检测到冲突的次数，节点恢复为调速器写入的值时清零
+    streaks: HashMap<&'static str, u32>,
+}
+
+impl ConflictDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 回读所有受控节点，返回值被外部修改的节点
+    pub fn check(&mut self) -> Vec<NodeConflict> {
+        let owned: Vec<(&'static str, i64)> = OWNED_NODES
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&path, &value)| (path, value))
+            .collect();
+        self.reported
+            .retain(|path, _| owned.iter().any(|(owned_path, _)| owned_path == path));
+        self.streaks
+            .retain(|path, _| owned.iter().any(|(owned_path, _)| owned_path == path));
+
+        owned
+            .into_iter()
+            .filter_map(|(path, expected)| {
+                let actual = read_node_value(path)?;
+                if actual == expected {
+                    self.reported.remove(path);
+                    self.streaks.remove(path);
+                    return None;
+                }
+                *self.streaks.entry(path).or_default() += 1;
+                Some(NodeConflict {
+                    path,
+                    expected,
+                    actual,
+                })
+            })
+            .collect()
+    }
+
+    /// 是否为首次出现的冲突
+    pub fn is_new(&mut self, conflict: &NodeConflict) -> bool {
+        self.reported.insert(conflict.path, conflict.actual) != Some(conflict.actual)
+    }
+
+    /// `reassert` 策略下本次检查是否重新写入
+    ///
+    /// 冲突持续时只在第1、2、4、8、16次检查时重新写入，之后不再与其他写入方争夺，
+    /// 直到节点恢复为调速器写入的值
+    pub fn should_reassert(&self, conflict: &NodeConflict) -> bool {
+        let streak = self.streaks.get(conflict.path).copied().unwrap_or(1);
+        streak.is_power_of_two() && streak <= 1 << (MAX_REASSERTS - 1)
+    }
+
+    /// 冲突是否已超过重新写入的次数上限（只在刚超过时返回 `true`）
+    pub fn reassert_exhausted(&self, conflict: &NodeConflict) -> bool {
+        self.streaks.get(conflict.path) == Some(&((1 << (MAX_REASSERTS - 1)) + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassert_backs_off_and_stops() {
+        let path: &'static str = Box::leak(
+            std::env::temp_dir()
+                .join(format!("gpugovernor-conflict-{}", std::process::id()))
+                .to_string_lossy()
+                .into_owned()
+                .into_boxed_str(),
+        );
+        fs::write(path, "dvfs_enable: 0\n").unwrap();
+        record_owned_write(path, 1);
+
+        let mut detector = ConflictDetector::new();
+        let mut reasserted = Vec::new();
+        let mut exhausted = Vec::new();
+        for check in 1..=20 {
+            let conflict = detector
+                .check()
+                .into_iter()
+                .find(|conflict| conflict.path == path)
+                .unwrap();
+            assert_eq!(conflict.actual, 0);
+            if detector.should_reassert(&conflict) {
+                reasserted.push(check);
+            }
+            if detector.reassert_exhausted(&conflict) {
+                exhausted.push(check);
+            }
+        }
+        assert_eq!(reasserted, [1, 2, 4, 8, 16]);
+        assert_eq!(exhausted, [17]);
+
+        // 节点恢复后重新计数
+        fs::write(path, "dvfs_enable: 1\n").unwrap();
+        assert!(
+            detector
+                .check()
+                .iter()
+                .all(|conflict| conflict.path != path)
+        );
+        fs::write(path, "dvfs_enable: 0\n").unwrap();
+        let conflict = detector
+            .check()
+            .into_iter()
+            .find(|conflict| conflict.path == path)
+            .unwrap();
+        assert!(detector.should_reassert(&conflict));
+
+        release_owned_node(path);
+        fs::remove_file(path).ok();
+    }
+}
@@ -1,9 +1,111 @@
-use std::{cell::Cell, fs};
+use std::{
+    cell::Cell,
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    sync::{Arc, Mutex},
+    thread,
+};
 
 use anyhow::Result;
-use log::{debug, warn};
+use log::{debug, info, warn};
+
+use crate::{
+    datasource::{
+        bandwidth_monitor::BandwidthMonitor,
+        config_parser::{DdrConfig, DdrPolicy},
+        file_path::*,
+    },
+    model::conflict_detector::{record_owned_write, release_owned_node},
+    utils::file_helper::FileHelper,
+};
+
+/// 带宽饱和状态解除的回差（百分比）
+const BANDWIDTH_HYSTERESIS: u32 = 10;
+
+/// DDR频率表中的一个档位
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DdrOppEntry {
+    /// 写入DDR控制节点的值
+    pub opp: i64,
+    /// 档位说明
+    pub description: String,
+}
+
+impl DdrOppEntry {
+    fn new(opp: i64, description: impl Into<String>) -> Self {
+        Self {
+            opp,
+            description: description.into(),
+        }
+    }
+}
+
+/// 解析dvfsrc OPP表文件
+fn parse_dvfsrc_opp_table(gpuv2: bool) -> Result<Vec<DdrOppEntry>> {
+    let mut entries = Vec::new();
+
+    if gpuv2 {
+        // v2 driver
+        for opp_table in [DVFSRC_V2_OPP_TABLE_1, DVFSRC_V2_OPP_TABLE_2] {
+            if !fs::exists(opp_table)? {
+                continue;
+            }
+            debug!("Reading v2 DDR OPP table: {opp_table}");
+
+            match File::open(opp_table) {
+                Ok(file) => {
+                    for line in BufReader::new(file).lines().map_while(Result::ok) {
+                        if line.contains("[OPP")
+                            && let Some(opp_str) = line.get(4..6)
+                            && let Ok(opp) = opp_str.parse::<i64>()
+                        {
+                            entries.push(DdrOppEntry::new(
+                                opp,
+                                format!("OPP{:02}: {}", opp, line.trim()),
+                            ));
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to open v2 DDR OPP table: {opp_table}: {e}");
+                }
+            }
+        }
+    } else if fs::exists(DVFSRC_V1_OPP_TABLE)? {
+        // v1 driver
+        debug!("Reading v1 DDR OPP table: {DVFSRC_V1_OPP_TABLE}");
+
+        match File::open(DVFSRC_V1_OPP_TABLE) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines().map_while(Result::ok) {
+                    if !line.contains("[OPP") {
+                        continue;
+                    }
+                    let parts: Vec<&str> = line.split(',').collect();
+                    if parts.len() < 2 {
+                        continue;
+                    }
+                    let opp_part = parts[0].trim();
+                    let ddr_part = parts[1].trim();
+
+                    if opp_part.starts_with("[OPP")
+                        && opp_part.len() >= 6
+                        && ddr_part.starts_with("ddr:")
+                        && let Ok(opp) = opp_part[4..6].parse::<i64>()
+                    {
+                        let ddr_desc = ddr_part.trim_start_matches("ddr:").trim();
+                        entries.push(DdrOppEntry::new(opp, format!("OPP{opp:02}: {ddr_desc}")));
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to open v1 DDR OPP table: {DVFSRC_V1_OPP_TABLE}: {e}");
+            }
+        }
+    }
 
-use crate::{datasource::file_path::*, utils::file_helper::FileHelper};
+    Ok(entries)
+}
 
 /// DDR频率管理器 - 负责内存频率控制
 #[derive(Clone)]
@@ -18,6 +120,20 @@ pub struct DdrManager {
     pub gpuv2: bool,
     /// 最近一次写入的DDR OPP值缓存
     last_written_ddr_opp: Cell<Option<i64>>,
+    /// EMI带宽监控器
+    bandwidth_monitor: BandwidthMonitor,
+    /// 带宽相关的DDR策略
+    bandwidth_policy: DdrConfig,
+    /// 当前是否处于带宽饱和状态
+    bandwidth_saturated: bool,
+    /// DDR档位下限（OPP索引），旁路充电配置使用
+    ddr_floor: Option<i64>,
+    /// 是否因温度过高降低了DDR档位
+    thermal_derated: bool,
+    /// 初始化时探测到的DDR节点接受的自动模式值
+    probed_auto_value: Option<i64>,
+    /// 解析后的dvfsrc OPP表缓存，所有克隆共享
+    opp_table_cache: Arc<Mutex<Option<Arc<Vec<DdrOppEntry>>>>>,
 }
 
 impl DdrManager {
@@ -28,18 +144,88 @@ impl DdrManager {
             ddr_v2_supported_freqs: Vec::new(),
             gpuv2: false,
             last_written_ddr_opp: Cell::new(None),
+            bandwidth_monitor: BandwidthMonitor::new(),
+            bandwidth_policy: DdrConfig::default(),
+            bandwidth_saturated: false,
+            ddr_floor: None,
+            thermal_derated: false,
+            probed_auto_value: None,
+            opp_table_cache: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// 按驱动版本的默认自动模式值
+    fn driver_auto_value(&self) -> i64 {
+        if self.gpuv2 {
+            DDR_AUTO_MODE_V2
+        } else {
+            DDR_AUTO_MODE_V1
+        }
+    }
+
+    /// 自动模式写入DDR节点的值：`[ddr] auto_value` 优先，其次是初始化时探测到的值，
+    /// 最后按驱动版本选择
+    pub fn auto_mode_value(&self) -> i64 {
+        self.bandwidth_policy
+            .auto_value
+            .or(self.probed_auto_value)
+            .unwrap_or_else(|| self.driver_auto_value())
+    }
+
+    /// 当前驱动对应的DDR控制节点
+    fn ddr_paths(&self) -> &'static [&'static str] {
+        if self.gpuv2 {
+            &[DVFSRC_V2_PATH_1, DVFSRC_V2_PATH_2]
+        } else {
+            &[DVFSRC_V1_PATH]
+        }
+    }
+
+    /// 探测DDR节点接受的自动模式值
+    ///
+    /// 并非所有dvfsrc实现都接受按驱动版本约定的值（部分v2节点只接受-1），
+    /// 依次写入两个候选值，以第一个写入成功的为准。初始化时DDR本就处于自动模式，写入不影响运行
+    pub fn probe_auto_value(&mut self) {
+        if let Some(value) = self.bandwidth_policy.auto_value {
+            info!("DDR auto mode value set by config: {value}");
+            return;
+        }
+        let Some(path) = self
+            .ddr_paths()
+            .iter()
+            .copied()
+            .find(|path| FileHelper::node_exists(path))
+        else {
+            return;
+        };
+
+        let default = self.driver_auto_value();
+        let alternative = if default == DDR_AUTO_MODE_V2 {
+            DDR_AUTO_MODE_V1
+        } else {
+            DDR_AUTO_MODE_V2
+        };
+        for candidate in [default, alternative] {
+            if FileHelper::write_string_safe(path, &candidate.to_string()) {
+                if candidate == default {
+                    debug!("DDR node {path} accepts {candidate} as auto mode");
+                } else {
+                    info!("DDR node {path} rejects {default}, using {candidate} as auto mode");
+                }
+                self.probed_auto_value = Some(candidate);
+                return;
+            }
+        }
+        warn!(
+            "DDR node {path} accepts neither {default} nor {alternative} as auto mode, keeping {default}"
+        );
+    }
+
     /// 设置DDR频率
     pub fn set_ddr_freq(&mut self, freq: i64) -> Result<()> {
         // 如果频率是999，表示不固定内存频率，让系统自己选择
         if freq == 999 {
-            self.ddr_freq = if self.gpuv2 {
-                DDR_AUTO_MODE_V2
-            } else {
-                DDR_AUTO_MODE_V1
-            };
+            self.ddr_freq = self.auto_mode_value();
             self.ddr_freq_fixed = false;
             self.last_written_ddr_opp.set(None);
             debug!("DDR frequency not fixed (auto mode)");
@@ -56,11 +242,7 @@ impl DdrManager {
 
         // 如果频率小于0，表示不固定内存频率
         if freq < 0 {
-            self.ddr_freq = if self.gpuv2 {
-                DDR_AUTO_MODE_V2
-            } else {
-                DDR_AUTO_MODE_V1
-            };
+            self.ddr_freq = self.auto_mode_value();
             self.ddr_freq_fixed = false;
             self.last_written_ddr_opp.set(None);
             debug!("DDR frequency not fixed");
@@ -97,37 +279,21 @@ impl DdrManager {
     pub fn write_ddr_freq(&self) -> Result<()> {
         if !self.ddr_freq_fixed {
             self.last_written_ddr_opp.set(None);
-            // 如果不固定内存频率，根据驱动类型写入不同的自动模式值
-            if self.gpuv2 {
-                // v2 driver，使用DDR_AUTO_MODE_V2（999）表示自动模式
-                let paths = [DVFSRC_V2_PATH_1, DVFSRC_V2_PATH_2];
-
-                let mut path_written = false;
-                for path in &paths {
-                    if fs::exists(path)? {
-                        let auto_mode_str = DDR_AUTO_MODE_V2.to_string();
-                        debug!("Writing {auto_mode_str} to v2 DDR path: {path}");
-                        if FileHelper::write_string_safe(path, &auto_mode_str) {
-                            path_written = true;
-                            break;
-                        }
-                    }
-                }
-
-                if !path_written {
-                    debug!(
-                        "Failed to write DDR_AUTO_MODE_V2 to any v2 driver path (continuing execution)"
-                    );
-                }
-            } else {
-                // v1 driver，使用DDR_AUTO_MODE_V1（-1）表示自动模式
-                if fs::exists(DVFSRC_V1_PATH)? {
-                    let auto_mode_str = DDR_AUTO_MODE_V1.to_string();
-                    debug!("Writing {auto_mode_str} to v1 DDR path: {DVFSRC_V1_PATH}");
-                    FileHelper::write_string_safe(DVFSRC_V1_PATH, &auto_mode_str);
-                } else {
-                    debug!("V1 DDR path does not exist: {DVFSRC_V1_PATH} (continuing execution)");
+            for path in [DVFSRC_V1_PATH, DVFSRC_V2_PATH_1, DVFSRC_V2_PATH_2] {
+                release_owned_node(path);
+            }
+            // 如果不固定内存频率，写入自动模式值
+            let auto_mode_str = self.auto_mode_value().to_string();
+            let path_written = self.ddr_paths().iter().any(|path| {
+                FileHelper::node_exists(path) && {
+                    debug!("Writing {auto_mode_str} to DDR path: {path}");
+                    FileHelper::write_string_safe(path, &auto_mode_str)
                 }
+            });
+            if !path_written {
+                debug!(
+                    "Failed to write DDR auto mode {auto_mode_str} to any DDR path (continuing execution)"
+                );
             }
 
             return Ok(());
@@ -143,9 +309,10 @@ impl DdrManager {
 
             let mut path_written = false;
             for path in &paths {
-                if fs::exists(path)? {
+                if FileHelper::node_exists(path) {
                     debug!("Writing {freq_str} to v2 DDR path: {path}");
                     if FileHelper::write_string_safe(path, &freq_str) {
+                        record_owned_write(path, ddr_opp);
                         path_written = true;
                         break;
                     }
@@ -159,9 +326,11 @@ impl DdrManager {
             }
         } else {
             // v1 driver
-            if fs::exists(DVFSRC_V1_PATH)? {
+            if FileHelper::node_exists(DVFSRC_V1_PATH) {
                 debug!("Writing {freq_str} to v1 DDR path: {DVFSRC_V1_PATH}");
-                FileHelper::write_string_safe(DVFSRC_V1_PATH, &freq_str);
+                if FileHelper::write_string_safe(DVFSRC_V1_PATH, &freq_str) {
+                    record_owned_write(DVFSRC_V1_PATH, ddr_opp);
+                }
             } else {
                 debug!("V1 DDR path does not exist: {DVFSRC_V1_PATH} (continuing execution)");
             }
@@ -183,114 +352,60 @@ impl DdrManager {
         Ok(())
     }
 
-    /// 获取DDR频率表
-    pub fn get_ddr_freq_table(&self) -> Result<Vec<(i64, String)>> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
-        let mut freq_table = Vec::new();
+    /// 获取DDR频率表：自动模式和预设档位，加上从dvfsrc OPP表解析出的档位
+    ///
+    /// OPP表只解析一次并缓存，缓存为空时同步解析
+    pub fn get_ddr_freq_table(&self) -> Result<Vec<DdrOppEntry>> {
+        let opp_table = self.cached_opp_table()?;
+
+        let mut freq_table = vec![
+            DdrOppEntry::new(self.auto_mode_value(), "Auto Mode"),
+            DdrOppEntry::new(DDR_HIGHEST_FREQ, "Highest Frequency and Voltage"),
+            DdrOppEntry::new(DDR_SECOND_FREQ, "Second Level Frequency and Voltage"),
+            DdrOppEntry::new(DDR_THIRD_FREQ, "Third Level Frequency and Voltage"),
+            DdrOppEntry::new(DDR_FOURTH_FREQ, "Fourth Level Frequency and Voltage"),
+            DdrOppEntry::new(DDR_FIFTH_FREQ, "Fifth Level Frequency and Voltage"),
+        ];
+        freq_table.extend(opp_table.iter().cloned());
+        Ok(freq_table)
+    }
 
-        // 添加自动模式
-        if self.gpuv2 {
-            freq_table.push((DDR_AUTO_MODE_V2, "Auto Mode".to_string()));
-        } else {
-            freq_table.push((DDR_AUTO_MODE_V1, "Auto Mode".to_string()));
+    /// 读取缓存的OPP表，未缓存时解析并写入缓存
+    fn cached_opp_table(&self) -> Result<Arc<Vec<DdrOppEntry>>> {
+        let mut cache = self.opp_table_cache.lock().unwrap();
+        if let Some(table) = cache.as_ref() {
+            return Ok(Arc::clone(table));
         }
+        let table = Arc::new(parse_dvfsrc_opp_table(self.gpuv2)?);
+        *cache = Some(Arc::clone(&table));
+        Ok(table)
+    }
 
-        // 添加预设的DDR_OPP值
-        freq_table.push((
-            DDR_HIGHEST_FREQ,
-            "Highest Frequency and Voltage".to_string(),
-        ));
-        freq_table.push((
-            DDR_SECOND_FREQ,
-            "Second Level Frequency and Voltage".to_string(),
-        ));
-        freq_table.push((
-            DDR_THIRD_FREQ,
-            "Third Level Frequency and Voltage".to_string(),
-        ));
-        freq_table.push((
-            DDR_FOURTH_FREQ,
-            "Fourth Level Frequency and Voltage".to_string(),
-        ));
-        freq_table.push((
-            DDR_FIFTH_FREQ,
-            "Fifth Level Frequency and Voltage".to_string(),
-        ));
-
-        // 尝试读取系统内存频率表
-        if self.gpuv2 {
-            // v2 driver
-            let opp_tables = [DVFSRC_V2_OPP_TABLE_1, DVFSRC_V2_OPP_TABLE_2];
-
-            for opp_table in &opp_tables {
-                if fs::exists(opp_table)? {
-                    debug!("Reading v2 DDR OPP table: {opp_table}");
-
-                    match File::open(opp_table) {
-                        Ok(file) => {
-                            let reader = BufReader::new(file);
-
-                            for line in reader.lines().map_while(Result::ok) {
-                                if line.contains("[OPP")
-                                    && let Some(opp_str) = line.get(4..6)
-                                    && let Ok(opp) = opp_str.parse::<i64>()
-                                {
-                                    freq_table
-                                        .push((opp, format!("OPP{:02}: {}", opp, line.trim())));
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to open v2 DDR OPP table: {opp_table}: {e}");
-                        }
-                    }
+    /// 在后台线程中解析并缓存OPP表，避免启动时阻塞
+    pub fn prefetch_ddr_freq_table(&self) {
+        let cache = Arc::clone(&self.opp_table_cache);
+        let gpuv2 = self.gpuv2;
+        let spawned = thread::Builder::new()
+            .name("DdrTableParser".to_string())
+            .spawn(move || match parse_dvfsrc_opp_table(gpuv2) {
+                Ok(table) => {
+                    debug!("Cached {} DDR OPP table entries", table.len());
+                    cache.lock().unwrap().get_or_insert_with(|| Arc::new(table));
                 }
-            }
-        } else {
-            // v1 driver
-            if fs::exists(DVFSRC_V1_OPP_TABLE)? {
-                debug!("Reading v1 DDR OPP table: {DVFSRC_V1_OPP_TABLE}");
-
-                match File::open(DVFSRC_V1_OPP_TABLE) {
-                    Ok(file) => {
-                        let reader = BufReader::new(file);
-
-                        for line in reader.lines().map_while(Result::ok) {
-                            if line.contains("[OPP") {
-                                let parts: Vec<&str> = line.split(',').collect();
-                                if parts.len() >= 2 {
-                                    let opp_part = parts[0].trim();
-                                    let ddr_part = parts[1].trim();
-
-                                    if opp_part.starts_with("[OPP")
-                                        && opp_part.len() >= 6
-                                        && ddr_part.starts_with("ddr:")
-                                        && let Ok(opp) = opp_part[4..6].parse::<i64>()
-                                    {
-                                        let ddr_desc = ddr_part.trim_start_matches("ddr:").trim();
-                                        freq_table.push((opp, format!("OPP{opp:02}: {ddr_desc}")));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to open v1 DDR OPP table: {DVFSRC_V1_OPP_TABLE}: {e}");
-                    }
-                }
-            }
+                Err(e) => debug!("Failed to prefetch DDR OPP table: {e}"),
+            });
+        if let Err(e) = spawned {
+            debug!("Failed to spawn DDR table parser: {e}");
         }
+    }
 
-        Ok(freq_table)
+    /// 丢弃缓存的OPP表，下次读取时重新解析
+    pub fn invalidate_ddr_freq_table(&self) {
+        self.opp_table_cache.lock().unwrap().take();
     }
 
     /// 读取v2 driver设备的内存频率表
     pub fn read_ddr_v2_freq_table(&self) -> Result<Vec<i64>> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
         let mut freq_list = Vec::new();
 
         // 检查v2 driver的内存频率表文件
@@ -332,6 +447,145 @@ impl DdrManager {
         Ok(freq_list)
     }
 
+    /// 根据内存带宽利用率修正目标DDR档位
+    ///
+    /// 仅在配置表指定了固定档位时生效：带宽饱和时提升一档（OPP值减一），
+    /// 自动模式下由内核dvfsrc自行根据带宽调节，不做干预。
+    /// 温度过高时固定档位再降低一档，优先级高于档位下限。
+    pub fn resolve_ddr_opp(&mut self, table_opp: i64) -> i64 {
+        let opp = self.resolve_bandwidth_opp(table_opp);
+        let opp = match self.ddr_floor {
+            // 自动模式或低于下限的档位提升到下限
+            Some(floor) if !(0..=floor).contains(&opp) => floor,
+            _ => opp,
+        };
+
+        if self.thermal_derated && (0..100).contains(&opp) {
+            (opp + 1).min(self.lowest_ddr_opp())
+        } else {
+            opp
+        }
+    }
+
+    /// 最低的DDR档位（OPP值最大），v1驱动无法读取档位数量时使用第五档
+    fn lowest_ddr_opp(&self) -> i64 {
+        match self.ddr_v2_supported_freqs.len() {
+            0 => DDR_FIFTH_FREQ,
+            n => n as i64 - 1,
+        }
+    }
+
+    /// 是否启用了温度感知的DDR降档
+    pub fn is_thermal_derate_enabled(&self) -> bool {
+        self.bandwidth_policy.thermal_derate
+    }
+
+    /// 根据GPU温度更新降档状态，返回状态是否变化
+    pub fn update_temperature(&mut self, temperature: i32) -> bool {
+        if !self.is_thermal_derate_enabled() {
+            return false;
+        }
+
+        let threshold = self.bandwidth_policy.thermal_threshold;
+        let derated = if self.thermal_derated {
+            temperature + self.bandwidth_policy.thermal_hysteresis.max(0) >= threshold
+        } else {
+            temperature >= threshold
+        };
+        if derated == self.thermal_derated {
+            return false;
+        }
+
+        self.thermal_derated = derated;
+        if derated {
+            info!("GPU temperature {temperature}°C reached {threshold}°C, lowering DDR OPP");
+        } else {
+            info!("GPU temperature cooled to {temperature}°C, restoring DDR OPP");
+        }
+        true
+    }
+
+    /// 设置DDR档位下限
+    pub fn set_ddr_floor(&mut self, floor: Option<i64>) {
+        if self.ddr_floor != floor {
+            info!("DDR OPP floor set to {floor:?}");
+            self.ddr_floor = floor;
+        }
+    }
+
+    fn resolve_bandwidth_opp(&mut self, table_opp: i64) -> i64 {
+        if !self.is_bandwidth_boost_enabled() || !(0..100).contains(&table_opp) {
+            return table_opp;
+        }
+
+        let Some(utilization) = self
+            .bandwidth_monitor
+            .utilization(self.bandwidth_policy.bandwidth_peak)
+        else {
+            return table_opp;
+        };
+
+        let threshold = self.bandwidth_policy.bandwidth_threshold;
+        let saturated = if self.bandwidth_saturated {
+            utilization + BANDWIDTH_HYSTERESIS >= threshold
+        } else {
+            utilization >= threshold
+        };
+
+        if saturated != self.bandwidth_saturated {
+            self.bandwidth_saturated = saturated;
+            if saturated {
+                info!("EMI bandwidth saturated ({utilization}%), raising DDR OPP");
+            } else {
+                info!("EMI bandwidth back to normal ({utilization}%)");
+            }
+        }
+
+        if saturated {
+            (table_opp - 1).max(DDR_HIGHEST_FREQ)
+        } else {
+            table_opp
+        }
+    }
+
+    /// 按负载区间选择DDR档位时返回区间对应的档位
+    pub fn zone_ddr_opp(&self, zone: usize) -> Option<i64> {
+        if self.bandwidth_policy.policy != DdrPolicy::LoadZone {
+            return None;
+        }
+        let opps = &self.bandwidth_policy.zone_opps;
+        opps.get(zone).or(opps.last()).copied()
+    }
+
+    /// 是否按负载区间选择DDR档位
+    pub fn is_load_zone_policy(&self) -> bool {
+        self.bandwidth_policy.policy == DdrPolicy::LoadZone
+            && !self.bandwidth_policy.zone_opps.is_empty()
+    }
+
+    /// 是否启用了带宽感知的DDR策略（且带宽节点可用）
+    pub fn is_bandwidth_boost_enabled(&self) -> bool {
+        self.bandwidth_policy.bandwidth_boost && self.bandwidth_monitor.is_available()
+    }
+
+    pub fn set_bandwidth_monitor(&mut self, monitor: BandwidthMonitor) {
+        self.bandwidth_monitor = monitor;
+    }
+
+    pub fn bandwidth_policy(&self) -> &DdrConfig {
+        &self.bandwidth_policy
+    }
+
+    pub fn set_bandwidth_policy(&mut self, policy: DdrConfig) {
+        if !policy.bandwidth_boost {
+            self.bandwidth_saturated = false;
+        }
+        if !policy.thermal_derate {
+            self.thermal_derated = false;
+        }
+        self.bandwidth_policy = policy;
+    }
+
     // Getter和Setter方法 - 手动实现
     pub fn is_ddr_freq_fixed(&self) -> bool {
         self.ddr_freq_fixed
@@ -341,6 +595,11 @@ impl DdrManager {
         self.ddr_freq
     }
 
+    /// 旁路充电时的DDR档位下限
+    pub fn ddr_floor(&self) -> Option<i64> {
+        self.ddr_floor
+    }
+
     pub fn get_ddr_v2_supported_freqs(&self) -> Vec<i64> {
         self.ddr_v2_supported_freqs.clone()
     }
@@ -0,0 +1,106 @@
+//! 频率交付统计
+//!
+//! 每次调频后读取一次驱动上报的实际频率，按请求档位汇总“请求频率与实际频率”的偏差。
+//! 部分内核在固定档位路径下对某些档位静默失败（写入成功但频率不变），
+//! 这类档位的交付率会明显偏低，通过 status.json 的 `delivery` 字段暴露出来。
+
+use std::collections::HashSet;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::{model::freq::Freq, utils::status_report::update_status};
+
+/// 实际频率允许低于预期档位的比例（百分比）
+const DELIVERY_TOLERANCE_PERCENT: i64 = 5;
+/// 档位累计多少次采样后才判断是否静默失败
+const DELIVERY_MIN_SAMPLES: u64 = 20;
+/// 交付率低于该值（百分比）时警告
+const DELIVERY_WARN_PERCENT: u64 = 50;
+
+/// 单个档位的交付统计
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct OppDelivery {
+    /// 调频后的采样次数
+    pub samples: u64,
+    /// 实际频率达到预期档位的次数
+    pub delivered: u64,
+    /// 实际频率与预期档位偏差的平均值（KHz，负数表示低于预期）
+    pub mean_deviation: i64,
+    /// 偏差绝对值最大的一次（KHz）
+    pub worst_deviation: i64,
+    /// 累计偏差，用于计算平均值
+    #[serde(skip)]
+    total_deviation: i64,
+}
+
+impl OppDelivery {
+    /// 交付率（百分比）
+    pub fn delivery_percent(&self) -> u64 {
+        self.delivered * 100 / self.samples.max(1)
+    }
+}
+
+/// 频率交付跟踪器
+#[derive(Clone, Default)]
+pub struct DeliveryTracker {
+    /// 等待回读的请求（请求档位, 预期频率）
+    pending: Option<(Freq, Freq)>,
+    /// 已警告过交付率偏低的档位
+    warned: HashSet<Freq>,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次频率写入，`expected` 为驱动应当运行的档位
+    pub fn record_request(&mut self, requested: Freq, expected: Freq) {
+        if requested.is_positive() && expected.is_positive() {
+            self.pending = Some((requested, expected));
+        }
+    }
+
+    /// 记录写入后读取到的实际频率，每次写入只统计第一次回读
+    pub fn observe(&mut self, actual: Freq) {
+        let Some((requested, expected)) = self.pending.take() else {
+            return;
+        };
+        if !actual.is_positive() {
+            return;
+        }
+
+        let deviation = actual.khz() - expected.khz();
+        let delivered = actual >= expected.percent(100 - DELIVERY_TOLERANCE_PERCENT);
+        let mut entry = OppDelivery::default();
+        update_status(|s| {
+            let stats = s.delivery.entry(requested.khz()).or_default();
+            stats.samples += 1;
+            if delivered {
+                stats.delivered += 1;
+            }
+            stats.total_deviation += deviation;
+            stats.mean_deviation = stats.total_deviation / stats.samples as i64;
+            if deviation.abs() > stats.worst_deviation.abs() {
+                stats.worst_deviation = deviation;
+            }
+            entry = stats.clone();
+        });
+
+        if entry.samples >= DELIVERY_MIN_SAMPLES
+            && entry.delivery_percent() < DELIVERY_WARN_PERCENT
+            && self.warned.insert(requested)
+        {
+            warn!(
+                "OPP {requested}KHz is not delivered: {}/{} samples reached {expected}KHz, mean deviation {}KHz",
+                entry.delivered, entry.samples, entry.mean_deviation
+            );
+        }
+    }
+
+    /// 频率表或驱动状态变化后丢弃等待回读的请求
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+}
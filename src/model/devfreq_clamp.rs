@@ -0,0 +1,153 @@
+//! Mali devfreq 下限辅助写入
+//!
+//! 部分 gpufreq 设备上固定档位写入会间歇性被驱动拒绝，此时内核 DVFS 重新接管并可能把频率降得很低。
+//! 启用 `devfreq_clamp` 后，游戏模式下同时把 `/sys/class/devfreq/*mali*/min_freq` 设为目标频率
+//! 对应的可用频率，让内核 DVFS 也不低于该频率；离开游戏模式或进入旁路模式时恢复原值。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info, warn};
+
+use crate::{
+    datasource::file_path::DEVFREQ_DIR,
+    model::freq::{Freq, FreqUnit},
+    utils::file_helper::FileHelper,
+};
+
+/// devfreq 设备名称中的关键字
+const MALI_DEVFREQ_KEYWORD: &str = "mali";
+
+/// Mali devfreq 下限写入器
+#[derive(Clone, Debug, Default)]
+pub struct DevfreqClamp {
+    enabled: bool,
+    /// 已探测的 devfreq 设备目录，外层 `None` 表示尚未探测
+    device: Option<Option<PathBuf>>,
+    /// 设备列出的可用频率（Hz），与设备一起探测
+    available: Vec<i64>,
+    /// 首次写入前的 min_freq（Hz），恢复时写回
+    original_min: Option<i64>,
+    /// 最近一次写入的 min_freq（Hz）
+    applied: Option<i64>,
+}
+
+/// 查找名称包含 mali 的 devfreq 设备
+fn find_mali_devfreq() -> Option<PathBuf> {
+    let mut devices = fs::read_dir(DEVFREQ_DIR)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name().is_some_and(|name| {
+                name.to_string_lossy()
+                    .to_lowercase()
+                    .contains(MALI_DEVFREQ_KEYWORD)
+            })
+        })
+        .collect::<Vec<_>>();
+    devices.sort();
+    devices.into_iter().next()
+}
+
+/// 读取设备的可用频率列表（Hz），未列出时为空
+fn read_available_frequencies(device: &Path) -> Vec<i64> {
+    fs::read_to_string(device.join("available_frequencies"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|value| value.parse::<i64>().ok())
+        .collect()
+}
+
+fn read_hz(path: PathBuf) -> Option<i64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+impl DevfreqClamp {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if self.enabled && !enabled {
+            self.release();
+        }
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn device(&mut self) -> Option<PathBuf> {
+        if self.device.is_none() {
+            let device = find_mali_devfreq();
+            match &device {
+                Some(path) => {
+                    info!("Mali devfreq device: {}", path.display());
+                    self.available = read_available_frequencies(path);
+                }
+                None => info!("No Mali devfreq device found, devfreq clamp unavailable"),
+            }
+            self.device = Some(device);
+        }
+        self.device.clone().flatten()
+    }
+
+    /// 不高于目标频率的最高可用频率（Hz），设备未列出可用频率时直接使用目标频率
+    fn floor_for(&self, freq: Freq) -> i64 {
+        let target = freq.to_unit(FreqUnit::Hz);
+        let available = &self.available;
+        if available.is_empty() {
+            return target;
+        }
+        available
+            .iter()
+            .copied()
+            .filter(|&hz| hz <= target)
+            .max()
+            .or_else(|| available.iter().copied().min())
+            .unwrap_or(target)
+    }
+
+    /// 将内核 DVFS 的频率下限设为目标频率
+    pub fn apply(&mut self, freq: Freq) {
+        if !self.enabled || !freq.is_positive() {
+            return;
+        }
+        let Some(device) = self.device() else {
+            return;
+        };
+
+        let floor = self.floor_for(freq);
+        if self.applied == Some(floor) {
+            return;
+        }
+        if self.original_min.is_none() {
+            self.original_min = read_hz(device.join("min_freq"));
+        }
+
+        let path = device.join("min_freq");
+        if FileHelper::write_string_safe(&path, &floor.to_string()) {
+            debug!("Devfreq min_freq set to {floor}Hz");
+            self.applied = Some(floor);
+        } else {
+            debug!("Failed to write {} = {floor}", path.display());
+        }
+    }
+
+    /// 恢复首次写入前的频率下限
+    pub fn release(&mut self) {
+        if self.applied.take().is_none() {
+            return;
+        }
+        let (Some(Some(device)), Some(original)) = (&self.device, self.original_min) else {
+            return;
+        };
+
+        let path = device.join("min_freq");
+        if FileHelper::write_string_safe(&path, &original.to_string()) {
+            debug!("Devfreq min_freq restored to {original}Hz");
+        } else {
+            warn!("Failed to restore {} to {original}", path.display());
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! GPU故障回退
+//!
+//! 降压等实验性频率表可能让GPU出现页错误或被驱动复位。启用 `fault_rollback` 后从
+//! `/dev/kmsg` 检测Mali故障特征，检测到后进入旁路模式把调频交还内核，将当时使用的频率表
+//! 复制为可疑快照，并写入包含最近内核日志的诊断报告 `fault-<时间>.txt`。
+//!
+//! 频率表与可疑快照相同时，下次启动直接进入旁路模式；修改频率表后恢复正常，
+//! 确认无误后也可以通过 `resume` 控制命令恢复调频。
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::Local;
+use log::{error, info, warn};
+
+use crate::{
+    datasource::{
+        file_path::{FAULT_REPORT_DIR, FREQ_TABLE_CONFIG_FILE, KMSG_PATH, SUSPECT_FREQ_TABLE_PATH},
+        kmsg::{KmsgReader, gpu_fault_signature, is_driver_error},
+    },
+    model::gpu::GPU,
+    utils::{
+        file_operate::ensure_parent_dir, log_tail::recent_lines, status_report::update_status,
+    },
+};
+
+/// 诊断报告中附带的GPU相关内核日志条数
+const RECENT_KMSG_COUNT: usize = 50;
+/// 诊断报告中附带的最近日志条数
+const RECENT_EVENT_COUNT: usize = 100;
+/// 检测到GPU故障时的旁路原因前缀
+const FAULT_BYPASS_REASON: &str = "GPU fault detected";
+/// 频率表被标记为可疑时的旁路原因
+const SUSPECT_TABLE_BYPASS_REASON: &str = "frequency table flagged suspect after a GPU fault";
+
+/// 内核日志中的GPU故障检测
+pub struct FaultWatcher {
+    kmsg: Option<KmsgReader>,
+    /// 打开内核日志失败后不再重试
+    unavailable: bool,
+    /// 最近的GPU相关内核日志
+    recent: VecDeque<String>,
+}
+
+impl FaultWatcher {
+    pub fn new() -> Self {
+        Self {
+            kmsg: None,
+            unavailable: false,
+            recent: VecDeque::with_capacity(RECENT_KMSG_COUNT),
+        }
+    }
+
+    /// 读取新的内核日志，检测到GPU故障时回退到内核调频
+    pub fn check(&mut self, gpu: &mut GPU) {
+        if !gpu.is_fault_rollback_enabled() {
+            self.kmsg = None;
+            return;
+        }
+        if self.kmsg.is_none() && !self.unavailable {
+            match KmsgReader::open() {
+                Ok(reader) => {
+                    info!("Watching {KMSG_PATH} for GPU faults");
+                    self.kmsg = Some(reader);
+                }
+                Err(e) => {
+                    warn!("GPU fault detection unavailable: {e}");
+                    self.unavailable = true;
+                }
+            }
+        }
+        let Some(kmsg) = &mut self.kmsg else {
+            return;
+        };
+
+        for message in kmsg.read_new() {
+            let signature = gpu_fault_signature(&message);
+            if signature.is_none() && !is_driver_error(&message) {
+                continue;
+            }
+            if self.recent.len() == RECENT_KMSG_COUNT {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(message.clone());
+
+            // 旁路期间的故障与调速器写入的频率无关
+            if let Some(signature) = signature
+                && !gpu.is_bypassed()
+            {
+                self.rollback(gpu, signature, &message);
+            }
+        }
+    }
+
+    /// 回退到内核调频，标记可疑频率表并写入诊断报告
+    fn rollback(&self, gpu: &mut GPU, signature: &str, message: &str) {
+        error!("GPU fault detected ({signature}): {message}");
+
+        if let Err(e) = flag_suspect_table() {
+            warn!("Failed to snapshot suspect frequency table: {e}");
+        }
+        match self.write_report(gpu, signature, message) {
+            Ok(path) => info!("GPU fault report written to {}", path.display()),
+            Err(e) => warn!("Failed to write GPU fault report: {e}"),
+        }
+
+        gpu.enter_bypass(&format!("{FAULT_BYPASS_REASON} ({signature})"));
+        let error = format!("GPU fault: {message}");
+        update_status(|s| s.error = Some(error));
+        warn!(
+            "Frequency table saved to {SUSPECT_FREQ_TABLE_PATH}, fix the table or send `resume` to restore control"
+        );
+    }
+
+    /// 写入诊断报告，需在进入旁路模式之前调用以记录故障时的频率和电压
+    fn write_report(&self, gpu: &GPU, signature: &str, message: &str) -> Result<PathBuf> {
+        let mut report = String::new();
+        let now = Local::now();
+
+        writeln!(report, "GPU Governor fault report")?;
+        writeln!(report, "time: {}", now.format("%Y-%m-%d %H:%M:%S"))?;
+        writeln!(report, "version: {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(report, "signature: {signature}")?;
+        writeln!(report, "message: {message}")?;
+        writeln!(report, "mode: {}", gpu.current_mode())?;
+        writeln!(report, "freq: {}KHz", gpu.get_cur_freq())?;
+        writeln!(report, "volt: {}", gpu.frequency().cur_volt)?;
+        writeln!(report, "suspect table: {SUSPECT_FREQ_TABLE_PATH}")?;
+
+        writeln!(report, "\nrecent GPU kernel messages:")?;
+        for line in &self.recent {
+            writeln!(report, "{line}")?;
+        }
+
+        writeln!(report, "\nrecent events:")?;
+        for event in recent_lines(RECENT_EVENT_COUNT) {
+            writeln!(report, "{event}")?;
+        }
+
+        let path =
+            Path::new(FAULT_REPORT_DIR).join(format!("fault-{}.txt", now.format("%Y%m%d-%H%M%S")));
+        fs::create_dir_all(FAULT_REPORT_DIR)?;
+        fs::write(&path, report)?;
+        Ok(path)
+    }
+}
+
+impl Default for FaultWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 将当前频率表复制为可疑快照
+fn flag_suspect_table() -> Result<()> {
+    ensure_parent_dir(SUSPECT_FREQ_TABLE_PATH)?;
+    fs::copy(FREQ_TABLE_CONFIG_FILE, SUSPECT_FREQ_TABLE_PATH)?;
+    Ok(())
+}
+
+/// 当前频率表与可疑快照相同时进入旁路模式
+pub fn bypass_if_table_suspect(gpu: &mut GPU) {
+    if !gpu.is_fault_rollback_enabled() {
+        return;
+    }
+    let (Ok(table), Ok(suspect)) = (
+        fs::read(FREQ_TABLE_CONFIG_FILE),
+        fs::read(SUSPECT_FREQ_TABLE_PATH),
+    ) else {
+        return;
+    };
+    if table == suspect {
+        warn!(
+            "{FREQ_TABLE_CONFIG_FILE} matches the table that caused the last GPU fault ({SUSPECT_FREQ_TABLE_PATH})"
+        );
+        gpu.enter_bypass(SUSPECT_TABLE_BYPASS_REASON);
+    }
+}
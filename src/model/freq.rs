@@ -0,0 +1,126 @@
+//! GPU频率类型
+//!
+//! 配置文件和调速器内部统一使用 KHz；部分内核的 gpufreqv2 频率表和当前频率节点以 Hz 上报，
+//! 直接与配置中的 KHz 比较会让最接近频率匹配选出错误的档位。驱动数值在解析时按
+//! [`FreqUnit::detect`] 识别单位并转换为 [`Freq`]，写回驱动节点时再换算为驱动的单位。
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// 驱动数值超过该值时按 Hz 解析（10GHz 的 KHz 值，10MHz 的 Hz 值）
+const HZ_THRESHOLD: i64 = 10_000_000;
+/// 驱动数值低于该值时按 MHz 解析（10GHz 的 MHz 值，10MHz 的 KHz 值）
+const MHZ_THRESHOLD: i64 = 10_000;
+
+/// 驱动节点使用的频率单位
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FreqUnit {
+    Hz,
+    #[default]
+    KHz,
+    MHz,
+}
+
+impl FreqUnit {
+    /// 按数值量级识别一组驱动数值的单位，使用其中的最大值判断，没有正数时视为 KHz
+    pub fn detect(values: impl IntoIterator<Item = i64>) -> Self {
+        match values.into_iter().filter(|&value| value > 0).max() {
+            Some(max) if max > HZ_THRESHOLD => Self::Hz,
+            Some(max) if max < MHZ_THRESHOLD => Self::MHz,
+            _ => Self::KHz,
+        }
+    }
+
+    /// 每单位对应的 Hz 数
+    const fn hz_per_unit(self) -> i64 {
+        match self {
+            Self::Hz => 1,
+            Self::KHz => 1_000,
+            Self::MHz => 1_000_000,
+        }
+    }
+}
+
+impl fmt::Display for FreqUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hz => "Hz",
+            Self::KHz => "KHz",
+            Self::MHz => "MHz",
+        })
+    }
+}
+
+/// GPU频率，内部以 KHz 保存
+///
+/// `Display` 只输出 KHz 数值，便于直接写入以 KHz 为单位的节点和沿用 `{freq}KHz` 的日志格式
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Freq(i64);
+
+impl Freq {
+    /// 0 表示频率未知或未设置
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_khz(khz: i64) -> Self {
+        Self(khz)
+    }
+
+    pub const fn from_hz(hz: i64) -> Self {
+        Self(hz / 1_000)
+    }
+
+    pub const fn from_mhz(mhz: i64) -> Self {
+        Self(mhz * 1_000)
+    }
+
+    /// 按指定单位解析驱动数值
+    pub const fn from_unit(value: i64, unit: FreqUnit) -> Self {
+        Self(value * unit.hz_per_unit() / FreqUnit::KHz.hz_per_unit())
+    }
+
+    /// 单个驱动读数，按自身量级识别单位
+    pub fn from_reading(value: i64) -> Self {
+        Self::from_unit(value, FreqUnit::detect([value]))
+    }
+
+    pub const fn khz(self) -> i64 {
+        self.0
+    }
+
+    pub const fn mhz(self) -> i64 {
+        self.0 / 1_000
+    }
+
+    /// 换算为指定单位的驱动数值
+    pub const fn to_unit(self, unit: FreqUnit) -> i64 {
+        self.0 * FreqUnit::KHz.hz_per_unit() / unit.hz_per_unit()
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// 两个频率之差的绝对值（KHz）
+    pub const fn abs_diff(self, other: Self) -> i64 {
+        (self.0 - other.0).abs()
+    }
+
+    /// 按百分比缩放
+    pub const fn percent(self, percent: i64) -> Self {
+        Self(self.0 * percent / 100)
+    }
+}
+
+impl fmt::Display for Freq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{Result, anyhow};
+
+use crate::{datasource::config_parser::CurvePoint, model::freq::Freq};
+
+/// 游戏规则的负载-频率曲线
+///
+/// 由若干断点组成，断点之间线性插值，低于第一个断点或高于最后一个断点时取端点频率。
+/// 生效期间代替 `当前频率 × (负载 + 余量)` 的调频公式，适用于负载百分比与所需频率
+/// 关系不稳定、余量无法同时兼顾的游戏
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FreqCurve {
+    /// 按负载升序排列的断点（负载百分比, 频率）
+    points: Vec<(i32, Freq)>,
+}
+
+impl FreqCurve {
+    /// 校验并构造曲线：负载须在 0-100 之间且互不相同，频率须为正数
+    pub fn new(points: &[CurvePoint]) -> Result<Self> {
+        if points.is_empty() {
+            return Err(anyhow!("Frequency curve has no breakpoints"));
+        }
+        let mut sorted = Vec::with_capacity(points.len());
+        for point in points {
+            if !(0..=100).contains(&point.load) {
+                return Err(anyhow!(
+                    "Curve breakpoint load {} out of range [0, 100]",
+                    point.load
+                ));
+            }
+            if point.freq <= 0 {
+                return Err(anyhow!(
+                    "Curve breakpoint at load {}% has non-positive frequency {}",
+                    point.load,
+                    point.freq
+                ));
+            }
+            sorted.push((point.load, Freq::from_khz(point.freq)));
+        }
+        sorted.sort_by_key(|&(load, _)| load);
+        if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(anyhow!("Duplicate curve breakpoint at load {}%", pair[0].0));
+        }
+        Ok(Self { points: sorted })
+    }
+
+    /// 计算负载对应的目标频率
+    pub fn evaluate(&self, load: i32) -> Freq {
+        let (first_load, first_freq) = self.points[0];
+        if load <= first_load {
+            return first_freq;
+        }
+        for pair in self.points.windows(2) {
+            let (lo_load, lo_freq) = pair[0];
+            let (hi_load, hi_freq) = pair[1];
+            if load <= hi_load {
+                let span = (hi_freq.khz() - lo_freq.khz()) * i64::from(load - lo_load);
+                return Freq::from_khz(lo_freq.khz() + span / i64::from(hi_load - lo_load));
+            }
+        }
+        self.points[self.points.len() - 1].1
+    }
+}
@@ -1,12 +1,813 @@
 use std::{
     sync::mpsc::Receiver,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use log::{debug, warn};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
 
-use crate::{datasource::load_monitor::get_gpu_load, model::gpu::GPU};
+#[cfg(feature = "shm-export")]
+use crate::utils::shm_export::ShmExporter;
+use crate::{
+    datasource::{
+        config_parser::{
+            ConfigDelta, ConflictPolicy, MarginPhase, read_config_delta, read_section,
+        },
+        control::ControlCommand,
+        file_path::{
+            DDR_AUTO_MODE_V1, FREQ_TABLE_CONFIG_FILE, GPUFREQV2_POWER_CONTROL, LOG_TAIL_PATH,
+        },
+        freq_table_parser::{dump_freq_table, freq_table_generation, freq_table_read},
+        load_monitor::{get_gpu_load, utilization_init},
+        suspend::ResumeDetector,
+        thermal::read_gpu_temperature,
+        update_activity::detect_update_activity,
+        voltage::{gpu_voltage_node_exists, read_gpu_voltage},
+    },
+    model::{
+        calibration::{apply_calibration, apply_saved_calibration, run_calibration},
+        conflict_detector::{ConflictDetector, NodeConflict},
+        fault_rollback::{FaultWatcher, bypass_if_table_suspect},
+        freq::Freq,
+        gpu::GPU,
+        idle_manager::PowerTransition,
+        margin_schedule::MarginSchedule,
+        mode_arbiter::{ModeArbiter, regenerate},
+        mode_state::ModeSource,
+        timer_wheel::TimerWheel,
+    },
+    utils::{
+        decision_log::{Decision, DecisionOutcome, record_decision},
+        file_helper::FileHelper,
+        log_tail::export_log_tail,
+        precise_timer::PreciseTimer,
+        realtime::apply_realtime_policy,
+        shutdown::is_shutdown_requested,
+        stats::{record_adjustment, record_foreground_game},
+        status_report::{flush_status_if_due, update_status},
+        trace_recorder::TraceRecorder,
+    },
+};
+
+/// 旁路模式下的轮询间隔
+const BYPASS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 空闲状态下额外的休眠时间
+const IDLE_SLEEP_INTERVAL: Duration = Duration::from_millis(160);
+/// 每丢弃这么多个异常频率读数输出一次警告，避免刷屏
+const FREQ_REJECT_LOG_INTERVAL: u64 = 100;
+/// 实际电压回读间隔
+const VOLT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// 电压回读连续失败这么多次后停止检查
+const VOLT_READ_FAILURE_LIMIT: u32 = 5;
+/// 实际电压与请求电压的允许偏差（10微伏，即一个6.25mV步进）
+const VOLT_DRIFT_TOLERANCE: i64 = 625;
+/// GPU温度读取间隔
+const THERMAL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// 受控节点回读间隔
+const CONFLICT_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// 检测到外部写入后让出控制的时长
+const CONFLICT_BACKOFF: Duration = Duration::from_secs(60);
+/// 因外部写入让出控制时的旁路原因前缀
+const CONFLICT_BYPASS_REASON: &str = "external write detected";
+/// GPU故障检测间隔
+const FAULT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// 系统更新检测间隔
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// 更新活动结束后保持旁路的时长，dex2oat 在连续编译多个应用之间会短暂退出
+const UPDATE_QUIET_PERIOD: Duration = Duration::from_secs(30);
+/// dexopt 进程扫描间隔，需小于 `UPDATE_QUIET_PERIOD`，否则编译期间会提前退出旁路
+const DEXOPT_SCAN_INTERVAL: Duration = Duration::from_secs(15);
+/// 系统更新期间进入旁路模式时的原因前缀
+const UPDATE_BYPASS_REASON: &str = "system update in progress";
+
+/// 写入电源控制节点以允许GPU断电
+const POWER_CONTROL_OFF: &str = "power_off";
+/// 写入电源控制节点以恢复按需上电
+const POWER_CONTROL_ON: &str = "power_on";
+
+/// 调频时间戳的起点
+static CLOCK_BASE: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// 调频线程内部的定时事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EngineTimer {
+    /// 临时模式到期
+    Boost,
+    /// 轨迹录制到期
+    Recording,
+    /// 频率锁定到期
+    FrequencyLock,
+    /// 回读实际电压
+    VoltageCheck,
+    /// 回读受控节点，检测外部写入
+    ConflictCheck,
+    /// 读取GPU温度，用于DDR降档
+    ThermalCheck,
+    /// 检测系统更新和应用安装
+    UpdateCheck,
+    /// 让出控制到期
+    ConflictBackoff,
+    /// 进入下一个余量阶段
+    MarginPhase,
+    /// 冷启动预热期结束
+    WarmupEnd,
+    /// 检测内核日志中的GPU故障
+    FaultCheck,
+}
+
+/// 调频线程上下文 - 保存控制命令和定时任务相关的状态
+struct EngineContext {
+    timers: TimerWheel<EngineTimer>,
+    /// 当前生效的临时模式
+    boost_mode: Option<String>,
+    /// 各来源的模式请求，临时模式结束后恢复仲裁结果
+    arbiter: ModeArbiter,
+    /// 正在进行的负载轨迹录制
+    recorder: Option<TraceRecorder>,
+    /// 通过控制命令锁定的频率
+    locked_freq: Option<Freq>,
+    /// 最近一次记录的电压偏差（请求电压, 实际电压）
+    voltage_drift: Option<(i64, i64)>,
+    /// 电压回读连续失败的次数
+    voltage_read_failures: u32,
+    /// 受控节点外部写入检测
+    conflicts: ConflictDetector,
+    /// 精确模式下用于休眠的定时器
+    precise_timer: Option<PreciseTimer>,
+    /// 最近一次检测到系统更新活动的时间
+    update_seen_at: Option<Instant>,
+    /// 最近一次扫描 dexopt 进程的时间
+    dexopt_scanned_at: Option<Instant>,
+    /// 系统休眠唤醒检测
+    resume: ResumeDetector,
+    /// 游戏会话的分阶段余量调度
+    margin_schedule: Option<MarginSchedule>,
+    /// 最近一次读取的GPU温度
+    temperature: Option<i32>,
+    /// 退出断电后保持高频的截止时间
+    exit_boost_until: Option<Instant>,
+    /// GPU故障检测
+    faults: FaultWatcher,
+    /// 决策共享内存
+    #[cfg(feature = "shm-export")]
+    shm: Option<ShmExporter>,
+}
+
+impl EngineContext {
+    fn new() -> Self {
+        Self {
+            timers: TimerWheel::new(),
+            boost_mode: None,
+            arbiter: ModeArbiter::new(),
+            recorder: None,
+            locked_freq: None,
+            voltage_drift: None,
+            voltage_read_failures: 0,
+            conflicts: ConflictDetector::new(),
+            precise_timer: None,
+            update_seen_at: None,
+            dexopt_scanned_at: None,
+            resume: ResumeDetector::new(),
+            margin_schedule: None,
+            temperature: None,
+            exit_boost_until: None,
+            faults: FaultWatcher::new(),
+            #[cfg(feature = "shm-export")]
+            shm: ShmExporter::new()
+                .inspect_err(|e| warn!("Shared memory export disabled: {e}"))
+                .ok(),
+        }
+    }
+
+    /// 精确模式下启用 timerfd 定时
+    fn enable_precise_timer(&mut self) {
+        match PreciseTimer::new() {
+            Ok(timer) => {
+                info!("Precise mode: using timerfd deadlines");
+                self.precise_timer = Some(timer);
+            }
+            Err(e) => warn!("Failed to create precise timer, falling back to sleep: {e}"),
+        }
+    }
+
+    /// 休眠指定时长，精确模式下等待定时器到期
+    fn sleep(&mut self, duration: Duration) {
+        if let Some(timer) = &mut self.precise_timer {
+            match timer.sleep(duration) {
+                Ok(()) => return,
+                Err(e) => {
+                    warn!("{e}, falling back to sleep");
+                    self.precise_timer = None;
+                }
+            }
+        }
+        std::thread::sleep(duration);
+    }
+
+    /// 按当前采样间隔休眠
+    fn sampling_sleep(&mut self, gpu: &GPU) {
+        let sleep_time = gpu.frequency_strategy.get_sampling_interval();
+
+        debug!(
+            "Sleeping for {sleep_time}ms (precise mode: {})",
+            gpu.is_precise()
+        );
+        self.sleep(Duration::from_millis(sleep_time));
+    }
+
+    /// 从频率表文件重新加载调频线程使用的频率表
+    fn refresh_freq_table(gpu: &mut GPU) {
+        if let Err(e) = freq_table_read(FREQ_TABLE_CONFIG_FILE, gpu) {
+            warn!("Failed to refresh frequency table: {e}");
+            return;
+        }
+        apply_saved_calibration(gpu);
+
+        // 当前频率可能已不在新表中，重新定位索引
+        let cur_freq = gpu.get_cur_freq();
+        gpu.frequency_mut().cur_freq_idx = gpu.frequency().read_freq_index(cur_freq);
+        info!(
+            "Frequency table refreshed to generation {}",
+            gpu.table_generation()
+        );
+    }
+
+    /// 取出通道中积压的所有增量，按生成时间顺序提交给模式仲裁，只应用一次仲裁结果
+    ///
+    /// 每个增量都是完整的参数集合，调频线程被长时间的 sysfs 写入阻塞后，各数据源先后发送的
+    /// 增量会一起积压；逐个应用会让参数来回切换，生成早于已提交增量的过期增量直接丢弃
+    fn drain_config_deltas(&mut self, gpu: &mut GPU, rx: &Receiver<ConfigDelta>) {
+        let mut deltas: Vec<ConfigDelta> = rx.try_iter().collect();
+        if deltas.is_empty() {
+            return;
+        }
+        deltas.sort_by_key(|delta| delta.created_at);
+        if deltas.len() > 1 {
+            debug!("Arbitrating {} queued config deltas", deltas.len());
+        }
+
+        let mut winner = None;
+        for delta in deltas {
+            if self.arbiter.is_stale(&delta) {
+                debug!("Dropping stale config delta");
+                continue;
+            }
+            let delta = Self::match_table_generation(gpu, delta);
+            winner = self.arbiter.submit(delta).or(winner);
+        }
+        if let Some(winner) = winner {
+            self.apply_arbitrated(gpu, winner);
+        }
+    }
+
+    /// 将增量提交给模式仲裁并应用仲裁结果
+    fn handle_config_delta(&mut self, gpu: &mut GPU, delta: ConfigDelta) {
+        let delta = Self::match_table_generation(gpu, delta);
+        if let Some(winner) = self.arbiter.submit(delta) {
+            self.apply_arbitrated(gpu, winner);
+        }
+    }
+
+    /// 增量与已加载的频率表代数不一致时，先刷新频率表，再按新表重新生成参数
+    fn match_table_generation(gpu: &mut GPU, delta: ConfigDelta) -> ConfigDelta {
+        if delta.table_generation == gpu.table_generation() {
+            return delta;
+        }
+        if freq_table_generation() != gpu.table_generation() {
+            Self::refresh_freq_table(gpu);
+        }
+        if delta.table_generation == gpu.table_generation() {
+            return delta;
+        }
+        debug!(
+            "Config delta built for table generation {}, regenerating for {}",
+            delta.table_generation,
+            gpu.table_generation()
+        );
+        let mut fresh = regenerate(&delta.config, &delta);
+        fresh.release = delta.release;
+        fresh.created_at = delta.created_at;
+        fresh
+    }
+
+    /// 应用仲裁结果：临时模式期间只记录，不覆盖临时模式参数
+    fn apply_arbitrated(&mut self, gpu: &mut GPU, delta: ConfigDelta) {
+        if let Some(mode) = &self.boost_mode {
+            debug!("Boost ({mode}) active, deferring config delta until it expires");
+        } else {
+            gpu.apply_config_delta(&delta);
+        }
+        self.update_margin_schedule(gpu, &delta.margin_phases);
+    }
+
+    /// 根据增量中的分阶段余量开始、继续或结束余量调度
+    ///
+    /// 同一游戏会话内重复收到相同的阶段列表时继续计时，只重新应用当前阶段的余量
+    fn update_margin_schedule(&mut self, gpu: &mut GPU, phases: &[MarginPhase]) {
+        if phases.is_empty() {
+            if self.margin_schedule.take().is_some() {
+                self.timers.cancel(|t| *t == EngineTimer::MarginPhase);
+                update_status(|s| s.margin_phase = None);
+                info!("Margin schedule ended");
+            }
+            return;
+        }
+
+        if !self
+            .margin_schedule
+            .as_ref()
+            .is_some_and(|schedule| schedule.matches(phases))
+        {
+            info!("Margin schedule started with {} phase(s)", phases.len());
+            self.margin_schedule = Some(MarginSchedule::new(phases));
+        }
+        self.advance_margin_schedule(gpu);
+    }
+
+    /// 进入已到开始时间的余量阶段并安排下一个阶段，临时模式期间只计时不覆盖余量
+    fn advance_margin_schedule(&mut self, gpu: &mut GPU) {
+        let Some(schedule) = self.margin_schedule.as_mut() else {
+            return;
+        };
+        if schedule.advance()
+            && let Some(phase) = schedule.current()
+        {
+            info!(
+                "Margin phase entered: {}% from {}s",
+                phase.margin, phase.after_secs
+            );
+        }
+
+        self.timers.cancel(|t| *t == EngineTimer::MarginPhase);
+        if let Some(delay) = schedule.until_next() {
+            self.timers.schedule(delay, EngineTimer::MarginPhase);
+        }
+
+        let margin = schedule.current().map(|phase| phase.margin);
+        update_status(|s| s.margin_phase = margin);
+        if self.boost_mode.is_none()
+            && let Some(margin) = margin
+        {
+            gpu.frequency_strategy_mut().set_margin(margin as u32);
+        }
+    }
+
+    fn handle_control_command(&mut self, gpu: &mut GPU, command: ControlCommand) {
+        match command {
+            ControlCommand::Boost { mode, duration } => {
+                let mut delta = match read_config_delta(Some(&mode)) {
+                    Ok(delta) => delta,
+                    Err(e) => {
+                        warn!("Failed to read config for boost mode {mode}: {e}");
+                        return;
+                    }
+                };
+                delta.mode = Some(mode.clone());
+                delta.mode_source = Some(ModeSource::Boost);
+                // 游戏的电压偏移关系到稳定性，临时模式期间继续保留
+                delta.volt_offset = self.arbiter.winner().and_then(|winner| winner.volt_offset);
+
+                // 重复的 boost 命令会刷新到期时间
+                self.timers.cancel(|t| *t == EngineTimer::Boost);
+                self.timers.schedule(duration, EngineTimer::Boost);
+
+                gpu.apply_config_delta(&delta);
+                info!("Boost to {mode} mode for {}s", duration.as_secs());
+                self.boost_mode = Some(mode);
+            }
+            ControlCommand::CancelBoost => {
+                self.timers.cancel(|t| *t == EngineTimer::Boost);
+                if self.boost_mode.is_some() {
+                    info!("Boost cancelled");
+                    self.end_boost(gpu);
+                }
+            }
+            ControlCommand::Bypass => gpu.enter_bypass("requested by control command"),
+            ControlCommand::Resume => gpu.exit_bypass(),
+            ControlCommand::StartRecording { duration } => self.start_recording(duration),
+            ControlCommand::StopRecording => {
+                self.timers.cancel(|t| *t == EngineTimer::Recording);
+                self.stop_recording();
+            }
+            ControlCommand::SetMode { mode } => {
+                let mut delta = match read_config_delta(Some(&mode)) {
+                    Ok(delta) => delta,
+                    Err(e) => {
+                        warn!("Failed to read config for mode {mode}: {e}");
+                        return;
+                    }
+                };
+                delta.mode = Some(mode.clone());
+                delta.mode_source = Some(ModeSource::Control);
+                info!("Mode set to {mode} by control command");
+                self.handle_config_delta(gpu, delta);
+            }
+            ControlCommand::Lock { freq, duration } => {
+                self.lock_frequency(gpu, Freq::from_khz(freq), duration)
+            }
+            ControlCommand::Unlock => {
+                self.timers.cancel(|t| *t == EngineTimer::FrequencyLock);
+                self.unlock_frequency();
+            }
+            ControlCommand::Calibrate => {
+                if gpu.is_bypassed() || self.locked_freq.is_some() {
+                    warn!("Calibration requires the governor to be in control and unlocked");
+                    return;
+                }
+                match run_calibration(gpu) {
+                    Ok(report) => apply_calibration(gpu, &report),
+                    Err(e) => warn!("Calibration failed: {e}"),
+                }
+            }
+            ControlCommand::DumpTable { format } => match dump_freq_table(gpu, format) {
+                Ok(path) => info!(
+                    "Dumped {} frequency entries to {path}",
+                    gpu.get_config_list().len()
+                ),
+                Err(e) => warn!("Failed to dump frequency table: {e}"),
+            },
+            ControlCommand::LogTail { lines } => match export_log_tail(lines) {
+                Ok(count) => debug!("Exported {count} recent log line(s) to {LOG_TAIL_PATH}"),
+                Err(e) => warn!("Failed to export log tail: {e}"),
+            },
+        }
+    }
+
+    /// 锁定GPU频率，目标频率会对齐到频率表中最接近的档位
+    fn lock_frequency(&mut self, gpu: &mut GPU, freq: Freq, duration: Option<Duration>) {
+        let freq_index = gpu.find_closest_freq_index(freq);
+        let locked = gpu.get_freq_by_index(freq_index);
+        if !locked.is_positive() {
+            warn!("Cannot lock frequency {freq}KHz: frequency table is empty");
+            return;
+        }
+
+        self.timers.cancel(|t| *t == EngineTimer::FrequencyLock);
+        if let Some(duration) = duration {
+            self.timers.schedule(duration, EngineTimer::FrequencyLock);
+        }
+
+        if let Err(e) = FrequencyAdjustmentEngine::apply_frequency_change(
+            gpu,
+            locked,
+            freq_index,
+            FrequencyAdjustmentEngine::get_current_time_ms(),
+        ) {
+            warn!("Failed to write locked frequency: {e}");
+        }
+        match duration {
+            Some(d) => info!("GPU frequency locked at {locked}KHz for {}s", d.as_secs()),
+            None => info!("GPU frequency locked at {locked}KHz until unlock"),
+        }
+        self.locked_freq = Some(locked);
+        update_status(|s| s.locked_freq = Some(locked));
+    }
+
+    fn unlock_frequency(&mut self) {
+        if self.locked_freq.take().is_some() {
+            info!("GPU frequency unlocked");
+            update_status(|s| s.locked_freq = None);
+        }
+    }
+
+    /// 开始录制负载轨迹，已在录制时先结束当前文件
+    fn start_recording(&mut self, duration: Duration) {
+        self.timers.cancel(|t| *t == EngineTimer::Recording);
+        self.stop_recording();
+
+        match TraceRecorder::start() {
+            Ok(recorder) => {
+                let path = recorder.path().display().to_string();
+                info!("Recording load trace to {path} for {}s", duration.as_secs());
+                update_status(|s| s.recording = Some(path));
+                self.timers.schedule(duration, EngineTimer::Recording);
+                self.recorder = Some(recorder);
+            }
+            Err(e) => warn!("Failed to start trace recording: {e}"),
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        match recorder.finish() {
+            Ok((path, samples)) => {
+                info!("Load trace saved: {} ({samples} samples)", path.display())
+            }
+            Err(e) => warn!("Failed to finish trace recording: {e}"),
+        }
+        update_status(|s| s.recording = None);
+    }
+
+    /// 记录一个采样点到正在录制的轨迹
+    fn record_sample(&mut self, gpu: &GPU, load: i32, current_time: u64) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+
+        let ddr_opp = if gpu.is_ddr_freq_fixed() {
+            gpu.ddr_manager().get_ddr_freq()
+        } else {
+            DDR_AUTO_MODE_V1
+        };
+        match recorder.record(current_time, load, gpu.get_cur_freq().khz(), ddr_opp) {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("Trace sample limit reached, stopping recording");
+                self.timers.cancel(|t| *t == EngineTimer::Recording);
+                self.stop_recording();
+            }
+            Err(e) => {
+                warn!("Failed to write trace sample: {e}");
+                self.timers.cancel(|t| *t == EngineTimer::Recording);
+                self.stop_recording();
+            }
+        }
+    }
+
+    fn handle_timers(&mut self, gpu: &mut GPU) {
+        if self.timers.is_empty() {
+            return;
+        }
+        for timer in self.timers.poll_expired() {
+            match timer {
+                EngineTimer::Boost => {
+                    info!("Boost expired");
+                    self.end_boost(gpu);
+                }
+                EngineTimer::Recording => self.stop_recording(),
+                EngineTimer::FrequencyLock => self.unlock_frequency(),
+                EngineTimer::VoltageCheck => self.check_voltage(gpu),
+                EngineTimer::ConflictCheck => self.check_conflicts(gpu),
+                EngineTimer::ThermalCheck => {
+                    self.timers
+                        .schedule(THERMAL_CHECK_INTERVAL, EngineTimer::ThermalCheck);
+                    let derate = gpu.ddr_manager().is_thermal_derate_enabled();
+                    if !derate && !cfg!(feature = "shm-export") {
+                        continue;
+                    }
+                    self.temperature = read_gpu_temperature();
+                    if derate && let Some(temperature) = self.temperature {
+                        gpu.ddr_manager_mut().update_temperature(temperature);
+                    }
+                }
+                EngineTimer::UpdateCheck => self.check_update_activity(gpu),
+                EngineTimer::FaultCheck => {
+                    self.timers
+                        .schedule(FAULT_CHECK_INTERVAL, EngineTimer::FaultCheck);
+                    self.faults.check(gpu);
+                }
+                EngineTimer::MarginPhase => self.advance_margin_schedule(gpu),
+                EngineTimer::WarmupEnd => {
+                    info!("Warmup finished, using configured margin and debounce");
+                    gpu.frequency_strategy.set_warmup(false);
+                    update_status(|s| s.warming_up = false);
+                }
+                EngineTimer::ConflictBackoff => {
+                    if gpu
+                        .bypass_reason()
+                        .is_some_and(|r| r.starts_with(CONFLICT_BYPASS_REASON))
+                    {
+                        info!("Conflict backoff expired, resuming GPU control");
+                        gpu.exit_bypass();
+                    }
+                }
+            }
+        }
+    }
+
+    /// 回读GPU实际电压，与请求电压不一致时记录日志（例如降压被驱动拒绝）
+    ///
+    /// 单次读取失败只跳过本次检查，节点不存在或连续多次读取失败时才停止检查
+    fn check_voltage(&mut self, gpu: &GPU) {
+        self.timers
+            .schedule(VOLT_CHECK_INTERVAL, EngineTimer::VoltageCheck);
+        let Some(actual) = read_gpu_voltage(gpu.is_gpuv2()) else {
+            self.voltage_read_failures += 1;
+            if !gpu_voltage_node_exists(gpu.is_gpuv2())
+                || self.voltage_read_failures >= VOLT_READ_FAILURE_LIMIT
+            {
+                debug!("GPU voltage readback not available, disabling voltage check");
+                self.timers.cancel(|t| *t == EngineTimer::VoltageCheck);
+            }
+            return;
+        };
+        self.voltage_read_failures = 0;
+
+        let requested = gpu.frequency().cur_volt;
+        if requested <= 0 || gpu.is_bypassed() {
+            return;
+        }
+
+        if (actual - requested).abs() > VOLT_DRIFT_TOLERANCE {
+            if self.voltage_drift != Some((requested, actual)) {
+                warn!(
+                    "GPU voltage drift: requested {requested}, actual {actual} ({:+}) at {}KHz",
+                    actual - requested,
+                    gpu.get_cur_freq()
+                );
+                self.voltage_drift = Some((requested, actual));
+            }
+        } else if self.voltage_drift.take().is_some() {
+            info!("GPU voltage matches requested value again: {actual}");
+        }
+    }
+
+    /// 休眠唤醒后重新同步：时间戳可能跳变，休眠前的防抖计时不再有意义；
+    /// 部分驱动唤醒后会重置节点，重新检测负载来源并写入一次当前目标频率
+    fn handle_resume(&mut self, gpu: &mut GPU, asleep: Duration) {
+        info!(
+            "Resumed from suspend (about {}s asleep), resyncing",
+            asleep.as_secs()
+        );
+        update_status(|s| s.resumes += 1);
+
+        gpu.frequency_strategy_mut().reset_last_adjustment_time();
+        gpu.frequency_strategy_mut().reset_hold();
+        if let Err(e) = utilization_init() {
+            warn!("Load source re-probe after resume failed: {e}");
+        }
+
+        if gpu.is_bypassed() {
+            return;
+        }
+        if let Err(e) = gpu.frequency().write_freq(gpu.need_dcs, gpu.is_idle()) {
+            warn!("Failed to re-assert GPU frequency after resume: {e}");
+        }
+        gpu.check_write_health();
+    }
+
+    /// 处理长时间空闲断电的状态变化
+    fn handle_power_transition(
+        &mut self,
+        gpu: &mut GPU,
+        transition: PowerTransition,
+        current_time: u64,
+    ) {
+        match transition {
+            PowerTransition::Collapse => {
+                if !gpu.is_gpuv2() || !FileHelper::node_exists(GPUFREQV2_POWER_CONTROL) {
+                    debug!("GPU power control node not available, power collapse skipped");
+                    gpu.idle_manager_mut().clear_power_collapse();
+                    return;
+                }
+                if !FileHelper::write_string_safe(GPUFREQV2_POWER_CONTROL, POWER_CONTROL_OFF) {
+                    warn!("Failed to allow GPU power collapse");
+                    gpu.idle_manager_mut().clear_power_collapse();
+                    return;
+                }
+                info!(
+                    "GPU idle for {}ms, allowing power collapse",
+                    gpu.idle_manager.power_collapse().power_off_after_ms
+                );
+                update_status(|s| s.power_collapsed = true);
+            }
+            PowerTransition::Wake => {
+                Self::release_power_collapse(gpu);
+
+                // 上电后的第一段负载直接使用高频，掩盖上电和升频的延迟
+                let config = gpu.idle_manager.power_collapse().clone();
+                let target = config
+                    .exit_boost_freq
+                    .map_or_else(|| gpu.get_max_freq(), Freq::from_khz);
+                let idx = gpu.find_closest_freq_index(target);
+                let freq = gpu.get_freq_by_index(idx);
+                if config.exit_boost_ms == 0 || !freq.is_positive() {
+                    return;
+                }
+                info!(
+                    "GPU woke from power collapse, boosting to {freq}KHz for {}ms",
+                    config.exit_boost_ms
+                );
+                if let Err(e) =
+                    FrequencyAdjustmentEngine::apply_frequency_change(gpu, freq, idx, current_time)
+                {
+                    warn!("Failed to apply power collapse exit boost: {e}");
+                    return;
+                }
+                self.exit_boost_until =
+                    Some(Instant::now() + Duration::from_millis(config.exit_boost_ms));
+            }
+        }
+    }
+
+    /// 恢复GPU按需上电
+    fn release_power_collapse(gpu: &mut GPU) {
+        gpu.idle_manager_mut().clear_power_collapse();
+        if !FileHelper::write_string_safe(GPUFREQV2_POWER_CONTROL, POWER_CONTROL_ON) {
+            warn!("Failed to restore GPU power control");
+        }
+        update_status(|s| s.power_collapsed = false);
+    }
+
+    /// 系统更新或应用安装期间进入旁路模式，活动结束一段时间后恢复调频
+    fn check_update_activity(&mut self, gpu: &mut GPU) {
+        self.timers
+            .schedule(UPDATE_CHECK_INTERVAL, EngineTimer::UpdateCheck);
+        let update_bypassed = gpu
+            .bypass_reason()
+            .is_some_and(|r| r.starts_with(UPDATE_BYPASS_REASON));
+        if !gpu.is_update_bypass_enabled() && !update_bypassed {
+            return;
+        }
+
+        let scan_processes = self
+            .dexopt_scanned_at
+            .is_none_or(|scanned| scanned.elapsed() >= DEXOPT_SCAN_INTERVAL);
+        if scan_processes {
+            self.dexopt_scanned_at = Some(Instant::now());
+        }
+        if gpu.is_update_bypass_enabled()
+            && let Some(activity) = detect_update_activity(scan_processes)
+        {
+            self.update_seen_at = Some(Instant::now());
+            if !gpu.is_bypassed() {
+                gpu.enter_bypass(&format!("{UPDATE_BYPASS_REASON} ({activity})"));
+            }
+            return;
+        }
+
+        let quiet = self
+            .update_seen_at
+            .is_none_or(|seen| seen.elapsed() >= UPDATE_QUIET_PERIOD);
+        if update_bypassed && (quiet || !gpu.is_update_bypass_enabled()) {
+            info!("System update activity finished, resuming GPU control");
+            self.update_seen_at = None;
+            gpu.exit_bypass();
+        }
+    }
+
+    /// 回读受控节点，按 `conflict_policy` 处理其他工具的写入
+    fn check_conflicts(&mut self, gpu: &mut GPU) {
+        self.timers
+            .schedule(CONFLICT_CHECK_INTERVAL, EngineTimer::ConflictCheck);
+        if gpu.is_bypassed() {
+            return;
+        }
+
+        for conflict in self.conflicts.check() {
+            let NodeConflict {
+                path,
+                expected,
+                actual,
+            } = conflict;
+            if self.conflicts.is_new(&conflict) {
+                warn!(
+                    "External write detected on {path}: expected {expected}, found {actual} ({:?} policy)",
+                    gpu.conflict_policy()
+                );
+            }
+
+            match gpu.conflict_policy() {
+                ConflictPolicy::Log => {}
+                ConflictPolicy::Reassert => {
+                    if self.conflicts.should_reassert(&conflict) {
+                        if !FileHelper::write_string_safe(path, &expected.to_string()) {
+                            debug!("Failed to reassert {expected} to {path}");
+                        }
+                    } else if self.conflicts.reassert_exhausted(&conflict) {
+                        warn!(
+                            "{path} keeps being overwritten, no longer reasserting it; consider conflict_policy = \"back_off\""
+                        );
+                    }
+                }
+                ConflictPolicy::BackOff => {
+                    gpu.yield_control(&format!("{CONFLICT_BYPASS_REASON} on {path}"));
+                    self.timers.cancel(|t| *t == EngineTimer::ConflictBackoff);
+                    self.timers
+                        .schedule(CONFLICT_BACKOFF, EngineTimer::ConflictBackoff);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 结束临时模式，恢复到模式仲裁的结果
+    fn end_boost(&mut self, gpu: &mut GPU) {
+        if self.boost_mode.take().is_none() {
+            return;
+        }
+
+        let delta = match self.arbiter.winner().cloned() {
+            Some(delta) => delta,
+            None => match read_config_delta(None) {
+                Ok(delta) => delta,
+                Err(e) => {
+                    warn!("Failed to read config when reverting boost: {e}");
+                    return;
+                }
+            },
+        };
+        gpu.apply_config_delta(&delta);
+        self.advance_margin_schedule(gpu);
+        info!(
+            "Reverted from boost to mode: {}",
+            delta.mode.as_deref().unwrap_or("unknown")
+        );
+    }
+}
 
 /// GPU频率调整引擎 - 负责执行智能调频算法
 pub struct FrequencyAdjustmentEngine;
@@ -15,59 +816,213 @@ impl FrequencyAdjustmentEngine {
     /// 主要的频率调整循环
     pub fn run_adjustment_loop(
         gpu: &mut GPU,
-        rx: Option<Receiver<crate::datasource::config_parser::ConfigDelta>>,
+        rx: Option<Receiver<ConfigDelta>>,
+        control_rx: Option<Receiver<ControlCommand>>,
     ) -> Result<()> {
         debug!(
             "config:{:?}, freq:{}",
             gpu.get_config_list(),
             gpu.get_cur_freq()
         );
-        let rx = rx; // shadow
+        let mut ctx = EngineContext::new();
+        ctx.timers
+            .schedule(VOLT_CHECK_INTERVAL, EngineTimer::VoltageCheck);
+        ctx.timers
+            .schedule(CONFLICT_CHECK_INTERVAL, EngineTimer::ConflictCheck);
+        ctx.timers
+            .schedule(THERMAL_CHECK_INTERVAL, EngineTimer::ThermalCheck);
+        ctx.timers
+            .schedule(UPDATE_CHECK_INTERVAL, EngineTimer::UpdateCheck);
+        ctx.timers
+            .schedule(FAULT_CHECK_INTERVAL, EngineTimer::FaultCheck);
+        bypass_if_table_suspect(gpu);
+        let warmup = gpu.warmup();
+        if !warmup.is_zero() {
+            info!("Warmup: converging quickly for {}s", warmup.as_secs());
+            gpu.frequency_strategy.set_warmup(true);
+            update_status(|s| s.warming_up = true);
+            ctx.timers.schedule(warmup, EngineTimer::WarmupEnd);
+        }
+        if gpu.is_precise() {
+            ctx.enable_precise_timer();
+        }
+        apply_realtime_policy(&read_section("realtime"));
+        // 负载连续无法读取期间只在开始和恢复时输出日志
+        let mut load_unreadable = false;
         loop {
+            if is_shutdown_requested() {
+                info!("Shutdown requested, leaving adjustment loop");
+                ctx.stop_recording();
+                // 结算前台游戏的本次时长
+                record_foreground_game(None);
+                if gpu.idle_manager.is_collapsed() {
+                    EngineContext::release_power_collapse(gpu);
+                }
+                return Ok(());
+            }
+
+            if let Some(asleep) = ctx.resume.check() {
+                ctx.handle_resume(gpu, asleep);
+            }
+
             let current_time = Self::get_current_time_ms();
 
-            // 非阻塞接收所有配置增量
+            // 非阻塞接收所有配置增量，只应用其中最新的一个
             if let Some(r) = &rx {
-                while let Ok(delta) = r.try_recv() {
-                    gpu.apply_config_delta(&delta);
+                ctx.drain_config_deltas(gpu, r);
+            }
+
+            // 非阻塞接收控制命令
+            if let Some(r) = &control_rx {
+                while let Ok(command) = r.try_recv() {
+                    ctx.handle_control_command(gpu, command);
+                }
+            }
+
+            // 处理到期的定时任务
+            ctx.handle_timers(gpu);
+
+            flush_status_if_due();
+
+            // 旁路模式下不读取负载也不写入频率
+            if gpu.is_bypassed() {
+                if gpu.idle_manager.is_collapsed() {
+                    EngineContext::release_power_collapse(gpu);
+                }
+                std::thread::sleep(BYPASS_POLL_INTERVAL);
+                continue;
+            }
+
+            // 频率锁定期间不进行调频
+            if ctx.locked_freq.is_some() {
+                ctx.sampling_sleep(gpu);
+                continue;
+            }
+
+            // 退出断电后的高频保持期间不调频
+            if let Some(until) = ctx.exit_boost_until {
+                if Instant::now() < until {
+                    ctx.sampling_sleep(gpu);
+                    continue;
                 }
+                ctx.exit_boost_until = None;
             }
 
             // 更新当前GPU频率
             Self::update_current_frequency(gpu)?;
 
-            // 读取当前GPU负载
-            let load = get_gpu_load()?;
+            // 读取当前GPU负载，无法读取时保持当前频率并跳过本次采样
+            let load = match get_gpu_load() {
+                Ok(load) => {
+                    if std::mem::take(&mut load_unreadable) {
+                        info!("GPU load readable again");
+                    }
+                    load
+                }
+                Err(e) => {
+                    if !std::mem::replace(&mut load_unreadable, true) {
+                        warn!("{e}, skipping samples until a load source recovers");
+                    }
+                    ctx.sampling_sleep(gpu);
+                    continue;
+                }
+            };
 
             // 处理负载
-            Self::process_load(gpu, load, current_time)?;
+            let idle = Self::process_load(gpu, load, current_time)?;
+            ctx.record_sample(gpu, load, current_time);
+            if let Some(transition) = gpu.idle_manager_mut().track_power_collapse(idle) {
+                ctx.handle_power_transition(gpu, transition, current_time);
+            }
+            #[cfg(feature = "shm-export")]
+            if let Some(shm) = &mut ctx.shm {
+                shm.publish(gpu, load, ctx.temperature);
+            }
+            if idle {
+                debug!(
+                    "Idle state, sleeping for {}ms (precise mode: {})",
+                    IDLE_SLEEP_INTERVAL.as_millis(),
+                    gpu.is_precise()
+                );
+                ctx.sleep(IDLE_SLEEP_INTERVAL);
+            }
 
             // 应用采样睡眠
-            Self::apply_sampling_sleep(gpu);
+            ctx.sampling_sleep(gpu);
         }
     }
 
-    /// 获取当前时间戳（毫秒）
+    /// 获取当前时间戳（单调时钟，毫秒）
+    ///
+    /// 防抖和温控探测只关心时间间隔，使用单调时钟避免NTP校时或用户修改系统时间
+    /// 导致防抖窗口异常延长
     fn get_current_time_ms() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64
+        CLOCK_BASE.elapsed().as_millis() as u64
     }
 
-    /// 处理负载数据
-    fn process_load(gpu: &mut GPU, load: i32, current_time: u64) -> Result<()> {
+    /// 处理一次负载采样（不休眠），返回是否处于空闲状态
+    ///
+    /// 调频主循环和离线轨迹回放共用此入口
+    pub fn process_load(gpu: &mut GPU, load: i32, current_time: u64) -> Result<bool> {
+        // 固定频率策略：解码类负载平稳但对抖动敏感，不随负载调频也不进入空闲降频
+        if let Some(pinned) = gpu.pinned_target() {
+            let decision = Self::apply_pinned_frequency(gpu, load, pinned, current_time)?;
+            record_decision(&decision);
+            return Ok(false);
+        }
+
         // 根据负载动态调整采样间隔（如果启用了自适应采样）
         gpu.adjust_sampling_interval_by_load(load);
 
+        // 学习负载源的空闲负载下限
+        let at_min_freq = gpu.get_cur_freq() <= gpu.get_min_freq();
+        if let Some(threshold) = gpu.idle_manager.observe(load, at_min_freq) {
+            info!(
+                "Idle threshold adjusted to {threshold}% (learned idle floor {:?}%)",
+                gpu.idle_manager.learned_floor()
+            );
+            update_status(|s| s.adaptive_idle_threshold = Some(threshold));
+        }
+
         // 检查空闲状态
         if load <= gpu.idle_manager.idle_threshold {
+            gpu.frequency_strategy_mut().reset_hold();
+            Self::end_emergency_ramp(gpu, load);
+            let decision = Self::decision(
+                gpu,
+                load,
+                gpu.get_min_freq(),
+                DecisionOutcome::Idle,
+                current_time,
+            );
             Self::handle_idle_state(gpu);
-            return Ok(());
+            Self::publish_load_analysis(gpu, load);
+            record_decision(&decision);
+            return Ok(true);
         }
 
+        gpu.load_analyzer.update(load);
+        Self::publish_load_analysis(gpu, load);
+
+        // 频率未变化时也需要跟踪内存带宽和负载区间变化
+        Self::update_ddr_for_bandwidth(gpu);
+
         // 执行频率调整逻辑，使用连续调频公式
-        Self::execute_frequency_adjustment_with_formula(gpu, load, current_time)
+        let decision = Self::execute_frequency_adjustment_with_formula(gpu, load, current_time)?;
+        record_decision(&decision);
+        Ok(false)
+    }
+
+    /// 将本次负载以及负载分析器的区间、趋势和最近采样写入状态文件
+    fn publish_load_analysis(gpu: &GPU, load: i32) {
+        let analyzer = &gpu.load_analyzer;
+        update_status(|s| {
+            s.gpu_load = load;
+            s.load_zone = analyzer.zone();
+            s.load_trend = analyzer.trend();
+            s.smoothed_load = analyzer.smoothed_load();
+            s.load_history = analyzer.history();
+        });
     }
 
     /// 更新当前GPU频率
@@ -77,11 +1032,36 @@ impl FrequencyAdjustmentEngine {
         // 传递驱动类型信息：!gpu.is_gpuv2() 表示是v1驱动
         match get_gpu_current_freq(!gpu.is_gpuv2()) {
             Ok(current_freq) => {
-                if current_freq > 0 {
+                if gpu.accept_freq_reading(current_freq) {
+                    let previous_cap = gpu.thermal_cap.cap();
+                    gpu.thermal_cap.observe(current_freq);
+                    let cap = gpu.thermal_cap.cap();
+                    if cap != previous_cap {
+                        update_status(|s| s.thermal_cap = cap);
+                    }
+                    // 内核温控限频时实际频率偏低是预期行为，不计入交付统计
+                    if cap.is_some() {
+                        gpu.delivery.reset();
+                    } else {
+                        gpu.delivery.observe(current_freq);
+                    }
+
                     gpu.set_cur_freq(current_freq);
                     gpu.frequency_mut().cur_freq_idx =
                         gpu.frequency().read_freq_index(current_freq);
                     debug!("Updated current GPU frequency from file: {current_freq}");
+                } else {
+                    // 异常读数不覆盖上一次的有效频率
+                    let rejected = gpu.rejected_freq_samples();
+                    if rejected == 1 || rejected.is_multiple_of(FREQ_REJECT_LOG_INTERVAL) {
+                        warn!(
+                            "Ignoring implausible GPU frequency reading: {current_freq}KHz, keeping {}KHz ({rejected} rejected so far)",
+                            gpu.get_cur_freq()
+                        );
+                    } else {
+                        debug!("Ignoring implausible GPU frequency reading: {current_freq}KHz");
+                    }
+                    update_status(|s| s.rejected_freq_samples = rejected);
                 }
             }
             Err(e) => {
@@ -98,7 +1078,7 @@ impl FrequencyAdjustmentEngine {
         let current_freq = gpu.get_cur_freq();
 
         // 如果当前频率不是最低频率,则降低到最低频率
-        if current_freq != min_freq && min_freq > 0 {
+        if current_freq != min_freq && min_freq.is_positive() {
             debug!("GPU idle detected, reducing frequency from {current_freq}KHz to {min_freq}KHz");
 
             // 更新频率管理器
@@ -107,19 +1087,14 @@ impl FrequencyAdjustmentEngine {
 
             // 生成电压并写入频率
             gpu.frequency_mut().gen_cur_volt();
+            gpu.thermal_cap.record_request(min_freq);
+            gpu.sync_devfreq_clamp(min_freq);
             if let Err(e) = gpu.frequency().write_freq(gpu.need_dcs, true) {
                 warn!("Failed to write idle frequency: {e}");
             } else {
                 debug!("Successfully set GPU to idle frequency: {min_freq}KHz");
             }
         }
-
-        let idle_sleep_time = 160; // 统一使用普通模式的休眠时间
-        debug!(
-            "Idle state, sleeping for {idle_sleep_time}ms (precise mode: {})",
-            gpu.is_precise()
-        );
-        std::thread::sleep(Duration::from_millis(idle_sleep_time));
     }
 
     /// 执行频率调整逻辑（使用连续调频公式）
@@ -127,63 +1102,254 @@ impl FrequencyAdjustmentEngine {
         gpu: &mut GPU,
         load: i32,
         current_time: u64,
-    ) -> Result<()> {
+    ) -> Result<Decision> {
         debug!("Executing frequency adjustment for load: {load}%");
 
         let current_freq = gpu.get_cur_freq();
-        let margin = gpu.frequency_strategy.margin;
 
-        // 使用新的连续调频公式：targetFreq = now_freq * (util + margin) / 100
-        // 其中util是负载百分比，margin是调整余量
-        let load_factor = (load as f64 + margin as f64) / 100.0;
-        let raw_target_freq = (current_freq as f64 * load_factor) as i64;
+        // 频率表最高档位持续满载时请求驱动支持的最高频率
+        let at_table_max = current_freq >= gpu.get_max_freq();
+        match gpu.frequency_strategy.update_emergency(load, at_table_max) {
+            Some(true) => {
+                info!(
+                    "Load saturated at table max {}KHz for {} samples, entering emergency ramp",
+                    gpu.get_max_freq(),
+                    gpu.frequency_strategy.emergency_samples
+                );
+                update_status(|s| s.emergency_ramp = true);
+            }
+            Some(false) => {
+                info!("Load dropped to {load}%, leaving emergency ramp");
+                update_status(|s| s.emergency_ramp = false);
+            }
+            None => {}
+        }
+        if gpu.frequency_strategy.is_emergency() {
+            return Self::apply_emergency_ramp(gpu, load, current_time);
+        }
+
+        let margin = gpu.frequency_strategy.effective_margin();
 
-        // 确保目标频率在有效范围内
-        let min_freq = gpu.get_min_freq();
-        let max_freq = gpu.get_max_freq();
+        let raw_target_freq = if let Some(curve) = gpu.freq_curve() {
+            // 游戏规则的负载-频率曲线代替调频公式
+            curve.evaluate(load)
+        } else {
+            // 使用新的连续调频公式：targetFreq = now_freq * (util + margin) / 100
+            // 其中util是负载百分比，margin是调整余量
+            let load_factor = (load as f64 + margin as f64) / 100.0;
+            Freq::from_khz((current_freq.khz() as f64 * load_factor) as i64)
+        };
+
+        // 确保目标频率在有效范围内（游戏模式下避开DCS核心缩减频率，不超过模式的频率上限）
+        let min_freq = gpu.min_target_freq();
+        let max_freq = gpu.max_target_freq().max(min_freq);
         let target_freq = raw_target_freq.clamp(min_freq, max_freq);
 
+        // 内核温控限频期间不请求高于上限的频率，避免无效写入
+        let capped_freq = gpu.thermal_cap.limit(target_freq, current_time);
+        if capped_freq < target_freq {
+            debug!("Target {target_freq}KHz limited to kernel thermal cap {capped_freq}KHz");
+        }
+        let target_freq = capped_freq.max(min_freq);
+
         debug!(
             "Current freq: {current_freq}KHz, load: {load}%, margin: {margin}%, calculated target: {target_freq}KHz"
         );
 
+        // 忽略连续高负载之后的单个低负载采样
+        let is_decreasing = target_freq < current_freq;
+        if gpu.frequency_strategy_mut().should_hold_down(is_decreasing) {
+            debug!("Holding {current_freq}KHz through a single low-load sample");
+            return Ok(Self::decision(
+                gpu,
+                load,
+                target_freq,
+                DecisionOutcome::HeldDown,
+                current_time,
+            ));
+        }
+
         // 如果频率没有变化，直接返回
         if target_freq == current_freq {
             debug!("No frequency change needed");
-            return Ok(());
+            return Ok(Self::decision(
+                gpu,
+                load,
+                target_freq,
+                DecisionOutcome::Unchanged,
+                current_time,
+            ));
         }
 
         // 确定频率变化方向用于防抖延迟
         let is_increasing = target_freq > current_freq;
+        let fast_down = !is_increasing && gpu.frequency_strategy.is_fast_down(load);
 
-        // 检查防抖延迟
-        let last_adjust_time = gpu.frequency_strategy.last_adjustment_time;
+        // 检查防抖延迟，快速降频跳过降频防抖
+        let (up_delay, down_delay) = gpu.frequency_strategy.effective_debounce_times();
         let delay = if is_increasing {
-            gpu.frequency_strategy.up_debounce_time
+            up_delay
+        } else if fast_down {
+            0
         } else {
-            gpu.frequency_strategy.down_debounce_time
+            down_delay
         };
 
-        if current_time - last_adjust_time < delay {
+        if let Some(elapsed) = gpu.frequency_strategy.since_last_adjustment(current_time)
+            && elapsed < delay
+        {
+            debug!("Rate delay not met: {elapsed}ms < {delay}ms, skipping frequency change");
+            return Ok(Self::decision(
+                gpu,
+                load,
+                target_freq,
+                DecisionOutcome::Debounced,
+                current_time,
+            ));
+        }
+
+        let target_freq = if is_increasing {
+            target_freq
+        } else if fast_down {
             debug!(
-                "Rate delay not met: {}ms < {}ms, skipping frequency change",
-                current_time - last_adjust_time,
-                delay
+                "Fast-path downscale: load {load}% < floor {}%, {current_freq}KHz -> {target_freq}KHz",
+                gpu.frequency_strategy.aggressive_down_floor
             );
-            return Ok(());
-        }
+            target_freq
+        } else {
+            // 保守降频：每次最多降到当前频率下方相邻的档位
+            let next_lower = gpu.read_freq_le(Freq::from_khz(current_freq.khz() - 1));
+            let stepped = if next_lower < current_freq {
+                target_freq.max(next_lower)
+            } else {
+                target_freq
+            };
+            debug!(
+                "Conservative downscale: one step {current_freq}KHz -> {stepped}KHz (target {target_freq}KHz)"
+            );
+            stepped
+        };
 
         // 找到最接近目标频率的索引
         let target_idx = gpu.find_closest_freq_index(target_freq);
+        let decision = Self::decision(
+            gpu,
+            load,
+            target_freq,
+            DecisionOutcome::Changed,
+            current_time,
+        );
         Self::apply_frequency_change(gpu, target_freq, target_idx, current_time)?;
 
-        Ok(())
+        Ok(decision)
+    }
+
+    /// 生成决策日志记录，需在写入新频率之前调用
+    fn decision(
+        gpu: &GPU,
+        load: i32,
+        target: Freq,
+        outcome: DecisionOutcome,
+        current_time: u64,
+    ) -> Decision {
+        let strategy = &gpu.frequency_strategy;
+        Decision {
+            load,
+            zone: gpu.load_analyzer.zone(),
+            trend: gpu.load_analyzer.trend(),
+            margin: strategy.effective_margin(),
+            current: gpu.get_cur_freq(),
+            target,
+            debounce: strategy.effective_debounce_times(),
+            since_last: strategy.since_last_adjustment(current_time),
+            outcome,
+        }
+    }
+
+    /// 保持固定频率，仅在实际频率偏离时重新写入
+    fn apply_pinned_frequency(
+        gpu: &mut GPU,
+        load: i32,
+        pinned: Freq,
+        current_time: u64,
+    ) -> Result<Decision> {
+        let limited = gpu
+            .thermal_cap
+            .limit(pinned.min(gpu.max_target_freq()), current_time);
+        let target_idx = gpu.find_closest_freq_index(limited);
+        let target_freq = gpu.get_freq_by_index(target_idx);
+        if !target_freq.is_positive() || target_freq == gpu.get_cur_freq() {
+            let current = gpu.get_cur_freq();
+            return Ok(Self::decision(
+                gpu,
+                load,
+                current,
+                DecisionOutcome::Pinned,
+                current_time,
+            ));
+        }
+
+        debug!(
+            "Pinned policy: {}KHz -> {target_freq}KHz",
+            gpu.get_cur_freq()
+        );
+        let decision = Self::decision(
+            gpu,
+            load,
+            target_freq,
+            DecisionOutcome::Pinned,
+            current_time,
+        );
+        Self::apply_frequency_change(gpu, target_freq, target_idx, current_time)?;
+        Ok(decision)
+    }
+
+    /// 紧急升频：请求驱动支持的最高频率，仍受内核温控上限限制
+    ///
+    /// 驱动频率表中没有高于频率表上限的档位（非v2驱动）时保持频率表最高档位
+    fn apply_emergency_ramp(gpu: &mut GPU, load: i32, current_time: u64) -> Result<Decision> {
+        let table_max = gpu.get_max_freq();
+        let stock_max = gpu
+            .get_v2_supported_freqs()
+            .into_iter()
+            .max()
+            .filter(|_| gpu.is_gpuv2())
+            .unwrap_or(table_max)
+            .max(table_max);
+        let target_freq = gpu
+            .thermal_cap
+            .limit(stock_max, current_time)
+            .max(table_max)
+            .min(gpu.max_target_freq());
+        let decision = Self::decision(
+            gpu,
+            load,
+            target_freq,
+            DecisionOutcome::Emergency,
+            current_time,
+        );
+        if target_freq == gpu.get_cur_freq() {
+            return Ok(decision);
+        }
+
+        debug!("Emergency ramp: requesting {target_freq}KHz (table max {table_max}KHz)");
+        let freq_index = gpu.find_closest_freq_index(table_max);
+        Self::apply_frequency_change(gpu, target_freq, freq_index, current_time)?;
+        Ok(decision)
+    }
+
+    /// 空闲时结束紧急升频
+    fn end_emergency_ramp(gpu: &mut GPU, load: i32) {
+        if gpu.frequency_strategy_mut().end_emergency() {
+            info!("Load dropped to {load}%, leaving emergency ramp");
+            update_status(|s| s.emergency_ramp = false);
+        }
     }
 
     /// 应用频率变化
     fn apply_frequency_change(
         gpu: &mut GPU,
-        new_freq: i64,
+        new_freq: Freq,
         freq_index: i64,
         current_time: u64,
     ) -> Result<()> {
@@ -198,7 +1364,12 @@ impl FrequencyAdjustmentEngine {
 
         // 生成电压并写入
         gpu.frequency_mut().gen_cur_volt();
+        let expected_freq = gpu.read_freq_le(new_freq);
+        gpu.thermal_cap.record_request(expected_freq);
         gpu.frequency().write_freq(gpu.need_dcs, gpu.is_idle())?;
+        gpu.sync_devfreq_clamp(expected_freq);
+        gpu.delivery.record_request(new_freq, expected_freq);
+        gpu.check_write_health();
 
         // 更新游戏模式下的DDR频率
         Self::update_ddr_if_gaming(gpu, new_freq)?;
@@ -206,15 +1377,16 @@ impl FrequencyAdjustmentEngine {
         // 更新时间
         gpu.frequency_strategy_mut()
             .update_last_adjustment_time(current_time);
+        record_adjustment();
 
         Ok(())
     }
 
     /// 在游戏模式下更新DDR频率
-    fn update_ddr_if_gaming(gpu: &mut GPU, freq: i64) -> Result<()> {
+    fn update_ddr_if_gaming(gpu: &mut GPU, freq: Freq) -> Result<()> {
         if gpu.is_gaming_mode() {
-            use crate::model::gpu::TabType;
-            let ddr_opp = gpu.read_tab(TabType::FreqDram, freq);
+            let table_opp = gpu.base_ddr_opp(freq);
+            let ddr_opp = gpu.ddr_manager_mut().resolve_ddr_opp(table_opp);
             if (ddr_opp > 0 || ddr_opp == crate::datasource::file_path::DDR_HIGHEST_FREQ)
                 && let Err(e) = gpu.set_ddr_freq(ddr_opp)
             {
@@ -224,14 +1396,93 @@ impl FrequencyAdjustmentEngine {
         Ok(())
     }
 
-    /// 应用采样间隔睡眠
-    fn apply_sampling_sleep(gpu: &GPU) {
-        let sleep_time = gpu.frequency_strategy.get_sampling_interval();
+    /// 在游戏模式下根据内存带宽饱和状态和负载区间更新DDR档位，仅在档位变化时写入
+    fn update_ddr_for_bandwidth(gpu: &mut GPU) {
+        let ddr = gpu.ddr_manager();
+        if !gpu.is_gaming_mode()
+            || !(ddr.is_bandwidth_boost_enabled()
+                || ddr.is_load_zone_policy()
+                || ddr.is_thermal_derate_enabled())
+        {
+            return;
+        }
 
-        debug!(
-            "Sleeping for {sleep_time}ms (precise mode: {})",
-            gpu.is_precise()
-        );
-        std::thread::sleep(Duration::from_millis(sleep_time));
+        let table_opp = gpu.base_ddr_opp(gpu.get_cur_freq());
+        let ddr_opp = gpu.ddr_manager_mut().resolve_ddr_opp(table_opp);
+        if gpu.is_ddr_freq_fixed() && gpu.ddr_manager().get_ddr_freq() == ddr_opp {
+            return;
+        }
+
+        if (ddr_opp > 0 || ddr_opp == crate::datasource::file_path::DDR_HIGHEST_FREQ)
+            && let Err(e) = gpu.set_ddr_freq(ddr_opp)
+        {
+            warn!("Failed to update DDR frequency for bandwidth: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datasource::{file_path::*, freq_table_parser::parse_freq_table},
+        utils::sysfs_mock,
+    };
+
+    const FREQ_TABLE: &str = r#"
+[[freq_table]]
+freq = 265000
+volt = 56250
+ddr_opp = 999
+
+[[freq_table]]
+freq = 550000
+volt = 62500
+ddr_opp = 999
+
+[[freq_table]]
+freq = 880000
+volt = 75000
+ddr_opp = 999
+"#;
+
+    /// 与轨迹回放相同的GPU：节点写入由模拟层接管，从频率表第一项开始
+    fn replay_gpu() -> GPU {
+        sysfs_mock::install(&[
+            GPUFREQ_VOLT,
+            GPUFREQ_OPP,
+            GPUFREQV2_VOLT,
+            GPUFREQV2_OPP,
+            MALI_DVFS_ENABLE,
+            DVFSRC_V1_PATH,
+            DVFSRC_V2_PATH_1,
+            DVFSRC_V2_PATH_2,
+        ]);
+        let mut gpu = GPU::new();
+        parse_freq_table(FREQ_TABLE, "test", &gpu)
+            .unwrap()
+            .apply(&mut gpu, 0);
+        gpu.set_cur_freq(gpu.get_freq_by_index(0));
+        gpu.frequency_mut().gen_cur_volt();
+        gpu
+    }
+
+    #[test]
+    fn sustained_load_raises_and_idle_drops_frequency() {
+        let mut gpu = replay_gpu();
+        let min_freq = gpu.get_min_freq();
+
+        let mut time = 0;
+        for _ in 0..50 {
+            time += 100;
+            let idle = FrequencyAdjustmentEngine::process_load(&mut gpu, 95, time).unwrap();
+            assert!(!idle);
+        }
+        assert!(gpu.get_cur_freq() > min_freq);
+
+        time += 100;
+        let idle = FrequencyAdjustmentEngine::process_load(&mut gpu, 0, time).unwrap();
+        assert!(idle);
+        assert_eq!(gpu.get_cur_freq(), min_freq);
     }
 }
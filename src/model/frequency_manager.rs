@@ -1,21 +1,47 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use anyhow::Result;
-use log::{debug, warn};
-
-use crate::{datasource::file_path::*, utils::file_helper::FileHelper};
+use log::{debug, info, warn};
+
+use crate::{
+    datasource::{
+        config_parser::{V2TuningConfig, WriteDelayConfig},
+        file_path::*,
+    },
+    model::{
+        conflict_detector::{record_owned_write, release_owned_node},
+        freq::{Freq, FreqUnit},
+    },
+    utils::file_helper::FileHelper,
+};
+
+/// v1驱动的DVFS是否被手动调频关闭
+///
+/// 使用进程级状态而不是实例字段：FrequencyManager 会被克隆到多个线程，
+/// 且 panic/退出路径无法访问调频线程中的实例
+static DVFS_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// 电压步进（10微伏，即6.25mV）
+pub const VOLT_STEP: i64 = 625;
+/// gpufreqv2 正常模式下释放固定档位后到写入电压前的默认等待时间
+pub const DEFAULT_WRITE_DELAY: Duration = Duration::from_millis(10);
 
 /// 频率管理器 - 负责GPU频率的计算和调整逻辑
 #[derive(Clone)]
 pub struct FrequencyManager {
     /// 可用频率列表
-    pub config_list: Vec<i64>,
+    pub config_list: Vec<Freq>,
     /// 频率到电压的映射
-    pub freq_volt: HashMap<i64, i64>,
+    pub freq_volt: HashMap<Freq, i64>,
     /// 频率到DDR的映射
-    pub freq_dram: HashMap<i64, i64>,
+    pub freq_dram: HashMap<Freq, i64>,
     /// 当前频率
-    pub cur_freq: i64,
+    pub cur_freq: Freq,
     /// 当前频率索引
     pub cur_freq_idx: i64,
     /// 当前电压
@@ -23,7 +49,27 @@ pub struct FrequencyManager {
     /// 是否使用v2驱动
     pub gpuv2: bool,
     /// v2驱动支持的频率列表
-    pub v2_supported_freqs: Vec<i64>,
+    pub v2_supported_freqs: Vec<Freq>,
+    /// v2驱动频率表使用的单位，写入频率节点时换算回该单位
+    pub v2_table_unit: FreqUnit,
+    /// 频率节点连续写入失败次数
+    consecutive_write_failures: Cell<u32>,
+    /// v1驱动是否已写入空闲释放序列，手动写入频率后重置
+    idle_released: Cell<bool>,
+    /// 叠加在频率表电压上的偏移（10微伏），游戏规则临时设置
+    pub volt_offset: i64,
+    /// 模式设置的电压上限（10微伏）
+    pub max_volt: Option<i64>,
+    /// 上一次生成的电压是否被上限截断，用于只在状态变化时输出日志
+    volt_capped: Cell<bool>,
+    /// 电压节点不可用（驱动兼容问题），只写入频率
+    pub skip_volt_writes: bool,
+    /// gpufreqv2 正常模式下释放固定档位后到写入电压前的默认等待时间，兼容性修正可延长
+    pub write_delay: Duration,
+    /// 配置文件中按驱动指定的写入间隔
+    pub write_delay_config: WriteDelayConfig,
+    /// 校准测得的写入间隔
+    pub calibrated_write_delay: Option<Duration>,
 }
 
 impl FrequencyManager {
@@ -32,58 +78,68 @@ impl FrequencyManager {
             config_list: Vec::new(),
             freq_volt: HashMap::new(),
             freq_dram: HashMap::new(),
-            cur_freq: 0,
+            cur_freq: Freq::ZERO,
             cur_freq_idx: 0,
             cur_volt: 0,
             gpuv2: false,
             v2_supported_freqs: Vec::new(),
+            v2_table_unit: FreqUnit::KHz,
+            consecutive_write_failures: Cell::new(0),
+            idle_released: Cell::new(false),
+            volt_offset: 0,
+            max_volt: None,
+            volt_capped: Cell::new(false),
+            skip_volt_writes: false,
+            write_delay: DEFAULT_WRITE_DELAY,
+            write_delay_config: WriteDelayConfig::default(),
+            calibrated_write_delay: None,
         }
     }
 
     /// 获取频率对应的电压
-    pub fn get_volt(&self, freq: i64) -> i64 {
+    pub fn get_volt(&self, freq: Freq) -> i64 {
         *self.freq_volt.get(&freq).unwrap_or(&0)
     }
 
     /// 根据索引获取频率
-    pub fn get_freq_by_index(&self, idx: i64) -> i64 {
+    pub fn get_freq_by_index(&self, idx: i64) -> Freq {
         let unified_idx = self.unify_id(idx);
         self.config_list
             .get(unified_idx as usize)
             .copied()
-            .unwrap_or(0)
+            .unwrap_or_default()
     }
 
     /// 获取大于等于指定频率的最小频率
-    pub fn read_freq_ge(&self, freq: i64) -> i64 {
+    pub fn read_freq_ge(&self, freq: Freq) -> Freq {
         debug!("readFreqGe={freq}");
-        if freq <= 0 {
-            return *self.config_list.last().unwrap_or(&0);
+        if !freq.is_positive() {
+            return self.get_max_freq();
         }
         for &cfreq in &self.config_list {
             if cfreq >= freq {
                 return cfreq;
             }
         }
-        *self.config_list.last().unwrap_or(&0)
+        self.get_max_freq()
     }
 
     /// 获取小于等于指定频率的最大频率
-    pub fn read_freq_le(&self, freq: i64) -> i64 {
+    pub fn read_freq_le(&self, freq: Freq) -> Freq {
         debug!("readFreqLe={freq}");
-        if freq <= 0 {
-            return *self.config_list.first().unwrap_or(&0);
+        if !freq.is_positive() {
+            return self.get_min_freq();
         }
         for &cfreq in self.config_list.iter().rev() {
             if cfreq <= freq {
                 return cfreq;
             }
         }
-        *self.config_list.first().unwrap_or(&0)
+        self.get_min_freq()
     }
 
     /// 获取频率对应的索引
-    pub fn read_freq_index(&self, freq: i64) -> i64 {
+    pub fn read_freq_index(&self, freq: Freq) -> i64 {
         for (i, &cfreq) in self.config_list.iter().enumerate() {
             if cfreq == freq {
                 return i as i64;
@@ -93,35 +149,35 @@ impl FrequencyManager {
     }
 
     /// 获取最高频率
-    pub fn get_max_freq(&self) -> i64 {
-        *self.config_list.last().unwrap_or(&0)
+    pub fn get_max_freq(&self) -> Freq {
+        self.config_list.last().copied().unwrap_or_default()
     }
 
     /// 获取最低频率
-    pub fn get_min_freq(&self) -> i64 {
-        *self.config_list.first().unwrap_or(&0)
+    pub fn get_min_freq(&self) -> Freq {
+        self.config_list.first().copied().unwrap_or_default()
     }
 
     /// 获取中等频率
-    pub fn get_middle_freq(&self) -> i64 {
+    pub fn get_middle_freq(&self) -> Freq {
         if self.config_list.is_empty() {
-            return 0;
+            return Freq::ZERO;
         }
         let mid_idx = self.config_list.len() / 2;
         self.config_list[mid_idx]
     }
 
     /// 获取v2驱动支持的最接近频率
-    pub fn get_closest_v2_supported_freq(&self, target_freq: i64) -> i64 {
+    pub fn get_closest_v2_supported_freq(&self, target_freq: Freq) -> Freq {
         if self.v2_supported_freqs.is_empty() {
             return target_freq;
         }
 
         let mut closest_freq = self.v2_supported_freqs[0];
-        let mut min_diff = (target_freq - closest_freq).abs();
+        let mut min_diff = target_freq.abs_diff(closest_freq);
 
         for &freq in &self.v2_supported_freqs {
-            let diff = (target_freq - freq).abs();
+            let diff = target_freq.abs_diff(freq);
             if diff < min_diff {
                 min_diff = diff;
                 closest_freq = freq;
@@ -131,29 +187,84 @@ impl FrequencyManager {
         closest_freq
     }
 
-    /// 生成当前电压
-    pub fn gen_cur_volt(&mut self) -> i64 {
+    /// 计算写入指定频率时实际使用的电压
+    pub fn effective_volt(&self, freq: Freq) -> i64 {
+        self.limit_volt(self.offset_volt(freq))
+    }
+
+    /// 频率表电压叠加偏移后的电压，不考虑电压上限
+    fn offset_volt(&self, freq: Freq) -> i64 {
         // 对于v2 driver设备，获取支持的最接近频率
-        let freq_to_use = self.get_closest_v2_supported_freq(self.cur_freq);
+        let freq_to_use = self.get_closest_v2_supported_freq(freq);
 
         // 获取电压值，优先使用原频率的电压，如果没有则使用最接近支持频率的电压
-        let original_volt = self.get_volt(self.cur_freq);
-        let closest_volt = self.get_volt(freq_to_use);
-
-        // 如果原频率有对应电压，优先使用原频率的电压
-        // 否则使用最接近支持频率的电压
-        self.cur_volt = if original_volt > 0 {
+        let original_volt = self.get_volt(freq);
+        let volt = if original_volt > 0 {
             original_volt
         } else {
-            closest_volt
+            self.get_volt(freq_to_use)
         };
 
+        // 偏移只叠加在有效电压上，结果仍需为正数
+        if volt > 0 && self.volt_offset != 0 {
+            (volt + self.volt_offset).max(VOLT_STEP)
+        } else {
+            volt
+        }
+    }
+
+    /// 将电压限制在模式的电压上限以内，0（不写电压）保持不变
+    fn limit_volt(&self, volt: i64) -> i64 {
+        match self.max_volt {
+            Some(cap) if volt > cap => cap,
+            _ => volt,
+        }
+    }
+
+    /// 生成当前电压
+    pub fn gen_cur_volt(&mut self) -> i64 {
+        let volt = self.offset_volt(self.cur_freq);
+        self.cur_volt = self.limit_volt(volt);
+
+        let capped = self.cur_volt != volt;
+        if capped != self.volt_capped.replace(capped) {
+            if capped {
+                info!(
+                    "Voltage ceiling active: {}KHz needs {volt}, limited to {}",
+                    self.cur_freq, self.cur_volt
+                );
+            } else {
+                info!("Voltage ceiling inactive at {}KHz", self.cur_freq);
+            }
+        }
         self.cur_volt
     }
 
+    /// 写入gpufreqv2的老化余量和节能模式设置，内核未提供对应节点时跳过
+    pub fn apply_v2_tuning(&self, tuning: &V2TuningConfig) {
+        for (name, path, value) in [
+            ("aging margin", GPUFREQV2_AGING_MODE, tuning.aging),
+            ("eco mode", GPUFREQV2_ECO_MODE, tuning.eco),
+        ] {
+            let Some(enable) = value else {
+                continue;
+            };
+            if !FileHelper::node_exists(path) {
+                debug!("gpufreqv2 {name} node not available: {path}");
+                continue;
+            }
+            let state = if enable { "enable" } else { "disable" };
+            if FileHelper::write_string_safe(path, state) {
+                info!("gpufreqv2 {name} set to {state}");
+            } else {
+                warn!("Failed to set gpufreqv2 {name} to {state}");
+            }
+        }
+    }
+
     /// 确保DVFS处于关闭状态
     fn ensure_dvfs_disabled(&self) -> Result<()> {
-        if !Path::new(MALI_DVFS_ENABLE).exists() {
+        if !FileHelper::node_exists(MALI_DVFS_ENABLE) {
             debug!("DVFS control file does not exist: {MALI_DVFS_ENABLE}");
             return Ok(());
         }
@@ -162,23 +273,50 @@ impl FrequencyManager {
         if !FileHelper::write_string_safe(MALI_DVFS_ENABLE, "0") {
             warn!("Failed to disable DVFS at {MALI_DVFS_ENABLE}");
         } else {
+            DVFS_DISABLED.store(true, Ordering::SeqCst);
+            record_owned_write(MALI_DVFS_ENABLE, 0);
             debug!("DVFS disabled successfully");
         }
 
         Ok(())
     }
 
+    /// DVFS当前是否被关闭
+    pub fn is_dvfs_disabled() -> bool {
+        DVFS_DISABLED.load(Ordering::SeqCst)
+    }
+
+    /// 重新开启被手动调频关闭的DVFS
+    ///
+    /// 在退出、panic和旁路模式路径上调用，保证守护进程停止后GPU不会停留在手动调频状态
+    pub fn restore_dvfs() {
+        if !DVFS_DISABLED.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if FileHelper::write_string_safe(MALI_DVFS_ENABLE, "1") {
+            DVFS_DISABLED.store(false, Ordering::SeqCst);
+            release_owned_node(MALI_DVFS_ENABLE);
+            info!("DVFS re-enabled at {MALI_DVFS_ENABLE}");
+        } else {
+            warn!("Failed to re-enable DVFS at {MALI_DVFS_ENABLE}");
+        }
+    }
+
     /// 写入频率到系统文件
     pub fn write_freq(&self, need_dcs: bool, is_idle: bool) -> Result<()> {
         // 根据驱动类型获取要使用的频率
+        // v2驱动按频率表的单位写入
         let freq_to_use = if self.gpuv2 {
             self.get_closest_v2_supported_freq(self.cur_freq)
+                .to_unit(self.v2_table_unit)
         } else {
-            self.cur_freq
+            self.cur_freq.khz()
         };
 
         let content = freq_to_use.to_string();
-        let volt_content = format!("{} {}", freq_to_use, self.cur_volt);
+        // 写入前再次确认电压上限，保证即使当前电压未经 gen_cur_volt 生成也不会越界
+        let volt_content = format!("{} {}", freq_to_use, self.limit_volt(self.cur_volt));
         let volt_reset = "0 0";
         let opp_reset_minus_one = "-1";
         let opp_reset_zero = "0";
@@ -195,13 +333,18 @@ impl FrequencyManager {
         };
 
         // 检查文件是否存在
-        if !std::path::Path::new(volt_path).exists() || !std::path::Path::new(opp_path).exists() {
+        if !FileHelper::node_exists(volt_path) || !FileHelper::node_exists(opp_path) {
             return Ok(());
         }
 
+        let volt_written = self.cur_volt != 0 && !self.skip_volt_writes;
         if !self.gpuv2 {
             if is_idle {
-                self.write_idle_mode_v1(volt_path, opp_path, volt_reset)?;
+                // 空闲期间只在进入空闲时写入一次释放序列
+                if !self.idle_released.get() {
+                    self.write_idle_mode_v1(volt_path, opp_path, volt_reset)?;
+                }
+                Self::release_freq_nodes(volt_path, opp_path);
             } else {
                 self.write_manual_mode_v1(
                     volt_path,
@@ -210,6 +353,7 @@ impl FrequencyManager {
                     &content,
                     &volt_content,
                 )?;
+                self.record_freq_write(volt_path, opp_path, freq_to_use, volt_written);
             }
             return Ok(());
         }
@@ -217,6 +361,7 @@ impl FrequencyManager {
         // 确定写入模式（v2驱动）
         if is_idle {
             self.write_idle_mode(volt_path, opp_path, volt_reset, opp_reset_zero)?;
+            Self::release_freq_nodes(volt_path, opp_path);
         } else if need_dcs && self.gpuv2 && self.cur_freq_idx == 0 {
             self.write_dcs_mode(
                 volt_path,
@@ -225,8 +370,10 @@ impl FrequencyManager {
                 opp_reset_minus_one,
                 opp_reset_zero,
             )?;
-        } else if self.cur_volt == 0 {
+            Self::release_freq_nodes(volt_path, opp_path);
+        } else if !volt_written {
             self.write_no_volt_mode(volt_path, opp_path, volt_reset, &content)?;
+            self.record_freq_write(volt_path, opp_path, freq_to_use, false);
         } else {
             self.write_normal_mode(
                 volt_path,
@@ -236,11 +383,62 @@ impl FrequencyManager {
                 opp_reset_zero,
                 &volt_content,
             )?;
+            self.record_freq_write(volt_path, opp_path, freq_to_use, true);
         }
 
         Ok(())
     }
 
+    /// 记录成功写入的固定频率供冲突检测回读：写入了电压时比较电压节点（回读内容的最后一个
+    /// 整数为电压），否则比较频率节点
+    fn record_freq_write(
+        &self,
+        volt_path: &'static str,
+        opp_path: &'static str,
+        freq: i64,
+        volt_written: bool,
+    ) {
+        if self.consecutive_write_failures.get() != 0 {
+            return;
+        }
+        if volt_written {
+            release_owned_node(opp_path);
+            record_owned_write(volt_path, self.limit_volt(self.cur_volt));
+        } else {
+            release_owned_node(volt_path);
+            record_owned_write(opp_path, freq);
+        }
+    }
+
+    /// 频率和电压节点已交还内核，不再检测外部写入
+    fn release_freq_nodes(volt_path: &str, opp_path: &str) {
+        release_owned_node(volt_path);
+        release_owned_node(opp_path);
+    }
+
+    /// 当前驱动的写入间隔：配置优先，其次为校准结果，最后为驱动默认值
+    pub fn effective_write_delay(&self) -> Duration {
+        let configured = if self.gpuv2 {
+            self.write_delay_config.gpufreqv2_ms
+        } else {
+            self.write_delay_config.gpufreq_ms
+        };
+        configured
+            .map(Duration::from_millis)
+            .or(self.calibrated_write_delay)
+            .unwrap_or(if self.gpuv2 {
+                self.write_delay
+            } else {
+                Duration::ZERO
+            })
+    }
+
+    fn sleep_between_writes(delay: Duration) {
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
     /// 空闲模式写入
     fn write_idle_mode(
         &self,
@@ -274,10 +472,9 @@ impl FrequencyManager {
     ) -> Result<()> {
         debug!("Writing in DCS mode");
         FileHelper::write_string_safe(volt_path, volt_reset);
-        let result = FileHelper::write_string_safe(opp_path, opp_reset_minus_one);
-        if !result {
-            FileHelper::write_string_safe(opp_path, opp_reset_zero);
-        }
+        let result = FileHelper::write_string_safe(opp_path, opp_reset_minus_one)
+            || FileHelper::write_string_safe(opp_path, opp_reset_zero);
+        self.record_write_result(result);
         Ok(())
     }
 
@@ -291,7 +488,8 @@ impl FrequencyManager {
     ) -> Result<()> {
         debug!("Writing in no-volt mode");
         FileHelper::write_string_safe(volt_path, volt_reset);
-        FileHelper::write_string_safe(opp_path, content);
+        let result = FileHelper::write_string_safe(opp_path, content);
+        self.record_write_result(result);
         Ok(())
     }
 
@@ -311,8 +509,9 @@ impl FrequencyManager {
         if !result {
             FileHelper::write_string_safe(opp_path, opp_reset_zero);
         }
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        FileHelper::write_string_safe(volt_path, volt_content);
+        Self::sleep_between_writes(self.effective_write_delay());
+        let result = FileHelper::write_string_safe(volt_path, volt_content);
+        self.record_write_result(result);
         Ok(())
     }
 
@@ -325,15 +524,18 @@ impl FrequencyManager {
         volt_content: &str,
     ) -> Result<()> {
         debug!("Writing V1 manual frequency");
+        self.idle_released.set(false);
         self.ensure_dvfs_disabled()?;
 
-        if self.cur_volt == 0 {
+        let result = if self.cur_volt == 0 || self.skip_volt_writes {
             FileHelper::write_string_safe(volt_path, volt_reset);
-            FileHelper::write_string_safe(opp_path, content);
+            FileHelper::write_string_safe(opp_path, content)
         } else {
             FileHelper::write_string_safe(opp_path, "0");
-            FileHelper::write_string_safe(volt_path, volt_content);
-        }
+            Self::sleep_between_writes(self.effective_write_delay());
+            FileHelper::write_string_safe(volt_path, volt_content)
+        };
+        self.record_write_result(result);
         Ok(())
     }
 
@@ -343,12 +545,58 @@ impl FrequencyManager {
         FileHelper::write_string_safe(opp_path, "0");
         FileHelper::write_string_safe(opp_path, "-1");
         FileHelper::write_string_safe(volt_path, volt_reset);
-        if std::path::Path::new(MALI_DVFS_ENABLE).exists() {
-            FileHelper::write_string_safe(MALI_DVFS_ENABLE, "1");
+        if FileHelper::node_exists(MALI_DVFS_ENABLE)
+            && FileHelper::write_string_safe(MALI_DVFS_ENABLE, "1")
+        {
+            DVFS_DISABLED.store(false, Ordering::SeqCst);
+            release_owned_node(MALI_DVFS_ENABLE);
         }
+        self.idle_released.set(true);
         Ok(())
     }
 
+    /// 将频率控制交还给内核（解除固定频率和电压）
+    pub fn release_to_kernel(&self) -> Result<()> {
+        let (volt_path, opp_path) = if self.gpuv2 {
+            (GPUFREQV2_VOLT, GPUFREQV2_OPP)
+        } else {
+            (GPUFREQ_VOLT, GPUFREQ_OPP)
+        };
+
+        Self::release_freq_nodes(volt_path, opp_path);
+        if !FileHelper::node_exists(volt_path) || !FileHelper::node_exists(opp_path) {
+            Self::restore_dvfs();
+            return Ok(());
+        }
+
+        if self.gpuv2 {
+            self.write_idle_mode(volt_path, opp_path, "0 0", "0")
+        } else {
+            self.write_idle_mode_v1(volt_path, opp_path, "0 0")
+        }
+    }
+
+    /// 记录关键频率写入的结果，用于统计连续失败次数
+    fn record_write_result(&self, success: bool) {
+        if success {
+            self.consecutive_write_failures.set(0);
+        } else {
+            let failures = self.consecutive_write_failures.get().saturating_add(1);
+            self.consecutive_write_failures.set(failures);
+            debug!("Frequency write failed ({failures} consecutive failures)");
+        }
+    }
+
+    /// 获取频率节点连续写入失败次数
+    pub fn consecutive_write_failures(&self) -> u32 {
+        self.consecutive_write_failures.get()
+    }
+
+    /// 清零连续写入失败计数
+    pub fn reset_write_failures(&self) {
+        self.consecutive_write_failures.set(0);
+    }
+
     /// 统一ID范围
     fn unify_id(&self, id: i64) -> i64 {
         if id < 0 {
@@ -361,30 +609,37 @@ impl FrequencyManager {
     }
 
     /// 设置配置列表
-    pub fn set_config_list(&mut self, config_list: Vec<i64>) {
+    pub fn set_config_list(&mut self, config_list: Vec<Freq>) {
         self.config_list = config_list;
     }
 
     /// 获取配置列表
-    pub fn get_config_list(&self) -> Vec<i64> {
+    pub fn get_config_list(&self) -> Vec<Freq> {
         self.config_list.clone()
     }
 
+    /// 频率表中的全部频率（升序），包括校准剔除的档位
+    pub fn table_freqs(&self) -> Vec<Freq> {
+        let mut freqs: Vec<Freq> = self.freq_volt.keys().copied().collect();
+        freqs.sort();
+        freqs
+    }
+
     /// 替换映射表
-    pub fn replace_freq_volt_tab(&mut self, tab: HashMap<i64, i64>) {
+    pub fn replace_freq_volt_tab(&mut self, tab: HashMap<Freq, i64>) {
         self.freq_volt = tab;
     }
 
-    pub fn replace_freq_dram_tab(&mut self, tab: HashMap<i64, i64>) {
+    pub fn replace_freq_dram_tab(&mut self, tab: HashMap<Freq, i64>) {
         self.freq_dram = tab;
     }
 
     /// 读取映射表值
-    pub fn read_freq_volt(&self, freq: i64) -> i64 {
+    pub fn read_freq_volt(&self, freq: Freq) -> i64 {
         *self.freq_volt.get(&freq).unwrap_or(&0)
     }
 
-    pub fn read_freq_dram(&self, freq: i64) -> i64 {
+    pub fn read_freq_dram(&self, freq: Freq) -> i64 {
         *self.freq_dram.get(&freq).unwrap_or(&0)
     }
 }
@@ -1,3 +1,12 @@
+/// 冷启动预热期间叠加在余量上的值（百分比）
+const WARMUP_MARGIN_BONUS: u32 = 15;
+/// 冷启动预热期间防抖时间的缩短倍数
+const WARMUP_DEBOUNCE_DIVISOR: u64 = 4;
+/// 视为满载的负载（百分比）
+const EMERGENCY_LOAD: i32 = 99;
+/// 紧急升频期间负载低于该值时退出（百分比）
+const EMERGENCY_EXIT_LOAD: i32 = 90;
+
 /// 调频策略配置 - 负责GPU调频的策略和参数管理
 #[derive(Clone)]
 pub struct FrequencyStrategy {
@@ -9,10 +18,26 @@ pub struct FrequencyStrategy {
     pub margin: u32, // 频率调整余量（MHz）
     /// 激进降频开关
     pub aggressive_down: bool, // 是否启用激进降频
+    /// 激进降频的负载下限（百分比）
+    pub aggressive_down_floor: i32,
     /// 采样间隔
     pub sampling_interval: u64, // 采样间隔（毫秒）
-    /// 上次调整时间
-    pub last_adjustment_time: u64, // 上次频率调整时间戳（毫秒）
+    /// 上次调整时间（单调时钟，毫秒），尚未调整过时为None
+    pub last_adjustment_time: Option<u64>,
+    /// 降频保持所需的连续高负载采样数，0表示关闭
+    pub hold_samples: u32,
+    /// 连续未要求降频的采样数
+    high_streak: u32,
+    /// 上一个采样是否已被保持
+    held_last: bool,
+    /// 是否处于冷启动预热期
+    warmup: bool,
+    /// 触发紧急升频所需的连续满载采样数，0表示关闭
+    pub emergency_samples: u32,
+    /// 在频率表最高档位连续满载的采样数
+    saturated_streak: u32,
+    /// 是否处于紧急升频状态
+    emergency: bool,
 }
 
 impl FrequencyStrategy {
@@ -21,9 +46,17 @@ impl FrequencyStrategy {
             up_debounce_time: up_time,
             margin: 27,
             aggressive_down: true,
+            aggressive_down_floor: 30,
             sampling_interval: 8,
-            last_adjustment_time: 0,
+            last_adjustment_time: None,
             down_debounce_time: down_time,
+            hold_samples: 0,
+            high_streak: 0,
+            held_last: false,
+            warmup: false,
+            emergency_samples: 0,
+            saturated_streak: 0,
+            emergency: false,
         }
     }
 
@@ -32,11 +65,57 @@ impl FrequencyStrategy {
         self.margin = margin;
     }
 
+    /// 进入或结束冷启动预热期
+    ///
+    /// 预热期间使用更高的余量和更短的防抖时间，尽快收敛到当前负载所需的频率；
+    /// 配置值保持不变，预热结束后自动恢复
+    pub fn set_warmup(&mut self, active: bool) {
+        self.warmup = active;
+    }
+
+    pub fn is_warming_up(&self) -> bool {
+        self.warmup
+    }
+
+    /// 本次调频使用的余量，预热期间叠加额外余量
+    pub fn effective_margin(&self) -> u32 {
+        if self.warmup {
+            (self.margin + WARMUP_MARGIN_BONUS).min(100)
+        } else {
+            self.margin
+        }
+    }
+
+    /// 本次调频使用的升频和降频防抖时间，预热期间缩短
+    pub fn effective_debounce_times(&self) -> (u64, u64) {
+        if self.warmup {
+            (
+                self.up_debounce_time / WARMUP_DEBOUNCE_DIVISOR,
+                self.down_debounce_time / WARMUP_DEBOUNCE_DIVISOR,
+            )
+        } else {
+            (self.up_debounce_time, self.down_debounce_time)
+        }
+    }
+
     /// 设置激进降频开关
     pub fn set_aggressive_down(&mut self, enable: bool) {
         self.aggressive_down = enable;
     }
 
+    /// 设置激进降频的负载下限
+    pub fn set_aggressive_down_floor(&mut self, floor: i32) {
+        self.aggressive_down_floor = floor.clamp(0, 100);
+    }
+
+    /// 本次采样是否走快速降频路径
+    ///
+    /// 开启激进降频且负载低于下限时跳过降频防抖并允许跨多个档位降频，
+    /// 否则降频需满足防抖时间且每次只降一个档位
+    pub fn is_fast_down(&self, load: i32) -> bool {
+        self.aggressive_down && load < self.aggressive_down_floor
+    }
+
     /// 设置采样间隔
     pub fn set_sampling_interval(&mut self, interval: u64) {
         self.sampling_interval = interval;
@@ -54,7 +133,18 @@ impl FrequencyStrategy {
 
     /// 更新最后调整时间
     pub fn update_last_adjustment_time(&mut self, time: u64) {
-        self.last_adjustment_time = time;
+        self.last_adjustment_time = Some(time);
+    }
+
+    /// 清除最后调整时间，下一次调整不受防抖限制
+    pub fn reset_last_adjustment_time(&mut self) {
+        self.last_adjustment_time = None;
+    }
+
+    /// 距上次调整经过的时间（毫秒），尚未调整过时返回None
+    pub fn since_last_adjustment(&self, current_time: u64) -> Option<u64> {
+        self.last_adjustment_time
+            .map(|last| current_time.saturating_sub(last))
     }
 
     /// 设置升频延迟
@@ -62,6 +152,79 @@ impl FrequencyStrategy {
         self.up_debounce_time = delay;
     }
 
+    /// 设置降频保持所需的连续高负载采样数
+    pub fn set_hold_samples(&mut self, samples: u32) {
+        self.hold_samples = samples;
+    }
+
+    /// 记录一次采样的调频方向，返回是否应忽略本次降频
+    ///
+    /// 前N个采样都未要求降频时，单个降频采样（加载画面、场景切换造成的瞬时低负载）被忽略，
+    /// 避免降频后再付出升频防抖的代价；连续两个降频采样时第二个正常执行
+    pub fn should_hold_down(&mut self, is_decreasing: bool) -> bool {
+        if !is_decreasing {
+            self.high_streak = self.high_streak.saturating_add(1);
+            self.held_last = false;
+            return false;
+        }
+
+        let hold =
+            self.hold_samples > 0 && !self.held_last && self.high_streak >= self.hold_samples;
+        self.held_last = hold;
+        if !hold {
+            self.high_streak = 0;
+        }
+        hold
+    }
+
+    /// 空闲状态打断连续高负载计数
+    pub fn reset_hold(&mut self) {
+        self.high_streak = 0;
+        self.held_last = false;
+    }
+
+    /// 设置触发紧急升频所需的连续满载采样数，0表示关闭
+    pub fn set_emergency_samples(&mut self, samples: u32) {
+        self.emergency_samples = samples;
+        if samples == 0 {
+            self.saturated_streak = 0;
+        }
+    }
+
+    /// 记录一次采样的负载，返回紧急升频状态的变化（`Some(true)` 进入，`Some(false)` 退出）
+    ///
+    /// 在频率表最高档位连续满载达到设定的采样数后进入，负载回落或功能关闭后退出
+    pub fn update_emergency(&mut self, load: i32, at_table_max: bool) -> Option<bool> {
+        if self.emergency {
+            if self.emergency_samples == 0 || load < EMERGENCY_EXIT_LOAD {
+                return self.end_emergency().then_some(false);
+            }
+            return None;
+        }
+
+        if self.emergency_samples == 0 || load < EMERGENCY_LOAD || !at_table_max {
+            self.saturated_streak = 0;
+            return None;
+        }
+        self.saturated_streak = self.saturated_streak.saturating_add(1);
+        if self.saturated_streak >= self.emergency_samples {
+            self.emergency = true;
+            return Some(true);
+        }
+        None
+    }
+
+    /// 结束紧急升频并清空满载计数，返回之前是否处于紧急升频状态
+    pub fn end_emergency(&mut self) -> bool {
+        self.saturated_streak = 0;
+        std::mem::take(&mut self.emergency)
+    }
+
+    /// 是否处于紧急升频状态
+    pub fn is_emergency(&self) -> bool {
+        self.emergency
+    }
+
     /// 设置防抖时间（升频和降频）
     pub fn set_debounce_times(&mut self, up_time: u64, down_time: u64) {
         self.up_debounce_time = up_time;
@@ -1,13 +1,34 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use anyhow::Result;
-use log::{debug, warn};
+use log::{debug, error, info, warn};
 
 use crate::{
-    datasource::file_path::*,
+    datasource::{
+        config_parser::{
+            ConflictPolicy, CurvePoint, DcsSnapPolicy, DdrConfig, SAMPLING_INTERVAL_RANGE,
+            V2TuningConfig,
+        },
+        file_path::*,
+    },
     model::{
-        ddr_manager::DdrManager, frequency_manager::FrequencyManager,
-        frequency_strategy::FrequencyStrategy, idle_manager::IdleManager,
+        conflict_detector::release_all_owned_nodes,
+        ddr_manager::DdrManager,
+        delivery_stats::DeliveryTracker,
+        devfreq_clamp::DevfreqClamp,
+        freq::{Freq, FreqUnit},
+        freq_curve::FreqCurve,
+        frequency_manager::FrequencyManager,
+        frequency_strategy::FrequencyStrategy,
+        idle_manager::IdleManager,
+        load_analyzer::LoadAnalyzer,
+        mode_state::{ModeSource, ModeState},
+        thermal_cap::ThermalCapDetector,
+    },
+    utils::{
+        file_operate::{FileClass, write_file},
+        mode_notify::notify_mode_change,
+        status_report::{flush_status, update_status},
     },
 };
 
@@ -28,25 +49,67 @@ pub struct GPU {
     pub ddr_manager: DdrManager,
     /// 空闲状态管理器
     pub idle_manager: IdleManager,
+    /// 内核温控限频检测器
+    pub thermal_cap: ThermalCapDetector,
+    /// Mali devfreq 下限辅助写入
+    pub devfreq_clamp: DevfreqClamp,
+    /// 请求频率与实际频率的交付统计
+    pub delivery: DeliveryTracker,
+    /// 负载区间分析器
+    pub load_analyzer: LoadAnalyzer,
+    /// 模式的频率上限，`None` 表示不限制
+    max_freq: Option<Freq>,
     /// GPU版本相关
     pub gpuv2: bool,
-    pub v2_supported_freqs: Vec<i64>,
+    pub v2_supported_freqs: Vec<Freq>,
     /// DCS相关
     pub dcs_enable: bool,
     pub need_dcs: bool,
+    /// 不触发DCS核心缩减的最低频率
+    dcs_floor_freq: Option<Freq>,
+    /// 游戏模式下是否允许选择会触发核心缩减的频率
+    allow_dcs_in_game: bool,
+    /// 目标频率落入核心缩减区间时的处理方式
+    dcs_snap_policy: DcsSnapPolicy,
     /// 游戏模式
     pub gaming_mode: bool,
     /// 精确模式
     pub precise: bool,
     /// 当前工作模式
-    current_mode: String,
+    mode_state: ModeState,
     /// 自适应采样相关
     adaptive_sampling_enabled: bool,
     min_adaptive_interval: u64,
     max_adaptive_interval: u64,
     last_load: i32,
+    /// 严格写入模式
+    strict_writes: bool,
+    write_failure_limit: u32,
+    /// 受控节点被外部修改时的处理方式
+    conflict_policy: ConflictPolicy,
+    /// 系统更新期间进入旁路模式
+    update_bypass: bool,
+    /// 检测到GPU故障时回退到内核调频
+    fault_rollback: bool,
+    /// 固定频率策略，0表示频率表中间档位
+    pinned_freq: Option<Freq>,
+    /// 游戏规则的负载-频率曲线
+    freq_curve: Option<FreqCurve>,
+    /// 已应用的gpufreqv2调节项
+    v2_tuning: V2TuningConfig,
+    /// 旁路模式原因，Some表示调速器已将频率控制交还内核
+    bypass_reason: Option<String>,
+    /// 因超出支持范围而被丢弃的当前频率读数
+    rejected_freq_samples: u64,
+    /// 已加载的频率表代数
+    table_generation: u64,
+    /// 启动后的预热时长
+    warmup: Duration,
 }
 
+/// 当前频率读数允许超出已知频率范围的比例（百分比），兼容驱动上报时的取整误差
+const FREQ_READING_TOLERANCE_PERCENT: i64 = 5;
+
 impl GPU {
     pub fn new() -> Self {
         Self {
@@ -54,29 +117,84 @@ impl GPU {
             frequency_strategy: FrequencyStrategy::new(500, 500),
             ddr_manager: DdrManager::new(),
             idle_manager: IdleManager::new(),
+            thermal_cap: ThermalCapDetector::new(),
+            devfreq_clamp: DevfreqClamp::default(),
+            delivery: DeliveryTracker::new(),
+            load_analyzer: LoadAnalyzer::default(),
+            max_freq: None,
             gpuv2: false,
             v2_supported_freqs: Vec::new(),
             dcs_enable: false,
             need_dcs: false,
+            dcs_floor_freq: None,
+            allow_dcs_in_game: false,
+            dcs_snap_policy: DcsSnapPolicy::Auto,
             gaming_mode: false,
             precise: false,
-            current_mode: String::new(),
+            mode_state: ModeState::default(),
             adaptive_sampling_enabled: false,
             min_adaptive_interval: 2,
             max_adaptive_interval: 20,
             last_load: 0,
+            strict_writes: false,
+            write_failure_limit: 10,
+            conflict_policy: ConflictPolicy::Log,
+            update_bypass: false,
+            fault_rollback: false,
+            pinned_freq: None,
+            freq_curve: None,
+            v2_tuning: V2TuningConfig::default(),
+            bypass_reason: None,
+            rejected_freq_samples: 0,
+            table_generation: 0,
+            warmup: Duration::ZERO,
         }
     }
 
     // 频率管理相关 - 使用 Deref 模式减少样板代码
-    pub fn get_cur_freq(&self) -> i64 {
+    pub fn get_cur_freq(&self) -> Freq {
         self.frequency_manager.cur_freq
     }
 
-    pub fn set_cur_freq(&mut self, cur_freq: i64) {
+    pub fn set_cur_freq(&mut self, cur_freq: Freq) {
         self.frequency_manager.cur_freq = cur_freq;
     }
 
+    /// 已知的频率范围（频率表与驱动支持的频率合并）
+    fn known_freq_range(&self) -> Option<(Freq, Freq)> {
+        self.frequency_manager
+            .config_list
+            .iter()
+            .chain(self.v2_supported_freqs.iter())
+            .copied()
+            .filter(|freq| freq.is_positive())
+            .fold(None, |range, freq| match range {
+                None => Some((freq, freq)),
+                Some((min, max)) => Some((min.min(freq), max.max(freq))),
+            })
+    }
+
+    /// 检查从驱动读取的当前频率是否可信
+    ///
+    /// 读数为0或超出已知频率范围时丢弃并计数，调用方应继续使用上一次的有效值
+    pub fn accept_freq_reading(&mut self, freq: Freq) -> bool {
+        let valid = freq.is_positive()
+            && self.known_freq_range().is_none_or(|(min, max)| {
+                let tolerance = max.percent(FREQ_READING_TOLERANCE_PERCENT).khz();
+                freq.khz() >= min.khz() - tolerance && freq.khz() <= max.khz() + tolerance
+            });
+
+        if !valid {
+            self.rejected_freq_samples += 1;
+        }
+        valid
+    }
+
+    /// 被丢弃的当前频率读数总数
+    pub fn rejected_freq_samples(&self) -> u64 {
+        self.rejected_freq_samples
+    }
+
     // 将频率管理方法直接暴露为引用，减少委托
     pub fn frequency(&self) -> &FrequencyManager {
         &self.frequency_manager
@@ -87,13 +205,17 @@ impl GPU {
     }
 
     // 保留最常用的快捷方法
-    pub fn get_max_freq(&self) -> i64 {
+    pub fn get_max_freq(&self) -> Freq {
         self.frequency_manager.get_max_freq()
     }
 
-    pub fn get_min_freq(&self) -> i64 {
+    pub fn get_min_freq(&self) -> Freq {
         self.frequency_manager.get_min_freq()
     }
+    pub fn frequency_strategy(&self) -> &FrequencyStrategy {
+        &self.frequency_strategy
+    }
+
     pub fn frequency_strategy_mut(&mut self) -> &mut FrequencyStrategy {
         &mut self.frequency_strategy
     }
@@ -106,6 +228,20 @@ impl GPU {
         &mut self.ddr_manager
     }
 
+    /// 更新DDR策略，同时配置负载区间划分
+    pub fn set_ddr_policy(&mut self, policy: DdrConfig) {
+        self.load_analyzer
+            .configure(&policy.zone_thresholds, policy.zone_hysteresis);
+        self.ddr_manager.set_bandwidth_policy(policy);
+    }
+
+    /// 游戏模式下GPU频率对应的基础DDR档位（带宽调整前）
+    pub fn base_ddr_opp(&self, freq: Freq) -> i64 {
+        self.ddr_manager
+            .zone_ddr_opp(self.load_analyzer.zone())
+            .unwrap_or_else(|| self.read_tab(TabType::FreqDram, freq))
+    }
+
     // DCS相关方法
     pub fn is_dcs_enabled(&self) -> bool {
         self.dcs_enable
@@ -119,6 +255,48 @@ impl GPU {
         );
     }
 
+    pub fn set_dcs_floor_freq(&mut self, floor: Option<Freq>) {
+        self.dcs_floor_freq = floor;
+    }
+
+    pub fn set_allow_dcs_in_game(&mut self, allow: bool) {
+        self.allow_dcs_in_game = allow;
+    }
+
+    pub fn allows_dcs_in_game(&self) -> bool {
+        self.allow_dcs_in_game
+    }
+
+    pub fn dcs_snap_policy(&self) -> DcsSnapPolicy {
+        self.dcs_snap_policy
+    }
+
+    pub fn set_dcs_snap_policy(&mut self, policy: DcsSnapPolicy) {
+        if self.dcs_snap_policy != policy {
+            info!("DCS snap policy set to {policy:?}");
+            self.dcs_snap_policy = policy;
+        }
+    }
+
+    /// 调频算法可选择的最低频率
+    ///
+    /// 按 `dcs_snap_policy` 决定是否避开会触发DCS核心缩减的频率，默认仅在游戏模式下避开
+    pub fn min_target_freq(&self) -> Freq {
+        let min_freq = self.get_min_freq();
+        let snap_up = match self.dcs_snap_policy {
+            DcsSnapPolicy::Auto => self.gaming_mode && !self.allow_dcs_in_game,
+            DcsSnapPolicy::SnapUp => true,
+            DcsSnapPolicy::Allow => false,
+        };
+        match self.dcs_floor_freq {
+            Some(floor) if self.dcs_enable && snap_up => {
+                let floor = self.read_freq_ge(floor);
+                floor.clamp(min_freq, self.get_max_freq())
+            }
+            _ => min_freq,
+        }
+    }
+
     // 游戏模式相关方法
     pub fn is_gaming_mode(&self) -> bool {
         self.gaming_mode
@@ -129,17 +307,15 @@ impl GPU {
 
         if gaming_mode {
             // 设置游戏模式下的DDR频率
-            let freq_to_use = if self.get_cur_freq() > 0 {
+            let freq_to_use = if self.get_cur_freq().is_positive() {
                 self.get_cur_freq()
-            } else if !self.get_config_list().is_empty() {
-                self.get_config_list()[0]
             } else {
-                0
+                self.get_min_freq()
             };
 
             let mut ddr_opp = 999; // 默认自动模式
-            if freq_to_use > 0 {
-                let config_ddr_opp = self.read_tab(TabType::FreqDram, freq_to_use);
+            if freq_to_use.is_positive() {
+                let config_ddr_opp = self.base_ddr_opp(freq_to_use);
                 if config_ddr_opp > 0 || config_ddr_opp == DDR_HIGHEST_FREQ {
                     ddr_opp = config_ddr_opp;
                 }
@@ -166,18 +342,350 @@ impl GPU {
         self.precise = precise;
     }
 
-    /// 设置当前工作模式
-    pub fn set_current_mode(&mut self, mode: String) {
-        self.current_mode = mode;
+    /// 切换当前工作模式，模式名称或来源变化时同步 current_mode 文件和运行状态
+    pub fn set_mode(&mut self, mode: &str, source: ModeSource) {
+        if self.mode_state.name == mode && self.mode_state.source == source {
+            return;
+        }
+
+        self.mode_state = ModeState::new(mode, source);
+        info!("Current mode: {mode} (source: {source})");
+
+        if let Err(e) = write_file(CURRENT_MODE_PATH, mode.as_bytes(), 1024, FileClass::State) {
+            warn!("Failed to write current_mode file: {e}");
+        }
+        let state = self.mode_state.clone();
+        update_status(|s| {
+            s.mode = state.name;
+            s.mode_source = Some(state.source);
+            s.mode_since = state.since;
+        });
+        notify_mode_change(mode, source);
     }
 
-    /// 获取当前工作模式
+    /// 获取当前工作模式名称
     pub fn current_mode(&self) -> &str {
-        &self.current_mode
+        &self.mode_state.name
+    }
+
+    /// 获取当前工作模式状态
+    pub fn mode_state(&self) -> &ModeState {
+        &self.mode_state
+    }
+
+    /// 设置严格写入模式
+    pub fn set_strict_writes(&mut self, strict: bool, failure_limit: u32) {
+        self.strict_writes = strict;
+        self.write_failure_limit = failure_limit.max(1);
+    }
+
+    /// 严格写入模式和连续失败上限
+    pub fn strict_writes(&self) -> (bool, u32) {
+        (self.strict_writes, self.write_failure_limit)
+    }
+
+    pub fn set_conflict_policy(&mut self, policy: ConflictPolicy) {
+        self.conflict_policy = policy;
+    }
+
+    pub fn conflict_policy(&self) -> ConflictPolicy {
+        self.conflict_policy
+    }
+
+    pub fn set_update_bypass(&mut self, enabled: bool) {
+        self.update_bypass = enabled;
+    }
+
+    pub fn is_update_bypass_enabled(&self) -> bool {
+        self.update_bypass
+    }
+
+    pub fn set_fault_rollback(&mut self, enabled: bool) {
+        self.fault_rollback = enabled;
+    }
+
+    pub fn is_fault_rollback_enabled(&self) -> bool {
+        self.fault_rollback
+    }
+
+    pub fn v2_tuning(&self) -> V2TuningConfig {
+        self.v2_tuning
+    }
+
+    /// 设置gpufreqv2调节项，仅在设置变化时写入；驱动类型尚未检测时在检测到v2驱动后写入
+    pub fn set_v2_tuning(&mut self, tuning: V2TuningConfig) {
+        if tuning == self.v2_tuning {
+            return;
+        }
+        self.v2_tuning = tuning;
+        if self.gpuv2 {
+            self.frequency_manager.apply_v2_tuning(&tuning);
+        }
+    }
+
+    /// 设置固定频率策略，`None` 恢复按负载调频
+    pub fn set_pinned_freq(&mut self, freq: Option<Freq>) {
+        if self.pinned_freq == freq {
+            return;
+        }
+        match freq {
+            Some(Freq::ZERO) => info!("Pinned policy: holding the middle frequency"),
+            Some(freq) => info!("Pinned policy: holding {freq}KHz"),
+            None => info!("Pinned policy disabled, resuming load-based scaling"),
+        }
+        self.pinned_freq = freq;
+        update_status(|s| s.pinned_freq = freq);
+    }
+
+    /// 设置负载-频率曲线，空列表或校验失败时恢复调频公式
+    pub fn set_freq_curve(&mut self, points: &[CurvePoint]) {
+        let curve = if points.is_empty() {
+            None
+        } else {
+            FreqCurve::new(points)
+                .inspect_err(|e| warn!("Ignoring frequency curve: {e}"))
+                .ok()
+        };
+        if self.freq_curve == curve {
+            return;
+        }
+        if curve.is_some() {
+            info!(
+                "Frequency curve enabled with {} breakpoint(s)",
+                points.len()
+            );
+        } else {
+            info!("Frequency curve disabled, using the margin formula");
+        }
+        self.freq_curve = curve;
+        update_status(|s| s.freq_curve = self.freq_curve.is_some());
+    }
+
+    /// 当前生效的负载-频率曲线
+    pub fn freq_curve(&self) -> Option<&FreqCurve> {
+        self.freq_curve.as_ref()
+    }
+
+    /// 设置叠加在频率表电压上的偏移，`None` 恢复频率表电压
+    ///
+    /// 偏移变化后立即按当前频率重新写入一次，退出游戏时恢复的电压无需等到下一次调频才生效
+    pub fn set_volt_offset(&mut self, offset: Option<i64>) {
+        let offset = offset.unwrap_or(0);
+        if self.frequency_manager.volt_offset == offset {
+            return;
+        }
+        if offset == 0 {
+            info!("Voltage offset cleared, using freq table voltages");
+        } else {
+            info!("Voltage offset set to {offset:+}");
+        }
+        self.frequency_manager.volt_offset = offset;
+        update_status(|s| s.volt_offset = (offset != 0).then_some(offset));
+
+        if !self.frequency_manager.cur_freq.is_positive() || self.is_bypassed() {
+            return;
+        }
+        self.frequency_manager.gen_cur_volt();
+        if let Err(e) = self
+            .frequency_manager
+            .write_freq(self.need_dcs, self.is_idle())
+        {
+            warn!("Failed to apply voltage offset: {e}");
+        }
+    }
+
+    /// 设置模式的电压上限，非正数视为不限制
+    pub fn set_max_volt(&mut self, max_volt: Option<i64>) {
+        let max_volt = match max_volt {
+            Some(v) if v <= 0 => {
+                warn!("Ignoring invalid max_volt={v}, must be positive");
+                None
+            }
+            other => other,
+        };
+        if self.frequency_manager.max_volt == max_volt {
+            return;
+        }
+        match max_volt {
+            Some(cap) => info!("Voltage ceiling set to {cap}"),
+            None => info!("Voltage ceiling cleared"),
+        }
+        self.frequency_manager.max_volt = max_volt;
+
+        if !self.frequency_manager.cur_freq.is_positive() || self.is_bypassed() {
+            return;
+        }
+        self.frequency_manager.gen_cur_volt();
+        if let Err(e) = self
+            .frequency_manager
+            .write_freq(self.need_dcs, self.is_idle())
+        {
+            warn!("Failed to apply voltage ceiling: {e}");
+        }
+    }
+
+    /// 设置模式的频率上限（KHz），非正数视为不限制
+    pub fn set_max_freq(&mut self, max_freq: Option<i64>) {
+        let max_freq = match max_freq {
+            Some(v) if v <= 0 => {
+                warn!("Ignoring invalid max_freq={v}, must be positive");
+                None
+            }
+            other => other.map(Freq::from_khz),
+        };
+        if self.max_freq == max_freq {
+            return;
+        }
+        match max_freq {
+            Some(cap) => info!("Frequency ceiling set to {cap}KHz"),
+            None => info!("Frequency ceiling cleared"),
+        }
+        self.max_freq = max_freq;
+        update_status(|s| s.max_freq = max_freq);
+    }
+
+    /// 模式设置的频率上限
+    pub fn max_freq(&self) -> Option<Freq> {
+        self.max_freq
+    }
+
+    /// 调频目标的上限：频率表最高档位，设置了频率上限时为不高于该上限的最高档位
+    pub fn max_target_freq(&self) -> Freq {
+        match self.max_freq {
+            Some(cap) => self.frequency_manager.read_freq_le(cap),
+            None => self.get_max_freq(),
+        }
+    }
+
+    /// 固定频率策略下的目标频率
+    pub fn pinned_target(&self) -> Option<Freq> {
+        self.pinned_freq.map(|freq| {
+            if freq.is_positive() {
+                freq
+            } else {
+                self.get_middle_freq()
+            }
+        })
+    }
+
+    /// 检查频率写入是否持续失败；严格写入模式下超过阈值时进入旁路模式
+    pub fn check_write_health(&mut self) {
+        let failures = self.frequency_manager.consecutive_write_failures();
+        if !self.strict_writes || self.is_bypassed() || failures < self.write_failure_limit {
+            return;
+        }
+
+        let message = format!("GPU frequency nodes rejected {failures} consecutive writes");
+        error!("{message}, governor is not in control");
+        update_status(|s| {
+            s.error = Some(message.clone());
+            s.write_failures = failures;
+        });
+        self.enter_bypass(&message);
+    }
+
+    /// 游戏模式下将Mali devfreq的频率下限同步为当前目标频率，其他情况下恢复原下限
+    pub fn sync_devfreq_clamp(&mut self, freq: Freq) {
+        if self.gaming_mode && !self.is_bypassed() {
+            self.devfreq_clamp.apply(freq);
+        } else {
+            self.devfreq_clamp.release();
+        }
+    }
+
+    /// 是否处于旁路模式
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass_reason.is_some()
+    }
+
+    /// 进入旁路模式的原因
+    pub fn bypass_reason(&self) -> Option<&str> {
+        self.bypass_reason.as_deref()
+    }
+
+    /// 进入旁路模式：将频率控制交还内核，调频循环停止写入
+    pub fn enter_bypass(&mut self, reason: &str) {
+        if self.is_bypassed() {
+            return;
+        }
+
+        warn!("Entering bypass mode: {reason}");
+        if let Err(e) = self.frequency_manager.release_to_kernel() {
+            warn!("Failed to release GPU frequency control: {e}");
+        }
+        self.devfreq_clamp.release();
+        if self.is_ddr_freq_fixed()
+            && let Err(e) = self.set_ddr_freq(999)
+        {
+            warn!("Failed to restore auto DDR mode: {e}");
+        }
+
+        self.mark_bypassed(reason);
+    }
+
+    /// 让出控制权：与旁路模式相同地停止调频，但不改动节点，保留其他工具写入的值
+    pub fn yield_control(&mut self, reason: &str) {
+        if self.is_bypassed() {
+            return;
+        }
+
+        warn!("Yielding GPU control: {reason}");
+        release_all_owned_nodes();
+        self.mark_bypassed(reason);
+    }
+
+    fn mark_bypassed(&mut self, reason: &str) {
+        self.bypass_reason = Some(reason.to_string());
+        update_status(|s| {
+            s.bypass = true;
+            s.bypass_reason = Some(reason.to_string());
+        });
+        if let Err(e) = flush_status() {
+            warn!("Failed to write status file: {e}");
+        }
+    }
+
+    /// 退出旁路模式，恢复调频
+    pub fn exit_bypass(&mut self) {
+        if self.bypass_reason.take().is_none() {
+            return;
+        }
+
+        info!("Leaving bypass mode, governor resumes control");
+        self.frequency_manager.reset_write_failures();
+        update_status(|s| {
+            s.bypass = false;
+            s.bypass_reason = None;
+            s.error = None;
+            s.write_failures = 0;
+        });
+    }
+
+    /// 启动后的预热时长，0表示不预热
+    pub fn warmup(&self) -> Duration {
+        self.warmup
+    }
+
+    /// 设置紧急升频，未允许时关闭
+    pub fn set_emergency_ramp(&mut self, allow: bool, samples: u32) {
+        self.frequency_strategy
+            .set_emergency_samples(if allow { samples } else { 0 });
+    }
+
+    pub fn set_warmup(&mut self, warmup: Duration) {
+        self.warmup = warmup;
+    }
+
+    pub fn table_generation(&self) -> u64 {
+        self.table_generation
+    }
+
+    pub fn set_table_generation(&mut self, generation: u64) {
+        self.table_generation = generation;
     }
 
     /// 读取映射表值 - 使用更简洁的模式匹配
-    pub fn read_tab(&self, tab_type: TabType, freq: i64) -> i64 {
+    pub fn read_tab(&self, tab_type: TabType, freq: Freq) -> i64 {
         match tab_type {
             TabType::FreqVolt => self.frequency_manager.read_freq_volt(freq),
             TabType::FreqDram => self.frequency_manager.read_freq_dram(freq),
@@ -185,7 +693,7 @@ impl GPU {
     }
 
     /// 替换映射表 - 使用更简洁的模式匹配
-    pub fn replace_tab(&mut self, tab_type: TabType, tab: HashMap<i64, i64>) {
+    pub fn replace_tab(&mut self, tab_type: TabType, tab: HashMap<Freq, i64>) {
         match tab_type {
             TabType::FreqVolt => self.frequency_manager.replace_freq_volt_tab(tab),
             TabType::FreqDram => self.frequency_manager.replace_freq_dram_tab(tab),
@@ -199,18 +707,29 @@ impl GPU {
 
     pub fn set_gpuv2(&mut self, gpuv2: bool) {
         self.gpuv2 = gpuv2;
+        self.frequency_manager.gpuv2 = gpuv2;
+        if self.ddr_manager.gpuv2 != gpuv2 {
+            self.ddr_manager.gpuv2 = gpuv2;
+            self.ddr_manager.invalidate_ddr_freq_table();
+        }
+        if gpuv2 {
+            self.frequency_manager.apply_v2_tuning(&self.v2_tuning);
+        }
     }
 
-    pub fn get_v2_supported_freqs(&self) -> Vec<i64> {
+    pub fn get_v2_supported_freqs(&self) -> Vec<Freq> {
         self.v2_supported_freqs.clone()
     }
 
-    pub fn set_v2_supported_freqs(&mut self, freqs: Vec<i64>) {
+    /// 保存v2驱动支持的频率列表及频率表使用的单位
+    pub fn set_v2_supported_freqs(&mut self, freqs: Vec<Freq>, unit: FreqUnit) {
+        self.frequency_manager.v2_supported_freqs = freqs.clone();
+        self.frequency_manager.v2_table_unit = unit;
         self.v2_supported_freqs = freqs;
     }
 
     /// 检查频率是否被v2驱动支持
-    pub fn is_freq_supported_by_v2_driver(&self, freq: i64) -> bool {
+    pub fn is_freq_supported_by_v2_driver(&self, freq: Freq) -> bool {
         if !self.gpuv2 || self.v2_supported_freqs.is_empty() {
             // 如果不是v2 driver或者没有读取到支持的频率，则不进行验证
             true
@@ -222,22 +741,26 @@ impl GPU {
 
     /// 快捷方法组合 - 提供更符合 Rust 习惯的API
     // 最常用的频率操作
-    pub fn get_freq_by_index(&self, idx: i64) -> i64 {
+    pub fn get_freq_by_index(&self, idx: i64) -> Freq {
         self.frequency_manager.get_freq_by_index(idx)
     }
 
-    pub fn get_middle_freq(&self) -> i64 {
+    pub fn get_middle_freq(&self) -> Freq {
         self.frequency_manager.get_middle_freq()
     }
 
-    pub fn get_config_list(&self) -> Vec<i64> {
+    pub fn get_config_list(&self) -> Vec<Freq> {
         self.frequency_manager.get_config_list()
     }
 
-    pub fn set_config_list(&mut self, config_list: Vec<i64>) {
+    pub fn set_config_list(&mut self, config_list: Vec<Freq>) {
         self.frequency_manager.set_config_list(config_list);
     }
 
+    pub fn table_freqs(&self) -> Vec<Freq> {
+        self.frequency_manager.table_freqs()
+    }
+
     // 最常用的空闲状态操作
     pub fn is_idle(&self) -> bool {
         self.idle_manager.is_idle()
@@ -275,6 +798,8 @@ impl GPU {
         fixed_interval: u64,
     ) {
         if enabled {
+            let (min_interval, max_interval) =
+                normalize_adaptive_bounds(min_interval, max_interval);
             // 启用自适应采样，初始设置为最小间隔
             self.frequency_strategy.set_sampling_interval(min_interval);
             self.adaptive_sampling_enabled = true;
@@ -288,6 +813,12 @@ impl GPU {
         }
     }
 
+    /// 自适应采样的间隔范围，未启用时为 `None`
+    pub fn adaptive_sampling(&self) -> Option<(u64, u64)> {
+        self.adaptive_sampling_enabled
+            .then_some((self.min_adaptive_interval, self.max_adaptive_interval))
+    }
+
     /// 根据GPU负载动态调整采样间隔
     pub fn adjust_sampling_interval_by_load(&mut self, current_load: i32) {
         if !self.adaptive_sampling_enabled {
@@ -310,7 +841,7 @@ impl GPU {
             self.max_adaptive_interval - (ratio * range as f64) as u64
         };
 
-        // 确保在有效范围内
+        // 确保在有效范围内（上下限已在设置时校正，不会颠倒）
         let new_interval =
             new_interval.clamp(self.min_adaptive_interval, self.max_adaptive_interval);
 
@@ -319,26 +850,26 @@ impl GPU {
     }
 
     // 添加缺失的频率管理委托方法
-    pub fn read_freq_ge(&self, freq: i64) -> i64 {
+    pub fn read_freq_ge(&self, freq: Freq) -> Freq {
         self.frequency_manager.read_freq_ge(freq)
     }
 
-    pub fn read_freq_le(&self, freq: i64) -> i64 {
+    pub fn read_freq_le(&self, freq: Freq) -> Freq {
         self.frequency_manager.read_freq_le(freq)
     }
 
     /// 找到最接近目标频率的索引
-    pub fn find_closest_freq_index(&self, target_freq: i64) -> i64 {
+    pub fn find_closest_freq_index(&self, target_freq: Freq) -> i64 {
         let config_list = self.get_config_list();
         if config_list.is_empty() {
             return 0;
         }
 
         let mut closest_idx = 0;
-        let mut min_diff = (config_list[0] - target_freq).abs();
+        let mut min_diff = config_list[0].abs_diff(target_freq);
 
         for (idx, &freq) in config_list.iter().enumerate() {
-            let diff = (freq - target_freq).abs();
+            let diff = freq.abs_diff(target_freq);
             if diff < min_diff {
                 min_diff = diff;
                 closest_idx = idx as i64;
@@ -352,46 +883,15 @@ impl GPU {
     pub fn adjust_gpufreq_with_updates(
         &mut self,
         rx: std::sync::mpsc::Receiver<crate::datasource::config_parser::ConfigDelta>,
+        control_rx: std::sync::mpsc::Receiver<crate::datasource::control::ControlCommand>,
     ) -> Result<()> {
         use crate::model::frequency_engine::FrequencyAdjustmentEngine;
-        FrequencyAdjustmentEngine::run_adjustment_loop(self, Some(rx))
+        FrequencyAdjustmentEngine::run_adjustment_loop(self, Some(rx), Some(control_rx))
     }
 
+    /// 应用配置增量，见 [`crate::model::config_apply::apply_delta`]
     pub fn apply_config_delta(&mut self, delta: &crate::datasource::config_parser::ConfigDelta) {
-        self.frequency_strategy.set_margin(delta.margin as u32);
-        self.frequency_strategy
-            .set_aggressive_down(delta.aggressive_down);
-        if delta.adaptive_sampling {
-            self.set_adaptive_sampling(
-                true,
-                delta.min_adaptive_interval,
-                delta.max_adaptive_interval,
-                delta.sampling_interval,
-            );
-        } else {
-            self.set_adaptive_sampling(false, 0, 0, delta.sampling_interval);
-        }
-        self.set_up_rate_delay(delta.up_rate_delay);
-        self.set_debounce_times(delta.up_rate_delay, delta.down_rate_delay);
-        self.set_gaming_mode(delta.gaming_mode);
-        if let Some(idle) = delta.idle_threshold {
-            self.idle_manager_mut().set_idle_threshold(idle);
-        }
-        // 同步模式名称（仅当提供且与当前不同）
-        if let Some(ref mode_name) = delta.mode
-            && self.current_mode != *mode_name
-        {
-            self.set_current_mode(mode_name.clone());
-            log::info!("Current mode synced to: {}", mode_name);
-        }
-        log::info!(
-            "Applied config delta: margin={} sampling={} adaptive={} gaming={} idle_threshold={:?}",
-            delta.margin,
-            delta.sampling_interval,
-            delta.adaptive_sampling,
-            delta.gaming_mode,
-            delta.idle_threshold
-        );
+        crate::model::config_apply::apply_delta(self, delta);
     }
 }
 
@@ -402,7 +902,121 @@ impl Default for GPU {
 }
 
 impl GPU {
+    pub fn idle_manager(&self) -> &IdleManager {
+        &self.idle_manager
+    }
+
     pub fn idle_manager_mut(&mut self) -> &mut IdleManager {
         &mut self.idle_manager
     }
 }
+
+/// 校正自适应采样的间隔范围：超出允许范围的值截断到范围内，上限低于下限时使用下限
+///
+/// ```
+/// use gpugovernor::{
+///     datasource::config_parser::SAMPLING_INTERVAL_RANGE, model::gpu::normalize_adaptive_bounds,
+/// };
+///
+/// let (lower, upper) = SAMPLING_INTERVAL_RANGE;
+/// assert_eq!(normalize_adaptive_bounds(20, 60), (20, 60));
+/// assert_eq!(normalize_adaptive_bounds(60, 20), (60, 60));
+/// assert_eq!(normalize_adaptive_bounds(0, 5000), (lower, upper));
+/// ```
+pub fn normalize_adaptive_bounds(min_interval: u64, max_interval: u64) -> (u64, u64) {
+    let (lower, upper) = SAMPLING_INTERVAL_RANGE;
+    let min = min_interval.clamp(lower, upper);
+    let max = max_interval.clamp(lower, upper).max(min);
+    if (min, max) != (min_interval, max_interval) {
+        warn!(
+            "Adaptive sampling bounds [{min_interval}, {max_interval}]ms corrected to [{min}, {max}]ms"
+        );
+    }
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sampling_interval(gpu: &GPU) -> u64 {
+        gpu.frequency_strategy.get_sampling_interval()
+    }
+
+    fn assert_in_range(interval: u64) {
+        let (lower, upper) = SAMPLING_INTERVAL_RANGE;
+        assert!(
+            (lower..=upper).contains(&interval),
+            "{interval} out of range"
+        );
+    }
+
+    /// 从负载0跳到 `load`，返回调整后的采样间隔
+    fn interval_after_jump(gpu: &mut GPU, load: i32) -> u64 {
+        gpu.last_load = 0;
+        gpu.adjust_sampling_interval_by_load(load);
+        sampling_interval(gpu)
+    }
+
+    #[test]
+    fn inverted_bounds_use_min_as_max() {
+        let mut gpu = GPU::new();
+        gpu.set_adaptive_sampling(true, 50, 10, 16);
+        assert_eq!(
+            (gpu.min_adaptive_interval, gpu.max_adaptive_interval),
+            (50, 50)
+        );
+        for load in [0, 3, 5, 17, 30, 31, 100] {
+            assert_eq!(interval_after_jump(&mut gpu, load), 50);
+        }
+    }
+
+    #[test]
+    fn oversized_max_is_clamped() {
+        let mut gpu = GPU::new();
+        gpu.set_adaptive_sampling(true, 0, 5000, 16);
+        let (lower, upper) = SAMPLING_INTERVAL_RANGE;
+        assert_eq!(
+            (gpu.min_adaptive_interval, gpu.max_adaptive_interval),
+            (lower, upper)
+        );
+        for load in [0, 4, 5, 30, 31, 100] {
+            assert_in_range(interval_after_jump(&mut gpu, load));
+        }
+        assert_eq!(interval_after_jump(&mut gpu, 0), upper);
+    }
+
+    #[test]
+    fn equal_bounds_keep_a_fixed_interval() {
+        let mut gpu = GPU::new();
+        gpu.set_adaptive_sampling(true, 16, 16, 8);
+        assert_eq!(sampling_interval(&gpu), 16);
+        for load in [0, 4, 5, 30, 31, 100] {
+            assert_eq!(interval_after_jump(&mut gpu, load), 16);
+        }
+    }
+
+    #[test]
+    fn load_change_thresholds() {
+        let mut gpu = GPU::new();
+        gpu.set_adaptive_sampling(true, 10, 40, 16);
+
+        // 变化小于5使用上限，5起按比例插值
+        assert_eq!(interval_after_jump(&mut gpu, 4), 40);
+        assert_eq!(interval_after_jump(&mut gpu, 5), 35);
+        // 变化为30时插值结果恰好等于下限，超过30直接使用下限
+        assert_eq!(interval_after_jump(&mut gpu, 30), 10);
+        assert_eq!(interval_after_jump(&mut gpu, 31), 10);
+        for load in [-100, 0, 100] {
+            assert_in_range(interval_after_jump(&mut gpu, load));
+        }
+    }
+
+    #[test]
+    fn disabled_adaptive_sampling_keeps_fixed_interval() {
+        let mut gpu = GPU::new();
+        gpu.set_adaptive_sampling(false, 0, 0, 16);
+        gpu.adjust_sampling_interval_by_load(100);
+        assert_eq!(sampling_interval(&gpu), 16);
+    }
+}
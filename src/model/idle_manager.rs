@@ -1,23 +1,167 @@
+use std::time::{Duration, Instant};
+
+use crate::datasource::config_parser::IdleConfig;
+
+/// 学习空闲负载下限所需的低负载采样数
+const IDLE_LEARN_SAMPLES: u32 = 300;
+/// 以低负载采样的该百分位作为空闲负载下限
+const IDLE_LEARN_PERCENTILE: u32 = 90;
+
 /// 空闲状态管理器 - 负责GPU空闲状态管理
 #[derive(Clone)]
 pub struct IdleManager {
     /// 是否空闲
     pub is_idle: bool,
-    /// 空闲阈值
+    /// 空闲阈值（生效值，开启自适应时可能高于配置值）
     pub idle_threshold: i32,
+    /// 配置的空闲阈值
+    base_threshold: i32,
+    /// 是否根据负载源的空闲负载下限自动调整阈值
+    adaptive: bool,
+    /// 自适应阈值上限，高于该值的负载不参与学习
+    adaptive_max: i32,
+    /// 最低频率下各负载值的采样次数
+    histogram: Vec<u32>,
+    /// 学习到的空闲负载下限
+    learned_floor: Option<i32>,
+    /// 长时间空闲断电配置
+    power_collapse: IdleConfig,
+    /// 本轮连续空闲的开始时间
+    idle_since: Option<Instant>,
+    /// 是否已允许GPU断电
+    collapsed: bool,
+}
+
+/// 断电状态变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerTransition {
+    /// 空闲足够久，允许GPU断电
+    Collapse,
+    /// 断电期间出现负载，需要上电并短暂提高频率
+    Wake,
 }
 
 impl IdleManager {
     pub fn new() -> Self {
+        let threshold = crate::utils::constants::strategy::IDLE_THRESHOLD;
         Self {
             is_idle: false,
-            idle_threshold: crate::utils::constants::strategy::IDLE_THRESHOLD,
+            idle_threshold: threshold,
+            base_threshold: threshold,
+            adaptive: false,
+            adaptive_max: 12,
+            histogram: Vec::new(),
+            learned_floor: None,
+            power_collapse: IdleConfig::default(),
+            idle_since: None,
+            collapsed: false,
         }
     }
 
     /// 设置空闲阈值
     pub fn set_idle_threshold(&mut self, threshold: i32) {
-        self.idle_threshold = threshold;
+        self.base_threshold = threshold;
+        self.update_threshold();
+    }
+
+    /// 设置自适应空闲阈值，关闭时丢弃已学习的下限
+    pub fn set_adaptive(&mut self, enabled: bool, max_threshold: i32) {
+        let max_threshold = max_threshold.clamp(0, 100);
+        if enabled != self.adaptive || max_threshold != self.adaptive_max {
+            self.histogram.clear();
+            self.learned_floor = None;
+        }
+        self.adaptive = enabled;
+        self.adaptive_max = max_threshold;
+        self.update_threshold();
+    }
+
+    /// 是否自适应空闲阈值及其上限
+    pub fn adaptive(&self) -> (bool, i32) {
+        (self.adaptive, self.adaptive_max)
+    }
+
+    /// 学习到的空闲负载下限
+    pub fn learned_floor(&self) -> Option<i32> {
+        self.learned_floor
+    }
+
+    /// 记录一次负载采样，阈值变化时返回新的阈值
+    ///
+    /// 只统计GPU处于最低频率且负载不超过上限的采样：部分负载源在真正空闲时仍上报3-7%，
+    /// 取这些采样的高百分位作为空闲负载下限，避免固定阈值把空闲误判为有负载
+    pub fn observe(&mut self, load: i32, at_min_freq: bool) -> Option<i32> {
+        if !self.adaptive || !at_min_freq || load < 0 || load > self.adaptive_max {
+            return None;
+        }
+
+        self.histogram.resize(self.adaptive_max as usize + 1, 0);
+        self.histogram[load as usize] += 1;
+        let total: u32 = self.histogram.iter().sum();
+        if total < IDLE_LEARN_SAMPLES {
+            return None;
+        }
+
+        let rank = total * IDLE_LEARN_PERCENTILE / 100;
+        let mut seen = 0;
+        let floor = self
+            .histogram
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(0) as i32;
+        self.histogram.clear();
+        self.learned_floor = Some(floor);
+
+        let previous = self.idle_threshold;
+        self.update_threshold();
+        (self.idle_threshold != previous).then_some(self.idle_threshold)
+    }
+
+    fn update_threshold(&mut self) {
+        self.idle_threshold = match self.learned_floor {
+            Some(floor) if self.adaptive => self.base_threshold.max(floor.min(self.adaptive_max)),
+            _ => self.base_threshold,
+        };
+    }
+
+    /// 设置长时间空闲断电配置
+    pub fn set_power_collapse(&mut self, config: IdleConfig) {
+        self.power_collapse = config;
+    }
+
+    /// 长时间空闲断电配置
+    pub fn power_collapse(&self) -> &IdleConfig {
+        &self.power_collapse
+    }
+
+    /// 是否已允许GPU断电
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed
+    }
+
+    /// 记录一次采样的空闲状态，需要切换断电状态时返回对应的变化
+    pub fn track_power_collapse(&mut self, idle: bool) -> Option<PowerTransition> {
+        if !idle {
+            self.idle_since = None;
+            return std::mem::take(&mut self.collapsed).then_some(PowerTransition::Wake);
+        }
+
+        let since = *self.idle_since.get_or_insert_with(Instant::now);
+        let threshold = Duration::from_millis(self.power_collapse.power_off_after_ms);
+        if self.power_collapse.power_off && !self.collapsed && since.elapsed() >= threshold {
+            self.collapsed = true;
+            return Some(PowerTransition::Collapse);
+        }
+        None
+    }
+
+    /// 断电状态已被外部恢复（退出、旁路），不再需要上电
+    pub fn clear_power_collapse(&mut self) {
+        self.idle_since = None;
+        self.collapsed = false;
     }
 
     /// 是否空闲
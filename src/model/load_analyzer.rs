@@ -0,0 +1,135 @@
+use std::{collections::VecDeque, fmt};
+
+use serde::Serialize;
+
+/// 负载平滑系数，越大越跟随最新采样
+const LOAD_SMOOTHING: f64 = 0.3;
+/// 平滑负载单次变化超过该值（百分比）时视为上升或下降
+const TREND_THRESHOLD: f64 = 1.5;
+/// 保留的最近负载采样数
+const LOAD_HISTORY_LEN: usize = 16;
+
+/// 平滑负载的变化趋势
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadTrend {
+    Rising,
+    #[default]
+    Stable,
+    Falling,
+}
+
+impl fmt::Display for LoadTrend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rising => "rising",
+            Self::Stable => "stable",
+            Self::Falling => "falling",
+        })
+    }
+}
+
+/// 负载分析器 - 对负载做平滑处理并按阈值划分负载区间
+///
+/// 区间编号从0开始，负载越高编号越大；降区间时需要低于阈值一个回差，避免在边界附近抖动
+#[derive(Clone, Debug)]
+pub struct LoadAnalyzer {
+    /// 平滑后的负载
+    smoothed: f64,
+    /// 当前负载区间
+    zone: usize,
+    /// 区间分界阈值（百分比，升序）
+    thresholds: Vec<i32>,
+    /// 降区间回差（百分比）
+    hysteresis: i32,
+    /// 平滑负载的变化趋势
+    trend: LoadTrend,
+    /// 最近的原始负载采样，最早的在前
+    history: VecDeque<i32>,
+}
+
+impl LoadAnalyzer {
+    pub fn new(thresholds: &[i32], hysteresis: i32) -> Self {
+        let mut analyzer = Self {
+            smoothed: 0.0,
+            zone: 0,
+            thresholds: Vec::new(),
+            hysteresis: 0,
+            trend: LoadTrend::Stable,
+            history: VecDeque::with_capacity(LOAD_HISTORY_LEN),
+        };
+        analyzer.configure(thresholds, hysteresis);
+        analyzer
+    }
+
+    /// 更新区间阈值和回差
+    pub fn configure(&mut self, thresholds: &[i32], hysteresis: i32) {
+        let mut thresholds = thresholds.to_vec();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        if thresholds != self.thresholds {
+            self.thresholds = thresholds;
+            self.zone = self.zone.min(self.thresholds.len());
+        }
+        self.hysteresis = hysteresis.max(0);
+    }
+
+    /// 记录一次负载采样，返回更新后的负载区间
+    pub fn update(&mut self, load: i32) -> usize {
+        if self.history.len() == LOAD_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(load);
+
+        let change = (load as f64 - self.smoothed) * LOAD_SMOOTHING;
+        self.smoothed += change;
+        self.trend = if change > TREND_THRESHOLD {
+            LoadTrend::Rising
+        } else if change < -TREND_THRESHOLD {
+            LoadTrend::Falling
+        } else {
+            LoadTrend::Stable
+        };
+        let load = self.smoothed.round() as i32;
+
+        let raw_zone = self.thresholds.iter().filter(|&&t| load >= t).count();
+        if raw_zone > self.zone {
+            self.zone = raw_zone;
+        } else if raw_zone < self.zone {
+            // 只有低于阈值减回差后才降低区间
+            self.zone = self
+                .thresholds
+                .iter()
+                .filter(|&&t| load >= t - self.hysteresis)
+                .count()
+                .min(self.zone);
+        }
+        self.zone
+    }
+
+    /// 当前负载区间
+    pub fn zone(&self) -> usize {
+        self.zone
+    }
+
+    /// 平滑后的负载
+    pub fn smoothed_load(&self) -> i32 {
+        self.smoothed.round() as i32
+    }
+
+    /// 平滑负载的变化趋势
+    pub fn trend(&self) -> LoadTrend {
+        self.trend
+    }
+
+    /// 最近的原始负载采样，最早的在前
+    pub fn history(&self) -> Vec<i32> {
+        self.history.iter().copied().collect()
+    }
+}
+
+impl Default for LoadAnalyzer {
+    fn default() -> Self {
+        Self::new(&[], 0)
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use crate::datasource::config_parser::MarginPhase;
+
+/// 游戏会话内的分阶段余量调度
+///
+/// 阶段按开始时间排序，会话开始后依次进入各阶段；第一个阶段开始之前沿用模式的余量
+#[derive(Clone, Debug)]
+pub struct MarginSchedule {
+    phases: Vec<MarginPhase>,
+    started: Instant,
+    /// 下一个尚未进入的阶段
+    next: usize,
+}
+
+impl MarginSchedule {
+    pub fn new(phases: &[MarginPhase]) -> Self {
+        let mut phases = phases.to_vec();
+        phases.sort_by_key(|phase| phase.after_secs);
+        Self {
+            phases,
+            started: Instant::now(),
+            next: 0,
+        }
+    }
+
+    /// 是否与给定阶段列表相同（游戏会话未变化）
+    pub fn matches(&self, phases: &[MarginPhase]) -> bool {
+        let mut sorted = phases.to_vec();
+        sorted.sort_by_key(|phase| phase.after_secs);
+        sorted == self.phases
+    }
+
+    /// 进入所有已到开始时间的阶段，返回是否进入了新阶段
+    pub fn advance(&mut self) -> bool {
+        let elapsed = self.started.elapsed();
+        let before = self.next;
+        while self
+            .phases
+            .get(self.next)
+            .is_some_and(|phase| elapsed >= Duration::from_secs(phase.after_secs))
+        {
+            self.next += 1;
+        }
+        self.next != before
+    }
+
+    /// 当前所处的阶段，`None` 表示尚未进入第一个阶段
+    pub fn current(&self) -> Option<&MarginPhase> {
+        self.next.checked_sub(1).and_then(|i| self.phases.get(i))
+    }
+
+    /// 距离下一个阶段开始的时间，已是最后一个阶段时返回 `None`
+    pub fn until_next(&self) -> Option<Duration> {
+        let phase = self.phases.get(self.next)?;
+        Some(Duration::from_secs(phase.after_secs).saturating_sub(self.started.elapsed()))
+    }
+}
@@ -0,0 +1,277 @@
+//! 模式仲裁
+//!
+//! 配置文件、控制命令和前台游戏都会请求切换模式，各线程的增量以前按到达顺序直接应用，
+//! 后到者覆盖先到者：例如游戏运行时修改 config.toml 会把游戏模式换回全局模式。
+//! 调频线程现在为每个来源保留一份请求，按固定优先级选出生效的模式：
+//!
+//! 1. 临时模式（`boost` 控制命令），到期或取消后恢复仲裁结果，由调频线程直接管理
+//! 2. 游戏模式：游戏列表、疑似游戏识别和 `game_background`，游戏离开前台时释放
+//! 3. 基础模式：config.toml 的全局模式和 `mode` 控制命令，两者中较新的请求生效
+//!
+//! 显示档案（刷新率、折叠屏、外接显示器、投屏）、充电和省电模式不是独立的模式来源，
+//! 而是叠加在生效模式参数上的配置层，按 `[display.*]` 的优先级在生成增量时合并，
+//! 这些数据源发出的增量只触发生效模式的重新生成。频率层面的限制在模式参数之后应用：
+//! `lock` 控制命令锁定的频率优先于一切，其次是温度上限和模式的 `max_freq`。
+//!
+//! 生效的来源写入 status.json 的 `mode_source`，各来源的请求写入 `mode_claims`。
+
+use std::{sync::Arc, time::Instant};
+
+use log::info;
+
+use crate::{
+    datasource::config_parser::{Config, ConfigDelta, config_delta},
+    model::mode_state::ModeSource,
+    utils::status_report::update_status,
+};
+
+/// 由内存中的配置按当前显示、充电和省电状态重新生成增量，保留模式、来源和游戏规则的参数
+pub fn regenerate(config: &Arc<Config>, delta: &ConfigDelta) -> ConfigDelta {
+    // 全局模式来源重新读取 global.mode，配置文件中的模式可能已经改变
+    let target_mode = match delta.mode_source {
+        Some(ModeSource::Config) => None,
+        _ => delta.mode.as_deref(),
+    };
+    let mut fresh = config_delta(config, target_mode);
+    if target_mode.is_some() {
+        fresh.mode = delta.mode.clone();
+    }
+    fresh.mode_source = delta.mode_source;
+    fresh.pinned_freq = delta.pinned_freq;
+    fresh.volt_offset = delta.volt_offset;
+    fresh.margin_phases = delta.margin_phases.clone();
+    fresh.freq_curve = delta.freq_curve.clone();
+    fresh
+}
+
+/// 本次提交写入的请求
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Claim {
+    Base,
+    Game,
+}
+
+/// 按优先级合并各来源的模式请求
+#[derive(Default)]
+pub struct ModeArbiter {
+    /// 基础模式请求（配置文件或控制命令）
+    base: Option<ConfigDelta>,
+    /// 游戏模式请求
+    game: Option<ConfigDelta>,
+    /// 已提交增量中最新的生成时间
+    latest: Option<Instant>,
+}
+
+impl ModeArbiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 增量是否早于已提交的增量生成
+    pub fn is_stale(&self, delta: &ConfigDelta) -> bool {
+        self.latest.is_some_and(|latest| delta.created_at < latest)
+    }
+
+    /// 当前生效的请求
+    pub fn winner(&self) -> Option<&ConfigDelta> {
+        self.game.as_ref().or(self.base.as_ref())
+    }
+
+    /// 记录一个来源的请求，返回仲裁后应生效的增量
+    ///
+    /// 未指定来源的增量（显示、充电等状态变化）和释放请求不携带新的模式，
+    /// 此时由该增量携带的配置重新生成生效的请求
+    pub fn submit(&mut self, mut delta: ConfigDelta) -> Option<ConfigDelta> {
+        self.latest = self.latest.max(Some(delta.created_at));
+        let config = Arc::clone(&delta.config);
+        let previous = self.winner_label();
+
+        let claimed = match delta.mode_source {
+            Some(ModeSource::Game) if delta.release => {
+                if self.game.take().is_some() {
+                    info!("Game mode released");
+                }
+                self.claim_base_if_empty(delta)
+            }
+            Some(ModeSource::Game) => {
+                self.game = Some(delta);
+                Some(Claim::Game)
+            }
+            Some(ModeSource::Config | ModeSource::Control) => {
+                self.base = Some(delta);
+                Some(Claim::Base)
+            }
+            Some(ModeSource::Boost) | None => {
+                if self.winner().is_none() {
+                    delta.mode_source = Some(ModeSource::Config);
+                    self.claim_base_if_empty(delta)
+                } else {
+                    None
+                }
+            }
+        };
+
+        let winner_claim = if self.game.is_some() {
+            Claim::Game
+        } else {
+            Claim::Base
+        };
+        let winner = match winner_claim {
+            Claim::Game => self.game.as_mut(),
+            Claim::Base => self.base.as_mut(),
+        }?;
+        if claimed != Some(winner_claim) {
+            *winner = regenerate(&config, winner);
+        }
+
+        let current = self.winner_label();
+        if current != previous
+            && let Some((mode, source)) = &current
+        {
+            info!("Mode arbitration: {mode} ({source}) takes control");
+        }
+        self.publish_claims();
+        self.winner().cloned()
+    }
+
+    /// 没有基础模式请求时以全局模式作为基础模式
+    fn claim_base_if_empty(&mut self, mut delta: ConfigDelta) -> Option<Claim> {
+        if self.base.is_some() {
+            return None;
+        }
+        delta.mode_source = Some(ModeSource::Config);
+        delta.release = false;
+        self.base = Some(delta);
+        Some(Claim::Base)
+    }
+
+    fn winner_label(&self) -> Option<(String, ModeSource)> {
+        let winner = self.winner()?;
+        Some((
+            winner.mode.clone().unwrap_or_default(),
+            winner.mode_source.unwrap_or_default(),
+        ))
+    }
+
+    fn publish_claims(&self) {
+        let claims = [&self.base, &self.game]
+            .into_iter()
+            .flatten()
+            .map(|claim| {
+                (
+                    claim.mode_source.unwrap_or_default().to_string(),
+                    claim.mode.clone().unwrap_or_default(),
+                )
+            })
+            .collect();
+        update_status(|s| s.mode_claims = claims);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(global_mode: &str) -> Arc<Config> {
+        let content = format!("[global]\nmode = \"{global_mode}\"\nidle_threshold = 5\n");
+        Arc::new(toml::from_str(&content).unwrap())
+    }
+
+    /// 由内存中的配置生成指定来源请求指定模式（`None` 表示全局模式）的增量
+    fn delta(config: &Arc<Config>, mode: Option<&str>, source: Option<ModeSource>) -> ConfigDelta {
+        let mut delta = config_delta(config, mode);
+        if let Some(mode) = mode {
+            delta.mode = Some(mode.to_string());
+        }
+        delta.mode_source = source;
+        delta
+    }
+
+    fn label(delta: Option<ConfigDelta>) -> Option<(String, ModeSource)> {
+        delta.map(|delta| {
+            (
+                delta.mode.unwrap_or_default(),
+                delta.mode_source.unwrap_or_default(),
+            )
+        })
+    }
+
+    fn winner(mode: &str, source: ModeSource) -> Option<(String, ModeSource)> {
+        Some((mode.to_string(), source))
+    }
+
+    #[test]
+    fn game_claim_wins_over_base() {
+        let config = config("balance");
+        let mut arbiter = ModeArbiter::new();
+        arbiter.submit(delta(&config, None, Some(ModeSource::Config)));
+
+        let game = delta(&config, Some("performance"), Some(ModeSource::Game));
+        assert_eq!(
+            label(arbiter.submit(game)),
+            winner("performance", ModeSource::Game)
+        );
+
+        // 游戏运行时修改配置文件只更新基础模式请求
+        let reloaded = delta(&config, None, Some(ModeSource::Config));
+        assert_eq!(
+            label(arbiter.submit(reloaded)),
+            winner("performance", ModeSource::Game)
+        );
+    }
+
+    #[test]
+    fn game_release_falls_back_to_base() {
+        let config = config("balance");
+        let mut arbiter = ModeArbiter::new();
+        let control = delta(&config, Some("powersave"), Some(ModeSource::Control));
+        arbiter.submit(control);
+        arbiter.submit(delta(&config, Some("performance"), Some(ModeSource::Game)));
+
+        let mut release = delta(&config, None, Some(ModeSource::Game));
+        release.release = true;
+        assert_eq!(
+            label(arbiter.submit(release)),
+            winner("powersave", ModeSource::Control)
+        );
+    }
+
+    #[test]
+    fn boost_and_sourceless_deltas_do_not_replace_a_claim() {
+        let config = config("balance");
+        let mut arbiter = ModeArbiter::new();
+        arbiter.submit(delta(&config, None, Some(ModeSource::Config)));
+        arbiter.submit(delta(&config, Some("performance"), Some(ModeSource::Game)));
+
+        let boost = delta(&config, Some("fast"), Some(ModeSource::Boost));
+        assert_eq!(
+            label(arbiter.submit(boost)),
+            winner("performance", ModeSource::Game)
+        );
+        let display = delta(&config, None, None);
+        assert_eq!(
+            label(arbiter.submit(display)),
+            winner("performance", ModeSource::Game)
+        );
+
+        let mut release = delta(&config, None, Some(ModeSource::Game));
+        release.release = true;
+        assert_eq!(
+            label(arbiter.submit(release)),
+            winner("balance", ModeSource::Config)
+        );
+    }
+
+    #[test]
+    fn sourceless_delta_rebuilds_the_winner_from_its_config() {
+        let mut arbiter = ModeArbiter::new();
+        arbiter.submit(delta(&config("balance"), None, Some(ModeSource::Config)));
+
+        // 状态变化的增量携带最新的配置，全局模式请求按其中的 global.mode 重新生成
+        let display = delta(&config("powersave"), None, None);
+        assert_eq!(
+            label(arbiter.submit(display)),
+            winner("powersave", ModeSource::Config)
+        );
+    }
+}
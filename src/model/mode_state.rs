@@ -0,0 +1,57 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// 模式切换的来源
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModeSource {
+    /// config.toml 中的全局模式
+    #[default]
+    Config,
+    /// 游戏列表中配置的游戏模式
+    Game,
+    /// 控制命令设置的模式
+    Control,
+    /// 控制命令触发的临时模式
+    Boost,
+}
+
+impl fmt::Display for ModeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Config => "config",
+            Self::Game => "game",
+            Self::Control => "control",
+            Self::Boost => "boost",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 当前生效的工作模式
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModeState {
+    /// 模式名称
+    pub name: String,
+    /// 切换来源
+    pub source: ModeSource,
+    /// 进入该模式的时间（Unix时间戳，秒）
+    pub since: u64,
+}
+
+impl ModeState {
+    pub fn new(name: &str, source: ModeSource) -> Self {
+        Self {
+            name: name.to_string(),
+            source,
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+}
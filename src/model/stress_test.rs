@@ -0,0 +1,326 @@
+//! 频率档位压力测试
+//!
+//! 多轮升序、降序切换频率表中的所有档位，每个档位短暂停留并回读实际频率，
+//! 同时监视内核日志中的驱动错误，生成每个档位的通过/失败矩阵。
+//! 为新设备编写频率表时用来代替手动逐档验证。
+//!
+//! 档位测试后逐个验证DDR控制节点：写入最高DDR档位，从 dvfsrc 调试信息回读当前 VCORE OPP，
+//! 再恢复自动模式，确认游戏模式下的DDR固定确实生效。
+
+use std::{
+    fmt::Write as _,
+    fs, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow};
+use log::warn;
+use serde::Serialize;
+
+use crate::{
+    datasource::{
+        file_path::{
+            DDR_HIGHEST_FREQ, DVFSRC_V1_DUMP, DVFSRC_V1_PATH, DVFSRC_V2_DUMP_1, DVFSRC_V2_DUMP_2,
+            DVFSRC_V2_PATH_1, DVFSRC_V2_PATH_2, STRESS_TEST_PATH,
+        },
+        kmsg::{KmsgReader, is_driver_error},
+        load_monitor::get_gpu_current_freq,
+    },
+    model::{freq::Freq, gpu::GPU},
+    utils::{
+        file_helper::FileHelper,
+        file_operate::{FileClass, write_file},
+    },
+};
+
+/// 扫描轮数，奇数轮升序、偶数轮降序
+const STRESS_CYCLES: u32 = 4;
+/// 每个档位的停留时间
+const STRESS_DWELL: Duration = Duration::from_millis(150);
+/// 停留期间的回读间隔
+const STRESS_SAMPLE_INTERVAL: Duration = Duration::from_millis(25);
+/// 回读频率允许低于期望频率的比例（百分比）
+const STRESS_TOLERANCE_PERCENT: i64 = 5;
+/// 每个档位最多保留的驱动错误日志条数
+const MAX_ERRORS_PER_OPP: usize = 5;
+/// 写入DDR档位后等待 dvfsrc 切换的时间
+const DDR_SETTLE: Duration = Duration::from_millis(100);
+
+/// 单个档位的测试结果
+#[derive(Serialize, Clone, Debug)]
+pub struct OppStressResult {
+    /// 请求的频率（KHz）
+    pub freq: Freq,
+    /// 切换到该档位的次数
+    pub switches: u32,
+    /// 频率写入失败次数
+    pub write_errors: u32,
+    /// 回读次数
+    pub readbacks: u32,
+    /// 回读频率与期望频率不符的次数
+    pub mismatches: u32,
+    /// 回读到的最低频率（KHz），没有成功回读时为0
+    pub lowest_readback: Freq,
+    /// 停留在该档位期间出现的驱动错误日志
+    pub driver_errors: Vec<String>,
+    pub passed: bool,
+}
+
+/// 单个DDR控制节点的验证结果
+#[derive(Serialize, Clone, Debug)]
+pub struct DdrPathResult {
+    pub path: String,
+    /// 写入的DDR档位
+    pub opp: i64,
+    /// 写入是否成功
+    pub written: bool,
+    /// 从 dvfsrc 调试信息回读的当前 VCORE OPP，无法读取时为 `None`
+    pub observed: Option<i64>,
+    /// 恢复自动模式是否成功
+    pub restored: bool,
+    pub passed: bool,
+}
+
+/// 压力测试报告
+#[derive(Serialize, Clone, Debug)]
+pub struct StressTestReport {
+    /// 生成时间（Unix时间戳，秒）
+    pub created_at: u64,
+    pub gpuv2: bool,
+    pub cycles: u32,
+    /// 是否监视了内核日志
+    pub kmsg_monitored: bool,
+    pub opps: Vec<OppStressResult>,
+    /// DDR控制节点的验证结果，设备没有DDR控制节点时为空
+    pub ddr: Vec<DdrPathResult>,
+}
+
+impl StressTestReport {
+    /// 未通过的档位数
+    pub fn failed(&self) -> usize {
+        self.opps.iter().filter(|opp| !opp.passed).count()
+    }
+
+    /// 以表格形式输出每个档位的结果
+    pub fn matrix(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:>10} {:>8} {:>8} {:>10} {:>10} {:>8}  result",
+            "freq", "switches", "w_err", "mismatch", "lowest", "drv_err"
+        );
+        for opp in &self.opps {
+            let _ = writeln!(
+                out,
+                "{:>10} {:>8} {:>8} {:>6}/{:<3} {:>10} {:>8}  {}",
+                opp.freq,
+                opp.switches,
+                opp.write_errors,
+                opp.mismatches,
+                opp.readbacks,
+                opp.lowest_readback,
+                opp.driver_errors.len(),
+                if opp.passed { "PASS" } else { "FAIL" }
+            );
+        }
+        let _ = write!(
+            out,
+            "{} OPP(s), {} passed, {} failed",
+            self.opps.len(),
+            self.opps.len() - self.failed(),
+            self.failed()
+        );
+        if !self.kmsg_monitored {
+            let _ = write!(out, " (kernel log not monitored)");
+        }
+        for ddr in &self.ddr {
+            let observed = ddr
+                .observed
+                .map_or_else(|| "?".to_string(), |opp| opp.to_string());
+            let _ = write!(
+                out,
+                "\nDDR {}: wrote OPP {} ({}), observed {observed}, restore {}  {}",
+                ddr.path,
+                ddr.opp,
+                if ddr.written { "ok" } else { "rejected" },
+                if ddr.restored { "ok" } else { "failed" },
+                if ddr.passed { "PASS" } else { "FAIL" }
+            );
+        }
+        out
+    }
+}
+
+impl OppStressResult {
+    fn new(freq: Freq) -> Self {
+        Self {
+            freq,
+            switches: 0,
+            write_errors: 0,
+            readbacks: 0,
+            mismatches: 0,
+            lowest_readback: Freq::ZERO,
+            driver_errors: Vec::new(),
+            passed: false,
+        }
+    }
+}
+
+/// 切换到一个档位并在停留期间回读频率、收集驱动错误
+fn stress_opp(gpu: &mut GPU, result: &mut OppStressResult, kmsg: &mut Option<KmsgReader>) {
+    let freq = result.freq;
+    gpu.set_cur_freq(freq);
+    gpu.frequency_mut().cur_freq_idx = gpu.frequency().read_freq_index(freq);
+    gpu.frequency_mut().gen_cur_volt();
+
+    result.switches += 1;
+    if gpu.frequency().write_freq(false, false).is_err() {
+        result.write_errors += 1;
+    }
+
+    let expected = if gpu.is_gpuv2() {
+        gpu.frequency().get_closest_v2_supported_freq(freq)
+    } else {
+        freq
+    };
+    let minimum = expected.percent(100 - STRESS_TOLERANCE_PERCENT);
+
+    // 切换后第一次回读可能仍是旧频率，只统计停留后半段的读数
+    let start = Instant::now();
+    while start.elapsed() < STRESS_DWELL {
+        thread::sleep(STRESS_SAMPLE_INTERVAL);
+        if start.elapsed() < STRESS_DWELL / 2 {
+            continue;
+        }
+        if let Ok(current) = get_gpu_current_freq(!gpu.is_gpuv2()) {
+            result.readbacks += 1;
+            if current < minimum {
+                result.mismatches += 1;
+            }
+            if result.lowest_readback.is_zero() || current < result.lowest_readback {
+                result.lowest_readback = current;
+            }
+        }
+    }
+
+    if let Some(reader) = kmsg {
+        for message in reader.read_new() {
+            if is_driver_error(&message) && result.driver_errors.len() < MAX_ERRORS_PER_OPP {
+                result.driver_errors.push(message);
+            }
+        }
+    }
+}
+
+/// 从 dvfsrc 调试信息中解析当前 VCORE OPP
+///
+/// 各内核的格式不同（如 `VCORE_OPP : 2`、`OPP = 2`），取第一个键中含 `opp`
+/// 且不是强制档位或档位数的行
+fn parse_current_vcore_opp(content: &str) -> Option<i64> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once([':', '='])?;
+        let key = key.trim().to_lowercase();
+        if !key.contains("opp") || key.contains("force") || key.contains("num") {
+            return None;
+        }
+        value
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|s| !s.is_empty())?
+            .parse()
+            .ok()
+    })
+}
+
+/// 逐个验证DDR控制节点：写入最高档位、回读当前 VCORE OPP，再恢复自动模式
+fn verify_ddr_control(gpu: &GPU) -> Vec<DdrPathResult> {
+    let nodes: &[(&str, &str)] = if gpu.is_gpuv2() {
+        &[
+            (DVFSRC_V2_PATH_1, DVFSRC_V2_DUMP_1),
+            (DVFSRC_V2_PATH_2, DVFSRC_V2_DUMP_2),
+        ]
+    } else {
+        &[(DVFSRC_V1_PATH, DVFSRC_V1_DUMP)]
+    };
+    let auto_value = gpu.ddr_manager().auto_mode_value().to_string();
+
+    nodes
+        .iter()
+        .filter(|(path, _)| FileHelper::node_exists(path))
+        .map(|&(path, dump)| {
+            let opp = DDR_HIGHEST_FREQ;
+            let written = FileHelper::write_string_safe(path, &opp.to_string());
+            thread::sleep(DDR_SETTLE);
+            let observed = fs::read_to_string(dump)
+                .ok()
+                .and_then(|content| parse_current_vcore_opp(&content));
+            let restored = FileHelper::write_string_safe(path, &auto_value);
+            DdrPathResult {
+                path: path.to_string(),
+                opp,
+                written,
+                observed,
+                restored,
+                passed: written && observed == Some(opp) && restored,
+            }
+        })
+        .collect()
+}
+
+/// 运行压力测试，保存并返回测试报告
+///
+/// 测试期间直接写入频率节点，调用前需确保守护进程未在调频
+pub fn run_stress_test(gpu: &mut GPU) -> Result<StressTestReport> {
+    let config_list = gpu.get_config_list();
+    if config_list.is_empty() {
+        return Err(anyhow!("Frequency table is empty"));
+    }
+
+    let mut kmsg = match KmsgReader::open() {
+        Ok(reader) => Some(reader),
+        Err(e) => {
+            warn!("Kernel log unavailable, driver errors will not be detected: {e}");
+            None
+        }
+    };
+    let kmsg_monitored = kmsg.is_some();
+
+    let mut results: Vec<OppStressResult> = config_list
+        .iter()
+        .map(|&freq| OppStressResult::new(freq))
+        .collect();
+    for cycle in 0..STRESS_CYCLES {
+        let order: Vec<usize> = if cycle % 2 == 0 {
+            (0..results.len()).collect()
+        } else {
+            (0..results.len()).rev().collect()
+        };
+        for index in order {
+            stress_opp(gpu, &mut results[index], &mut kmsg);
+        }
+    }
+
+    let ddr = verify_ddr_control(gpu);
+
+    for result in &mut results {
+        result.passed = result.write_errors == 0
+            && result.readbacks > 0
+            && result.mismatches == 0
+            && result.driver_errors.is_empty();
+    }
+
+    let report = StressTestReport {
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        gpuv2: gpu.is_gpuv2(),
+        cycles: STRESS_CYCLES,
+        kmsg_monitored,
+        opps: results,
+        ddr,
+    };
+
+    let json = serde_json::to_string_pretty(&report)?;
+    write_file(STRESS_TEST_PATH, json.as_bytes(), 1 << 20, FileClass::State)?;
+    Ok(report)
+}
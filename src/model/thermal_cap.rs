@@ -0,0 +1,95 @@
+use log::info;
+
+use crate::model::freq::Freq;
+
+/// 连续多少个采样实际频率低于请求频率时判定为内核温控限频
+const THROTTLE_DETECT_SAMPLES: u32 = 5;
+/// 限频期间每隔多久允许请求一次上限以上的频率，用于探测限频是否解除（毫秒）
+const THROTTLE_PROBE_INTERVAL_MS: u64 = 2000;
+
+/// 内核温控限频检测器
+///
+/// 比较调速器请求的频率和驱动上报的实际频率，实际频率持续低于请求时认为内核温控
+/// 正在限制GPU频率，此后不再请求高于上限的频率，直到探测到限频解除
+#[derive(Clone, Default)]
+pub struct ThermalCapDetector {
+    /// 连续低于请求频率的采样数
+    mismatch_count: u32,
+    /// 本轮检测中观察到的最高实际频率
+    observed_max: Freq,
+    /// 当前生效的限频上限
+    cap: Option<Freq>,
+    /// 上一次探测的时间
+    last_probe_ms: u64,
+    /// 最近一次请求的频率档位
+    requested: Freq,
+}
+
+impl ThermalCapDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前检测到的限频上限
+    pub fn cap(&self) -> Option<Freq> {
+        self.cap
+    }
+
+    /// 记录写入的频率
+    ///
+    /// `expected` 为请求频率在频率表中对应的不高于请求的档位，驱动不会选择低于它的频率，
+    /// 除非存在外部限频
+    pub fn record_request(&mut self, expected: Freq) {
+        self.requested = expected;
+    }
+
+    /// 记录一次从驱动读取的实际频率
+    pub fn observe(&mut self, actual: Freq) {
+        let expected = self.requested;
+        if !expected.is_positive() || !actual.is_positive() {
+            return;
+        }
+
+        if actual >= expected {
+            self.mismatch_count = 0;
+            self.observed_max = Freq::ZERO;
+            if let Some(cap) = self.cap
+                && actual > cap
+            {
+                info!("Kernel thermal cap lifted: running at {actual}KHz above {cap}KHz");
+                self.cap = None;
+            }
+            return;
+        }
+
+        self.mismatch_count += 1;
+        self.observed_max = self.observed_max.max(actual);
+        if self.mismatch_count < THROTTLE_DETECT_SAMPLES {
+            return;
+        }
+
+        let cap = self.observed_max;
+        if self.cap != Some(cap) {
+            info!("Kernel thermal cap detected: requested {expected}KHz, running at {cap}KHz");
+            self.cap = Some(cap);
+        }
+        self.mismatch_count = 0;
+        self.observed_max = Freq::ZERO;
+    }
+
+    /// 将目标频率限制在限频上限以内，到达探测间隔时放行一次以检测限频是否解除
+    pub fn limit(&mut self, target: Freq, current_time: u64) -> Freq {
+        let Some(cap) = self.cap else {
+            return target;
+        };
+        if target <= cap {
+            return target;
+        }
+
+        if current_time.saturating_sub(self.last_probe_ms) >= THROTTLE_PROBE_INTERVAL_MS {
+            self.last_probe_ms = current_time;
+            return target;
+        }
+        cap
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+/// 时间轮的槽位数量
+const WHEEL_SLOTS: usize = 64;
+/// 每个槽位对应的时间粒度（毫秒）
+const TICK_MS: u64 = 100;
+
+/// 简单的哈希时间轮 - 用于调频线程内的定时任务（如临时性能模式到期恢复）
+///
+/// 定时精度为一个tick（100ms），到期判断基于绝对tick编号，
+/// 因此超过一圈的定时任务也能正确触发。
+pub struct TimerWheel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    origin: Instant,
+    current_tick: u64,
+    len: usize,
+}
+
+impl<T> TimerWheel<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: (0..WHEEL_SLOTS).map(|_| Vec::new()).collect(),
+            origin: Instant::now(),
+            current_tick: 0,
+            len: 0,
+        }
+    }
+
+    /// 在指定延迟后触发任务
+    pub fn schedule(&mut self, delay: Duration, item: T) {
+        let delay_ticks = (delay.as_millis() as u64).div_ceil(TICK_MS).max(1);
+        let deadline = self.tick_at(Instant::now()) + delay_ticks;
+        self.slots[(deadline % WHEEL_SLOTS as u64) as usize].push((deadline, item));
+        self.len += 1;
+    }
+
+    /// 取消所有满足条件的任务，返回取消的数量
+    pub fn cancel<F: Fn(&T) -> bool>(&mut self, predicate: F) -> usize {
+        let mut cancelled = 0;
+        for slot in &mut self.slots {
+            let before = slot.len();
+            slot.retain(|(_, item)| !predicate(item));
+            cancelled += before - slot.len();
+        }
+        self.len -= cancelled;
+        cancelled
+    }
+
+    /// 推进时间轮并取出所有已到期的任务
+    pub fn poll_expired(&mut self) -> Vec<T> {
+        let now_tick = self.tick_at(Instant::now());
+        let mut expired = Vec::new();
+
+        if self.len == 0 {
+            self.current_tick = now_tick;
+            return expired;
+        }
+
+        // 最多扫描一整圈即可覆盖所有槽位
+        let steps = (now_tick - self.current_tick).min(WHEEL_SLOTS as u64 - 1);
+        for tick in (now_tick - steps)..=now_tick {
+            let slot = &mut self.slots[(tick % WHEEL_SLOTS as u64) as usize];
+            let mut i = 0;
+            while i < slot.len() {
+                if slot[i].0 <= now_tick {
+                    expired.push(slot.swap_remove(i).1);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        self.len -= expired.len();
+        self.current_tick = now_tick;
+        expired
+    }
+
+    /// 是否没有待触发的任务
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn tick_at(&self, instant: Instant) -> u64 {
+        instant.duration_since(self.origin).as_millis() as u64 / TICK_MS
+    }
+}
+
+impl<T> Default for TimerWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
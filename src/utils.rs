@@ -1,9 +1,26 @@
 pub mod constants;
+pub mod crash_report;
+pub mod decision_log;
 pub mod file_helper;
 pub mod file_operate;
 pub mod file_status;
 pub mod inotify;
 pub mod log_level_manager;
 pub mod log_rotation;
+pub mod log_tail;
 pub mod logger;
 pub mod macros;
+pub mod mode_list;
+pub mod mode_notify;
+pub mod node_probe;
+pub mod precise_timer;
+pub mod realtime;
+#[cfg(feature = "shm-export")]
+pub mod shm_export;
+pub mod shutdown;
+pub mod stats;
+pub mod status_report;
+pub mod sysfs_audit;
+#[cfg(any(test, feature = "trace-replay"))]
+pub mod sysfs_mock;
+pub mod trace_recorder;
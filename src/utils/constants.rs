@@ -10,4 +10,5 @@ pub const VERSION: &str = "Version: v2.12.3";
 pub mod strategy {
     pub const IDLE_THRESHOLD: i32 = 5;
     pub const FOREGROUND_APP_STARTUP_DELAY: u64 = 60; // seconds
+    pub const FOREGROUND_APP_RESTART_DELAY: u64 = 5; // seconds
 }
@@ -0,0 +1,127 @@
+//! 崩溃报告
+//!
+//! panic 时将最近的日志连同版本、线程、调用栈和当前模式一起写入 `crash-<时间>.txt`，
+//! 避免守护进程崩溃后日志中没有任何线索。反复崩溃重启时只保留最新的若干份报告。
+
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    fs,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use anyhow::Result;
+use chrono::Local;
+use log::warn;
+
+use crate::{
+    datasource::file_path::CRASH_REPORT_DIR,
+    utils::{log_tail::recent_lines, status_report::try_status_snapshot},
+};
+
+/// 崩溃报告中附带的最近日志条数
+const RECENT_EVENT_COUNT: usize = 200;
+/// 保留的崩溃报告数量
+const MAX_CRASH_REPORTS: usize = 10;
+
+/// 生成崩溃报告并写入崩溃报告目录，返回报告路径
+pub fn write_crash_report(info: &PanicHookInfo<'_>) -> Result<PathBuf> {
+    let mut report = String::new();
+    let now = Local::now();
+    let current = thread::current();
+
+    writeln!(report, "GPU Governor crash report")?;
+    writeln!(report, "time: {}", now.format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(report, "version: {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(report, "thread: {}", current.name().unwrap_or("<unnamed>"))?;
+    writeln!(report, "panic: {info}")?;
+
+    match try_status_snapshot() {
+        Some(status) => {
+            let source = status
+                .mode_source
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(report, "mode: {} ({source})", status.mode)?;
+            writeln!(report, "bypass: {}", status.bypass)?;
+            writeln!(report, "adjustments: {}", status.adjustments)?;
+        }
+        None => writeln!(report, "mode: <status unavailable>")?,
+    }
+
+    writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture())?;
+
+    writeln!(report, "recent events:")?;
+    for event in recent_lines(RECENT_EVENT_COUNT) {
+        writeln!(report, "{event}")?;
+    }
+
+    let dir = Path::new(CRASH_REPORT_DIR);
+    let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+    fs::create_dir_all(dir)?;
+    fs::write(&path, report)?;
+    if let Err(e) = prune_crash_reports(dir, MAX_CRASH_REPORTS) {
+        warn!("Failed to remove old crash reports: {e}");
+    }
+    Ok(path)
+}
+
+/// 删除较旧的崩溃报告，只保留最新的 `keep` 份，返回删除的数量
+///
+/// 报告文件名中的时间按字典序即为时间顺序
+fn prune_crash_reports(dir: &Path, keep: usize) -> Result<usize> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".txt"))
+        })
+        .collect();
+    reports.sort();
+
+    let excess = reports.len().saturating_sub(keep);
+    for path in &reports[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_reports() {
+        let dir = std::env::temp_dir().join(format!("crash-reports-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "crash-20260101-000000.txt",
+            "crash-20260102-000000.txt",
+            "crash-20260103-000000.txt",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(prune_crash_reports(&dir, 2).unwrap(), 1);
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "crash-20260102-000000.txt",
+                "crash-20260103-000000.txt",
+                "notes.txt"
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
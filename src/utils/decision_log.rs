@@ -0,0 +1,122 @@
+//! 调频决策日志
+//!
+//! 按 `[logging] decision_log` 记录调频引擎的决策输入和结果，排查“为什么没有升频”一类问题时
+//! 不必把全局日志等级调到debug。`summary` 只记录实际调频，`full` 记录每个采样周期。
+//! 每条记录为一行 `key=value`，以 `DECISION` 标记写入主日志，不受日志等级过滤。
+
+use std::{
+    fmt::{self, Write as _},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{
+    datasource::config_parser::DecisionLogLevel,
+    model::{freq::Freq, load_analyzer::LoadTrend},
+    utils::logger::log_unfiltered,
+};
+
+static LEVEL: AtomicU8 = AtomicU8::new(DecisionLogLevel::Off as u8);
+
+/// 设置决策日志的详细程度，由日志配置加载和热重载调用
+pub fn set_decision_log_level(level: DecisionLogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+fn decision_log_level() -> DecisionLogLevel {
+    match LEVEL.load(Ordering::Relaxed) {
+        x if x == DecisionLogLevel::Summary as u8 => DecisionLogLevel::Summary,
+        x if x == DecisionLogLevel::Full as u8 => DecisionLogLevel::Full,
+        _ => DecisionLogLevel::Off,
+    }
+}
+
+/// 决策结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecisionOutcome {
+    /// 写入了新频率
+    Changed,
+    /// 目标频率与当前频率相同
+    Unchanged,
+    /// 连续高负载后的单个低负载采样被忽略
+    HeldDown,
+    /// 防抖时间未到
+    Debounced,
+    /// 紧急升频
+    Emergency,
+    /// 固定频率策略
+    Pinned,
+    /// 空闲降频
+    Idle,
+}
+
+impl fmt::Display for DecisionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+            Self::HeldDown => "held_down",
+            Self::Debounced => "debounced",
+            Self::Emergency => "emergency",
+            Self::Pinned => "pinned",
+            Self::Idle => "idle",
+        })
+    }
+}
+
+/// 一次调频决策的输入和结果
+#[derive(Clone, Debug)]
+pub struct Decision {
+    pub load: i32,
+    /// 负载分析器判定的负载区间
+    pub zone: usize,
+    /// 平滑负载的变化趋势
+    pub trend: LoadTrend,
+    /// 生效的余量（百分比，含预热加成）
+    pub margin: u32,
+    pub current: Freq,
+    pub target: Freq,
+    /// 生效的升频、降频防抖时间（毫秒）
+    pub debounce: (u64, u64),
+    /// 距上次调频的时间（毫秒），尚未调频过时为 `None`
+    pub since_last: Option<u64>,
+    pub outcome: DecisionOutcome,
+}
+
+impl Decision {
+    /// 是否实际改变了频率，`summary` 只记录这类决策
+    fn is_change(&self) -> bool {
+        matches!(
+            self.outcome,
+            DecisionOutcome::Changed
+                | DecisionOutcome::Emergency
+                | DecisionOutcome::Pinned
+                | DecisionOutcome::Idle
+        ) && self.target != self.current
+    }
+}
+
+/// 按当前详细程度记录一次决策
+pub fn record_decision(decision: &Decision) {
+    match decision_log_level() {
+        DecisionLogLevel::Off => return,
+        DecisionLogLevel::Summary if !decision.is_change() => return,
+        DecisionLogLevel::Summary | DecisionLogLevel::Full => {}
+    }
+
+    let mut line = format!(
+        "load={}% zone={} trend={} margin={}% cur={}KHz target={}KHz up_delay={}ms down_delay={}ms",
+        decision.load,
+        decision.zone,
+        decision.trend,
+        decision.margin,
+        decision.current,
+        decision.target,
+        decision.debounce.0,
+        decision.debounce.1
+    );
+    if let Some(since) = decision.since_last {
+        let _ = write!(line, " since_last={since}ms");
+    }
+    let _ = write!(line, " outcome={}", decision.outcome);
+    log_unfiltered("DECISION", &line);
+}
@@ -1,5 +1,9 @@
 use log::debug;
-use std::{fs::OpenOptions, io::Write, path::Path};
+use std::{fs::OpenOptions, io::Write, path::Path, time::Instant};
+
+#[cfg(any(test, feature = "trace-replay"))]
+use crate::utils::sysfs_mock;
+use crate::utils::{node_probe, sysfs_audit};
 
 /// 改进的文件操作辅助工具
 /// 提供统一的文件读写接口，减少重复代码
@@ -10,16 +14,26 @@ impl FileHelper {
     /// 尝试写入文件，失败时只记录调试信息，不终止程序
     pub fn write_string_safe<P: AsRef<Path>>(path: P, content: &str) -> bool {
         let path = path.as_ref();
-        match OpenOptions::new().write(true).open(path) {
+        #[cfg(any(test, feature = "trace-replay"))]
+        if let Some(result) = sysfs_mock::write(&path.to_string_lossy(), content) {
+            return result;
+        }
+        // 启动检查中写入失败的节点不再尝试
+        if node_probe::is_excluded(&path.to_string_lossy()) {
+            return false;
+        }
+        let audit = sysfs_audit::is_enabled();
+        let start = audit.then(Instant::now);
+        let result = match OpenOptions::new().write(true).open(path) {
             Ok(mut file) => match file.write_all(content.as_bytes()) {
-                Ok(_) => true,
+                Ok(_) => Ok(()),
                 Err(e) => {
                     debug!(
                         "Failed to write file: {} - Error: {} (continuing execution)",
                         path.display(),
                         e
                     );
-                    false
+                    Err(e)
                 }
             },
             Err(e) => {
@@ -28,8 +42,28 @@ impl FileHelper {
                     path.display(),
                     e
                 );
-                false
+                Err(e)
             }
+        };
+        if let Some(start) = start {
+            let error = result.as_ref().err().map(|e| e.to_string());
+            sysfs_audit::record_write(
+                &path.to_string_lossy(),
+                content,
+                error.as_deref().map_or(Ok(()), Err),
+                start.elapsed(),
+            );
+        }
+        result.is_ok()
+    }
+
+    /// 检查内核节点是否存在（安装了 sysfs 模拟层时查询模拟节点）
+    pub fn node_exists<P: AsRef<Path>>(path: P) -> bool {
+        let path = path.as_ref();
+        #[cfg(any(test, feature = "trace-replay"))]
+        if let Some(exists) = sysfs_mock::exists(&path.to_string_lossy()) {
+            return exists;
         }
+        path.exists()
     }
 }
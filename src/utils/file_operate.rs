@@ -1,15 +1,17 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::{Read, Write},
-    os::unix::fs::PermissionsExt,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    os::unix::fs::{PermissionsExt, chown},
     path::Path,
+    sync::Mutex,
 };
 
 use anyhow::{Context, Result};
-use log::{debug, error};
+use log::warn;
+use once_cell::sync::Lazy;
 
 use crate::{
-    datasource::file_path::{GPUFREQ_OPP, GPUFREQV2_OPP},
+    datasource::config_parser::{FilePermission, PermissionsConfig},
     utils::file_status::write_status,
 };
 
@@ -43,65 +45,167 @@ pub fn read_file<P: AsRef<Path>>(path: P, max_len: usize) -> Result<String> {
     Ok(content)
 }
 
+/// 调速器生成文件的类别，决定写入后应用的权限
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileClass {
+    /// 状态文件（current_mode、status.json）
+    State,
+    /// 控制命令的响应文件
+    Control,
+}
+
+static PERMISSIONS_POLICY: Lazy<Mutex<PermissionsConfig>> =
+    Lazy::new(|| Mutex::new(PermissionsConfig::default()));
+
+/// 更新文件权限配置
+pub fn set_permissions_policy(config: PermissionsConfig) {
+    *PERMISSIONS_POLICY.lock().unwrap() = config;
+}
+
+fn permission_for(class: FileClass) -> FilePermission {
+    let policy = PERMISSIONS_POLICY.lock().unwrap();
+    match class {
+        FileClass::State => policy.state,
+        FileClass::Control => policy.control,
+    }
+}
+
+/// 按文件类别设置权限位和属主
+fn apply_permissions(path: &Path, class: FileClass) -> Result<()> {
+    let permission = permission_for(class);
+    fs::set_permissions(path, fs::Permissions::from_mode(permission.mode))
+        .with_context(|| format!("Failed to set permissions for: {}", path.display()))?;
+
+    if (permission.owner.is_some() || permission.group.is_some())
+        && let Err(e) = chown(path, permission.owner, permission.group)
+    {
+        // 属主设置失败不影响文件内容，只记录警告
+        warn!("Failed to change owner of {}: {e}", path.display());
+    }
+    Ok(())
+}
+
+/// 确保调速器写入的文件所在目录存在，新建的目录按 `[permissions] directory` 设置权限
+///
+/// 配置目录被清空后，状态文件、模式文件和日志的写入不会因目录缺失而失败。
+/// 日志模块也会调用此函数，因此这里不输出日志
+pub fn ensure_parent_dir<P: AsRef<Path>>(path: P) -> Result<()> {
+    let Some(parent) = path
+        .as_ref()
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    else {
+        return Ok(());
+    };
+    if parent.is_dir() {
+        return Ok(());
+    }
+
+    let permission = PERMISSIONS_POLICY.lock().unwrap().directory;
+    // 从最上层缺失的目录开始逐级创建，只对新建的目录设置权限
+    let missing: Vec<&Path> = parent.ancestors().take_while(|dir| !dir.exists()).collect();
+    for dir in missing.into_iter().rev() {
+        match fs::create_dir(dir) {
+            Ok(()) => {
+                fs::set_permissions(dir, fs::Permissions::from_mode(permission.mode))
+                    .with_context(|| format!("Failed to set permissions for: {}", dir.display()))?;
+                // 属主设置失败不影响写入
+                if permission.owner.is_some() || permission.group.is_some() {
+                    let _ = chown(dir, permission.owner, permission.group);
+                }
+            }
+            // 其他线程同时创建了该目录
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to create directory: {}", dir.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 原子写入调速器生成的文件：先写入临时文件并设置权限，再重命名覆盖目标文件，
+/// 读取方不会看到写了一半的内容
 pub fn write_file<P: AsRef<Path>, C: AsRef<[u8]>>(
     path: P,
     content: C,
     max_len: usize,
+    class: FileClass,
 ) -> Result<usize> {
     let path_ref = path.as_ref();
+    let mut tmp_path = path_ref.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = Path::new(&tmp_path);
 
-    // 设置文件权限为可写
-    if path_ref.exists() {
-        let metadata = path_ref
-            .metadata()
-            .with_context(|| format!("Failed to get metadata for: {}", path_ref.display()))?;
-        let mut perms = metadata.permissions();
-        perms.set_mode(0o644);
-        std::fs::set_permissions(path_ref, perms)
-            .with_context(|| format!("Failed to set permissions for: {}", path_ref.display()))?;
-    }
+    let content_ref = content.as_ref();
+    let len = std::cmp::min(content_ref.len(), max_len);
+
+    ensure_parent_dir(path_ref)?;
 
     let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
-        .open(path_ref)
-        .with_context(|| format!("Failed to open file for writing: {}", path_ref.display()))?;
+        .open(tmp_path)
+        .with_context(|| format!("Failed to open file for writing: {}", tmp_path.display()))?;
+    file.write_all(&content_ref[..len])
+        .with_context(|| format!("Failed to write to file: {}", tmp_path.display()))?;
+    drop(file);
 
-    let content_ref = content.as_ref();
-    let len = std::cmp::min(content_ref.len(), max_len);
-    let bytes_written = match file.write(&content_ref[..len]) {
-        Ok(n) => n,
-        Err(e) => {
-            // 检查是否是特定文件路径，如果是则使用debug级别记录错误并返回成功
-            let path_str = path_ref.to_str().unwrap_or("");
-            if path_str == GPUFREQV2_OPP || path_str == GPUFREQ_OPP {
-                debug!(
-                    "Failed to write to file: {}. Error: {} (continuing execution)",
-                    path_ref.display(),
-                    e
-                );
-                // 对于GPU频率文件，即使写入失败也返回成功，避免程序终止
-                return Ok(len);
-            } else {
-                error!(
-                    "Failed to write to file: {}. Error: {}",
-                    path_ref.display(),
-                    e
-                );
-            }
-            return Err(anyhow::anyhow!(""));
-        }
+    apply_permissions(tmp_path, class)?;
+
+    fs::rename(tmp_path, path_ref).with_context(|| {
+        format!(
+            "Failed to replace {} with {}",
+            path_ref.display(),
+            tmp_path.display()
+        )
+    })?;
+
+    Ok(len)
+}
+
+/// 修改用户维护的TOML文件（配置文件、频率表等）
+///
+/// 守护进程自动修改用户文件时必须使用此函数：基于 `toml_edit` 只改动 `edit` 触及的条目，
+/// 保留用户的注释、空行和条目顺序；写入临时文件后替换原文件，并沿用原文件的权限。
+/// 文件不存在时从空文档开始编辑。返回 `edit` 的结果，`edit` 出错时不写入
+pub fn edit_toml_file<P, T, F>(path: P, edit: F) -> Result<T>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut toml_edit::DocumentMut) -> Result<T>,
+{
+    let path_ref = path.as_ref();
+    let content = match fs::read_to_string(path_ref) {
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        result => result.with_context(|| format!("Failed to read {}", path_ref.display()))?,
     };
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path_ref.display()))?;
+
+    let result = edit(&mut document)?;
+    let updated = document.to_string();
+    if updated == content && path_ref.exists() {
+        return Ok(result);
+    }
 
-    // 设置文件权限为只读
-    let metadata = path_ref
-        .metadata()
-        .with_context(|| format!("Failed to get metadata for: {}", path_ref.display()))?;
-    let mut perms = metadata.permissions();
-    perms.set_mode(0o444);
-    std::fs::set_permissions(path_ref, perms)
-        .with_context(|| format!("Failed to set permissions for: {}", path_ref.display()))?;
+    let mut tmp_path = path_ref.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = Path::new(&tmp_path);
+    fs::write(tmp_path, updated)
+        .with_context(|| format!("Failed to write to file: {}", tmp_path.display()))?;
+    if let Ok(metadata) = fs::metadata(path_ref) {
+        fs::set_permissions(tmp_path, metadata.permissions())?;
+    }
+    fs::rename(tmp_path, path_ref).with_context(|| {
+        format!(
+            "Failed to replace {} with {}",
+            path_ref.display(),
+            tmp_path.display()
+        )
+    })?;
 
-    Ok(bytes_written)
+    Ok(result)
 }
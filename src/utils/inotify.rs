@@ -1,9 +1,10 @@
 use std::{
     collections::HashMap,
     ffi::{CString, OsStr},
+    os::fd::AsRawFd,
     path::Path,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
@@ -11,6 +12,8 @@ use inotify::{EventMask, Inotify, WatchMask};
 
 const WAIT_MOVE_US: u64 = 500 * 1000;
 const RECREATE_DEFAULT_PERM: u32 = 0o666;
+/// 默认的事件合并窗口
+pub const DEFAULT_COALESCE_WINDOW: Duration = Duration::from_millis(50);
 
 #[derive(Debug, Clone)]
 pub struct SimpleEvent {
@@ -21,9 +24,15 @@ pub struct SimpleEvent {
     pub name: Option<String>,
 }
 
+/// inotify 监控器
+///
+/// 一次保存操作通常会连续产生 MODIFY、CLOSE_WRITE、MOVED_TO 等多个事件。收到第一个事件后
+/// 在合并窗口内继续收集，同一路径的事件合并为一个（掩码取并集），各监控线程只需处理一次
 pub struct InotifyWatcher {
     inotify: Inotify,
     watches: HashMap<inotify::WatchDescriptor, String>,
+    /// 事件合并窗口，为0时不等待，只合并同一批读取到的事件
+    coalesce_window: Duration,
 }
 
 impl InotifyWatcher {
@@ -33,9 +42,15 @@ impl InotifyWatcher {
         Ok(Self {
             inotify,
             watches: HashMap::new(),
+            coalesce_window: DEFAULT_COALESCE_WINDOW,
         })
     }
 
+    /// 设置事件合并窗口
+    pub fn set_coalesce_window(&mut self, window: Duration) {
+        self.coalesce_window = window;
+    }
+
     pub fn add<P: AsRef<Path>>(&mut self, path: P, mask: WatchMask) -> Result<()> {
         let path_ref = path.as_ref();
         let path_str = path_ref
@@ -62,8 +77,19 @@ impl InotifyWatcher {
             .inotify
             .read_events_blocking(&mut buffer)
             .with_context(|| "Failed to read inotify events")?;
+        let mut collected = self.process_events(events)?;
 
-        self.process_events(events)
+        // 在合并窗口内继续收集同一次操作产生的后续事件
+        let deadline = Instant::now() + self.coalesce_window;
+        while self.wait_readable(deadline.saturating_duration_since(Instant::now())) {
+            let events = self
+                .inotify
+                .read_events(&mut buffer)
+                .with_context(|| "Failed to read inotify events")?;
+            collected.extend(self.process_events(events)?);
+        }
+
+        Ok(coalesce(collected))
     }
 
     // 新增：非阻塞地检查事件
@@ -74,7 +100,21 @@ impl InotifyWatcher {
             .read_events(&mut buffer)
             .with_context(|| "Failed to read inotify events")?;
 
-        self.process_events(events)
+        Ok(coalesce(self.process_events(events)?))
+    }
+
+    /// 等待 inotify 有可读事件，超时返回false
+    fn wait_readable(&self, timeout: Duration) -> bool {
+        if timeout.is_zero() {
+            return false;
+        }
+        let mut pollfd = libc::pollfd {
+            fd: self.inotify.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().clamp(1, i32::MAX as u128) as i32;
+        unsafe { libc::poll(&mut pollfd, 1, timeout_ms) > 0 }
     }
 
     fn process_events<'a, I>(&mut self, events: I) -> Result<Vec<SimpleEvent>>
@@ -146,6 +186,24 @@ impl InotifyWatcher {
     }
 }
 
+/// 合并同一监控项下同一文件的事件，保留首次出现的顺序
+fn coalesce(events: Vec<SimpleEvent>) -> Vec<SimpleEvent> {
+    let mut merged: Vec<SimpleEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        match merged
+            .iter_mut()
+            .find(|existing| existing.wd == event.wd && existing.name == event.name)
+        {
+            Some(existing) => {
+                existing.mask |= event.mask;
+                existing.cookie = event.cookie;
+            }
+            None => merged.push(event),
+        }
+    }
+    merged
+}
+
 fn try_path(path: &str) -> Result<()> {
     if !Path::new(path).exists() {
         // 稍作等待，让文件系统操作完成
@@ -16,9 +16,90 @@ use crate::{
     },
 };
 
+/// 日志等级规则
+///
+/// 日志等级文件支持逗号分隔的 `等级:模块` 写法，例如 `debug:datasource::load_monitor,info:*`，
+/// 省略模块或模块为 `*` 时设置默认等级；模块按最长前缀匹配，可省略crate名前缀
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogSpec {
+    pub default: LevelFilter,
+    /// 模块路径及其日志等级，按路径长度降序排列
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogSpec {
+    /// 所有模块使用同一等级
+    pub fn uniform(level: LevelFilter) -> Self {
+        Self {
+            default: level,
+            modules: Vec::new(),
+        }
+    }
+
+    /// 解析日志等级规则，任一条目无效时返回 `None`
+    pub fn parse(content: &str, default: LevelFilter) -> Option<Self> {
+        let mut spec = Self::uniform(default);
+        let mut has_entry = false;
+        for entry in content.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            has_entry = true;
+            let (level, module) = match entry.split_once(':') {
+                Some((level, module)) => (level, module.trim()),
+                None => (entry, "*"),
+            };
+            let level = LogLevelManager::parse_level(level)?;
+            if module == "*" || module.is_empty() {
+                spec.default = level;
+            } else {
+                let module = module.trim_start_matches("crate::").to_string();
+                spec.modules.retain(|(m, _)| *m != module);
+                spec.modules.push((module, level));
+            }
+        }
+        if !has_entry {
+            return None;
+        }
+        spec.modules
+            .sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Some(spec)
+    }
+
+    /// 所有规则中最详细的等级，作为全局日志等级
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, std::cmp::max)
+    }
+
+    /// 指定日志目标适用的等级
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        // 日志目标形如 `gpugovernor::datasource::load_monitor`，规则中可省略crate名
+        let relative = target.split_once("::").map_or(target, |(_, rest)| rest);
+        let matches = |path: &str, module: &str| {
+            path.strip_prefix(module)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        self.modules
+            .iter()
+            .find(|(module, _)| matches(target, module) || matches(relative, module))
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+impl std::fmt::Display for LogSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (module, level) in &self.modules {
+            write!(f, "{}:{module},", level.as_str().to_lowercase())?;
+        }
+        write!(f, "{}:*", self.default.as_str().to_lowercase())
+    }
+}
+
 /// 统一的日志等级管理器
 pub struct LogLevelManager {
     current_level: Arc<Mutex<LevelFilter>>,
+    /// 按模块过滤的日志等级规则
+    current_spec: Arc<Mutex<LogSpec>>,
     rotation_monitor: Arc<Mutex<Option<LogRotationMonitor>>>,
 }
 
@@ -27,54 +108,79 @@ impl LogLevelManager {
     pub fn new() -> Self {
         Self {
             current_level: Arc::new(Mutex::new(LevelFilter::Info)),
+            current_spec: Arc::new(Mutex::new(LogSpec::uniform(LevelFilter::Info))),
             rotation_monitor: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// 解析日志等级字符串
+    pub fn parse_level(level: &str) -> Option<LevelFilter> {
+        match level.trim().to_lowercase().as_str() {
+            "debug" => Some(LevelFilter::Debug),
+            "info" => Some(LevelFilter::Info),
+            "warn" => Some(LevelFilter::Warn),
+            "error" => Some(LevelFilter::Error),
+            _ => None,
+        }
+    }
+
     /// 读取日志等级配置文件
-    pub fn read_log_level_config() -> Result<LevelFilter> {
-        // 默认日志等级为Info
-        let default_level = LevelFilter::Info;
+    pub fn read_log_level_config() -> Result<LogSpec> {
+        // 默认日志等级来自 `[logging]` 配置，未配置时为Info
+        let default_level = *DEFAULT_LOG_LEVEL.lock().unwrap();
+        let default_spec = LogSpec::uniform(default_level);
 
         // 检查配置文件是否存在
         if !fs::exists(LOG_LEVEL_PATH).unwrap_or(false) {
-            return Ok(default_level);
+            return Ok(default_spec);
         }
 
         // 尝试读取配置文件
         let content = match std::fs::read_to_string(LOG_LEVEL_PATH) {
             Ok(content) => content,
-            Err(_) => return Ok(default_level),
+            Err(_) => return Ok(default_spec),
         };
 
         // 解析日志等级
-        let level_str = content.trim().to_lowercase();
-        match level_str.as_str() {
-            "debug" => Ok(LevelFilter::Debug),
-            "info" => Ok(LevelFilter::Info),
-            "warn" => Ok(LevelFilter::Warn),
-            "error" => Ok(LevelFilter::Error),
-            _ => Ok(default_level),
-        }
+        Ok(LogSpec::parse(&content, default_level).unwrap_or(default_spec))
     }
 
-    /// 获取当前日志等级
+    /// 获取当前日志等级（所有模块中最详细的等级）
     pub fn get_current_level(&self) -> LevelFilter {
         *self.current_level.lock().unwrap()
     }
 
-    /// 更新日志等级
-    pub fn update_level(&self, new_level: LevelFilter) {
+    /// 指定日志目标和等级的日志是否输出
+    pub fn is_enabled(&self, target: &str, level: log::Level) -> bool {
+        level <= self.current_spec.lock().unwrap().level_for(target)
+    }
+
+    /// 只替换模块过滤规则，不触发等级变化的副作用，用于日志系统初始化
+    pub fn install_spec(&self, spec: LogSpec) {
+        *self.current_spec.lock().unwrap() = spec;
+    }
+
+    /// 更新日志等级规则
+    pub fn update_level(&self, new_spec: LogSpec) {
+        let new_level = new_spec.max_level();
+        let mut spec = self.current_spec.lock().unwrap();
+        let spec_changed = *spec != new_spec;
+        *spec = new_spec.clone();
+        drop(spec); // 释放锁
+
         let mut current = self.current_level.lock().unwrap();
-        if *current != new_level {
-            let old_level = *current;
-            *current = new_level;
-            drop(current); // 释放锁
+        let old_level = *current;
+        *current = new_level;
+        drop(current); // 释放锁
 
+        if old_level != new_level {
             // 更新全局日志等级
             log::set_max_level(new_level);
-            info!("Log level updated to: {new_level}");
-
+        }
+        if spec_changed || old_level != new_level {
+            info!("Log level updated to: {new_spec}");
+        }
+        if old_level != new_level {
             // 根据日志等级变化管理日志轮转监控
             self.manage_log_rotation_monitor(old_level, new_level);
         }
@@ -124,9 +230,9 @@ impl LogLevelManager {
 
         // 初始化当前日志等级
         match Self::read_log_level_config() {
-            Ok(level) => {
-                self.update_level(level);
-                info!("Initial log level set to: {level}");
+            Ok(spec) => {
+                info!("Initial log level set to: {spec}");
+                self.update_level(spec);
             }
             Err(e) => {
                 warn!("Failed to read initial log level config: {e}");
@@ -154,8 +260,8 @@ impl LogLevelManager {
 
             // 读取新的日志等级配置
             match Self::read_log_level_config() {
-                Ok(new_level) => {
-                    self.update_level(new_level);
+                Ok(new_spec) => {
+                    self.update_level(new_spec);
                 }
                 Err(e) => {
                     warn!("Failed to update log level: {e}");
@@ -165,6 +271,21 @@ impl LogLevelManager {
     }
 }
 
+impl Default for LogLevelManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 日志等级文件未指定有效等级时使用的默认等级
+static DEFAULT_LOG_LEVEL: once_cell::sync::Lazy<Mutex<LevelFilter>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(LevelFilter::Info));
+
+/// 设置默认日志等级
+pub fn set_default_log_level(level: LevelFilter) {
+    *DEFAULT_LOG_LEVEL.lock().unwrap() = level;
+}
+
 /// 全局日志等级管理器实例
 static LOG_LEVEL_MANAGER: once_cell::sync::Lazy<Arc<LogLevelManager>> =
     once_cell::sync::Lazy::new(|| Arc::new(LogLevelManager::new()));
@@ -2,7 +2,7 @@ use std::{
     fs,
     path::Path,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicBool, Ordering},
     },
     thread,
@@ -18,12 +18,20 @@ use crate::{
     utils::{log_level_manager::get_current_log_level, logger::reset_log_file_writer},
 };
 
+/// 日志轮转参数，可在运行时通过 `[logging]` 配置更新
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RotationSettings {
+    pub max_size_bytes: u64,
+    pub rotation_threshold: f64,
+    pub monitor_interval: Duration,
+    /// 保留的备份文件数量，0表示轮转时直接丢弃旧日志
+    pub backups: u32,
+}
+
 /// 日志轮转管理器
 pub struct LogRotationManager {
-    max_size_bytes: u64,
-    rotation_threshold: f64,
+    settings: Arc<Mutex<RotationSettings>>,
     monitor_running: Arc<AtomicBool>,
-    monitor_interval: Duration,
 }
 
 /// 后台监控线程句柄
@@ -44,21 +52,47 @@ impl LogRotationManager {
         rotation_threshold: Option<f64>,
         monitor_interval_seconds: Option<u64>,
     ) -> Self {
-        Self {
+        Self::with_settings(RotationSettings {
             max_size_bytes: max_size_mb * 1024 * 1024,
             rotation_threshold: rotation_threshold.unwrap_or(0.8),
-            monitor_running: Arc::new(AtomicBool::new(false)),
             monitor_interval: Duration::from_secs(monitor_interval_seconds.unwrap_or(30)),
+            backups: 1,
+        })
+    }
+
+    fn with_settings(settings: RotationSettings) -> Self {
+        Self {
+            settings: Arc::new(Mutex::new(settings)),
+            monitor_running: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    /// 创建默认的日志轮转管理器（10MB，80%阈值，60秒检查间隔）
-    pub fn default() -> Self {
-        Self::new(10, Some(0.8), Some(60))
+    /// 获取当前轮转参数
+    pub fn settings(&self) -> RotationSettings {
+        *self.settings.lock().unwrap()
+    }
+
+    /// 更新轮转参数，后台监控线程在下一次检查时生效
+    pub fn update_settings(&self, settings: RotationSettings) {
+        let mut current = self.settings.lock().unwrap();
+        if *current != settings {
+            *current = settings;
+            info!(
+                "Log rotation settings updated: max_size={}MB, threshold={}%, interval={}s, backups={}",
+                settings.max_size_bytes / 1024 / 1024,
+                (settings.rotation_threshold * 100.0) as u8,
+                settings.monitor_interval.as_secs(),
+                settings.backups
+            );
+        }
     }
 
     /// 检查是否需要轮转日志
     pub fn should_rotate(&self, log_file_path: &str) -> Result<bool> {
+        Self::should_rotate_with(&self.settings(), log_file_path)
+    }
+
+    fn should_rotate_with(settings: &RotationSettings, log_file_path: &str) -> Result<bool> {
         // 只有在debug日志等级时才检测日志文件大小
         if get_current_log_level() != LevelFilter::Debug {
             return Ok(false);
@@ -75,7 +109,7 @@ impl LogRotationManager {
             .with_context(|| format!("Failed to get metadata for: {log_file_path}"))?;
 
         let file_size = metadata.len();
-        let threshold_size = (self.max_size_bytes as f64 * self.rotation_threshold) as u64;
+        let threshold_size = (settings.max_size_bytes as f64 * settings.rotation_threshold) as u64;
 
         debug!("Log file size: {file_size} bytes, threshold: {threshold_size} bytes");
 
@@ -84,6 +118,18 @@ impl LogRotationManager {
 
     /// 执行日志轮转
     pub fn rotate_log(&self, log_file_path: &str) -> Result<()> {
+        Self::rotate_log_with(&self.settings(), log_file_path)
+    }
+
+    /// 第n个备份文件路径：`.bak`、`.bak.1`、`.bak.2` ...
+    fn backup_path(log_file_path: &str, index: u32) -> String {
+        match index {
+            0 => format!("{log_file_path}.bak"),
+            n => format!("{log_file_path}.bak.{n}"),
+        }
+    }
+
+    fn rotate_log_with(settings: &RotationSettings, log_file_path: &str) -> Result<()> {
         let log_path = Path::new(log_file_path);
 
         if !log_path.exists() {
@@ -91,28 +137,46 @@ impl LogRotationManager {
             return Ok(());
         }
 
-        let backup_path = format!("{log_file_path}.bak");
+        let rotation_msg = if settings.backups == 0 {
+            fs::remove_file(log_path)
+                .with_context(|| format!("Failed to remove log file: {log_file_path}"))?;
+            info!("Log file rotated without backup: {log_file_path}");
+            format!(
+                "{} - Log rotated, previous log discarded\n",
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            )
+        } else {
+            // 删除最旧的备份，其余备份依次后移
+            let oldest = Self::backup_path(log_file_path, settings.backups - 1);
+            if fs::exists(&oldest)? {
+                fs::remove_file(&oldest)
+                    .with_context(|| format!("Failed to remove old backup file: {oldest}"))?;
+                debug!("Removed old backup file: {oldest}");
+            }
+            for index in (0..settings.backups - 1).rev() {
+                let from = Self::backup_path(log_file_path, index);
+                if fs::exists(&from)? {
+                    let to = Self::backup_path(log_file_path, index + 1);
+                    fs::rename(&from, &to)
+                        .with_context(|| format!("Failed to shift backup file: {from} -> {to}"))?;
+                }
+            }
 
-        // 如果备份文件已存在，删除它
-        if fs::exists(&backup_path)? {
-            fs::remove_file(&backup_path)
-                .with_context(|| format!("Failed to remove old backup file: {backup_path}"))?;
-            debug!("Removed old backup file: {backup_path}");
-        }
+            // 将当前日志文件重命名为备份文件
+            let backup_path = Self::backup_path(log_file_path, 0);
+            fs::rename(log_path, &backup_path)
+                .with_context(|| format!("Failed to rename log file to backup: {backup_path}"))?;
 
-        // 将当前日志文件重命名为备份文件
-        fs::rename(log_path, &backup_path)
-            .with_context(|| format!("Failed to rename log file to backup: {backup_path}"))?;
+            info!("Log file rotated: {log_file_path} -> {backup_path}");
 
-        info!("Log file rotated: {log_file_path} -> {backup_path}");
+            format!(
+                "{} - Log rotated, previous log backed up to {}\n",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                backup_path
+            )
+        };
 
         // 创建新的日志文件并写入轮转信息
-        let rotation_msg = format!(
-            "{} - Log rotated, previous log backed up to {}\n",
-            Local::now().format("%Y-%m-%d %H:%M:%S"),
-            backup_path
-        );
-
         fs::write(log_path, rotation_msg)
             .with_context(|| format!("Failed to create new log file: {log_file_path}"))?;
 
@@ -126,8 +190,12 @@ impl LogRotationManager {
 
     /// 检查并在需要时执行日志轮转
     pub fn check_and_rotate(&self, log_file_path: &str) -> Result<bool> {
-        if self.should_rotate(log_file_path)? {
-            self.rotate_log(log_file_path)?;
+        Self::check_and_rotate_with(&self.settings(), log_file_path)
+    }
+
+    fn check_and_rotate_with(settings: &RotationSettings, log_file_path: &str) -> Result<bool> {
+        if Self::should_rotate_with(settings, log_file_path)? {
+            Self::rotate_log_with(settings, log_file_path)?;
             Ok(true)
         } else {
             Ok(false)
@@ -142,31 +210,24 @@ impl LogRotationManager {
 
         self.monitor_running.store(true, Ordering::Relaxed);
         let running_flag = Arc::clone(&self.monitor_running);
-        let monitor_interval = self.monitor_interval;
-        let max_size_bytes = self.max_size_bytes;
-        let rotation_threshold = self.rotation_threshold;
+        let shared_settings = Arc::clone(&self.settings);
 
         let join_handle = thread::Builder::new()
             .name("LogRotationMonitor".to_string())
             .spawn(move || {
                 info!("Log rotation background monitor started");
-                debug!(
-                    "Monitor interval: {:?}, max_size: {}MB, threshold: {}%",
-                    monitor_interval,
-                    max_size_bytes / 1024 / 1024,
-                    (rotation_threshold * 100.0) as u8
-                );
 
                 while running_flag.load(Ordering::Relaxed) {
-                    // 使用临时的管理器实例来执行检查
-                    let temp_manager = LogRotationManager {
-                        max_size_bytes,
-                        rotation_threshold,
-                        monitor_running: Arc::new(AtomicBool::new(false)), // 临时的，不使用
-                        monitor_interval,
-                    };
-
-                    match temp_manager.check_and_rotate(LOG_PATH) {
+                    // 每次检查前读取最新参数，支持配置热重载
+                    let settings = *shared_settings.lock().unwrap();
+                    debug!(
+                        "Monitor interval: {:?}, max_size: {}MB, threshold: {}%",
+                        settings.monitor_interval,
+                        settings.max_size_bytes / 1024 / 1024,
+                        (settings.rotation_threshold * 100.0) as u8
+                    );
+
+                    match Self::check_and_rotate_with(&settings, LOG_PATH) {
                         Ok(rotated) => {
                             if rotated {
                                 info!("Background monitor: Log file rotated successfully");
@@ -181,7 +242,7 @@ impl LogRotationManager {
 
                     // 等待下一次检查，但要响应停止信号
                     let sleep_duration = Duration::from_millis(1000); // 1秒为单位检查停止信号
-                    let total_iterations = monitor_interval.as_secs();
+                    let total_iterations = settings.monitor_interval.as_secs();
 
                     for _ in 0..total_iterations {
                         if !running_flag.load(Ordering::Relaxed) {
@@ -202,12 +263,19 @@ impl LogRotationManager {
 
     /// 获取最大日志文件大小（字节）
     pub fn max_size_bytes(&self) -> u64 {
-        self.max_size_bytes
+        self.settings().max_size_bytes
     }
 
     /// 获取日志轮转阈值（0.0-1.0）
     pub fn rotation_threshold(&self) -> f64 {
-        self.rotation_threshold
+        self.settings().rotation_threshold
+    }
+}
+
+impl Default for LogRotationManager {
+    /// 创建默认的日志轮转管理器（10MB，80%阈值，60秒检查间隔）
+    fn default() -> Self {
+        Self::new(10, Some(0.8), Some(60))
     }
 }
 
@@ -248,3 +316,8 @@ pub fn check_and_rotate_main_log() -> Result<bool> {
 pub fn start_main_log_monitor() -> Result<LogRotationMonitor> {
     LOG_ROTATION_MANAGER.start_background_monitor()
 }
+
+/// 获取主日志文件的轮转管理器
+pub fn main_log_rotation_manager() -> &'static LogRotationManager {
+    &LOG_ROTATION_MANAGER
+}
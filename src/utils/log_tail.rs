@@ -0,0 +1,62 @@
+//! 内存中的最近日志
+//!
+//! 日志系统将最近的日志行保存在固定容量的环形缓冲区中，前端通过 `log-tail` 控制命令
+//! 导出到控制目录即可显示最近日志，无需读取完整日志文件；崩溃报告也从这里取最近的日志。
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{
+    datasource::file_path::LOG_TAIL_PATH,
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 环形缓冲区保留的日志行数
+pub const LOG_TAIL_CAPACITY: usize = 500;
+
+static LOG_TAIL: Lazy<Mutex<VecDeque<String>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(LOG_TAIL_CAPACITY)));
+
+/// 锁定日志缓冲区，锁中毒时继续使用其中的数据
+fn log_tail() -> MutexGuard<'static, VecDeque<String>> {
+    LOG_TAIL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 记录一行日志
+///
+/// 临界区内只有出入队，不会 panic 也不会记录日志，panic 钩子中记录日志时不会死锁，
+/// 多个线程同时记录时也不会丢失日志
+pub fn record_line(line: &str) {
+    let line = line.trim_end().to_string();
+    let mut lines = log_tail();
+    if lines.len() == LOG_TAIL_CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// 最近的至多 `limit` 行日志，按时间顺序排列
+pub fn recent_lines(limit: usize) -> Vec<String> {
+    let lines = log_tail();
+    let skip = lines.len().saturating_sub(limit);
+    lines.iter().skip(skip).cloned().collect()
+}
+
+/// 将最近的至多 `limit` 行日志导出到 [`LOG_TAIL_PATH`]，返回导出的行数
+pub fn export_log_tail(limit: usize) -> Result<usize> {
+    let lines = recent_lines(limit);
+    let mut content = lines.join("\n");
+    content.push('\n');
+    write_file(
+        LOG_TAIL_PATH,
+        content.as_bytes(),
+        content.len(),
+        FileClass::Control,
+    )?;
+    Ok(lines.len())
+}
@@ -2,6 +2,8 @@ use std::{
     fs::{File, OpenOptions},
     io::{BufWriter, Write},
     sync::Mutex,
+    thread,
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
@@ -10,20 +12,85 @@ use log::{LevelFilter, Metadata, Record};
 use once_cell::sync::Lazy;
 
 use crate::{
-    datasource::file_path::{LOG_LEVEL_PATH, LOG_PATH},
-    utils::log_level_manager::LogLevelManager,
-    utils::log_rotation::{LogRotationManager, check_and_rotate_main_log, start_main_log_monitor},
+    datasource::{
+        config_parser::{LoggingConfig, read_section},
+        file_path::{LOG_LEVEL_PATH, LOG_PATH},
+    },
+    utils::decision_log::set_decision_log_level,
+    utils::file_operate::ensure_parent_dir,
+    utils::log_level_manager::{LogLevelManager, get_log_level_manager, set_default_log_level},
+    utils::log_rotation::{
+        RotationSettings, check_and_rotate_main_log, main_log_rotation_manager,
+        start_main_log_monitor,
+    },
+    utils::log_tail::record_line,
 };
 
+/// 默认的间隔刷盘周期
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// 非间隔刷盘策略下后台刷盘线程检查策略变化的周期
+const FLUSH_POLICY_POLL: Duration = Duration::from_millis(500);
+
+/// 日志刷盘策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// 每行日志写入后立即刷盘
+    Line,
+    /// 由后台线程按固定间隔刷盘
+    Interval(Duration),
+    /// 未刷盘的日志累计达到指定字节数时刷盘
+    Size(usize),
+}
+
+impl FlushPolicy {
+    /// 解析 `line`、`interval:<毫秒>`、`size:<KB>`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value == "line" {
+            return Some(Self::Line);
+        }
+        let (kind, amount) = value.split_once(':')?;
+        let amount: u64 = amount.trim().parse().ok().filter(|&n| n > 0)?;
+        match kind.trim() {
+            "interval" => Some(Self::Interval(Duration::from_millis(amount))),
+            "size" => Some(Self::Size(amount as usize * 1024)),
+            _ => None,
+        }
+    }
+
+    /// 未配置刷盘策略时的默认值：debug等级逐行刷盘，便于实时跟踪；其他等级按间隔刷盘
+    fn default_for(level: LevelFilter) -> Self {
+        if level >= LevelFilter::Debug {
+            Self::Line
+        } else {
+            Self::Interval(DEFAULT_FLUSH_INTERVAL)
+        }
+    }
+}
+
+/// 配置的刷盘策略，`None` 表示按日志等级选择
+static FLUSH_POLICY: Lazy<Mutex<Option<FlushPolicy>>> = Lazy::new(|| Mutex::new(None));
+
+/// 当前生效的刷盘策略
+fn flush_policy() -> FlushPolicy {
+    FLUSH_POLICY
+        .lock()
+        .unwrap()
+        .unwrap_or_else(|| FlushPolicy::default_for(log::max_level()))
+}
+
 // 自定义日志实现 - 支持文件写入和轮转
 struct CustomLogger {
     file_writer: Mutex<Option<BufWriter<File>>>,
+    /// 上次刷盘后写入的字节数
+    unflushed: Mutex<usize>,
 }
 
 impl CustomLogger {
     fn new() -> Self {
         Self {
             file_writer: Mutex::new(None),
+            unflushed: Mutex::new(0),
         }
     }
 
@@ -31,7 +98,8 @@ impl CustomLogger {
         let mut writer = self.file_writer.lock().unwrap();
 
         if writer.is_none() {
-            // 创建或打开日志文件
+            // 创建或打开日志文件，日志目录可能已被清空
+            ensure_parent_dir(LOG_PATH)?;
             let file = OpenOptions::new()
                 .create(true)
                 .append(true)
@@ -53,9 +121,20 @@ impl CustomLogger {
             buf_writer
                 .write_all(message.as_bytes())
                 .with_context(|| "Failed to write to log file")?;
-            buf_writer
-                .flush()
-                .with_context(|| "Failed to flush log file")?;
+
+            let mut unflushed = self.unflushed.lock().unwrap();
+            *unflushed += message.len();
+            let should_flush = match flush_policy() {
+                FlushPolicy::Line => true,
+                FlushPolicy::Size(limit) => *unflushed >= limit,
+                FlushPolicy::Interval(_) => false,
+            };
+            if should_flush {
+                buf_writer
+                    .flush()
+                    .with_context(|| "Failed to flush log file")?;
+                *unflushed = 0;
+            }
         }
 
         Ok(())
@@ -74,18 +153,20 @@ impl CustomLogger {
 }
 
 impl log::Log for CustomLogger {
-    fn enabled(&self, _metadata: &Metadata) -> bool {
-        // 这个方法只检查日志级别是否被启用
-        // 实际的过滤由log库根据设置的max_level完成
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // log库已按max_level（所有模块中最详细的等级）过滤，这里再按模块规则过滤
+        get_log_level_manager().is_enabled(metadata.target(), metadata.level())
     }
 
     fn log(&self, record: &Record) {
-        // 这里不需要再次检查enabled，因为log库已经根据max_level过滤了
+        if !self.enabled(record.metadata()) {
+            return;
+        }
         let now = Local::now();
         let timestamp = now.format("%Y-%m-%d %H:%M:%S").to_string();
         let level_str = record.level().to_string();
         let log_message = format!("[{}] [{}]: {}\n", timestamp, level_str, record.args());
+        record_line(&log_message);
 
         // 只写入到文件（忽略错误以避免程序崩溃）
         if let Err(e) = self.write_to_file(&log_message) {
@@ -98,6 +179,7 @@ impl log::Log for CustomLogger {
         let mut writer = self.file_writer.lock().unwrap();
         if let Some(ref mut buf_writer) = *writer {
             let _ = buf_writer.flush();
+            *self.unflushed.lock().unwrap() = 0;
         }
     }
 }
@@ -105,23 +187,126 @@ impl log::Log for CustomLogger {
 // 全局日志实例
 static LOGGER: Lazy<CustomLogger> = Lazy::new(CustomLogger::new);
 
+/// 不经日志等级过滤直接写入日志文件，用于独立于日志等级开关的结构化记录
+pub fn log_unfiltered(tag: &str, message: &str) {
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
+    let log_message = format!("[{timestamp}] [{tag}]: {message}\n");
+    record_line(&log_message);
+    if let Err(e) = LOGGER.write_to_file(&log_message) {
+        eprintln!("Warning: Failed to write to log file: {e}");
+    }
+}
+
 pub fn reset_log_file_writer() -> Result<()> {
     LOGGER.reset_writer()
 }
 
+/// 将 `[logging]` 配置应用到日志轮转参数和默认日志等级
+fn apply_logging_settings(config: &LoggingConfig) {
+    main_log_rotation_manager().update_settings(RotationSettings {
+        max_size_bytes: config.max_size_mb.max(1) * 1024 * 1024,
+        rotation_threshold: config.rotation_threshold.clamp(0.1, 1.0),
+        monitor_interval: Duration::from_secs(config.check_interval.max(1)),
+        backups: config.backups,
+    });
+
+    match LogLevelManager::parse_level(&config.level) {
+        Some(level) => set_default_log_level(level),
+        None => log::warn!("Invalid default log level '{}', ignored", config.level),
+    }
+
+    let policy = config.flush_policy.as_deref().and_then(|value| {
+        let policy = FlushPolicy::parse(value);
+        if policy.is_none() {
+            log::warn!("Invalid log flush policy '{value}', using the default");
+        }
+        policy
+    });
+    *FLUSH_POLICY.lock().unwrap() = policy;
+
+    set_decision_log_level(config.decision_log);
+}
+
+/// 启动后台刷盘线程，间隔刷盘策略下按周期将缓冲的日志写入文件
+fn start_log_flusher() -> Result<()> {
+    thread::Builder::new()
+        .name("LogFlusher".to_string())
+        .spawn(|| {
+            loop {
+                match flush_policy() {
+                    FlushPolicy::Interval(interval) => {
+                        thread::sleep(interval);
+                        log::logger().flush();
+                    }
+                    FlushPolicy::Line | FlushPolicy::Size(_) => thread::sleep(FLUSH_POLICY_POLL),
+                }
+            }
+        })
+        .context("Failed to spawn log flusher thread")?;
+    Ok(())
+}
+
+/// 热重载 `[logging]` 配置，由配置文件监控线程调用
+pub fn reload_logging_config() {
+    apply_logging_settings(&read_section("logging"));
+
+    // 默认等级可能变化，日志等级文件未指定等级时需要重新生效
+    match LogLevelManager::read_log_level_config() {
+        Ok(spec) => get_log_level_manager().update_level(spec),
+        Err(e) => log::warn!("Failed to re-read log level after config change: {e}"),
+    }
+}
+
+/// 追加模式下写入本次启动的分隔标记
+fn write_session_banner() -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(LOG_PATH)
+        .with_context(|| format!("Failed to open log file: {LOG_PATH}"))?;
+    writeln!(
+        file,
+        "\n========== gpugovernor {} session started at {} (pid {}) ==========",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        std::process::id()
+    )
+    .context("Failed to write log session banner")?;
+    Ok(())
+}
+
 pub fn init_logger() -> Result<()> {
-    // 启动时清空日志文件，保证每次启动都是新日志
-    let _ = File::create(LOG_PATH)?;
+    // 读取日志配置（轮转参数和默认等级）
+    let logging: LoggingConfig = read_section("logging");
+    apply_logging_settings(&logging);
+
+    ensure_parent_dir(LOG_PATH)?;
+    if logging.truncate_on_start {
+        // 启动时清空日志文件，保证每次启动都是新日志
+        let _ = File::create(LOG_PATH)?;
+    } else {
+        // 保留上次运行的日志以便排查重启循环，追加前先按大小轮转，避免日志无限增长
+        if let Err(e) = check_and_rotate_main_log() {
+            eprintln!("Warning: Failed to rotate log file before appending: {e}");
+        }
+        write_session_banner()?;
+    }
     // 读取日志等级配置
-    let log_level = LogLevelManager::read_log_level_config()?;
+    let log_spec = LogLevelManager::read_log_level_config()?;
+    let log_level = log_spec.max_level();
+    // 在记录第一条日志前安装模块过滤规则
+    get_log_level_manager().install_spec(log_spec.clone());
 
     // 设置日志记录器
     log::set_logger(&*LOGGER)
         .map(|()| log::set_max_level(log_level))
         .map_err(|e| anyhow::anyhow!("Failed to set logger: {e:?}"))?;
 
+    start_log_flusher()?;
+
     // 记录当前使用的日志等级
-    log::info!("Logger initialized with level: {log_level}");
+    log::info!("Logger initialized with level: {log_spec}");
+    log::info!("Log flush policy: {:?}", flush_policy());
 
     // 获取当前日志等级
     let current_level = crate::utils::log_level_manager::get_current_log_level();
@@ -129,15 +314,16 @@ pub fn init_logger() -> Result<()> {
     log::info!("Log file path: {LOG_PATH}");
     log::info!("Log level config path: {LOG_LEVEL_PATH}");
 
-    // 初始化日志轮转管理器
-    let rotation_manager = LogRotationManager::default();
+    // 输出日志轮转参数
+    let rotation_settings = main_log_rotation_manager().settings();
     log::info!(
         "Max log file size: {}MB",
-        rotation_manager.max_size_bytes() / 1024 / 1024
+        rotation_settings.max_size_bytes / 1024 / 1024
     );
     log::info!(
-        "Log rotation threshold: {}%",
-        (rotation_manager.rotation_threshold() * 100.0) as u8
+        "Log rotation threshold: {}%, backups: {}",
+        (rotation_settings.rotation_threshold * 100.0) as u8,
+        rotation_settings.backups
     );
 
     // 检查并执行日志轮转（仅在debug等级时）
@@ -0,0 +1,100 @@
+//! 前端模式列表
+//!
+//! 启动和配置变化时生成 `modes.toml`，列出所有可切换的模式及其多语言说明，
+//! 前端据此渲染模式选择而不必硬编码模式名。内置说明可在模式段中用
+//! `description` 覆盖或补充其他语言，例如 `[balance.description] en = "..."`。
+
+use std::{collections::BTreeMap, fs};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    datasource::{
+        config_parser::Config,
+        control::{BOOST_MODES, MODES},
+        device_profile::active_config_path,
+        file_path::MODES_LIST_PATH,
+    },
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 内置模式说明（中文, 英文）
+fn builtin_description(mode: &str) -> Option<(&'static str, &'static str)> {
+    match mode {
+        "powersave" => Some((
+            "省电：以较低频率运行，优先续航",
+            "Power saving: runs at lower frequencies to extend battery life",
+        )),
+        "balance" => Some((
+            "均衡：兼顾流畅度与功耗",
+            "Balanced: trades off smoothness against power draw",
+        )),
+        "performance" => Some((
+            "性能：保留更多余量，优先流畅",
+            "Performance: keeps more headroom to prioritize smoothness",
+        )),
+        "fast" => Some((
+            "极速：最快升频，功耗最高",
+            "Fast: ramps up fastest at the highest power draw",
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Serialize)]
+struct ModeEntry {
+    name: &'static str,
+    /// 是否为配置中的全局模式
+    default: bool,
+    /// 是否可作为临时提升（`boost`）的目标
+    boost: bool,
+    /// 语言 -> 说明
+    description: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct ModeList {
+    modes: Vec<ModeEntry>,
+}
+
+/// 根据当前配置文件生成模式列表文件
+pub fn write_mode_list() -> Result<()> {
+    let config: Config = toml::from_str(&fs::read_to_string(active_config_path())?)?;
+    let modes = MODES
+        .iter()
+        .map(|&name| {
+            let mut description = BTreeMap::new();
+            if let Some((zh, en)) = builtin_description(name) {
+                description.insert("zh".to_string(), zh.to_string());
+                description.insert("en".to_string(), en.to_string());
+            }
+            if let Some(overrides) = config.mode_overrides(name) {
+                description.extend(
+                    overrides
+                        .description
+                        .iter()
+                        .map(|(lang, text)| (lang.clone(), text.clone())),
+                );
+            }
+            ModeEntry {
+                name,
+                default: config.global_mode() == name,
+                boost: BOOST_MODES.contains(&name),
+                description,
+            }
+        })
+        .collect();
+
+    let content = format!(
+        "# 由 gpugovernor 自动生成，请勿手动修改\n\n{}",
+        toml::to_string_pretty(&ModeList { modes })?
+    );
+    write_file(
+        MODES_LIST_PATH,
+        content.as_bytes(),
+        content.len(),
+        FileClass::State,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! 模式切换通知
+//!
+//! 当前模式变化时通过 `am broadcast` 通知游戏内悬浮窗等配套应用。启动 am 进程开销较大，
+//! 需在 `[mode_notify]` 中开启；不需要广播的应用可以直接读取 `current_mode` 文件。
+
+use std::{
+    process::{Command, Stdio},
+    sync::Mutex,
+    thread,
+};
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+
+use crate::{datasource::config_parser::ModeNotifyConfig, model::mode_state::ModeSource};
+
+static NOTIFY_POLICY: Lazy<Mutex<ModeNotifyConfig>> =
+    Lazy::new(|| Mutex::new(ModeNotifyConfig::default()));
+
+/// 最近一次通知的模式，多个线程持有GPU副本时避免重复广播
+static LAST_NOTIFIED: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// 更新模式切换通知配置
+pub fn set_mode_notify_policy(config: ModeNotifyConfig) {
+    *NOTIFY_POLICY.lock().unwrap() = config;
+}
+
+/// 通知配套应用当前模式已变化，广播在后台线程中发送，不阻塞调用方
+pub fn notify_mode_change(mode: &str, source: ModeSource) {
+    let policy = NOTIFY_POLICY.lock().unwrap().clone();
+    if !policy.broadcast {
+        return;
+    }
+
+    {
+        let mut last = LAST_NOTIFIED.lock().unwrap();
+        if last.as_deref() == Some(mode) {
+            return;
+        }
+        *last = Some(mode.to_string());
+    }
+
+    let mode = mode.to_string();
+    let spawned = thread::Builder::new()
+        .name("ModeBroadcast".to_string())
+        .spawn(move || {
+            let status = Command::new("am")
+                .args(["broadcast", "-a", &policy.action])
+                .args(["--es", "mode", &mode])
+                .args(["--es", "source", &source.to_string()])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            match status {
+                Ok(status) if status.success() => {
+                    debug!("Mode change broadcast sent: {mode} ({source})")
+                }
+                Ok(status) => warn!("Mode change broadcast failed: am exited with {status}"),
+                Err(e) => warn!("Failed to run am for mode change broadcast: {e}"),
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to spawn mode change broadcast thread: {e}");
+    }
+}
@@ -0,0 +1,73 @@
+//! 受控节点的启动检查
+//!
+//! 初始化时对每个准备写入的节点做一次无副作用的往返写入：内容为单个整数的节点写回当前值，
+//! 其他节点写入交还内核时使用的值（初始化时内核本就在调频，写入不影响运行）。
+//! 检查失败的节点输出警告并从写入路径中排除，[`crate::utils::file_helper::FileHelper`]
+//! 对这些节点的写入直接返回失败，不再每个周期静默重试；结果写入 status.json。
+
+use std::{collections::HashSet, fs, sync::Mutex};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+
+use crate::{
+    datasource::file_path::{
+        DVFSRC_V1_PATH, DVFSRC_V2_PATH_1, DVFSRC_V2_PATH_2, GPUFREQ_OPP, GPUFREQ_VOLT,
+        GPUFREQV2_OPP, GPUFREQV2_VOLT, MALI_DVFS_ENABLE,
+    },
+    model::gpu::GPU,
+    utils::{file_helper::FileHelper, status_report::update_status},
+};
+
+/// 检查失败、已从写入路径中排除的节点
+static EXCLUDED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 节点是否已从写入路径中排除
+pub fn is_excluded(path: &str) -> bool {
+    EXCLUDED.lock().unwrap().contains(path)
+}
+
+/// 往返写入使用的值：内容为单个整数时写回当前值，否则使用 `neutral`
+fn probe_value(path: &str, neutral: &str) -> String {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| content.parse::<i64>().is_ok())
+        .unwrap_or_else(|| neutral.to_string())
+}
+
+/// 检查调速器将要写入的节点，不存在的节点跳过
+pub fn probe_controlled_nodes(gpu: &GPU) {
+    let ddr_auto = gpu.ddr_manager().auto_mode_value().to_string();
+    let nodes: Vec<(&str, &str)> = if gpu.is_gpuv2() {
+        vec![
+            (GPUFREQV2_OPP, "-1"),
+            (GPUFREQV2_VOLT, "0 0"),
+            (DVFSRC_V2_PATH_1, &ddr_auto),
+            (DVFSRC_V2_PATH_2, &ddr_auto),
+        ]
+    } else {
+        vec![
+            (GPUFREQ_OPP, "0"),
+            (GPUFREQ_VOLT, "0 0"),
+            (MALI_DVFS_ENABLE, "1"),
+            (DVFSRC_V1_PATH, &ddr_auto),
+        ]
+    };
+
+    let mut excluded = Vec::new();
+    for (path, neutral) in nodes {
+        if !FileHelper::node_exists(path) {
+            continue;
+        }
+        if FileHelper::write_string_safe(path, &probe_value(path, neutral)) {
+            info!("{path}: writable");
+        } else {
+            warn!("{path} rejected a round-trip write, excluding it from frequency control");
+            excluded.push(path.to_string());
+        }
+    }
+
+    EXCLUDED.lock().unwrap().extend(excluded.iter().cloned());
+    update_status(|s| s.unwritable_nodes = excluded);
+}
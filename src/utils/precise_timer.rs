@@ -0,0 +1,132 @@
+//! 精确模式定时器
+//!
+//! 基于 timerfd 的单次绝对定时：每次休眠把下一个截止时间设为上一个截止时间加上休眠时长
+//! （`TFD_TIMER_ABSTIME`，不设周期），处理负载的耗时计入本周期，采样周期不会因此漂移；
+//! 每个采样周期只唤醒一次，不像固定频率节拍那样在休眠期间反复唤醒。
+
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+
+pub struct PreciseTimer {
+    fd: OwnedFd,
+    /// 上一次休眠的截止时间（CLOCK_MONOTONIC）
+    deadline: Option<Duration>,
+}
+
+/// 当前 CLOCK_MONOTONIC 时间
+fn monotonic_now() -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+fn to_timespec(time: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: time.as_secs() as libc::time_t,
+        tv_nsec: time.subsec_nanos() as libc::c_long,
+    }
+}
+
+impl PreciseTimer {
+    pub fn new() -> Result<Self> {
+        let raw = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC) };
+        if raw < 0 {
+            return Err(anyhow!(
+                "timerfd_create failed: {}",
+                io::Error::last_os_error()
+            ));
+        }
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(raw) },
+            deadline: None,
+        })
+    }
+
+    /// 休眠 `duration`，从上一次的截止时间开始计时
+    pub fn sleep(&mut self, duration: Duration) -> Result<()> {
+        let now = monotonic_now();
+        let mut deadline = self.deadline.unwrap_or(now) + duration;
+        // 落后超过一个周期时从当前时间重新计时，避免连续多次不休眠
+        if deadline + duration < now {
+            deadline = now + duration;
+        }
+        self.deadline = Some(deadline);
+        if deadline <= now {
+            return Ok(());
+        }
+
+        let spec = libc::itimerspec {
+            it_interval: to_timespec(Duration::ZERO),
+            it_value: to_timespec(deadline),
+        };
+        if unsafe {
+            libc::timerfd_settime(
+                self.fd.as_raw_fd(),
+                libc::TFD_TIMER_ABSTIME,
+                &spec,
+                std::ptr::null_mut(),
+            )
+        } < 0
+        {
+            return Err(anyhow!(
+                "timerfd_settime failed: {}",
+                io::Error::last_os_error()
+            ));
+        }
+
+        loop {
+            let mut expirations = 0u64;
+            let n = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    &mut expirations as *mut u64 as *mut libc::c_void,
+                    size_of::<u64>(),
+                )
+            };
+            if n >= 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(anyhow!("Failed to read timerfd: {err}"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn sleeps_until_each_deadline() {
+        let mut timer = PreciseTimer::new().unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            timer.sleep(Duration::from_millis(5)).unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(15));
+    }
+
+    #[test]
+    fn work_time_counts_towards_the_period() {
+        let mut timer = PreciseTimer::new().unwrap();
+        timer.sleep(Duration::from_millis(20)).unwrap();
+        thread::sleep(Duration::from_millis(10));
+
+        let start = Instant::now();
+        timer.sleep(Duration::from_millis(20)).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(18));
+    }
+}
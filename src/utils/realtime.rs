@@ -0,0 +1,55 @@
+//! 调频线程实时调度
+//!
+//! 游戏中CPU满载时，普通分时调度下的调频线程可能被推迟数十毫秒才得到运行，负载突增时
+//! 升频明显滞后。按 `[realtime]` 配置将调频线程切换为 `SCHED_FIFO`/`SCHED_RR`；
+//! 没有权限（缺少 `CAP_SYS_NICE` 或被 SELinux/cgroup 限制）时保持普通调度继续运行。
+
+use std::io;
+
+use log::{info, warn};
+
+use crate::datasource::config_parser::{RealtimeConfig, RealtimePolicy};
+
+/// 按配置设置当前线程的调度策略，失败时保持原有调度
+pub fn apply_realtime_policy(config: &RealtimeConfig) {
+    let (policy, name) = match config.policy {
+        RealtimePolicy::None => return,
+        RealtimePolicy::Fifo => (libc::SCHED_FIFO, "SCHED_FIFO"),
+        RealtimePolicy::Rr => (libc::SCHED_RR, "SCHED_RR"),
+    };
+
+    let min = unsafe { libc::sched_get_priority_min(policy) };
+    let max = unsafe { libc::sched_get_priority_max(policy) };
+    if min < 0 || max < 0 {
+        warn!(
+            "Failed to query {name} priority range: {}, keeping normal scheduling",
+            io::Error::last_os_error()
+        );
+        return;
+    }
+    let priority = config.priority.clamp(min, max);
+    if priority != config.priority {
+        warn!(
+            "Realtime priority {} out of range {min}-{max}, using {priority}",
+            config.priority
+        );
+    }
+
+    // pid 为0时作用于调用线程，只影响调频线程
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    if unsafe { libc::sched_setscheduler(0, policy, &param) } == 0 {
+        info!("Adjustment loop running with {name} priority {priority}");
+        return;
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EPERM) {
+        warn!(
+            "Permission denied setting {name} priority {priority} (needs CAP_SYS_NICE or a realtime cgroup budget), keeping normal scheduling"
+        );
+    } else {
+        warn!("Failed to set {name} priority {priority}: {err}, keeping normal scheduling");
+    }
+}
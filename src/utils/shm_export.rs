@@ -0,0 +1,205 @@
+//! 调频决策共享内存导出（`shm-export` 特性）
+//!
+//! 调频线程每个周期把当前决策写入一块 memfd 共享内存，悬浮窗帧率工具以只读方式映射后
+//! 直接读取，双方都不需要读写文件。共享内存的路径（`/proc/<pid>/fd/<fd>`）写入
+//! [`SHM_EXPORT_PATH`]，读取方需要有权限打开守护进程的文件描述符（通常为root）。
+//!
+//! 内存布局（小端，共64字节，大小已用 `F_SEAL_SHRINK`/`F_SEAL_GROW` 封存）：
+//!
+//! | 偏移 | 类型       | 字段                                                         |
+//! |------|------------|--------------------------------------------------------------|
+//! | 0    | `u32`      | `magic`，固定为 `0x47505547`（"GPUG"）                        |
+//! | 4    | `u16`      | `version`，当前为 1                                          |
+//! | 6    | `u16`      | `size`，结构体字节数                                         |
+//! | 8    | `u32`      | `seq`，写入期间为奇数                                        |
+//! | 12   | `i32`      | `load`，GPU负载（百分比）                                    |
+//! | 16   | `i64`      | `freq`，当前频率（KHz）                                      |
+//! | 24   | `i32`      | `temp`，GPU温度（摄氏度），未知时为 `i32::MIN`               |
+//! | 28   | `u32`      | `flags`：bit0 空闲，bit1 旁路，bit2 游戏模式                 |
+//! | 32   | `u64`      | `updated_ms`，更新时间（`CLOCK_MONOTONIC`，毫秒）            |
+//! | 40   | `[u8; 24]` | `mode`，当前模式名（UTF-8），不足部分补0                     |
+//!
+//! 读取方先读 `seq`，为奇数时稍后重试；读完数据后再读一次 `seq`，两次相同才说明数据完整。
+
+use std::{
+    ffi::CString,
+    fs, io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicU32, Ordering, fence},
+};
+
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+
+use crate::{
+    datasource::file_path::SHM_EXPORT_PATH,
+    model::gpu::GPU,
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 结构体标识 "GPUG"
+const SHM_MAGIC: u32 = 0x4750_5547;
+/// 布局版本，字段变化时递增
+const SHM_VERSION: u16 = 1;
+/// 模式名的最大字节数
+const MODE_LEN: usize = 24;
+
+const FLAG_IDLE: u32 = 1 << 0;
+const FLAG_BYPASS: u32 = 1 << 1;
+const FLAG_GAMING: u32 = 1 << 2;
+
+/// 共享内存中的决策结构
+#[repr(C)]
+struct SharedDecision {
+    magic: u32,
+    version: u16,
+    size: u16,
+    seq: AtomicU32,
+    load: i32,
+    freq: i64,
+    temp: i32,
+    flags: u32,
+    updated_ms: u64,
+    mode: [u8; MODE_LEN],
+}
+
+const SHM_SIZE: usize = size_of::<SharedDecision>();
+const _: () = assert!(SHM_SIZE == 64);
+
+/// 单调时钟（毫秒），与读取方的 `CLOCK_MONOTONIC` 一致
+fn monotonic_ms() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1000 + ts.tv_nsec as u64 / 1_000_000
+}
+
+/// 共享内存导出器
+pub struct ShmExporter {
+    /// 保持 memfd 打开，发布的 `/proc` 路径才有效
+    _fd: OwnedFd,
+    region: NonNull<SharedDecision>,
+}
+
+impl ShmExporter {
+    /// 创建共享内存并发布其路径
+    pub fn new() -> Result<Self> {
+        let name = CString::new("gpugovernor-decision").unwrap();
+        let raw = unsafe {
+            libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING)
+        };
+        if raw < 0 {
+            return Err(anyhow!(
+                "memfd_create failed: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        if unsafe { libc::ftruncate(fd.as_raw_fd(), SHM_SIZE as libc::off_t) } < 0 {
+            return Err(anyhow!("ftruncate failed: {}", io::Error::last_os_error()));
+        }
+        // 封存大小，读取方映射后不会因截断收到 SIGBUS
+        if unsafe {
+            libc::fcntl(
+                fd.as_raw_fd(),
+                libc::F_ADD_SEALS,
+                libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_SEAL,
+            )
+        } < 0
+        {
+            warn!(
+                "Failed to seal shared memory: {}",
+                io::Error::last_os_error()
+            );
+        }
+
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                SHM_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(anyhow!("mmap failed: {}", io::Error::last_os_error()));
+        }
+        let region = NonNull::new(addr.cast::<SharedDecision>())
+            .ok_or_else(|| anyhow!("mmap returned a null pointer"))?;
+
+        unsafe {
+            region.as_ptr().write(SharedDecision {
+                magic: SHM_MAGIC,
+                version: SHM_VERSION,
+                size: SHM_SIZE as u16,
+                seq: AtomicU32::new(0),
+                load: 0,
+                freq: 0,
+                temp: i32::MIN,
+                flags: 0,
+                updated_ms: 0,
+                mode: [0; MODE_LEN],
+            });
+        }
+
+        let path = format!("/proc/{}/fd/{}", std::process::id(), fd.as_raw_fd());
+        write_file(
+            SHM_EXPORT_PATH,
+            path.as_bytes(),
+            path.len(),
+            FileClass::State,
+        )?;
+        info!("Exporting governor decisions via shared memory at {path}");
+
+        Ok(Self { _fd: fd, region })
+    }
+
+    /// 写入一次决策
+    pub fn publish(&mut self, gpu: &GPU, load: i32, temperature: Option<i32>) {
+        let mut flags = 0;
+        if gpu.is_idle() {
+            flags |= FLAG_IDLE;
+        }
+        if gpu.is_bypassed() {
+            flags |= FLAG_BYPASS;
+        }
+        if gpu.is_gaming_mode() {
+            flags |= FLAG_GAMING;
+        }
+        let mut mode = [0u8; MODE_LEN];
+        let name = gpu.current_mode().as_bytes();
+        let len = name.len().min(MODE_LEN);
+        mode[..len].copy_from_slice(&name[..len]);
+
+        let shared = self.region.as_ptr();
+        unsafe {
+            let seq = &(*shared).seq;
+            seq.fetch_add(1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            ptr::addr_of_mut!((*shared).load).write_volatile(load);
+            ptr::addr_of_mut!((*shared).freq).write_volatile(gpu.get_cur_freq().khz());
+            ptr::addr_of_mut!((*shared).temp).write_volatile(temperature.unwrap_or(i32::MIN));
+            ptr::addr_of_mut!((*shared).flags).write_volatile(flags);
+            ptr::addr_of_mut!((*shared).updated_ms).write_volatile(monotonic_ms());
+            ptr::addr_of_mut!((*shared).mode).write_volatile(mode);
+            seq.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+impl Drop for ShmExporter {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.region.as_ptr().cast(), SHM_SIZE);
+        }
+        if let Err(e) = fs::remove_file(SHM_EXPORT_PATH) {
+            warn!("Failed to remove {SHM_EXPORT_PATH}: {e}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! 退出信号处理
+//!
+//! 收到 SIGTERM/SIGINT/SIGHUP 时只设置标志位，由调频主循环在下一个周期退出并完成清理。
+//!
+//! 模块更新时使用文件握手代替 `kill -9`，避免GPU停留在固定频率：
+//! 1. 脚本删除旧的 `shutdown_ack`，向控制目录写入 `shutdown_request`
+//! 2. 控制命令线程检测到请求后设置退出标志，调频主循环退出
+//! 3. 守护进程将频率控制交还内核、恢复DVFS后写入 `shutdown_ack`（内容为 `ok` 或
+//!    `error: <原因>`），删除 `shutdown_request`，随后退出
+//! 4. 脚本轮询 `shutdown_ack`，超时仍未出现时再强制结束进程
+
+use std::{
+    fs, io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use log::{info, warn};
+
+use crate::{
+    datasource::file_path::{SHUTDOWN_ACK_FILE, SHUTDOWN_REQUEST_FILE},
+    utils::file_operate::{FileClass, write_file},
+};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// 退出是否由握手文件发起
+static HANDSHAKE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_signal(_signal: libc::c_int) {
+    // 信号处理函数中只做原子写入，保证异步信号安全
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// 注册退出信号处理函数
+pub fn install_signal_handlers() {
+    for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+        unsafe {
+            libc::signal(signal, handle_signal as *const () as libc::sighandler_t);
+        }
+    }
+    info!("Signal handlers installed");
+}
+
+/// 是否收到了退出请求
+pub fn is_shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// 清理上一次运行遗留的握手文件，避免脚本误读旧的确认或新进程启动后立即退出
+pub fn clear_shutdown_handshake() {
+    for path in [SHUTDOWN_REQUEST_FILE, SHUTDOWN_ACK_FILE] {
+        match fs::remove_file(path) {
+            Ok(()) => info!("Removed stale shutdown handshake file {path}"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove {path}: {e}"),
+        }
+    }
+}
+
+/// 通过握手文件请求退出
+pub fn request_shutdown_handshake() {
+    if !HANDSHAKE_REQUESTED.swap(true, Ordering::SeqCst) {
+        info!("Shutdown requested by {SHUTDOWN_REQUEST_FILE}");
+    }
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// 恢复内核调频后确认握手，`restore` 为恢复结果；退出不是由握手发起时不做任何事
+pub fn acknowledge_shutdown(restore: Result<(), String>) {
+    if !HANDSHAKE_REQUESTED.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let ack = match restore {
+        Ok(()) => "ok\n".to_string(),
+        Err(e) => format!("error: {e}\n"),
+    };
+    if let Err(e) = write_file(
+        SHUTDOWN_ACK_FILE,
+        ack.as_bytes(),
+        ack.len(),
+        FileClass::Control,
+    ) {
+        warn!("Failed to write shutdown acknowledgement: {e}");
+        return;
+    }
+    if let Err(e) = fs::remove_file(SHUTDOWN_REQUEST_FILE)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        warn!("Failed to remove {SHUTDOWN_REQUEST_FILE}: {e}");
+    }
+    info!("Shutdown acknowledged: {}", ack.trim_end());
+}
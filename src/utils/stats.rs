@@ -0,0 +1,221 @@
+//! 运行统计模块
+//!
+//! 记录进程启动时间和同一次开机内的重启次数（持久化到文件），用于排查被 init 脚本
+//! 反复拉起的静默崩溃；调频次数等计数通过 status.json 输出。
+//!
+//! 各游戏的累计前台时长持久化到 play_time.json，供前端与按游戏调优一起展示；
+//! 前台游戏的本次时长通过 status.json 输出。
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::file_path::{BOOT_ID_PATH, PLAY_TIME_PATH, STATS_PATH},
+    utils::{
+        file_operate::{FileClass, write_file},
+        status_report::update_status,
+    },
+};
+
+/// 持久化的启动记录
+#[derive(Serialize, Deserialize, Default)]
+struct StartRecord {
+    /// 记录所属的开机ID，开机ID变化时重新计数
+    boot_id: String,
+    /// 本次开机以来的启动次数
+    starts: u64,
+}
+
+fn read_boot_id() -> String {
+    fs::read_to_string(BOOT_ID_PATH)
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// 记录一次进程启动，返回本次开机以来的重启次数（首次启动为0）
+pub fn record_start() -> u64 {
+    let boot_id = read_boot_id();
+    let mut record = fs::read_to_string(STATS_PATH)
+        .ok()
+        .and_then(|content| serde_json::from_str::<StartRecord>(&content).ok())
+        .filter(|record| record.boot_id == boot_id)
+        .unwrap_or(StartRecord { boot_id, starts: 0 });
+    record.starts += 1;
+
+    match serde_json::to_string(&record) {
+        Ok(json) => {
+            if let Err(e) = write_file(STATS_PATH, json.as_bytes(), json.len(), FileClass::State) {
+                warn!("Failed to persist start record: {e}");
+            }
+        }
+        Err(e) => debug!("Failed to serialize start record: {e}"),
+    }
+
+    let restarts = record.starts - 1;
+    let started_at = unix_now();
+    update_status(|s| {
+        s.started_at = started_at;
+        s.restarts = restarts;
+    });
+    restarts
+}
+
+/// 记录一次频率调整
+pub fn record_adjustment() {
+    update_status(|s| s.adjustments += 1);
+}
+
+/// 游戏时长的持久化间隔
+const PLAY_TIME_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 单个游戏的累计时长
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct GamePlayTime {
+    /// 累计前台时长（秒）
+    pub total_secs: u64,
+    /// 进入前台的次数
+    pub sessions: u64,
+    /// 最近一次在前台的时间（Unix时间戳，秒）
+    pub last_played: u64,
+}
+
+/// 持久化的游戏时长记录
+#[derive(Serialize, Deserialize, Default)]
+struct PlayTimeRecord {
+    games: BTreeMap<String, GamePlayTime>,
+}
+
+/// 当前在前台的游戏
+struct GameSession {
+    package: String,
+    started: Instant,
+    /// 已计入累计时长的截止时间
+    accounted: Instant,
+}
+
+struct PlayTimeTracker {
+    record: PlayTimeRecord,
+    current: Option<GameSession>,
+    last_flush: Instant,
+}
+
+static PLAY_TIME: Lazy<Mutex<PlayTimeTracker>> = Lazy::new(|| {
+    let record = fs::read_to_string(PLAY_TIME_PATH)
+        .ok()
+        .and_then(|content| serde_json::from_str::<PlayTimeRecord>(&content).ok())
+        .unwrap_or_default();
+    Mutex::new(PlayTimeTracker {
+        record,
+        current: None,
+        last_flush: Instant::now(),
+    })
+});
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl PlayTimeTracker {
+    /// 将当前游戏未计入的整秒数累加到记录中
+    fn accrue(&mut self) {
+        let Some(session) = &mut self.current else {
+            return;
+        };
+        let secs = session.accounted.elapsed().as_secs();
+        session.accounted += Duration::from_secs(secs);
+
+        let entry = self
+            .record
+            .games
+            .entry(session.package.clone())
+            .or_default();
+        entry.total_secs += secs;
+        entry.last_played = unix_now();
+
+        let session_secs = session.started.elapsed().as_secs();
+        update_status(|s| s.current_game_secs = session_secs);
+    }
+
+    fn persist(&mut self) {
+        self.last_flush = Instant::now();
+        match serde_json::to_string_pretty(&self.record) {
+            Ok(json) => {
+                if let Err(e) = write_file(
+                    PLAY_TIME_PATH,
+                    json.as_bytes(),
+                    json.len(),
+                    FileClass::State,
+                ) {
+                    warn!("Failed to persist play time: {e}");
+                }
+            }
+            Err(e) => debug!("Failed to serialize play time: {e}"),
+        }
+    }
+}
+
+/// 记录前台游戏的变化，`None` 表示前台不是游戏
+///
+/// 游戏离开前台时结算本次时长并立即持久化
+pub fn record_foreground_game(package: Option<&str>) {
+    let mut tracker = PLAY_TIME.lock().unwrap();
+    if tracker
+        .current
+        .as_ref()
+        .map(|session| session.package.as_str())
+        == package
+    {
+        return;
+    }
+
+    tracker.accrue();
+    if let Some(session) = tracker.current.take() {
+        info!(
+            "Play session ended: {} ({}s)",
+            session.package,
+            session.started.elapsed().as_secs()
+        );
+        tracker.persist();
+    }
+
+    if let Some(package) = package {
+        let entry = tracker.record.games.entry(package.to_string()).or_default();
+        entry.sessions += 1;
+        entry.last_played = unix_now();
+        let now = Instant::now();
+        tracker.current = Some(GameSession {
+            package: package.to_string(),
+            started: now,
+            accounted: now,
+        });
+    }
+
+    let current = package.map(str::to_string);
+    update_status(|s| {
+        s.current_game = current;
+        s.current_game_secs = 0;
+    });
+}
+
+/// 累计当前游戏的时长，距上次持久化超过间隔时写入文件
+pub fn tick_play_time() {
+    let mut tracker = PLAY_TIME.lock().unwrap();
+    if tracker.current.is_none() {
+        return;
+    }
+    tracker.accrue();
+    if tracker.last_flush.elapsed() >= PLAY_TIME_FLUSH_INTERVAL {
+        tracker.persist();
+    }
+}
@@ -0,0 +1,188 @@
+//! 运行状态报告模块
+//!
+//! 汇总守护进程的运行状态并写入 status.json，供前端和脚本读取。
+//! 各线程通过 [`update_status`] 修改状态，调频主循环定期调用 [`flush_status_if_due`] 落盘。
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{
+    datasource::{
+        file_path::STATUS_JSON_PATH,
+        load_monitor::{SourceTiming, source_timings},
+    },
+    model::{
+        delivery_stats::OppDelivery, freq::Freq, load_analyzer::LoadTrend, mode_state::ModeSource,
+    },
+    utils::file_operate::{FileClass, write_file},
+};
+
+/// 两次写入 status.json 的最小间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 守护进程运行状态
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct GovernorStatus {
+    /// 当前生效的模式
+    pub mode: String,
+    /// 模式切换来源
+    pub mode_source: Option<ModeSource>,
+    /// 各来源当前请求的模式（来源 -> 模式），生效的来源见 `mode_source`
+    pub mode_claims: BTreeMap<String, String>,
+    /// 进入当前模式的时间（Unix时间戳，秒）
+    pub mode_since: u64,
+    /// 是否处于旁路模式（调速器不再写入频率节点）
+    pub bypass: bool,
+    /// 进入旁路模式的原因
+    pub bypass_reason: Option<String>,
+    /// 需要用户关注的错误状态
+    pub error: Option<String>,
+    /// 频率节点连续写入失败次数
+    pub write_failures: u32,
+    /// 因超出支持范围而被丢弃的当前频率读数
+    pub rejected_freq_samples: u64,
+    /// 前台应用检测方式（dumpsys 或 cgroup）
+    pub foreground_backend: Option<String>,
+    /// dumpsys 是否可用
+    pub dumpsys_healthy: bool,
+    /// dumpsys 连续失败次数
+    pub dumpsys_failures: u32,
+    /// 正在录制的负载轨迹文件
+    pub recording: Option<String>,
+    /// 通过控制命令锁定的GPU频率（KHz）
+    pub locked_freq: Option<Freq>,
+    /// 固定频率策略的目标频率（KHz），0表示频率表中间档位
+    pub pinned_freq: Option<Freq>,
+    /// 是否使用游戏规则的负载-频率曲线代替调频公式
+    pub freq_curve: bool,
+    /// 游戏规则设置的电压偏移（10微伏）
+    pub volt_offset: Option<i64>,
+    /// 系统省电模式是否开启（仅在启用 `[battery_saver]` 时跟踪）
+    pub battery_saver: bool,
+    /// 是否处于冷启动预热期
+    pub warming_up: bool,
+    /// 是否因持续满载请求了高于频率表上限的驱动最高频率
+    pub emergency_ramp: bool,
+    /// 游戏分阶段余量中当前阶段的余量（百分比）
+    pub margin_phase: Option<i64>,
+    /// 自适应调整后的空闲阈值（百分比）
+    pub adaptive_idle_threshold: Option<i32>,
+    /// 最近一次采样的GPU负载（百分比）
+    pub gpu_load: i32,
+    /// 负载分析器判定的负载区间，负载越高编号越大
+    pub load_zone: usize,
+    /// 平滑负载的变化趋势
+    pub load_trend: LoadTrend,
+    /// 平滑后的负载（百分比）
+    pub smoothed_load: i32,
+    /// 最近的原始负载采样，最早的在前
+    pub load_history: Vec<i32>,
+    /// 当前模式的频率上限（KHz）
+    pub max_freq: Option<Freq>,
+    /// 检测到的内核温控限频上限（KHz）
+    pub thermal_cap: Option<Freq>,
+    /// 正在压低频率上限的内核限频来源，限制最严的在前
+    pub freq_limiters: Vec<String>,
+    /// 各请求档位（KHz）调频后实际频率的交付统计
+    pub delivery: BTreeMap<i64, OppDelivery>,
+    /// 启动检查中拒绝写入、已从写入路径中排除的节点
+    pub unwritable_nodes: Vec<String>,
+    /// 各负载来源的读取耗时统计
+    pub load_sources: BTreeMap<String, SourceTiming>,
+    /// 进程启动时间（Unix时间戳，秒）
+    pub started_at: u64,
+    /// 运行时长（秒），写入状态文件时更新
+    pub uptime_secs: u64,
+    /// 本次开机以来守护进程的重启次数
+    pub restarts: u64,
+    /// 是否因长时间空闲允许了GPU断电
+    pub power_collapsed: bool,
+    /// 本次运行检测到的休眠唤醒次数
+    pub resumes: u64,
+    /// 本次运行检测到的 system_server 重启（软重启）次数
+    pub system_server_restarts: u64,
+    /// 本次运行累计的频率调整次数
+    pub adjustments: u64,
+    /// 根据负载判定的疑似游戏（未列入游戏列表）
+    pub likely_games: Vec<String>,
+    /// 当前在前台的游戏
+    pub current_game: Option<String>,
+    /// 当前游戏本次在前台的时长（秒）
+    pub current_game_secs: u64,
+    /// 状态更新时间（Unix时间戳，秒）
+    pub updated_at: u64,
+}
+
+static STATUS: Lazy<Mutex<GovernorStatus>> = Lazy::new(|| Mutex::new(GovernorStatus::default()));
+static DIRTY: AtomicBool = AtomicBool::new(true);
+static LAST_FLUSH: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// 修改运行状态
+pub fn update_status<F: FnOnce(&mut GovernorStatus)>(f: F) {
+    let mut status = STATUS.lock().unwrap();
+    f(&mut status);
+    DIRTY.store(true, Ordering::Relaxed);
+}
+
+/// 读取运行状态中的字段
+pub fn read_status<T, F: FnOnce(&GovernorStatus) -> T>(f: F) -> T {
+    f(&STATUS.lock().unwrap())
+}
+
+/// 获取当前运行状态的副本，状态锁被占用时返回 `None`（用于 panic 等不能阻塞的场景）
+pub fn try_status_snapshot() -> Option<GovernorStatus> {
+    STATUS.try_lock().ok().map(|status| status.clone())
+}
+
+/// 立即将运行状态写入 status.json
+pub fn flush_status() -> Result<()> {
+    let load_sources = source_timings();
+    let json = {
+        let mut status = STATUS.lock().unwrap();
+        status.load_sources = load_sources;
+        status.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if status.started_at > 0 {
+            status.uptime_secs = status.updated_at.saturating_sub(status.started_at);
+        }
+        serde_json::to_string_pretty(&*status)?
+    };
+
+    DIRTY.store(false, Ordering::Relaxed);
+    *LAST_FLUSH.lock().unwrap() = Some(Instant::now());
+    write_file(
+        STATUS_JSON_PATH,
+        json.as_bytes(),
+        json.len(),
+        FileClass::State,
+    )?;
+    Ok(())
+}
+
+/// 状态有变化且距上次写入超过最小间隔时写入 status.json
+pub fn flush_status_if_due() {
+    if !DIRTY.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let due = LAST_FLUSH
+        .lock()
+        .unwrap()
+        .is_none_or(|last| last.elapsed() >= FLUSH_INTERVAL);
+    if due && let Err(e) = flush_status() {
+        warn!("Failed to write status file: {e}");
+    }
+}
@@ -0,0 +1,148 @@
+//! sysfs 写入审计
+//!
+//! 开启 `[audit]` 后，每次写入内核节点的路径、内容、结果和耗时都会追加到独立的审计文件，
+//! 用于排查特定设备驱动的行为，并向内核开发者提供失败的写入序列。
+//! 每秒记录条数有上限，超出部分只统计数量，避免高频调频时审计文件增长过快。
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use log::warn;
+use once_cell::sync::Lazy;
+
+use crate::{
+    datasource::{config_parser::AuditConfig, file_path::SYSFS_AUDIT_PATH},
+    utils::file_operate::ensure_parent_dir,
+};
+
+/// 审计文件超过上限后清空重写
+const MAX_AUDIT_FILE_SIZE: u64 = 4 << 20;
+/// 限流统计窗口
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct AuditState {
+    config: AuditConfig,
+    writer: Option<BufWriter<File>>,
+    written: u64,
+    window_start: Option<Instant>,
+    window_count: u32,
+    dropped: u64,
+}
+
+impl AuditState {
+    fn open(&mut self) -> Option<&mut BufWriter<File>> {
+        if self.writer.is_none() {
+            let file = ensure_parent_dir(SYSFS_AUDIT_PATH)
+                .map_err(std::io::Error::other)
+                .and_then(|()| {
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(SYSFS_AUDIT_PATH)
+                });
+            match file {
+                Ok(file) => {
+                    self.written = file.metadata().map(|m| m.len()).unwrap_or(0);
+                    self.writer = Some(BufWriter::new(file));
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to open sysfs audit file {SYSFS_AUDIT_PATH}: {e}, audit disabled"
+                    );
+                    self.config.enabled = false;
+                    return None;
+                }
+            }
+        }
+        self.writer.as_mut()
+    }
+
+    /// 当前窗口是否还能记录
+    fn take_quota(&mut self) -> bool {
+        let now = Instant::now();
+        if self
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= RATE_WINDOW)
+        {
+            self.window_start = Some(now);
+            self.window_count = 0;
+        }
+        if self.window_count >= self.config.max_per_second {
+            self.dropped += 1;
+            return false;
+        }
+        self.window_count += 1;
+        true
+    }
+
+    fn append(&mut self, line: String) {
+        if self.written + line.len() as u64 > MAX_AUDIT_FILE_SIZE {
+            self.writer = None;
+            if let Err(e) = fs::write(SYSFS_AUDIT_PATH, "") {
+                warn!("Failed to truncate sysfs audit file: {e}");
+            }
+            self.written = 0;
+        }
+        let Some(writer) = self.open() else {
+            return;
+        };
+        // 每条立即刷新，驱动写入导致死机时审计文件中也保留着最后的写入
+        if writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_ok()
+        {
+            self.written += line.len() as u64;
+        }
+    }
+}
+
+static AUDIT: Lazy<Mutex<AuditState>> = Lazy::new(|| Mutex::new(AuditState::default()));
+
+/// 更新审计配置，关闭时释放审计文件
+pub fn set_audit_policy(config: AuditConfig) {
+    let mut state = AUDIT.lock().unwrap();
+    if !config.enabled {
+        state.writer = None;
+    }
+    state.config = config;
+}
+
+/// 审计是否开启
+pub fn is_enabled() -> bool {
+    AUDIT.lock().unwrap().config.enabled
+}
+
+/// 记录一次sysfs写入，`result` 为写入失败时的错误信息
+pub fn record_write(path: &str, value: &str, result: Result<(), &str>, latency: Duration) {
+    let mut state = AUDIT.lock().unwrap();
+    if !state.config.enabled || !state.take_quota() {
+        return;
+    }
+
+    let time = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+    let mut line = String::new();
+    if state.dropped > 0 {
+        line.push_str(&format!(
+            "{time} -- {} write(s) dropped by rate cap\n",
+            state.dropped
+        ));
+        state.dropped = 0;
+    }
+    let outcome = match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("err: {e}"),
+    };
+    line.push_str(&format!(
+        "{time} {path} <- {:?} {outcome} {}us\n",
+        value.trim_end(),
+        latency.as_micros()
+    ));
+    state.append(line);
+}
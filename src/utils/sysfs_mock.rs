@@ -0,0 +1,72 @@
+//! sysfs 模拟层
+//!
+//! 安装后 [`FileHelper`](crate::utils::file_helper::FileHelper) 不再访问真实的内核节点，
+//! 写入只记录在内存中，供离线回放负载轨迹和调试调频算法使用。
+//! 仅在测试和 `trace-replay` feature 下编译，守护进程的写入路径不经过模拟层。
+
+use std::{collections::HashMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+/// 模拟的节点内容和写入记录
+#[derive(Default)]
+struct MockSysfs {
+    nodes: HashMap<String, String>,
+    writes: Vec<(String, String)>,
+}
+
+static MOCK: Lazy<Mutex<Option<MockSysfs>>> = Lazy::new(|| Mutex::new(None));
+
+/// 安装模拟层，`nodes` 为视为存在的节点路径
+pub fn install(nodes: &[&str]) {
+    let mock = MockSysfs {
+        nodes: nodes
+            .iter()
+            .map(|path| (path.to_string(), String::new()))
+            .collect(),
+        writes: Vec::new(),
+    };
+    *MOCK.lock().unwrap() = Some(mock);
+}
+
+/// 模拟层是否已安装
+pub fn is_installed() -> bool {
+    MOCK.lock().unwrap().is_some()
+}
+
+/// 模拟写入节点，未安装模拟层时返回 `None`
+pub fn write(path: &str, content: &str) -> Option<bool> {
+    let mut guard = MOCK.lock().unwrap();
+    let mock = guard.as_mut()?;
+    let Some(node) = mock.nodes.get_mut(path) else {
+        return Some(false);
+    };
+    *node = content.to_string();
+    mock.writes.push((path.to_string(), content.to_string()));
+    Some(true)
+}
+
+/// 查询节点是否存在，未安装模拟层时返回 `None`
+pub fn exists(path: &str) -> Option<bool> {
+    MOCK.lock()
+        .unwrap()
+        .as_ref()
+        .map(|mock| mock.nodes.contains_key(path))
+}
+
+/// 读取节点最后一次写入的内容
+pub fn read(path: &str) -> Option<String> {
+    MOCK.lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|mock| mock.nodes.get(path).cloned())
+}
+
+/// 取出并清空自上次调用以来的写入记录
+pub fn take_writes() -> Vec<(String, String)> {
+    MOCK.lock()
+        .unwrap()
+        .as_mut()
+        .map(|mock| std::mem::take(&mut mock.writes))
+        .unwrap_or_default()
+}
@@ -0,0 +1,94 @@
+//! 负载轨迹录制模块
+//!
+//! 按采样频率记录 (时间戳, 负载, 频率, DDR档位, 温度)，输出的CSV可直接交给 trace-replay 回放。
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+
+use crate::datasource::{file_path::TRACE_DIR, thermal::read_gpu_temperature};
+
+/// 单个轨迹文件的最大采样数，防止录制占满存储
+const MAX_TRACE_SAMPLES: usize = 500_000;
+/// 单次录制允许的最长时间
+pub const MAX_TRACE_DURATION: Duration = Duration::from_secs(30 * 60);
+/// 温度读取间隔，温度变化较慢，无需每个采样周期都读取
+const TEMP_READ_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 负载轨迹录制器
+pub struct TraceRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    start_ms: Option<u64>,
+    samples: usize,
+    last_temp: Option<i32>,
+    last_temp_read: Option<Instant>,
+}
+
+impl TraceRecorder {
+    /// 在轨迹目录中创建新的轨迹文件并写入表头
+    pub fn start() -> Result<Self> {
+        fs::create_dir_all(TRACE_DIR)
+            .with_context(|| format!("Failed to create trace directory: {TRACE_DIR}"))?;
+
+        let file_name = format!("trace_{}.csv", Local::now().format("%Y%m%d_%H%M%S"));
+        let path = Path::new(TRACE_DIR).join(file_name);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create trace file: {}", path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "timestamp_ms,load,freq,ddr_opp,temp")?;
+
+        Ok(Self {
+            writer,
+            path,
+            start_ms: None,
+            samples: 0,
+            last_temp: None,
+            last_temp_read: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 记录一个采样点，达到采样数上限时返回 `false`
+    pub fn record(&mut self, time_ms: u64, load: i32, freq: i64, ddr_opp: i64) -> Result<bool> {
+        if self.samples >= MAX_TRACE_SAMPLES {
+            return Ok(false);
+        }
+
+        if self
+            .last_temp_read
+            .is_none_or(|last| last.elapsed() >= TEMP_READ_INTERVAL)
+        {
+            self.last_temp = read_gpu_temperature();
+            self.last_temp_read = Some(Instant::now());
+        }
+
+        // 时间戳相对于录制开始，便于对比不同轨迹
+        let start = *self.start_ms.get_or_insert(time_ms);
+        let temp = self.last_temp.map(|t| t.to_string()).unwrap_or_default();
+        writeln!(
+            self.writer,
+            "{},{load},{freq},{ddr_opp},{temp}",
+            time_ms.saturating_sub(start)
+        )?;
+
+        self.samples += 1;
+        Ok(self.samples < MAX_TRACE_SAMPLES)
+    }
+
+    /// 结束录制，返回轨迹文件路径和采样数
+    pub fn finish(mut self) -> Result<(PathBuf, usize)> {
+        self.writer.flush()?;
+        Ok((self.path, self.samples))
+    }
+}